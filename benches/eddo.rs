@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use eddo::gen_keypair;
+use eddo::{gen_keypair, verify_batch, PrivateKey, VerifyingKey};
 use rand::rngs::OsRng;
 
 const KB: usize = 1024;
@@ -11,6 +11,25 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         c.bench_function("generating_keypair", |b| b.iter(|| gen_keypair(&mut OsRng)));
     }
 
+    {
+        let mut group = c.benchmark_group("batch_key_derivation");
+        for &size in &[16, 256] {
+            let keys: Vec<PrivateKey> = (0..size).map(|_| gen_keypair(&mut OsRng).1).collect();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(BenchmarkId::new("individual", size), &size, |b, _size| {
+                b.iter(|| {
+                    keys.iter()
+                        .map(|k| k.derive_public_key())
+                        .collect::<Vec<_>>()
+                });
+            });
+            group.bench_with_input(BenchmarkId::new("batched", size), &size, |b, _size| {
+                b.iter(|| PrivateKey::derive_public_keys(black_box(&keys)));
+            });
+        }
+        group.finish();
+    }
+
     {
         let mut group = c.benchmark_group("signing");
         for &size in &[KB, 4 * KB, 16 * KB, 64 * KB, 256 * KB, 1024 * KB] {
@@ -35,6 +54,41 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         }
         group.finish();
     }
+
+    {
+        let mut group = c.benchmark_group("short_verification");
+        let signature = private.sign(b"short");
+        let key = VerifyingKey::from(public);
+        group.bench_function("public_key", |b| {
+            b.iter(|| public.verify(black_box(b"short"), black_box(signature)));
+        });
+        group.bench_function("verifying_key", |b| {
+            b.iter(|| key.verify(black_box(b"short"), black_box(signature)));
+        });
+        group.finish();
+    }
+
+    {
+        let mut group = c.benchmark_group("batch_verification");
+        for &size in &[16, 64, 256, 1024] {
+            let mut messages = Vec::new();
+            let mut signatures = Vec::new();
+            let mut public_keys = Vec::new();
+            for i in 0..size as u32 {
+                let (public, private) = gen_keypair(&mut OsRng);
+                let message = i.to_le_bytes().to_vec();
+                signatures.push(private.sign(&message));
+                public_keys.push(public);
+                messages.push(message);
+            }
+            let messages: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _size| {
+                b.iter(|| verify_batch(&messages, &signatures, &public_keys, &mut OsRng));
+            });
+        }
+        group.finish();
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
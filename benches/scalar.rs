@@ -0,0 +1,47 @@
+//! Compares scalar multiplication with Barrett reduction against Montgomery multiplication.
+//!
+//! `Scalar` multiplication uses Barrett reduction on 64 bit targets, unless the
+//! `montgomery_scalar` feature is enabled. Running this with, and without, that
+//! feature compares the two for single products:
+//!
+//!     cargo bench --features hazmat --bench scalar
+//!     cargo bench --features hazmat,montgomery_scalar --bench scalar
+//!
+//! On x86_64, the `adx` feature speeds up Barrett reduction with BMI2, and ADX,
+//! when the CPU supports them.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use eddo::hazmat::Scalar;
+use rand::{rngs::OsRng, RngCore};
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0; 64];
+    OsRng.fill_bytes(&mut bytes);
+    Scalar::from(bytes)
+}
+
+pub fn scalar_benchmark(c: &mut Criterion) {
+    let a = random_scalar();
+    let b = random_scalar();
+    let (a_mont, b_mont) = (a.to_montgomery(), b.to_montgomery());
+
+    let mut group = c.benchmark_group("scalar");
+    group.bench_function("multiplication", |bench| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    group.bench_function("montgomery_multiplication", |bench| {
+        bench.iter(|| black_box(a_mont) * black_box(b_mont))
+    });
+    group.bench_function("montgomery_conversion", |bench| {
+        bench.iter(|| black_box(a).to_montgomery().to_scalar())
+    });
+    group.bench_function("reduction", |bench| {
+        let mut bytes = [0; 64];
+        OsRng.fill_bytes(&mut bytes);
+        bench.iter(|| Scalar::from(black_box(bytes)))
+    });
+    group.bench_function("inverse", |bench| bench.iter(|| black_box(a).inverse()));
+    group.finish();
+}
+
+criterion_group!(benches, scalar_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+//! Decoding a scalar should never panic, and only accept values below L.
+#![no_main]
+use std::convert::TryFrom;
+
+use eddo::hazmat::Scalar;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(scalar) = Scalar::try_from(data) {
+        assert_eq!(<[u8; 32]>::from(scalar)[..], data[..32]);
+    }
+    // Wide reduction accepts any 64 bytes, and always produces a canonical scalar.
+    if data.len() >= 64 {
+        let wide = <[u8; 64]>::try_from(&data[..64]).unwrap();
+        let reduced: [u8; 32] = Scalar::from(wide).into();
+        assert!(Scalar::try_from(&reduced[..]).is_ok());
+    }
+});
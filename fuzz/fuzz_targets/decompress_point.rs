@@ -0,0 +1,21 @@
+//! Decompressing a point, and a field element, should never panic, and only
+//! accept canonical encodings, which compress back to the same bytes.
+#![no_main]
+use std::convert::TryFrom;
+
+use eddo::hazmat::{Point, Z25519};
+use eddo::PublicKey;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(point) = Point::try_from(data) {
+        assert_eq!(<[u8; 32]>::from(point)[..], data[..32]);
+    }
+    if let Ok(z) = Z25519::try_from(data) {
+        assert_eq!(<[u8; 32]>::from(z)[..], data[..32]);
+    }
+    // Public keys need exactly the right length, unlike the raw decoding.
+    if let Ok(public) = PublicKey::try_from(data) {
+        assert_eq!(public.as_bytes()[..], data[..]);
+    }
+});
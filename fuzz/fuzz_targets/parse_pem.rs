@@ -0,0 +1,26 @@
+//! Reading PEM armor should never panic, and anything accepted should survive being
+//! armored, and read, again. The key parsers built on top get exercised too.
+#![no_main]
+use std::io::Read;
+
+use eddo::{pem, PrivateKey, PublicKey};
+use libfuzzer_sys::fuzz_target;
+
+fn read(data: &[u8]) -> Option<(String, Vec<u8>)> {
+    let mut reader = pem::Reader::new(data).ok()?;
+    let label = reader.label().to_string();
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body).ok()?;
+    Some((label, body))
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((label, body)) = read(data) {
+        let armored = pem::encode(&label, &body);
+        assert_eq!(read(armored.as_bytes()), Some((label, body)));
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = PrivateKey::from_pkcs8_pem(text);
+        let _ = PublicKey::from_public_key_pem(text);
+    }
+});
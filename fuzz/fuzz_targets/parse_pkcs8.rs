@@ -0,0 +1,16 @@
+//! Parsing PKCS#8, and SubjectPublicKeyInfo, DER should never panic, and anything
+//! accepted should survive being encoded, and parsed, again.
+#![no_main]
+use eddo::{PrivateKey, PublicKey};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(private) = PrivateKey::from_pkcs8_der(data) {
+        let again = PrivateKey::from_pkcs8_der(&private.to_pkcs8_der()).unwrap();
+        assert_eq!(again.as_bytes(), private.as_bytes());
+    }
+    if let Ok(public) = PublicKey::from_public_key_der(data) {
+        let again = PublicKey::from_public_key_der(&public.to_public_key_der()).unwrap();
+        assert_eq!(again, public);
+    }
+});
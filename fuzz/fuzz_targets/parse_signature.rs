@@ -0,0 +1,38 @@
+//! Parsing a signature, and a public key, and verifying the pair, should never panic.
+//!
+//! The input is split into a signature, a public key, and a message.
+#![no_main]
+use std::convert::TryFrom;
+
+use eddo::{verify_batch, PublicKey, Signature, PUBLIC_KEY_SIZE, SIGNATURE_SIZE};
+use libfuzzer_sys::fuzz_target;
+use rand::rngs::OsRng;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < SIGNATURE_SIZE + PUBLIC_KEY_SIZE {
+        let _ = Signature::try_from(data);
+        return;
+    }
+    let (signature, rest) = data.split_at(SIGNATURE_SIZE);
+    let (public, message) = rest.split_at(PUBLIC_KEY_SIZE);
+    // Keys which don't decode can't be built, so there's nothing to verify against.
+    let public = match PublicKey::try_from(public) {
+        Ok(public) => public,
+        Err(_) => return,
+    };
+    let unchecked = Signature {
+        bytes: <[u8; SIGNATURE_SIZE]>::try_from(signature).unwrap(),
+    };
+    let accepted = public.verify(message, unchecked);
+    // Every signature we accept should also parse, and pass the cofactored check.
+    if accepted {
+        assert!(Signature::try_from(signature).is_ok());
+        assert!(verify_batch(
+            &[message],
+            &[unchecked],
+            &[public],
+            &mut OsRng
+        ));
+    }
+    let _ = public.verify_low_memory(message, unchecked);
+});
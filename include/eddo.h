@@ -0,0 +1,88 @@
+#ifndef EDDO_H
+#define EDDO_H
+
+/* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define EDDO_PUBLIC_KEY_SIZE 32
+
+#define EDDO_PRIVATE_KEY_SIZE 32
+
+#define EDDO_SIGNATURE_SIZE 64
+
+#define EDDO_X25519_SIZE 32
+
+/**
+ * Generate a new key pair, using the randomness of the operating system.
+ *
+ * # Safety
+ *
+ * `public_out` must point to `EDDO_PUBLIC_KEY_SIZE` writable bytes, and `private_out`
+ * to `EDDO_PRIVATE_KEY_SIZE` writable bytes.
+ */
+int eddo_keygen(uint8_t *public_out, uint8_t *private_out);
+
+/**
+ * Calculate the public key corresponding to a private key.
+ *
+ * # Safety
+ *
+ * `private_key` must point to `EDDO_PRIVATE_KEY_SIZE` readable bytes, and `public_out`
+ * to `EDDO_PUBLIC_KEY_SIZE` writable bytes.
+ */
+int eddo_public_key(const uint8_t *private_key, uint8_t *public_out);
+
+/**
+ * Sign a message with a private key.
+ *
+ * # Safety
+ *
+ * `private_key` must point to `EDDO_PRIVATE_KEY_SIZE` readable bytes, `message` to
+ * `message_len` readable bytes, and `signature_out` to `EDDO_SIGNATURE_SIZE` writable bytes.
+ */
+int eddo_sign(const uint8_t *private_key,
+              const uint8_t *message,
+              size_t message_len,
+              uint8_t *signature_out);
+
+/**
+ * Verify a signature of a message, returning 0 only if it's valid.
+ *
+ * # Safety
+ *
+ * `public_key` must point to `EDDO_PUBLIC_KEY_SIZE` readable bytes, `message` to
+ * `message_len` readable bytes, and `signature` to `EDDO_SIGNATURE_SIZE` readable bytes.
+ */
+int eddo_verify(const uint8_t *public_key,
+                const uint8_t *message,
+                size_t message_len,
+                const uint8_t *signature);
+
+/**
+ * Calculate the X25519 function of RFC 7748, with a scalar, and a u coordinate.
+ *
+ * This fails if the result is all zeros, which happens for points of small order.
+ *
+ * # Safety
+ *
+ * `scalar`, and `u`, must point to `EDDO_X25519_SIZE` readable bytes, and `out`
+ * to `EDDO_X25519_SIZE` writable bytes.
+ */
+int eddo_x25519(const uint8_t *scalar, const uint8_t *u, uint8_t *out);
+
+/**
+ * Calculate the X25519 public key for a scalar, i.e. X25519 with the base point.
+ *
+ * # Safety
+ *
+ * `scalar` must point to `EDDO_X25519_SIZE` readable bytes, and `out` to
+ * `EDDO_X25519_SIZE` writable bytes.
+ */
+int eddo_x25519_base(const uint8_t *scalar, uint8_t *out);
+
+#endif /* EDDO_H */
@@ -0,0 +1,50 @@
+//! This crate checks that the core operations of eddo work without an allocator.
+//!
+//! It's built as a `no_std` dynamic library, without a global allocator, so it only
+//! links if nothing reachable from these functions allocates:
+//!
+//! ```text
+//! cargo build --manifest-path no-alloc/Cargo.toml
+//! ```
+#![no_std]
+
+use core::panic::PanicInfo;
+
+use eddo::{x25519, PrivateKey, PublicKey, Signature};
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub extern "C" fn no_alloc_public_key(private: &[u8; 32], public_out: &mut [u8; 32]) {
+    let public = PrivateKey::from(*private).derive_public_key();
+    *public_out = public.to_bytes();
+}
+
+#[no_mangle]
+pub extern "C" fn no_alloc_sign(
+    private: &[u8; 32],
+    message: &[u8; 32],
+    signature_out: &mut [u8; 64],
+) {
+    *signature_out = PrivateKey::from(*private).sign(message).bytes;
+}
+
+#[no_mangle]
+pub extern "C" fn no_alloc_verify(
+    public: &[u8; 32],
+    message: &[u8; 32],
+    signature: &[u8; 64],
+) -> bool {
+    match PublicKey::from_bytes(*public) {
+        Ok(public) => public.verify(message, Signature { bytes: *signature }),
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn no_alloc_x25519(scalar: &[u8; 32], u: &[u8; 32], out: &mut [u8; 32]) {
+    *out = x25519(*scalar, *u);
+}
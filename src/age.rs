@@ -0,0 +1,309 @@
+//! This module implements X25519 recipients for the age file encryption format:
+//! https://age-encryption.org/v1
+//!
+//! An age file starts with a header, containing one stanza per recipient, each wrapping
+//! the same 16 byte file key. This only handles the X25519 stanzas, along with the
+//! Bech32 encodings of recipients, and identities, leaving the rest of the format,
+//! such as the header MAC, and the payload, to the caller.
+
+use core::{convert::TryInto, fmt};
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::{
+    base64, bech32, box_, chacha20, hkdf, sha512, x25519, zeroize::Zeroizing, Error,
+    ExchangePublicKey, PrivateKey,
+};
+
+/// The size of the file key wrapped in each stanza.
+pub const FILE_KEY_SIZE: usize = 16;
+
+const RECIPIENT_HRP: &str = "age";
+
+const IDENTITY_HRP: &str = "age-secret-key-";
+
+const LABEL: &[u8] = b"age-encryption.org/v1/X25519";
+
+/// Encode some bytes as standard Base64, without padding, as age requires.
+fn encode_base64(data: &[u8]) -> String {
+    base64::encode(data).trim_end_matches('=').to_string()
+}
+
+fn decode_base64<const N: usize>(data: &str) -> Result<[u8; N], Error> {
+    if data.contains('=') || data.contains(char::is_whitespace) {
+        return Err(Error::InvalidEncoding);
+    }
+    let bytes = base64::decode(data.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    bytes.try_into().map_err(|_| Error::InvalidLength)
+}
+
+/// Derive the key wrapping the file key, from the ephemeral share, and the recipient.
+fn wrap_key(shared: &[u8; 32], share: &[u8; 32], recipient: &[u8; 32]) -> [u8; 32] {
+    let mut salt = [0; 64];
+    salt[..32].copy_from_slice(share);
+    salt[32..].copy_from_slice(recipient);
+    let mut out = [0; 32];
+    hkdf::derive_sha256(&salt, shared, LABEL, &mut out);
+    out
+}
+
+/// An X25519 recipient stanza, in a header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AgeStanza {
+    /// The ephemeral X25519 public key of the sender.
+    pub share: [u8; 32],
+    /// The file key, encrypted with ChaCha20-Poly1305.
+    pub body: [u8; FILE_KEY_SIZE + chacha20::TAG_SIZE],
+}
+
+impl AgeStanza {
+    /// Encode this stanza as it appears in a header, including the final newline.
+    pub fn encode(&self) -> String {
+        format!(
+            "-> X25519 {}\n{}\n",
+            encode_base64(&self.share),
+            encode_base64(&self.body)
+        )
+    }
+
+    /// Parse a stanza encoded with `encode`.
+    ///
+    /// Stanzas of other types return `Error::UnsupportedAlgorithm`.
+    pub fn decode(input: &str) -> Result<Self, Error> {
+        let mut lines = input.strip_suffix('\n').unwrap_or(input).split('\n');
+        let args: Vec<&str> = lines
+            .next()
+            .and_then(|line| line.strip_prefix("-> "))
+            .ok_or(Error::InvalidEncoding)?
+            .split(' ')
+            .collect();
+        if args[0] != "X25519" {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        if args.len() != 2 {
+            return Err(Error::InvalidEncoding);
+        }
+        let share = decode_base64(args[1])?;
+        let body = decode_base64(lines.next().ok_or(Error::InvalidEncoding)?)?;
+        if lines.next().is_some() {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(AgeStanza { share, body })
+    }
+}
+
+/// The public half of an age X25519 key, encoded as "age1...".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AgeRecipient {
+    pub bytes: [u8; 32],
+}
+
+impl From<ExchangePublicKey> for AgeRecipient {
+    fn from(public: ExchangePublicKey) -> Self {
+        AgeRecipient {
+            bytes: public.bytes,
+        }
+    }
+}
+
+impl AgeRecipient {
+    /// Encode this recipient with Bech32, in lowercase.
+    pub fn encode(&self) -> String {
+        bech32::encode(RECIPIENT_HRP, &self.bytes)
+    }
+
+    pub fn decode(input: &str) -> Result<Self, Error> {
+        let (hrp, data) = bech32::decode(input).ok_or(Error::InvalidEncoding)?;
+        if hrp != RECIPIENT_HRP {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        Ok(AgeRecipient {
+            bytes: data.try_into().map_err(|_| Error::InvalidLength)?,
+        })
+    }
+
+    fn wrap_with_ephemeral(
+        &self,
+        file_key: &[u8; FILE_KEY_SIZE],
+        ephemeral_secret: &[u8; 32],
+    ) -> Result<AgeStanza, Error> {
+        let share = box_::public_key(ephemeral_secret);
+        let shared = Zeroizing::new(x25519(*ephemeral_secret, self.bytes));
+        if bool::from(shared.ct_eq(&[0; 32])) {
+            return Err(Error::WeakPublicKey);
+        }
+        let key = Zeroizing::new(wrap_key(&shared, &share, &self.bytes));
+        let body = chacha20::seal(&key, &[0; chacha20::NONCE_SIZE], &[], file_key);
+        Ok(AgeStanza {
+            share,
+            body: body.try_into().unwrap(),
+        })
+    }
+
+    /// Wrap a file key for this recipient, producing a stanza for the header.
+    pub fn wrap<R: RngCore + CryptoRng>(
+        &self,
+        file_key: &[u8; FILE_KEY_SIZE],
+        rng: &mut R,
+    ) -> Result<AgeStanza, Error> {
+        let mut ephemeral_secret = Zeroizing::new([0; 32]);
+        rng.fill_bytes(&mut *ephemeral_secret);
+        self.wrap_with_ephemeral(file_key, &ephemeral_secret)
+    }
+}
+
+/// The secret half of an age X25519 key, encoded as "AGE-SECRET-KEY-1...".
+pub struct AgeIdentity {
+    scalar: [u8; 32],
+}
+
+/// Any 32 bytes make a valid identity.
+impl From<[u8; 32]> for AgeIdentity {
+    fn from(scalar: [u8; 32]) -> Self {
+        AgeIdentity { scalar }
+    }
+}
+
+impl AgeIdentity {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut scalar = [0; 32];
+        rng.fill_bytes(&mut scalar);
+        AgeIdentity { scalar }
+    }
+
+    /// Use the exchange key of a private key as an identity.
+    ///
+    /// The recipient then matches `ExchangePublicKey::from_signing_key`, which lets
+    /// files be encrypted to the holder of an Ed25519 key.
+    pub fn from_private_key(private: &PrivateKey) -> Self {
+        let hash = Zeroizing::new(sha512::hash(private.as_bytes()));
+        AgeIdentity {
+            scalar: hash[..32].try_into().unwrap(),
+        }
+    }
+
+    /// The recipient files get encrypted to, for this identity.
+    pub fn recipient(&self) -> AgeRecipient {
+        AgeRecipient {
+            bytes: box_::public_key(&self.scalar),
+        }
+    }
+
+    /// Encode this identity with Bech32, in uppercase, as age does.
+    pub fn encode(&self) -> String {
+        bech32::encode(IDENTITY_HRP, &self.scalar).to_ascii_uppercase()
+    }
+
+    pub fn decode(input: &str) -> Result<Self, Error> {
+        let (hrp, data) = bech32::decode(input).ok_or(Error::InvalidEncoding)?;
+        if hrp != IDENTITY_HRP {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        Ok(AgeIdentity {
+            scalar: data.try_into().map_err(|_| Error::InvalidLength)?,
+        })
+    }
+
+    /// Unwrap the file key from a stanza, failing if it wasn't made for this identity.
+    pub fn unwrap(&self, stanza: &AgeStanza) -> Result<[u8; FILE_KEY_SIZE], Error> {
+        let recipient = self.recipient();
+        let shared = Zeroizing::new(x25519(self.scalar, stanza.share));
+        if bool::from(shared.ct_eq(&[0; 32])) {
+            return Err(Error::WeakPublicKey);
+        }
+        let key = Zeroizing::new(wrap_key(&shared, &stanza.share, &recipient.bytes));
+        let file_key = chacha20::open(&key, &[0; chacha20::NONCE_SIZE], &[], &stanza.body)
+            .ok_or(Error::DecryptionFailed)?;
+        Ok(Zeroizing::new(file_key)[..].try_into().unwrap())
+    }
+}
+
+/// Like with `PrivateKey`, this never prints the secret scalar.
+impl fmt::Debug for AgeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgeIdentity").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for AgeIdentity {
+    fn drop(&mut self) {
+        self.scalar.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    // The identities, and stanzas, of the x25519, and x25519_low_order, files in the
+    // age testkit, https://github.com/C2SP/CCTV/tree/main/age, as vendored in version
+    // 0.11.1 of the age crate.
+    const IDENTITY: &str =
+        "AGE-SECRET-KEY-1XMWWC06LY3EE5RYTXM9MFLAZ2U56JJJ36S0MYPDRWSVLUL66MV4QX3S7F6";
+    const STANZA: &str = "-> X25519 TEiF0ypqr+bpvcqXNyCVJpL7OuwPdVwPL7KQEbFDOCc\nEmECAEcKN+n/Vs9SbWiV+Hu0r+E8R77DdWYyd83nw7U\n";
+    const LOW_ORDER_IDENTITY: &str =
+        "AGE-SECRET-KEY-1EGTZVFFV20835NWYV6270LXYVK2VKNX2MMDKWYKLMGR48UAWX40Q2P2LM0";
+    const LOW_ORDER_STANZA: &str = "-> X25519 X5yVvKNQjCSx0LFVnIPvWwREXMRYHI6G2CJO3dCfEdc\n3E0NpFans/m0WLWF7+54ZBdNj3iqQqpraGDFiaRkvBA\n";
+
+    #[test]
+    fn test_encodings() {
+        let identity = AgeIdentity::decode(IDENTITY).unwrap();
+        assert_eq!(identity.encode(), IDENTITY);
+        assert_eq!(
+            AgeIdentity::decode(&IDENTITY.to_lowercase())
+                .unwrap()
+                .recipient(),
+            identity.recipient()
+        );
+        assert_eq!(
+            AgeRecipient::decode(&identity.recipient().encode()).unwrap(),
+            identity.recipient()
+        );
+        assert_eq!(
+            AgeRecipient::decode(IDENTITY).unwrap_err(),
+            Error::UnsupportedAlgorithm
+        );
+        let from_key = AgeIdentity::from_private_key(&PrivateKey::from([1; 32]));
+        let public = PrivateKey::from([1; 32]).into_signing_only().public_key();
+        assert_eq!(
+            from_key.recipient(),
+            AgeRecipient::from(ExchangePublicKey::from_signing_key(&public).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_testkit_stanzas() {
+        let identity = AgeIdentity::decode(IDENTITY).unwrap();
+        let stanza = AgeStanza::decode(STANZA).unwrap();
+        assert_eq!(stanza.encode(), STANZA);
+        assert_eq!(identity.unwrap(&stanza).unwrap(), *b"YELLOW SUBMARINE");
+
+        // The share is a point of low order, making the shared secret zero.
+        let identity = AgeIdentity::decode(LOW_ORDER_IDENTITY).unwrap();
+        let stanza = AgeStanza::decode(LOW_ORDER_STANZA).unwrap();
+        assert!(identity.unwrap(&stanza).is_err());
+    }
+
+    #[test]
+    fn test_wrap_roundtrip() {
+        let identity = AgeIdentity::generate(&mut OsRng);
+        let file_key = [42; FILE_KEY_SIZE];
+        let mut stanza = identity.recipient().wrap(&file_key, &mut OsRng).unwrap();
+        assert_eq!(identity.unwrap(&stanza).unwrap(), file_key);
+        let other = AgeIdentity::generate(&mut OsRng);
+        assert_eq!(other.unwrap(&stanza), Err(Error::DecryptionFailed));
+        stanza.body[0] ^= 1;
+        assert_eq!(identity.unwrap(&stanza), Err(Error::DecryptionFailed));
+        assert_eq!(
+            AgeStanza::decode("-> scrypt abc 18\nxyz\n"),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert!(AgeStanza::decode("-> X25519 abc=\nxyz\n").is_err());
+    }
+}
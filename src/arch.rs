@@ -9,7 +9,10 @@ pub fn adc(carry: u8, a: u64, b: u64, out: &mut u64) -> u8 {
     #[cfg(target_arch = "x86_64")]
     {
         // Using this intrinsic is perfectly safe
-        unsafe { arch::_addcarry_u64(carry, a, b, out) }
+        #[allow(unused_unsafe)]
+        unsafe {
+            arch::_addcarry_u64(carry, a, b, out)
+        }
     }
     #[cfg(not(target_arch = "x86_64"))]
     {
@@ -31,7 +34,10 @@ pub fn sbb(borrow: u8, a: u64, b: u64, out: &mut u64) -> u8 {
     #[cfg(target_arch = "x86_64")]
     {
         // Using this intrinsic is perfectly safe
-        unsafe { arch::_subborrow_u64(borrow, a, b, out) }
+        #[allow(unused_unsafe)]
+        unsafe {
+            arch::_subborrow_u64(borrow, a, b, out)
+        }
     }
     #[cfg(not(target_arch = "x86_64"))]
     {
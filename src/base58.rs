@@ -0,0 +1,80 @@
+//! This module implements Base58, with the alphabet used by Bitcoin, and multibase:
+//! https://datatracker.ietf.org/doc/html/draft-msporny-base58-03
+//!
+//! This is only used for public data, like did:key identifiers, so unlike `base64`,
+//! no care is taken to avoid branching on the data.
+
+use alloc::{string::String, vec, vec::Vec};
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encode some data as Base58.
+pub fn encode(data: &[u8]) -> String {
+    // Each leading zero byte becomes a '1', and the rest gets treated as a big number.
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    // The digits of that number, in base 58, least significant first.
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &b in &data[zeros..] {
+        let mut carry = u32::from(b);
+        for d in digits.iter_mut() {
+            carry += u32::from(*d) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(core::iter::repeat_n('1', zeros));
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&d| char::from(ALPHABET[usize::from(d)])),
+    );
+    out
+}
+
+/// Decode some Base58 data, returning `None` if it contains invalid characters.
+pub fn decode(data: &str) -> Option<Vec<u8>> {
+    let zeros = data.bytes().take_while(|&c| c == b'1').count();
+    // The bytes of the number, least significant first.
+    let mut bytes: Vec<u8> = Vec::with_capacity(data.len());
+    for c in data[zeros..].bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        let vectors: [(&[u8], &str); 4] = [
+            (b"", ""),
+            (b"hello world", "StV1DL6CwTryKyV"),
+            (&[0, 0, 1, 2], "115T"),
+            (&[0], "1"),
+        ];
+        for &(data, encoded) in vectors.iter() {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded).unwrap(), data);
+        }
+        assert!(decode("0OIl").is_none());
+    }
+}
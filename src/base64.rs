@@ -0,0 +1,400 @@
+//! This module implements Base64, as described in Section 4 of RFC 4648:
+//! https://datatracker.ietf.org/doc/html/rfc4648#section-4
+//!
+//! The URL safe variant of Section 5, without padding, is also provided, since
+//! web formats like JWK and JWS use it.
+//!
+//! Besides one-shot encoding and decoding, this provides a streaming `Decoder`,
+//! which decodes armored input incrementally, using a constant amount of memory.
+//!
+//! Armored data often contains secrets, like encrypted key backups, so characters
+//! are converted without lookup tables or branches depending on their value.
+
+use std::io::{self, Read};
+
+/// How many bytes of encoded input we read from the underlying reader at once.
+const BUFFER_SIZE: usize = 1024;
+
+/// Which of the two alphabets of RFC 4648 to use.
+#[derive(Clone, Copy, PartialEq)]
+enum Alphabet {
+    Standard,
+    Url,
+}
+
+/// Convert a 6 bit value into its Base64 character, in constant time.
+fn encode_sextet(x: u8, alphabet: Alphabet) -> u8 {
+    let x = i16::from(x);
+    // We start at 'A', and shift the offset as we cross into each range.
+    let mut diff = 0x41;
+    // 26..51 map to 'a'...'z'
+    diff += ((25 - x) >> 8) & 6;
+    // 52..61 map to '0'...'9'
+    diff -= ((51 - x) >> 8) & 75;
+    if alphabet == Alphabet::Standard {
+        // 62 maps to '+'
+        diff -= ((61 - x) >> 8) & 15;
+        // 63 maps to '/'
+        diff += ((62 - x) >> 8) & 3;
+    } else {
+        // 62 maps to '-'
+        diff -= ((61 - x) >> 8) & 13;
+        // 63 maps to '_'
+        diff += ((62 - x) >> 8) & 49;
+    }
+    (x + diff) as u8
+}
+
+/// Convert a Base64 character into its 6 bit value, in constant time.
+///
+/// This returns -1 for characters outside of the alphabet.
+fn decode_char(c: u8, alphabet: Alphabet) -> i16 {
+    let c = i16::from(c);
+    // (lo - c) & (c - hi) is negative exactly when lo < c < hi, so each line adds
+    // the value of c, plus 1, if c is in a given range.
+    let mut out = -1;
+    out += (((0x40 - c) & (c - 0x5b)) >> 8) & (c - 64);
+    out += (((0x60 - c) & (c - 0x7b)) >> 8) & (c - 70);
+    out += (((0x2f - c) & (c - 0x3a)) >> 8) & (c + 5);
+    if alphabet == Alphabet::Standard {
+        out += (((0x2a - c) & (c - 0x2c)) >> 8) & 63;
+        out += (((0x2e - c) & (c - 0x30)) >> 8) & 64;
+    } else {
+        out += (((0x2c - c) & (c - 0x2e)) >> 8) & 63;
+        out += (((0x5e - c) & (c - 0x60)) >> 8) & 64;
+    }
+    out
+}
+
+/// Encode some data as Base64, with padding.
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, Alphabet::Standard)
+}
+
+/// Encode some data as URL safe Base64, without padding.
+pub fn encode_url(data: &[u8]) -> String {
+    encode_with(data, Alphabet::Url)
+}
+
+fn encode_with(data: &[u8], alphabet: Alphabet) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let sextets = [
+            group[0] >> 2,
+            ((group[0] & 0b11) << 4) | (group[1] >> 4),
+            ((group[1] & 0b1111) << 2) | (group[2] >> 6),
+            group[2] & 0b11_1111,
+        ];
+        for (i, &s) in sextets.iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(char::from(encode_sextet(s, alphabet)));
+            } else if alphabet == Alphabet::Standard {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode some Base64 data, ignoring whitespace.
+///
+/// Padding is optional, but if present, it must be correct.
+pub fn decode(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    Decoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Decode some URL safe Base64 data.
+///
+/// Unlike `decode`, this rejects whitespace and padding, since the formats using
+/// this alphabet never contain them.
+pub fn decode_url(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut decoder = Decoder::new(data);
+    decoder.alphabet = Alphabet::Url;
+    decoder.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A reader decoding Base64 from an underlying reader, as it goes.
+///
+/// Whitespace in the input is ignored, which makes this suitable for armored
+/// formats, which split their data across multiple lines.
+pub struct Decoder<R> {
+    inner: R,
+    input: [u8; BUFFER_SIZE],
+    input_pos: usize,
+    input_len: usize,
+    // The sextets of the group of 4 characters we're currently reading
+    group: [u8; 4],
+    group_len: usize,
+    // Bytes we've decoded, but not yet returned
+    output: [u8; 3],
+    output_pos: usize,
+    output_len: usize,
+    // How many more padding characters we expect, once we've seen the first one
+    padding: Option<usize>,
+    eof: bool,
+    alphabet: Alphabet,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a new decoder, reading encoded data from some reader.
+    pub fn new(inner: R) -> Self {
+        Decoder {
+            inner,
+            input: [0; BUFFER_SIZE],
+            input_pos: 0,
+            input_len: 0,
+            group: [0; 4],
+            group_len: 0,
+            output: [0; 3],
+            output_pos: 0,
+            output_len: 0,
+            padding: None,
+            eof: false,
+            alphabet: Alphabet::Standard,
+        }
+    }
+
+    /// A reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    fn next_char(&mut self) -> io::Result<Option<u8>> {
+        while self.input_pos == self.input_len {
+            match self.inner.read(&mut self.input) {
+                Ok(0) => return Ok(None),
+                Ok(n) => {
+                    self.input_pos = 0;
+                    self.input_len = n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let c = self.input[self.input_pos];
+        self.input_pos += 1;
+        Ok(Some(c))
+    }
+
+    /// Decode the current group, which may be partial, into our output.
+    fn flush_group(&mut self) -> io::Result<()> {
+        let g = self.group;
+        // The bits past the last full byte must be 0, so that each encoding is unique.
+        let leftover = match self.group_len {
+            0 | 4 => 0,
+            2 => g[1] & 0b1111,
+            3 => g[2] & 0b11,
+            _ => return Err(invalid("truncated base64 group")),
+        };
+        if leftover != 0 {
+            return Err(invalid("non canonical base64"));
+        }
+        self.output = [
+            (g[0] << 2) | (g[1] >> 4),
+            (g[1] << 4) | (g[2] >> 2),
+            (g[2] << 6) | g[3],
+        ];
+        self.output_pos = 0;
+        self.output_len = self.group_len.saturating_sub(1);
+        self.group = [0; 4];
+        self.group_len = 0;
+        Ok(())
+    }
+
+    fn push(&mut self, c: u8) -> io::Result<()> {
+        if self.alphabet == Alphabet::Url && (c == b'=' || c.is_ascii_whitespace()) {
+            return Err(invalid("invalid base64 character"));
+        }
+        if c.is_ascii_whitespace() {
+            return Ok(());
+        }
+        match (c, self.padding) {
+            (b'=', None) => {
+                if self.group_len < 2 {
+                    return Err(invalid("unexpected base64 padding"));
+                }
+                self.padding = Some(3 - self.group_len);
+                self.flush_group()
+            }
+            (b'=', Some(n)) if n > 0 => {
+                self.padding = Some(n - 1);
+                Ok(())
+            }
+            (_, Some(_)) => Err(invalid("data after base64 padding")),
+            (_, None) => {
+                let value = decode_char(c, self.alphabet);
+                if value < 0 {
+                    return Err(invalid("invalid base64 character"));
+                }
+                self.group[self.group_len] = value as u8;
+                self.group_len += 1;
+                if self.group_len == 4 {
+                    self.flush_group()?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.eof = true;
+        if let Some(n) = self.padding {
+            if n > 0 {
+                return Err(invalid("truncated base64 padding"));
+            }
+        }
+        self.flush_group()
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if self.output_pos < self.output_len {
+                let n = (self.output_len - self.output_pos).min(buf.len() - written);
+                buf[written..written + n]
+                    .copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+                self.output_pos += n;
+                written += n;
+                continue;
+            }
+            // Avoid blocking on more input, if we already have something to return
+            if self.eof || (written > 0 && self.input_pos == self.input_len) {
+                break;
+            }
+            match self.next_char()? {
+                None => self.finish()?,
+                Some(c) => self.push(c)?,
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const URL_ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    /// A reader returning a single byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((&b, rest)), Some(out)) => {
+                    *out = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_alphabet() {
+        for &(alphabet, chars) in [
+            (Alphabet::Standard, ALPHABET),
+            (Alphabet::Url, URL_ALPHABET),
+        ]
+        .iter()
+        {
+            for (i, &c) in chars.iter().enumerate() {
+                assert_eq!(encode_sextet(i as u8, alphabet), c);
+                assert_eq!(decode_char(c, alphabet), i as i16);
+            }
+            for c in 0..=255u8 {
+                if !chars.contains(&c) {
+                    assert_eq!(decode_char(c, alphabet), -1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_url_variant() {
+        assert_eq!(encode_url(&[0xfb, 0xff]), "-_8");
+        assert_eq!(decode_url(b"-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(encode_url(b"foobar"), "Zm9vYmFy");
+        assert!(decode_url(b"Zg==").is_err());
+        assert!(decode_url(b"Zm9v YmFy").is_err());
+        assert!(decode_url(b"+/8").is_err());
+    }
+
+    #[test]
+    fn test_rfc_vectors() {
+        // These come from Section 10 of RFC 4648:
+        // https://datatracker.ietf.org/doc/html/rfc4648#section-10
+        let vectors: [(&[u8], &str); 7] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+        for &(data, encoded) in vectors.iter() {
+            assert_eq!(encode(data), encoded);
+            assert_eq!(decode(encoded.as_bytes()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_decode_without_padding_or_with_whitespace() {
+        assert_eq!(decode(b"Zm9vYg").unwrap(), b"foob");
+        assert_eq!(decode(b"Zm9v\r\nYmE=\n").unwrap(), b"fooba");
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        assert!(decode(b"Zm9v!").is_err());
+        assert!(decode(b"Z").is_err());
+        assert!(decode(b"Zg=").is_err());
+        assert!(decode(b"Zg==Zg==").is_err());
+        assert!(decode(b"=").is_err());
+        // The bits after the last byte must be 0
+        assert!(decode(b"Zh==").is_err());
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..5000u32).map(|i| (i * 7 + 3) as u8).collect();
+        let mut encoded = encode(&data).into_bytes();
+        // Split the encoding into lines, like armored formats do
+        for i in (64..encoded.len()).step_by(64).rev() {
+            encoded.insert(i, b'\n');
+        }
+        let mut out = Vec::new();
+        Decoder::new(Trickle(&encoded))
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+        let mut small = [0u8; 5];
+        let mut decoder = Decoder::new(&encoded[..]);
+        let mut out = Vec::new();
+        loop {
+            let n = decoder.read(&mut small).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&small[..n]);
+        }
+        assert_eq!(out, data);
+    }
+}
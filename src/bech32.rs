@@ -0,0 +1,144 @@
+//! This module implements the Bech32 encoding, as described in BIP 173:
+//! https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki
+//!
+//! This is the original checksum, not Bech32m, since that's what age uses. The 90
+//! character limit from BIP 173 isn't enforced, since age doesn't enforce it either.
+
+use alloc::{string::String, string::ToString, vec::Vec};
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ u32::from(v);
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn expand_hrp(hrp: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    hrp.iter()
+        .map(|c| c >> 5)
+        .chain(core::iter::once(0))
+        .chain(hrp.iter().map(|c| c & 31))
+}
+
+/// Regroup bits, from groups of `from` bits, into groups of `to` bits.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &x in data {
+        acc = (acc << from) | u32::from(x);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & ((1 << to) - 1)) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & ((1 << to) - 1)) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & ((1 << to) - 1) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+/// Encode some bytes, with a human readable part, which should be lowercase.
+pub fn encode(hrp: &str, data: &[u8]) -> String {
+    let values = convert_bits(data, 8, 5, true).unwrap();
+    let checksum = polymod(
+        expand_hrp(hrp.as_bytes())
+            .chain(values.iter().copied())
+            .chain([0; 6].iter().copied()),
+    ) ^ 1;
+    let mut out = String::with_capacity(hrp.len() + 1 + values.len() + 6);
+    out.push_str(hrp);
+    out.push('1');
+    for v in values {
+        out.push(char::from(CHARSET[v as usize]));
+    }
+    for i in 0..6 {
+        out.push(char::from(
+            CHARSET[((checksum >> (5 * (5 - i))) & 31) as usize],
+        ));
+    }
+    out
+}
+
+/// Decode a string, returning its lowercase human readable part, and its bytes.
+///
+/// Strings mixing uppercase and lowercase are rejected.
+pub fn decode(input: &str) -> Option<(String, Vec<u8>)> {
+    if !input.is_ascii() {
+        return None;
+    }
+    let has_lower = input.bytes().any(|c| c.is_ascii_lowercase());
+    let has_upper = input.bytes().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return None;
+    }
+    let input = input.to_ascii_lowercase();
+    let split = input.rfind('1')?;
+    let (hrp, data) = (&input[..split], &input.as_bytes()[split + 1..]);
+    if hrp.is_empty() || data.len() < 6 || hrp.bytes().any(|c| !(33..=126).contains(&c)) {
+        return None;
+    }
+    let values = data
+        .iter()
+        .map(|&c| CHARSET.iter().position(|&x| x == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    if polymod(expand_hrp(hrp.as_bytes()).chain(values.iter().copied())) != 1 {
+        return None;
+    }
+    let bytes = convert_bits(&values[..values.len() - 6], 5, 8, false)?;
+    Some((hrp.to_string(), bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bip_173_checksums() {
+        for valid in [
+            "A12UEL5L",
+            "a12uel5l",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        ] {
+            assert!(decode(valid).is_some(), "{}", valid);
+        }
+        for invalid in [
+            "pzry9x0s0muk",
+            "1pzry9x0s0muk",
+            "x1b4n0q5v",
+            "li1dgmt3",
+            "A1G7SgD8",
+        ] {
+            assert!(decode(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (hrp, data) = decode("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw").unwrap();
+        assert_eq!(hrp, "abcdef");
+        assert_eq!(
+            encode(&hrp, &data),
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw"
+        );
+        let encoded = encode("age", &[0xAB; 32]);
+        assert_eq!(decode(&encoded), Some(("age".to_string(), vec![0xAB; 32])));
+    }
+}
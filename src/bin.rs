@@ -1,28 +1,65 @@
 use eddo::{gen_keypair, PrivateKey, PublicKey, Signature};
 use rand::rngs::OsRng;
 use std::fs::{self, File};
-use std::io::{self, BufReader};
-use std::io::{BufRead, Write};
+use std::io;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use structopt::StructOpt;
 
 extern crate hex;
 extern crate structopt;
 
+/// The format keys get written in.
+///
+/// Reading a key file accepts either format, regardless of this.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    /// Hex, with a prefix identifying the kind of key
+    Hex,
+    /// PEM, containing PKCS#8, or SubjectPublicKeyInfo, as in RFC 8410
+    Pem,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(Format::Hex),
+            "pem" => Ok(Format::Pem),
+            _ => Err(format!("unknown format {:?}, expected hex or pem", s)),
+        }
+    }
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "eddo")]
 enum Args {
     /// Generate a new keypair
     ///
     /// The public key will be printed out, the private key will be saved to a file
-    Generate {
+    #[structopt(alias = "generate")]
+    Keygen {
         /// The file to write the private key into
         #[structopt(short = "o", long = "out", parse(from_os_str))]
         out_file: PathBuf,
+        /// The format to use for both keys, either hex or pem
+        #[structopt(short = "f", long = "format", default_value = "hex")]
+        format: Format,
+    },
+    /// Print the public key corresponding to a private key file
+    Pubkey {
+        /// A path to your private key file
+        #[structopt(short = "k", long = "key", parse(from_os_str))]
+        key_file: PathBuf,
+        /// The format to print the public key in, either hex or pem
+        #[structopt(short = "f", long = "format", default_value = "hex")]
+        format: Format,
     },
     /// Verify a signature for a file, by a given public key
     Verify {
-        /// The public key used to sign this file
+        /// The public key used to sign this file, or a path to a file containing it
         #[structopt(short = "p", long = "public")]
         public: String,
         /// The signature for this file
@@ -49,12 +86,15 @@ enum AppError {
     /// A parse error, with a string for information.
     ///
     /// This could probably be improved further.
+    #[allow(dead_code)]
     ParseError(String),
     /// An error that occurrs when a signature check fails
     FailedSignature,
     /// An error that happened while doing IO of some kind
+    #[allow(dead_code)]
     IO(io::Error),
     /// An error that happened while doing hex decoding
+    #[allow(dead_code)]
     HexError(hex::FromHexError),
 }
 
@@ -70,13 +110,19 @@ impl From<hex::FromHexError> for AppError {
     }
 }
 
+impl From<eddo::Error> for AppError {
+    fn from(err: eddo::Error) -> Self {
+        AppError::ParseError(err.to_string())
+    }
+}
+
 /// The type of result produced our application
 type AppResult<T> = Result<T, AppError>;
 
 fn decode_prefixed_hex<const N: usize>(prefix: &str, input: &str) -> AppResult<[u8; N]> {
     let just_hex = input
         .strip_prefix(prefix)
-        .ok_or(AppError::ParseError("incorrect prefix".into()))?;
+        .ok_or_else(|| AppError::ParseError("incorrect prefix".into()))?;
     if just_hex.len() != 2 * N {
         return Err(AppError::ParseError("incorrect size".into()));
     }
@@ -85,31 +131,54 @@ fn decode_prefixed_hex<const N: usize>(prefix: &str, input: &str) -> AppResult<[
     Ok(bytes)
 }
 
-const PUBLIC_KEY_PREFIX: &'static str = "エッドの公開鍵";
+/// Find the first line of a key file which isn't a comment.
+fn first_line(contents: &str) -> AppResult<&str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| AppError::ParseError("no key in file".into()))
+}
+
+const PUBLIC_KEY_PREFIX: &str = "エッドの公開鍵";
 
-fn format_public_key(public: PublicKey) -> String {
-    format!("{}{}", PUBLIC_KEY_PREFIX, hex::encode(public.bytes))
+fn format_public_key(public: &PublicKey, format: Format) -> String {
+    match format {
+        Format::Hex => format!("{}{}", PUBLIC_KEY_PREFIX, hex::encode(public.as_bytes())),
+        Format::Pem => public.to_public_key_pem(),
+    }
 }
 
 fn decode_public_key(input: &str) -> AppResult<PublicKey> {
-    Ok(PublicKey {
-        bytes: decode_prefixed_hex(PUBLIC_KEY_PREFIX, input)?,
-    })
+    if input.trim_start().starts_with("-----BEGIN") {
+        return Ok(PublicKey::from_public_key_pem(input)?);
+    }
+    let bytes = decode_prefixed_hex(PUBLIC_KEY_PREFIX, first_line(input)?)?;
+    Ok(PublicKey::from_bytes(bytes)?)
 }
 
-const PRIVATE_KEY_PREFIX: &'static str = "エッドの秘密鍵";
+const PRIVATE_KEY_PREFIX: &str = "エッドの秘密鍵";
 
-fn format_private_key(private: PrivateKey) -> String {
-    format!("{}{}", PRIVATE_KEY_PREFIX, hex::encode(private.bytes))
+fn format_private_key(private: &PrivateKey, format: Format) -> String {
+    match format {
+        Format::Hex => format!("{}{}", PRIVATE_KEY_PREFIX, hex::encode(private.as_bytes())),
+        Format::Pem => private.to_pkcs8_pem(),
+    }
 }
 
 fn decode_private_key(input: &str) -> AppResult<PrivateKey> {
-    Ok(PrivateKey {
-        bytes: decode_prefixed_hex(PRIVATE_KEY_PREFIX, input)?,
-    })
+    if input.trim_start().starts_with("-----BEGIN") {
+        return Ok(PrivateKey::from_pkcs8_pem(input)?);
+    }
+    let bytes = decode_prefixed_hex(PRIVATE_KEY_PREFIX, first_line(input)?)?;
+    Ok(PrivateKey::from_bytes(bytes))
 }
 
-const SIGNATURE_PREFIX: &'static str = "エッドの署名";
+fn read_private_key(key_path: &Path) -> AppResult<PrivateKey> {
+    decode_private_key(&fs::read_to_string(key_path)?)
+}
+
+const SIGNATURE_PREFIX: &str = "エッドの署名";
 
 fn format_signature(signature: Signature) -> String {
     format!("{}{}", SIGNATURE_PREFIX, hex::encode(signature.bytes))
@@ -121,29 +190,31 @@ fn decode_signature(input: &str) -> AppResult<Signature> {
     })
 }
 
-fn generate(out_path: &Path) -> AppResult<()> {
+fn keygen(out_path: &Path, format: Format) -> AppResult<()> {
     let (public, private) = gen_keypair(&mut OsRng);
-    let formatted_public = format_public_key(public);
-    let formatted_private = format_private_key(private);
+    let formatted_public = format_public_key(&public, format);
     let mut out_file = File::create(out_path)?;
-    writeln!(out_file, "# Public Key: {}", formatted_public)?;
-    writeln!(out_file, "{}", formatted_private)?;
+    if let Format::Hex = format {
+        writeln!(out_file, "# Public Key: {}", formatted_public)?;
+    }
+    writeln!(
+        out_file,
+        "{}",
+        format_private_key(&private, format).trim_end()
+    )?;
+    println!("{}", formatted_public.trim_end());
+    Ok(())
+}
+
+fn pubkey(key_path: &Path, format: Format) -> AppResult<()> {
+    let private = read_private_key(key_path)?;
+    let public = private.into_signing_only().public_key();
+    println!("{}", format_public_key(&public, format).trim_end());
     Ok(())
 }
 
 fn sign(key_path: &Path, in_path: &Path) -> AppResult<()> {
-    let key_file = File::open(key_path)?;
-    let key_reader = BufReader::new(key_file);
-    let mut maybe_private = None;
-    for maybe_line in key_reader.lines() {
-        let line = maybe_line?;
-        if line.starts_with("#") {
-            continue;
-        }
-        maybe_private = Some(decode_private_key(&line)?);
-        break;
-    }
-    let private = maybe_private.ok_or(AppError::ParseError("no private key in file".into()))?;
+    let private = read_private_key(key_path)?;
     let in_data = fs::read(in_path)?;
     let sig = private.sign(&in_data);
     println!("{}", format_signature(sig));
@@ -162,15 +233,21 @@ fn verify(public: PublicKey, signature: Signature, in_path: &Path) -> AppResult<
 fn main() -> AppResult<()> {
     let args = Args::from_args();
     match args {
-        Args::Generate { out_file } => generate(&out_file),
-        Args::Sign { key_file, in_file } => sign(&key_file, &&in_file),
+        Args::Keygen { out_file, format } => keygen(&out_file, format),
+        Args::Pubkey { key_file, format } => pubkey(&key_file, format),
+        Args::Sign { key_file, in_file } => sign(&key_file, &in_file),
         Args::Verify {
             public,
             signature,
             in_file,
         } => {
-            let public_key = decode_public_key(&public)?;
-            let decoded_signature = decode_signature(&signature)?;
+            // The key is either given directly, or through a file.
+            let public_key = if public.starts_with(PUBLIC_KEY_PREFIX) {
+                decode_public_key(&public)?
+            } else {
+                decode_public_key(&fs::read_to_string(&public)?)?
+            };
+            let decoded_signature = decode_signature(signature.trim())?;
             verify(public_key, decoded_signature, &in_file)
         }
     }
@@ -0,0 +1,194 @@
+//! This module implements the BLAKE2b hash function, as described in RFC 7693:
+//! https://datatracker.ietf.org/doc/html/rfc7693
+//!
+//! This is only needed for interoperability, e.g. with minisign, which prehashes messages
+//! with it. Keyed hashing isn't supported.
+
+use core::convert::TryInto;
+
+const BLOCK_SIZE: usize = 128;
+/// The largest output BLAKE2b can produce.
+pub const MAX_HASH_SIZE: usize = 64;
+
+/// The initialization vector, from Section 2.6, which is the same as SHA-512's.
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The message word permutations, from Section 2.7.
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The mixing function G, from Section 3.1.
+fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The compression function F, from Section 3.2.
+fn compress(h: &mut [u64; 8], block: &[u8; BLOCK_SIZE], t: u128, last: bool) {
+    let mut m = [0; 16];
+    for (word, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t as u64;
+    v[13] ^= (t >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+    for i in 0..12 {
+        let s = &SIGMA[i % 10];
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// An incremental BLAKE2b computation, with a fixed output size.
+#[derive(Clone)]
+pub struct Blake2b {
+    h: [u64; 8],
+    t: u128,
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    hash_size: usize,
+}
+
+impl Blake2b {
+    /// Start a computation producing `hash_size` bytes, which has to be in 1..=64.
+    pub fn new(hash_size: usize) -> Self {
+        assert!((1..=MAX_HASH_SIZE).contains(&hash_size));
+        let mut h = IV;
+        // The parameter block, with no key, in sequential mode.
+        h[0] ^= 0x01010000 ^ hash_size as u64;
+        Blake2b {
+            h,
+            t: 0,
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            hash_size,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // The last block has to be compressed differently, so we only
+            // compress a full buffer once we know more data follows.
+            if self.buffer_len == BLOCK_SIZE {
+                self.t += BLOCK_SIZE as u128;
+                compress(&mut self.h, &self.buffer, self.t, false);
+                self.buffer_len = 0;
+            }
+            let take = data.len().min(BLOCK_SIZE - self.buffer_len);
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+        }
+    }
+
+    /// Finish the computation, filling `out`, which has to be as large as the hash.
+    pub fn finalize(mut self, out: &mut [u8]) {
+        assert_eq!(out.len(), self.hash_size);
+        self.t += self.buffer_len as u128;
+        for b in &mut self.buffer[self.buffer_len..] {
+            *b = 0;
+        }
+        compress(&mut self.h, &self.buffer, self.t, true);
+        for (chunk, word) in out.chunks_mut(8).zip(self.h.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+/// Calculate BLAKE2b over a message, with an output as large as `out`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub fn hash(message: &[u8], out: &mut [u8]) {
+    let mut hasher = Blake2b::new(out.len());
+    hasher.update(message);
+    hasher.finalize(out);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        // From Appendix A
+        let mut expected = [0; 64];
+        let mut actual = [0; 64];
+        hex::decode_to_slice(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            &mut expected,
+        )
+        .unwrap();
+        hash(b"abc", &mut actual);
+        assert_eq!(actual[..], expected[..]);
+
+        let mut expected = [0; 32];
+        let mut actual = [0; 32];
+        hex::decode_to_slice(
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+            &mut expected,
+        )
+        .unwrap();
+        hash(b"", &mut actual);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_incremental_matches_one_shot() {
+        // Exactly two blocks, making sure the last one is flagged properly
+        let message: Vec<u8> = (0..256).map(|i| i as u8).collect();
+        let mut expected = [0; 64];
+        hash(&message, &mut expected);
+
+        let mut hasher = Blake2b::new(64);
+        for chunk in message.chunks(37) {
+            hasher.update(chunk);
+        }
+        let mut actual = [0; 64];
+        hasher.finalize(&mut actual);
+        assert_eq!(actual[..], expected[..]);
+        hex::decode_to_slice(
+            "1ecc896f34d3f9cac484c73f75f6a5fb58ee6784be41b35f46067b9c65c63a6794d3d744112c653f73dd7deb6666204c5a9bfa5b46081fc10fdbe7884fa5cbf8",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(actual[..], expected[..]);
+    }
+}
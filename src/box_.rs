@@ -0,0 +1,314 @@
+//! This module implements NaCl's crypto_box, as found in libsodium:
+//! https://nacl.cr.yp.to/box.html
+//! https://doc.libsodium.org/public-key_cryptography/authenticated_encryption
+//!
+//! A box combines X25519 with XSalsa20-Poly1305. The shared secret goes through HSalsa20,
+//! producing a key that encrypts with Salsa20, and authenticates with Poly1305, using
+//! the first 32 bytes of the keystream as the one-time Poly1305 key.
+//!
+//! Boxes use the "easy" format, with the 16 byte tag placed before the ciphertext,
+//! so boxes made here can be opened with `crypto_box_open_easy`, and vice versa.
+//! Nonces must never be reused with the same pair of keys; random nonces are fine.
+//!
+//! Sealed boxes, like `crypto_box_seal`, encrypt to a public key anonymously, using an
+//! ephemeral key pair, and taking the nonce from BLAKE2b of both public keys:
+//! https://doc.libsodium.org/public-key_cryptography/sealed_boxes
+
+use alloc::{vec, vec::Vec};
+use core::convert::TryInto;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::{blake2b::Blake2b, poly1305, salsa20, x25519, zeroize::Zeroizing, Error};
+
+/// The size of a public key, an X25519 u coordinate.
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// The size of a secret key, a raw X25519 scalar.
+pub const SECRET_KEY_SIZE: usize = 32;
+
+/// The size of the nonce used for each box.
+pub const NONCE_SIZE: usize = salsa20::NONCE_SIZE;
+
+/// How many bytes boxing adds to a message.
+pub const MAC_SIZE: usize = poly1305::TAG_SIZE;
+
+/// The size of a precomputed shared key, from `beforenm`.
+pub const BEFORENM_SIZE: usize = salsa20::KEY_SIZE;
+
+/// How many bytes sealing adds to a message: an ephemeral public key, and a tag.
+pub const SEAL_OVERHEAD: usize = PUBLIC_KEY_SIZE + MAC_SIZE;
+
+/// Calculate the public key corresponding to a secret key.
+pub fn public_key(secret: &[u8; SECRET_KEY_SIZE]) -> [u8; PUBLIC_KEY_SIZE] {
+    let mut base = [0; 32];
+    base[0] = 9;
+    x25519(*secret, base)
+}
+
+/// Generate a new secret key, along with its public key, returned first.
+pub fn keypair<R: RngCore + CryptoRng>(
+    rng: &mut R,
+) -> ([u8; PUBLIC_KEY_SIZE], [u8; SECRET_KEY_SIZE]) {
+    let mut secret = [0; SECRET_KEY_SIZE];
+    rng.fill_bytes(&mut secret);
+    (public_key(&secret), secret)
+}
+
+/// Precompute the key shared between two parties, to speed up boxing many messages.
+///
+/// Like libsodium, this fails if their public key has small order.
+pub fn beforenm(
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+    our_secret: &[u8; SECRET_KEY_SIZE],
+) -> Result<[u8; BEFORENM_SIZE], Error> {
+    let shared = Zeroizing::new(x25519(*our_secret, *their_public));
+    if bool::from(shared.ct_eq(&[0; 32])) {
+        return Err(Error::WeakPublicKey);
+    }
+    Ok(salsa20::hsalsa20(&shared, &[0; 16]))
+}
+
+/// Box a message, with a key precomputed with `beforenm`.
+pub fn seal_afternm(
+    message: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    key: &[u8; BEFORENM_SIZE],
+) -> Vec<u8> {
+    // The first 32 bytes of the keystream end up as the Poly1305 key.
+    let mut data = vec![0; 32 + message.len()];
+    data[32..].copy_from_slice(message);
+    salsa20::xsalsa20_xor(key, nonce, &mut data);
+    let mac_key = Zeroizing::new(data[..32].try_into().unwrap());
+    let tag = poly1305::mac(&mac_key, &data[32..]);
+    data[16..32].copy_from_slice(&tag);
+    data.drain(..16);
+    data
+}
+
+/// Open a box, with a key precomputed with `beforenm`.
+///
+/// This fails, without decrypting anything, if the box has been tampered with.
+pub fn open_afternm(
+    boxed: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    key: &[u8; BEFORENM_SIZE],
+) -> Result<Vec<u8>, Error> {
+    if boxed.len() < MAC_SIZE {
+        return Err(Error::InvalidLength);
+    }
+    let mut mac_key = Zeroizing::new([0; 32]);
+    salsa20::xsalsa20_xor(key, nonce, &mut *mac_key);
+    let tag: [u8; MAC_SIZE] = boxed[..MAC_SIZE].try_into().unwrap();
+    if !poly1305::verify(&mac_key, &boxed[MAC_SIZE..], &tag) {
+        return Err(Error::DecryptionFailed);
+    }
+    let mut data = vec![0; 32 + boxed.len() - MAC_SIZE];
+    data[32..].copy_from_slice(&boxed[MAC_SIZE..]);
+    salsa20::xsalsa20_xor(key, nonce, &mut data);
+    data.drain(..32);
+    Ok(data)
+}
+
+/// Box a message from us, to them, like `crypto_box_easy`.
+pub fn seal(
+    message: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+    our_secret: &[u8; SECRET_KEY_SIZE],
+) -> Result<Vec<u8>, Error> {
+    let key = Zeroizing::new(beforenm(their_public, our_secret)?);
+    Ok(seal_afternm(message, nonce, &key))
+}
+
+/// Open a box sent from them, to us, like `crypto_box_open_easy`.
+pub fn open(
+    boxed: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+    our_secret: &[u8; SECRET_KEY_SIZE],
+) -> Result<Vec<u8>, Error> {
+    let key = Zeroizing::new(beforenm(their_public, our_secret)?);
+    open_afternm(boxed, nonce, &key)
+}
+
+/// Derive the nonce for a sealed box, as BLAKE2b-192(ephemeral public key || their public key).
+fn seal_nonce(
+    ephemeral: &[u8; PUBLIC_KEY_SIZE],
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+) -> [u8; NONCE_SIZE] {
+    let mut hasher = Blake2b::new(NONCE_SIZE);
+    hasher.update(ephemeral);
+    hasher.update(their_public);
+    let mut out = [0; NONCE_SIZE];
+    hasher.finalize(&mut out);
+    out
+}
+
+fn seal_with_ephemeral(
+    message: &[u8],
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+    ephemeral_secret: &[u8; SECRET_KEY_SIZE],
+) -> Result<Vec<u8>, Error> {
+    let ephemeral = public_key(ephemeral_secret);
+    let nonce = seal_nonce(&ephemeral, their_public);
+    let mut out = ephemeral.to_vec();
+    out.extend_from_slice(&seal(message, &nonce, their_public, ephemeral_secret)?);
+    Ok(out)
+}
+
+/// Encrypt a message to a public key, anonymously, like `crypto_box_seal`.
+///
+/// The recipient can't tell who sent the message, and the sender can't decrypt it
+/// after the fact, since the ephemeral secret key is discarded.
+pub fn seal_anonymous<R: RngCore + CryptoRng>(
+    message: &[u8],
+    their_public: &[u8; PUBLIC_KEY_SIZE],
+    rng: &mut R,
+) -> Result<Vec<u8>, Error> {
+    let ephemeral_secret = Zeroizing::new(keypair(rng).1);
+    seal_with_ephemeral(message, their_public, &ephemeral_secret)
+}
+
+/// Open a sealed box sent to us, like `crypto_box_seal_open`.
+pub fn open_anonymous(
+    sealed: &[u8],
+    our_public: &[u8; PUBLIC_KEY_SIZE],
+    our_secret: &[u8; SECRET_KEY_SIZE],
+) -> Result<Vec<u8>, Error> {
+    if sealed.len() < SEAL_OVERHEAD {
+        return Err(Error::InvalidLength);
+    }
+    let ephemeral: [u8; PUBLIC_KEY_SIZE] = sealed[..PUBLIC_KEY_SIZE].try_into().unwrap();
+    let nonce = seal_nonce(&ephemeral, our_public);
+    open(&sealed[PUBLIC_KEY_SIZE..], &nonce, &ephemeral, our_secret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    fn decode<const N: usize>(data: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(data, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_nacl_vector() {
+        // From tests/box.c in NaCl, which uses the keys from RFC 7748.
+        let alice_secret =
+            decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob_public = decode("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+        let nonce = decode("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37");
+        let message = hex::decode(concat!(
+            "be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffc",
+            "e5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb31",
+            "0e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde",
+            "048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f93776384864",
+            "5e0705"
+        ))
+        .unwrap();
+        let expected = concat!(
+            "f3ffc7703f9400e52a7dfb4b3d3305d9",
+            "8e993b9f48681273c29650ba32fc76ce48332ea7164d96a4476fb8c531a1186a",
+            "c0dfc17c98dce87b4da7f011ec48c97271d2c20f9b928fe2270d6fb863d51738",
+            "b48eeee314a7cc8ab932164548e526ae90224368517acfeabd6bb3732bc0e9da",
+            "99832b61ca01b6de56244a9e88d5f9b37973f622a43d14a6599b1f654cb45a74",
+            "e355a5"
+        );
+        let boxed = seal(&message, &nonce, &bob_public, &alice_secret).unwrap();
+        assert_eq!(hex::encode(&boxed), expected);
+        let bob_secret = decode("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_public = public_key(&alice_secret);
+        assert_eq!(
+            open(&boxed, &nonce, &alice_public, &bob_secret).unwrap(),
+            message
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_and_tampering() {
+        let (alice_public, alice_secret) = keypair(&mut OsRng);
+        let (bob_public, bob_secret) = keypair(&mut OsRng);
+        let nonce = [3; NONCE_SIZE];
+        let mut boxed = seal(b"hello", &nonce, &bob_public, &alice_secret).unwrap();
+        assert_eq!(boxed.len(), 5 + MAC_SIZE);
+        let key = beforenm(&alice_public, &bob_secret).unwrap();
+        assert_eq!(open_afternm(&boxed, &nonce, &key).unwrap(), b"hello");
+        assert!(open_afternm(&seal_afternm(b"", &nonce, &key), &nonce, &key)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            open_afternm(&boxed, &[4; NONCE_SIZE], &key),
+            Err(Error::DecryptionFailed)
+        );
+        boxed[MAC_SIZE] ^= 1;
+        assert_eq!(
+            open_afternm(&boxed, &nonce, &key),
+            Err(Error::DecryptionFailed)
+        );
+        assert_eq!(
+            open_afternm(&boxed[..15], &nonce, &key),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            beforenm(&[0; PUBLIC_KEY_SIZE], &bob_secret),
+            Err(Error::WeakPublicKey)
+        );
+    }
+
+    #[test]
+    fn test_sealed_box() {
+        // From tests/lib.rs in version 0.9.1 of the crypto_box crate, whose sealed box
+        // matches crypto_box_seal in libsodium: https://github.com/RustCrypto/nacl-compat
+        let secret = decode("15b36cb00213373fb3fb03958fb0cc0012ecaca112fd249d3cf0961e311caac9");
+        let public = public_key(&secret);
+        assert_eq!(
+            hex::encode(public),
+            "fb4cb34f74a928b79123333c1e63d991060244cda98affee14c3398c6d315574"
+        );
+        let sealed = hex::decode(concat!(
+            "95eb5bf05ada25ee51f4158201c261a00bfb1955a9176c8c7f1a62f299a32e54",
+            "f6ebccc8ab9d2ce1b1d3710ba37d8db17aeeec0b78fc3d32b39b79ed96f18948",
+            "c5a574b8e3f8eccc2f132408c21646f3aedae4a67fde4f77153b5458b8a6bd71",
+            "2dd8365534c567ec"
+        ))
+        .unwrap();
+        assert_eq!(
+            open_anonymous(&sealed, &public, &secret).unwrap(),
+            b"Lorem ipsum dolor sit amet, consectetur adipiscing elit."
+        );
+
+        // The box gets prefixed with the public key of the ephemeral secret.
+        let bob_secret = decode("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let bob_public = public_key(&bob_secret);
+        let ephemeral_secret =
+            decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let sealed =
+            seal_with_ephemeral(b"sealed with a kiss", &bob_public, &ephemeral_secret).unwrap();
+        assert_eq!(sealed[..PUBLIC_KEY_SIZE], public_key(&ephemeral_secret));
+        assert_eq!(
+            open_anonymous(&sealed, &bob_public, &bob_secret).unwrap(),
+            b"sealed with a kiss"
+        );
+
+        let sealed = seal_anonymous(b"", &bob_public, &mut OsRng).unwrap();
+        assert_eq!(sealed.len(), SEAL_OVERHEAD);
+        assert!(open_anonymous(&sealed, &bob_public, &bob_secret)
+            .unwrap()
+            .is_empty());
+        let (other_public, other_secret) = keypair(&mut OsRng);
+        assert_eq!(
+            open_anonymous(&sealed, &other_public, &other_secret),
+            Err(Error::DecryptionFailed)
+        );
+        assert_eq!(
+            open_anonymous(&sealed[1..], &bob_public, &bob_secret),
+            Err(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! This module implements the subset of CBOR, as described in RFC 8949, needed for COSE:
+//! https://datatracker.ietf.org/doc/html/rfc8949
+//!
+//! Encoding is always deterministic, following Section 4.2.1. Decoding only accepts
+//! definite lengths in their shortest form, and no floating point numbers.
+
+use crate::Error;
+use alloc::{boxed::Box, string::String, string::ToString, vec::Vec};
+
+/// How deeply arrays, maps, and tags may be nested, which keeps recursion bounded.
+const MAX_DEPTH: usize = 16;
+
+/// A decoded CBOR data item.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Both unsigned and negative integers, which fit in major types 0 and 1.
+    Int(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Bool(bool),
+    Null,
+}
+
+fn encode_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    if arg < 24 {
+        out.push(major | arg as u8);
+    } else if arg <= 0xFF {
+        out.push(major | 24);
+        out.push(arg as u8);
+    } else if arg <= 0xFFFF {
+        out.push(major | 25);
+        out.extend_from_slice(&(arg as u16).to_be_bytes());
+    } else if arg <= 0xFFFF_FFFF {
+        out.push(major | 26);
+        out.extend_from_slice(&(arg as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+}
+
+fn encode_into(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Int(x) if *x >= 0 => encode_head(out, 0, *x as u64),
+        Value::Int(x) => encode_head(out, 1, (-1 - *x) as u64),
+        Value::Bytes(x) => {
+            encode_head(out, 2, x.len() as u64);
+            out.extend_from_slice(x);
+        }
+        Value::Text(x) => {
+            encode_head(out, 3, x.len() as u64);
+            out.extend_from_slice(x.as_bytes());
+        }
+        Value::Array(items) => {
+            encode_head(out, 4, items.len() as u64);
+            for item in items {
+                encode_into(out, item);
+            }
+        }
+        Value::Map(entries) => {
+            // Deterministic encoding sorts entries by the bytes of their keys.
+            let mut encoded: Vec<(Vec<u8>, Vec<u8>)> = entries
+                .iter()
+                .map(|(k, v)| (encode(k), encode(v)))
+                .collect();
+            encoded.sort();
+            encode_head(out, 5, encoded.len() as u64);
+            for (k, v) in encoded {
+                out.extend_from_slice(&k);
+                out.extend_from_slice(&v);
+            }
+        }
+        Value::Tag(tag, inner) => {
+            encode_head(out, 6, *tag);
+            encode_into(out, inner);
+        }
+        Value::Bool(false) => out.push(0xF4),
+        Value::Bool(true) => out.push(0xF5),
+        Value::Null => out.push(0xF6),
+    }
+}
+
+/// Encode a value, deterministically.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into(&mut out, value);
+    out
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.input.len() < n {
+            return Err(Error::InvalidEncoding);
+        }
+        let (x, rest) = self.input.split_at(n);
+        self.input = rest;
+        Ok(x)
+    }
+
+    /// Read the head of an item, returning its major type, and argument.
+    fn head(&mut self) -> Result<(u8, u64), Error> {
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1F);
+        let (arg, min) = match info {
+            0..=23 => return Ok((major, u64::from(info))),
+            24 => (u64::from(self.take(1)?[0]), 24),
+            25 => {
+                let mut bytes = [0; 2];
+                bytes.copy_from_slice(self.take(2)?);
+                (u64::from(u16::from_be_bytes(bytes)), 0x100)
+            }
+            26 => {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(self.take(4)?);
+                (u64::from(u32::from_be_bytes(bytes)), 0x1_0000)
+            }
+            27 => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                (u64::from_be_bytes(bytes), 0x1_0000_0000)
+            }
+            // Reserved values, and indefinite lengths
+            _ => return Err(Error::InvalidEncoding),
+        };
+        // Simple values and floats use the argument differently, and we reject them anyways.
+        if arg < min || major == 7 {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok((major, arg))
+    }
+
+    fn len(&mut self, arg: u64) -> Result<usize, Error> {
+        // Every item takes at least a byte, which bounds the allocations we make.
+        if arg > self.input.len() as u64 {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(arg as usize)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidEncoding);
+        }
+        let (major, arg) = self.head()?;
+        Ok(match major {
+            0 => Value::Int(i128::from(arg)),
+            1 => Value::Int(-1 - i128::from(arg)),
+            2 => {
+                let len = self.len(arg)?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.len(arg)?;
+                let text =
+                    core::str::from_utf8(self.take(len)?).map_err(|_| Error::InvalidEncoding)?;
+                Value::Text(text.to_string())
+            }
+            4 => {
+                let len = self.len(arg)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            5 => {
+                let len = self.len(arg)?;
+                let mut entries: Vec<(Value, Value)> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let k = self.value(depth + 1)?;
+                    let v = self.value(depth + 1)?;
+                    if entries.iter().any(|(other, _)| *other == k) {
+                        return Err(Error::InvalidEncoding);
+                    }
+                    entries.push((k, v));
+                }
+                Value::Map(entries)
+            }
+            6 => Value::Tag(arg, Box::new(self.value(depth + 1)?)),
+            _ => match arg {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 => Value::Null,
+                _ => return Err(Error::InvalidEncoding),
+            },
+        })
+    }
+}
+
+/// Decode a single value, which has to take up all of the input.
+pub fn decode(input: &[u8]) -> Result<Value, Error> {
+    let mut decoder = Decoder { input };
+    let out = decoder.value(0)?;
+    if !decoder.input.is_empty() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(out)
+}
+
+impl Value {
+    /// Look up an entry of a map by an integer key, returning `None` if it's missing.
+    pub fn get(&self, key: i128) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == Value::Int(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rfc_examples() {
+        // From Appendix A
+        let examples = vec![
+            (Value::Int(0), "00"),
+            (Value::Int(24), "1818"),
+            (Value::Int(1000000), "1a000f4240"),
+            (Value::Int(-1000), "3903e7"),
+            (Value::Int(18446744073709551615), "1bffffffffffffffff"),
+            (Value::Int(-18446744073709551616), "3bffffffffffffffff"),
+            (Value::Text("\u{fc}".to_string()), "62c3bc"),
+            (Value::Bytes(vec![1, 2, 3, 4]), "4401020304"),
+            (
+                Value::Array(vec![
+                    Value::Int(1),
+                    Value::Array(vec![Value::Int(2), Value::Int(3)]),
+                ]),
+                "8201820203",
+            ),
+            (
+                Value::Map(vec![
+                    (Value::Text("a".to_string()), Value::Int(1)),
+                    (Value::Text("b".to_string()), Value::Bool(false)),
+                ]),
+                "a26161016162f4",
+            ),
+            (
+                Value::Tag(1, Box::new(Value::Int(1363896240))),
+                "c11a514b67b0",
+            ),
+            (Value::Null, "f6"),
+        ];
+        for (value, encoded) in examples.iter() {
+            assert_eq!(hex::encode(encode(value)), *encoded);
+            assert_eq!(decode(&hex::decode(encoded).unwrap()).as_ref(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_deterministic_map_order() {
+        // Shorter keys come first, then keys get compared bytewise.
+        let map = Value::Map(vec![
+            (Value::Int(-1), Value::Int(0)),
+            (Value::Int(100), Value::Int(0)),
+            (Value::Int(1), Value::Int(0)),
+        ]);
+        assert_eq!(hex::encode(encode(&map)), "a301001864002000");
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        for input in [
+            // Non minimal lengths
+            "1817",
+            "190017",
+            // Indefinite lengths
+            "5f4101ff",
+            // Floats
+            "f93c00",
+            // Duplicate keys
+            "a201000100",
+            // Truncated input
+            "4401",
+            // Trailing data
+            "0000",
+        ]
+        .iter()
+        {
+            assert!(decode(&hex::decode(input).unwrap()).is_err(), "{}", input);
+        }
+    }
+}
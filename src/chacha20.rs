@@ -0,0 +1,172 @@
+//! This module implements the ChaCha20-Poly1305 AEAD, as described in RFC 8439:
+//! https://datatracker.ietf.org/doc/html/rfc8439
+//!
+//! This uses the IETF variant of ChaCha20, with a 96 bit nonce, and a 32 bit counter.
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use subtle::ConstantTimeEq;
+
+use crate::poly1305;
+use crate::zeroize::Zeroizing;
+
+/// The size of a ChaCha20 key.
+pub const KEY_SIZE: usize = 32;
+
+/// The size of a ChaCha20 nonce.
+pub const NONCE_SIZE: usize = 12;
+
+/// The size of the tag appended to each ciphertext.
+pub const TAG_SIZE: usize = poly1305::TAG_SIZE;
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+/// Calculate a block of keystream, following Section 2.3.
+fn block(key: &[u8; KEY_SIZE], counter: u32, nonce: &[u8; NONCE_SIZE]) -> [u8; 64] {
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+    let mut state = Zeroizing::new([0; 16]);
+    state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    for i in 0..8 {
+        state[4 + i] = word(&key[4 * i..4 * i + 4]);
+    }
+    state[12] = counter;
+    for i in 0..3 {
+        state[13 + i] = word(&nonce[4 * i..4 * i + 4]);
+    }
+    let mut x = Zeroizing::new(*state);
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    let mut out = [0; 64];
+    for i in 0..16 {
+        out[4 * i..4 * i + 4].copy_from_slice(&x[i].wrapping_add(state[i]).to_le_bytes());
+    }
+    out
+}
+
+/// XOR data, in place, with the keystream, starting from some block counter.
+fn xor(key: &[u8; KEY_SIZE], counter: u32, nonce: &[u8; NONCE_SIZE], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let stream = Zeroizing::new(block(key, counter.wrapping_add(i as u32), nonce));
+        for (byte, s) in chunk.iter_mut().zip(stream.iter()) {
+            *byte ^= s;
+        }
+    }
+}
+
+/// Calculate the tag over the associated data, and ciphertext, following Section 2.8.
+fn tag(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    associated_data: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_SIZE] {
+    let first = Zeroizing::new(block(key, 0, nonce));
+    let mac_key: &[u8; 32] = first[..32].try_into().unwrap();
+    let pad = |data: &mut Vec<u8>| data.resize(data.len().div_ceil(16) * 16, 0);
+    let mut data = associated_data.to_vec();
+    pad(&mut data);
+    data.extend_from_slice(ciphertext);
+    pad(&mut data);
+    data.extend_from_slice(&(associated_data.len() as u64).to_le_bytes());
+    data.extend_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    poly1305::mac(mac_key, &data)
+}
+
+/// Encrypt a message, returning the ciphertext, with the tag appended.
+pub fn seal(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    associated_data: &[u8],
+    message: &[u8],
+) -> Vec<u8> {
+    let mut out = message.to_vec();
+    xor(key, 1, nonce, &mut out);
+    let tag = tag(key, nonce, associated_data, &out);
+    out.extend_from_slice(&tag);
+    out
+}
+
+/// Decrypt a ciphertext, returning `None` if the tag doesn't match.
+pub fn open(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    associated_data: &[u8],
+    ciphertext: &[u8],
+) -> Option<Vec<u8>> {
+    if ciphertext.len() < TAG_SIZE {
+        return None;
+    }
+    let (ciphertext, expected) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+    let actual = tag(key, nonce, associated_data, ciphertext);
+    if !bool::from(actual[..].ct_eq(expected)) {
+        return None;
+    }
+    let mut out = ciphertext.to_vec();
+    xor(key, 1, nonce, &mut out);
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rfc_8439_aead_vector() {
+        // From Section 2.8.2
+        let mut key = [0; KEY_SIZE];
+        for (i, x) in key.iter_mut().enumerate() {
+            *x = 0x80 + i as u8;
+        }
+        let mut nonce = [0; NONCE_SIZE];
+        hex::decode_to_slice("070000004041424344454647", &mut nonce).unwrap();
+        let associated_data = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let message: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let sealed = seal(&key, &nonce, &associated_data, message);
+        assert_eq!(
+            hex::encode(&sealed[..16]),
+            "d31a8d34648e60db7b86afbc53ef7ec2"
+        );
+        assert_eq!(
+            hex::encode(&sealed[message.len()..]),
+            "1ae10b594f09e26a7e902ecbd0600691"
+        );
+        assert_eq!(
+            open(&key, &nonce, &associated_data, &sealed).unwrap(),
+            message
+        );
+        assert!(open(&key, &nonce, b"", &sealed).is_none());
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let key = [1; KEY_SIZE];
+        let nonce = [2; NONCE_SIZE];
+        let mut sealed = seal(&key, &nonce, b"", &[0x55; 100]);
+        assert_eq!(open(&key, &nonce, b"", &sealed).unwrap(), vec![0x55; 100]);
+        sealed[70] ^= 1;
+        assert!(open(&key, &nonce, b"", &sealed).is_none());
+        assert!(open(&key, &nonce, b"", &sealed[..15]).is_none());
+        assert_eq!(
+            open(&key, &nonce, b"x", &seal(&key, &nonce, b"x", b"")).unwrap(),
+            b""
+        );
+    }
+}
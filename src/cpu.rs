@@ -0,0 +1,146 @@
+//! This module detects the CPU features used by our faster backends, and chooses
+//! between the backends at runtime.
+//!
+//! With `std`, features get detected when they're first needed, so a single binary
+//! uses ADX on the machines which have it, and the portable code elsewhere.
+//! Without `std`, only the features enabled at build time, e.g. with
+//! `-C target-cpu=native`, get used.
+//!
+//! The backends themselves still need to be compiled in, with the `adx` feature.
+//! Every backend produces exactly the same results, so this only affects speed.
+//! `restrict` can turn backends off, for benchmarking, or to work around a
+//! misbehaving machine.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const AVX2: u8 = 1 << 0;
+
+const ADX: u8 = 1 << 1;
+
+/// A set of CPU features, which our backends can make use of.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    /// AVX2, which no backend uses at the moment.
+    ///
+    /// The vectorized scalar multiplication was slower than the serial code, so
+    /// it got removed, and the `simd` feature no longer compiles anything in.
+    pub avx2: bool,
+    /// BMI2, and ADX, used for scalar arithmetic, with the `adx` feature.
+    pub adx: bool,
+}
+
+impl CpuFeatures {
+    pub const NONE: Self = CpuFeatures {
+        avx2: false,
+        adx: false,
+    };
+
+    pub const ALL: Self = CpuFeatures {
+        avx2: true,
+        adx: true,
+    };
+
+    /// The features present in both sets.
+    pub fn intersection(self, other: Self) -> Self {
+        CpuFeatures {
+            avx2: self.avx2 && other.avx2,
+            adx: self.adx && other.adx,
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        (if self.avx2 { AVX2 } else { 0 }) | (if self.adx { ADX } else { 0 })
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        CpuFeatures {
+            avx2: bits & AVX2 != 0,
+            adx: bits & ADX != 0,
+        }
+    }
+}
+
+/// The features our backends are allowed to use, as set by `restrict`.
+static ALLOWED: AtomicU8 = AtomicU8::new(AVX2 | ADX);
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+fn detect_avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(all(feature = "std", target_arch = "x86_64")))]
+fn detect_avx2() -> bool {
+    cfg!(target_feature = "avx2")
+}
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+fn detect_adx() -> bool {
+    is_x86_feature_detected!("bmi2") && is_x86_feature_detected!("adx")
+}
+
+#[cfg(not(all(feature = "std", target_arch = "x86_64")))]
+fn detect_adx() -> bool {
+    cfg!(all(target_feature = "bmi2", target_feature = "adx"))
+}
+
+/// The features supported by the CPU we're running on.
+///
+/// Without `std`, these are the features enabled at build time instead.
+pub fn detected() -> CpuFeatures {
+    CpuFeatures {
+        avx2: detect_avx2(),
+        adx: detect_adx(),
+    }
+}
+
+/// The features with a backend compiled into this build of the crate.
+pub fn compiled() -> CpuFeatures {
+    CpuFeatures {
+        avx2: false,
+        adx: cfg!(all(feature = "adx", target_arch = "x86_64")),
+    }
+}
+
+/// Only use the backends needing some set of features.
+///
+/// This applies to every thread, and `restrict(CpuFeatures::ALL)` undoes it.
+/// Features the CPU doesn't support are never used, even when allowed here.
+pub fn restrict(allowed: CpuFeatures) {
+    ALLOWED.store(allowed.to_bits(), Ordering::Relaxed);
+}
+
+/// The features our backends are currently using.
+///
+/// These are the features which are detected, compiled in, and not restricted.
+pub fn enabled() -> CpuFeatures {
+    let allowed = CpuFeatures::from_bits(ALLOWED.load(Ordering::Relaxed));
+    detected().intersection(compiled()).intersection(allowed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_bits_roundtrip() {
+        for &features in &[CpuFeatures::NONE, CpuFeatures::ALL, detected(), compiled()] {
+            assert_eq!(CpuFeatures::from_bits(features.to_bits()), features);
+        }
+    }
+
+    #[test]
+    fn test_restricted_backends_agree() {
+        let private = PrivateKey::from_seed([7; 32]);
+        let public = private.derive_public_key();
+        let signature = private.sign(b"any backend");
+        restrict(CpuFeatures::NONE);
+        assert_eq!(enabled(), CpuFeatures::NONE);
+        assert_eq!(private.derive_public_key(), public);
+        assert_eq!(private.sign(b"any backend"), signature);
+        assert!(public.verify(b"any backend", signature));
+        restrict(CpuFeatures::ALL);
+        assert_eq!(enabled(), detected().intersection(compiled()));
+    }
+}
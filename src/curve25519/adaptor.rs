@@ -0,0 +1,250 @@
+//! This module implements Schnorr adaptor signatures, compatible with Ed25519, following
+//! "Generalized Channels from Limited Blockchain Scripts and Adaptor Signatures":
+//! https://eprint.iacr.org/2020/476
+//!
+//! A pre-signature (R', s') is bound to an adaptor point T = t * B. It shows that the
+//! signer committed to the signature (R' + T, s' + t), which anyone knowing t can then
+//! complete. Once that signature is published, t can be extracted from the pair, which
+//! is what atomic swaps, and other off-chain protocols, rely on.
+//!
+//! Completed signatures are ordinary Ed25519 signatures, verifiable with `PublicKey::verify`.
+
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::sha512::{self, Sha512};
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error, PrivateKey, PublicKey, Signature,
+};
+
+/// The size of a pre-signature, containing a point, and a scalar.
+pub const PRE_SIGNATURE_SIZE: usize = 64;
+
+/// A signature which can only be completed with the secret behind an adaptor point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreSignature {
+    pub bytes: [u8; PRE_SIGNATURE_SIZE],
+}
+
+impl From<[u8; PRE_SIGNATURE_SIZE]> for PreSignature {
+    fn from(bytes: [u8; PRE_SIGNATURE_SIZE]) -> Self {
+        PreSignature { bytes }
+    }
+}
+
+/// The point T = t * B a pre-signature is bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptorPoint {
+    pub bytes: [u8; 32],
+}
+
+impl AdaptorPoint {
+    fn decode(&self) -> Result<Point, Error> {
+        Point::try_from(&self.bytes[..]).map_err(|_| Error::PointNotOnCurve)
+    }
+}
+
+/// The secret t behind an adaptor point, which completes pre-signatures.
+pub struct AdaptorSecret {
+    scalar: Scalar,
+}
+
+impl AdaptorSecret {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        AdaptorSecret {
+            scalar: Scalar::random(rng),
+        }
+    }
+
+    /// The adaptor point T = t * B, which can be shared with the signer.
+    pub fn point(&self) -> AdaptorPoint {
+        AdaptorPoint {
+            bytes: (point::B * self.scalar).into(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.scalar.into()
+    }
+}
+
+/// A secret is a canonical scalar, i.e. less than the order of the curve.
+impl<'a> TryFrom<&'a [u8]> for AdaptorSecret {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() != 32 {
+            return Err(Error::InvalidLength);
+        }
+        let scalar = Scalar::try_from(value).map_err(|_| Error::NonCanonicalScalar)?;
+        Ok(AdaptorSecret { scalar })
+    }
+}
+
+/// This never prints the secret.
+impl fmt::Debug for AdaptorSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdaptorSecret").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for AdaptorSecret {
+    fn drop(&mut self) {
+        self.scalar.zeroize();
+    }
+}
+
+impl PreSignature {
+    fn parts(&self) -> Result<(Point, Scalar), Error> {
+        let r = Point::try_from(&self.bytes[..32]).map_err(|_| Error::PointNotOnCurve)?;
+        let s = Scalar::try_from(&self.bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+        Ok((r, s))
+    }
+
+    /// Complete this pre-signature into a full signature, using the adaptor secret.
+    pub fn adapt(&self, secret: &AdaptorSecret) -> Result<Signature, Error> {
+        let (r, s) = self.parts()?;
+        let mut out = Signature { bytes: [0; 64] };
+        out.bytes[..32].copy_from_slice(&<[u8; 32]>::from(r + point::B * secret.scalar));
+        out.bytes[32..].copy_from_slice(&<[u8; 32]>::from(s + secret.scalar));
+        Ok(out)
+    }
+
+    /// Extract the adaptor secret, from a signature completing this pre-signature.
+    ///
+    /// This fails if the signature wasn't made from this pre-signature, and this point.
+    pub fn extract(
+        &self,
+        signature: &Signature,
+        adaptor: &AdaptorPoint,
+    ) -> Result<AdaptorSecret, Error> {
+        let (_, pre_s) = self.parts()?;
+        let s = Scalar::try_from(&signature.bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+        let secret = AdaptorSecret { scalar: s - pre_s };
+        if secret.point() != *adaptor {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(secret)
+    }
+}
+
+impl PrivateKey {
+    /// Create a pre-signature of a message, bound to an adaptor point.
+    ///
+    /// The nonce mixes fresh randomness with the usual deterministic nonce, since
+    /// reusing one for two different adaptor points leaks the private key.
+    pub fn pre_sign<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8],
+        adaptor: &AdaptorPoint,
+        rng: &mut R,
+    ) -> Result<PreSignature, Error> {
+        let t = adaptor.decode()?;
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        let a = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
+        let public = point::B * *a;
+
+        let mut randomness = Zeroizing::new([0; 32]);
+        rng.fill_bytes(&mut *randomness);
+        let mut hasher = Sha512::new();
+        hasher.update(&hash[32..]);
+        hasher.update(&*randomness);
+        hasher.update(&adaptor.bytes);
+        hasher.update(message);
+        let r = Zeroizing::new(Scalar::from_hash(hasher));
+
+        let pre_r = point::B * *r;
+        let big_r: [u8; 32] = (pre_r + t).into();
+        let k = PublicKey::challenge(&big_r, public, &[message]);
+        let mut out = PreSignature {
+            bytes: [0; PRE_SIGNATURE_SIZE],
+        };
+        out.bytes[..32].copy_from_slice(&<[u8; 32]>::from(pre_r));
+        out.bytes[32..].copy_from_slice(&<[u8; 32]>::from(*r + k * *a));
+        Ok(out)
+    }
+}
+
+impl PublicKey {
+    /// Verify that a pre-signature of a message is bound to an adaptor point.
+    ///
+    /// If this succeeds, then adapting the pre-signature with the secret behind
+    /// the point produces a valid signature of the message.
+    pub fn verify_pre_signature(
+        &self,
+        message: &[u8],
+        adaptor: &AdaptorPoint,
+        pre_signature: &PreSignature,
+    ) -> Result<(), Error> {
+        let a = Point::try_from(&self.bytes[..]).map_err(|_| Error::PointNotOnCurve)?;
+        let t = adaptor.decode()?;
+        let (pre_r, s) = pre_signature.parts()?;
+        let big_r: [u8; 32] = (pre_r + t).into();
+        let k = PublicKey::challenge(&big_r, a, &[message]);
+        let expected: [u8; 32] = Point::vartime_multiscalar_mul(&[s, -k], &[point::B, a]).into();
+        if expected[..] != pre_signature.bytes[..32] {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_adapt_and_extract() {
+        let private = PrivateKey::from([9; 32]);
+        let public = private.derive_public_key();
+        let secret = AdaptorSecret::generate(&mut OsRng);
+        let adaptor = secret.point();
+        let pre = private.pre_sign(b"swap", &adaptor, &mut OsRng).unwrap();
+        assert!(public.verify_pre_signature(b"swap", &adaptor, &pre).is_ok());
+
+        let signature = pre.adapt(&secret).unwrap();
+        assert!(public.verify(b"swap", signature));
+        let extracted = pre.extract(&signature, &adaptor).unwrap();
+        assert_eq!(extracted.to_bytes(), secret.to_bytes());
+    }
+
+    #[test]
+    fn test_rejects_mismatches() {
+        let private = PrivateKey::from([9; 32]);
+        let public = private.derive_public_key();
+        let secret = AdaptorSecret::generate(&mut OsRng);
+        let other = AdaptorSecret::generate(&mut OsRng);
+        let pre = private
+            .pre_sign(b"swap", &secret.point(), &mut OsRng)
+            .unwrap();
+        assert_eq!(
+            public.verify_pre_signature(b"swap", &other.point(), &pre),
+            Err(Error::SignatureInvalid)
+        );
+        assert_eq!(
+            public.verify_pre_signature(b"other", &secret.point(), &pre),
+            Err(Error::SignatureInvalid)
+        );
+        // Adapting with the wrong secret gives an invalid signature, and nothing to extract.
+        let wrong = pre.adapt(&other).unwrap();
+        assert!(!public.verify(b"swap", wrong));
+        assert!(pre.extract(&wrong, &secret.point()).is_err());
+        let bytes = secret.to_bytes();
+        assert_eq!(
+            AdaptorSecret::try_from(&bytes[..]).unwrap().point(),
+            secret.point()
+        );
+        assert!(AdaptorSecret::try_from(&[0xFF; 32][..]).is_err());
+    }
+}
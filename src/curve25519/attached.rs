@@ -0,0 +1,72 @@
+//! This module implements attached signatures, where the signature gets prepended
+//! to the message, producing a single signed message, as with NaCl's `crypto_sign`:
+//! https://nacl.cr.yp.to/sign.html
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+#[cfg(feature = "alloc")]
+use super::PrivateKey;
+use super::{error::Error, PublicKey, Signature, SIGNATURE_SIZE};
+
+#[cfg(feature = "alloc")]
+impl PrivateKey {
+    /// Sign a message, returning the signature followed by the message.
+    ///
+    /// `PublicKey::open` checks the signature, and gives back the message.
+    pub fn sign_combined(&self, message: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(SIGNATURE_SIZE + message.len());
+        out.extend_from_slice(&self.sign(message).bytes);
+        out.extend_from_slice(message);
+        out
+    }
+}
+
+impl PublicKey {
+    /// Verify a signed message, made by `sign_combined`, returning the message.
+    ///
+    /// This fails with `Error::InvalidLength` if there's no room for a signature,
+    /// and with `Error::SignatureInvalid` if the signature doesn't check out.
+    pub fn open<'a>(&self, signed_message: &'a [u8]) -> Result<&'a [u8], Error> {
+        if signed_message.len() < SIGNATURE_SIZE {
+            return Err(Error::InvalidLength);
+        }
+        let (signature, message) = signed_message.split_at(SIGNATURE_SIZE);
+        self.try_verify(message, Signature::try_from(signature)?)?;
+        Ok(message)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_open() {
+        let private = PrivateKey::from_seed([11; 32]);
+        let public = private.derive_public_key();
+        let signed = private.sign_combined(b"hello");
+        assert_eq!(&signed[..SIGNATURE_SIZE], &private.sign(b"hello").bytes[..]);
+        assert_eq!(public.open(&signed), Ok(&b"hello"[..]));
+        assert_eq!(public.open(&private.sign_combined(b"")), Ok(&b""[..]));
+    }
+
+    #[test]
+    fn test_open_rejects_tampering() {
+        let private = PrivateKey::from_seed([12; 32]);
+        let public = private.derive_public_key();
+        let mut signed = private.sign_combined(b"hello");
+        *signed.last_mut().unwrap() ^= 1;
+        assert_eq!(public.open(&signed), Err(Error::SignatureInvalid));
+        assert_eq!(
+            public.open(&signed[..SIGNATURE_SIZE - 1]),
+            Err(Error::InvalidLength)
+        );
+        let other = PrivateKey::from_seed([13; 32]).derive_public_key();
+        assert_eq!(
+            other.open(&private.sign_combined(b"hello")),
+            Err(Error::SignatureInvalid)
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! This module provides tamper evident logs, made of signed entries forming a chain.
+//!
+//! Each entry's signature covers the hash of the previous entry, so modifying,
+//! removing, or reordering entries breaks the chain. Checking a segment of the log
+//! reports the first link which doesn't hold, so that everything before it can
+//! still be trusted.
+
+use std::{fmt, io::IoSlice};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::sha512::Sha512;
+
+use super::{batch::verify_batch, PrivateKey, PublicKey, Signature};
+
+/// The size of the hashes linking entries together.
+pub const ENTRY_HASH_SIZE: usize = 32;
+
+/// The hash which the first entry of a log links to.
+pub const GENESIS_HASH: [u8; ENTRY_HASH_SIZE] = [0; ENTRY_HASH_SIZE];
+
+/// The prefix of each signed message, for separation from other uses of the key.
+const SIGNATURE_CONTEXT: &[u8] = b"eddo audit log entry";
+
+/// The prefix of each hashed entry.
+const HASH_CONTEXT: &[u8] = b"eddo audit log hash";
+
+/// A single entry in a log, linked to the entry before it.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// The hash of the previous entry, or `GENESIS_HASH` for the first one.
+    pub previous: [u8; ENTRY_HASH_SIZE],
+    /// The contents of this entry.
+    pub data: Vec<u8>,
+    /// A signature over the previous hash, and the data.
+    pub signature: Signature,
+}
+
+impl LogEntry {
+    /// The hash of this entry, which the next entry links to.
+    pub fn hash(&self) -> [u8; ENTRY_HASH_SIZE] {
+        let mut hasher = Sha512::new();
+        hasher.update(HASH_CONTEXT);
+        hasher.update(&self.previous);
+        hasher.update(&(self.data.len() as u64).to_be_bytes());
+        hasher.update(&self.data);
+        hasher.update(&self.signature.bytes);
+        let mut out = [0; ENTRY_HASH_SIZE];
+        out.copy_from_slice(&hasher.finalize()[..ENTRY_HASH_SIZE]);
+        out
+    }
+
+    fn signed_message(previous: &[u8; ENTRY_HASH_SIZE], data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(SIGNATURE_CONTEXT.len() + ENTRY_HASH_SIZE + data.len());
+        out.extend_from_slice(SIGNATURE_CONTEXT);
+        out.extend_from_slice(previous);
+        out.extend_from_slice(data);
+        out
+    }
+}
+
+/// Appends signed entries to a log.
+#[derive(Debug)]
+pub struct LogWriter {
+    key: PrivateKey,
+    head: [u8; ENTRY_HASH_SIZE],
+}
+
+impl LogWriter {
+    /// Start a new log, whose first entry links to `GENESIS_HASH`.
+    pub fn new(key: PrivateKey) -> Self {
+        Self::resume(key, GENESIS_HASH)
+    }
+
+    /// Continue an existing log, given the hash of its last entry.
+    pub fn resume(key: PrivateKey, head: [u8; ENTRY_HASH_SIZE]) -> Self {
+        LogWriter { key, head }
+    }
+
+    /// The hash of the last entry written, which the next entry will link to.
+    pub fn head(&self) -> [u8; ENTRY_HASH_SIZE] {
+        self.head
+    }
+
+    /// Sign some data, producing the next entry of the log.
+    pub fn append(&mut self, data: Vec<u8>) -> LogEntry {
+        let signature = self.key.sign_vectored(&[
+            IoSlice::new(SIGNATURE_CONTEXT),
+            IoSlice::new(&self.head),
+            IoSlice::new(&data),
+        ]);
+        let entry = LogEntry {
+            previous: self.head,
+            data,
+            signature,
+        };
+        self.head = entry.hash();
+        entry
+    }
+}
+
+/// The ways a link in a log can be broken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrokenLinkReason {
+    /// The entry doesn't link to the hash of the entry before it.
+    WrongPrevious,
+    /// The signature of the entry is invalid.
+    InvalidSignature,
+}
+
+/// The first entry in a segment of a log which fails to check out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The position of this entry in the segment.
+    pub index: usize,
+    /// What's wrong with this entry.
+    pub reason: BrokenLinkReason,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            BrokenLinkReason::WrongPrevious => {
+                write!(
+                    f,
+                    "log entry {} doesn't link to the previous entry",
+                    self.index
+                )
+            }
+            BrokenLinkReason::InvalidSignature => {
+                write!(f, "log entry {} has an invalid signature", self.index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BrokenLink {}
+
+/// Check a segment of a log, starting after an entry with a given hash.
+///
+/// To check a log from the beginning, start at `GENESIS_HASH`. On success, this
+/// returns the hash of the last entry in the segment, which the next segment starts from.
+///
+/// The signatures are first checked as a batch. Only if that fails do we check each
+/// of them on their own, to find the first invalid one.
+pub fn verify_log<R: RngCore + CryptoRng>(
+    public: &PublicKey,
+    start: [u8; ENTRY_HASH_SIZE],
+    entries: &[LogEntry],
+    rng: &mut R,
+) -> Result<[u8; ENTRY_HASH_SIZE], BrokenLink> {
+    let mut expected = start;
+    let mut first_broken = None;
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.previous != expected {
+            first_broken = Some(BrokenLink {
+                index,
+                reason: BrokenLinkReason::WrongPrevious,
+            });
+            break;
+        }
+        expected = entry.hash();
+    }
+    // Only the signatures before the break matter, since that one gets reported anyways.
+    let checked = first_broken.map_or(entries.len(), |broken| broken.index);
+    let entries = &entries[..checked];
+
+    let messages: Vec<Vec<u8>> = entries
+        .iter()
+        .map(|entry| LogEntry::signed_message(&entry.previous, &entry.data))
+        .collect();
+    let message_refs: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+    let signatures: Vec<Signature> = entries.iter().map(|entry| entry.signature).collect();
+    let public_keys = vec![*public; entries.len()];
+    if !verify_batch(&message_refs, &signatures, &public_keys, rng) {
+        for (index, (message, signature)) in messages.iter().zip(signatures).enumerate() {
+            if !public.verify(message, signature) {
+                return Err(BrokenLink {
+                    index,
+                    reason: BrokenLinkReason::InvalidSignature,
+                });
+            }
+        }
+    }
+
+    match first_broken {
+        Some(broken) => Err(broken),
+        None => Ok(expected),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    fn example_log(n: usize) -> (PublicKey, Vec<LogEntry>) {
+        let key = PrivateKey { bytes: [6; 32] };
+        let public = key.derive_public_key();
+        let mut writer = LogWriter::new(key);
+        let entries = (0..n).map(|i| writer.append(vec![i as u8; i])).collect();
+        (public, entries)
+    }
+
+    #[test]
+    fn test_valid_log() {
+        let (public, entries) = example_log(10);
+        let head = verify_log(&public, GENESIS_HASH, &entries, &mut OsRng).unwrap();
+        assert_eq!(head, entries[9].hash());
+        // Segments can be checked separately
+        let middle = verify_log(&public, GENESIS_HASH, &entries[..4], &mut OsRng).unwrap();
+        assert_eq!(
+            verify_log(&public, middle, &entries[4..], &mut OsRng),
+            Ok(head)
+        );
+    }
+
+    #[test]
+    fn test_resume_continues_chain() {
+        let (public, mut entries) = example_log(3);
+        let mut writer = LogWriter::resume(PrivateKey { bytes: [6; 32] }, entries[2].hash());
+        entries.push(writer.append(b"more".to_vec()));
+        assert!(verify_log(&public, GENESIS_HASH, &entries, &mut OsRng).is_ok());
+    }
+
+    #[test]
+    fn test_finds_first_broken_link() {
+        let (public, entries) = example_log(10);
+
+        let mut tampered = entries.clone();
+        tampered[6].data.push(0xFF);
+        let err = verify_log(&public, GENESIS_HASH, &tampered, &mut OsRng).unwrap_err();
+        assert_eq!(err.index, 6);
+        assert_eq!(err.reason, BrokenLinkReason::InvalidSignature);
+
+        let mut removed = entries.clone();
+        removed.remove(3);
+        let err = verify_log(&public, GENESIS_HASH, &removed, &mut OsRng).unwrap_err();
+        assert_eq!(err.index, 3);
+        assert_eq!(err.reason, BrokenLinkReason::WrongPrevious);
+
+        let mut swapped = entries;
+        swapped.swap(7, 8);
+        let err = verify_log(&public, GENESIS_HASH, &swapped, &mut OsRng).unwrap_err();
+        assert_eq!(err.index, 7);
+    }
+}
@@ -0,0 +1,223 @@
+//! This module implements batch verification of signatures.
+//!
+//! Instead of checking each equation [s]B = R + [k]A separately, we check a random
+//! linear combination of them, with one large multiscalar multiplication, which
+//! is much faster than verifying each signature on its own.
+//!
+//! With the `parallel` feature, the batch gets split into shards, which are
+//! decompressed, hashed, and multiplied on separate threads.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{
+    error::SignatureError, point, point::Point, scalar::Scalar, trace, PublicKey, Signature,
+};
+
+/// The number of signatures handled together in each shard of a batch.
+///
+/// This is large enough for each shard to benefit from Pippenger's method.
+const SHARD_SIZE: usize = 256;
+
+/// A single signature to verify, along with its random weight in the batch.
+struct BatchItem<'a> {
+    message: &'a [u8],
+    signature: Signature,
+    public_key: PublicKey,
+    weight: Scalar,
+}
+
+/// Generate a random 128 bit weight for one of the equations.
+///
+/// 128 bits are enough to make the chance of an invalid batch passing negligible.
+fn random_weight<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes[..16]);
+    Scalar::from(bytes)
+}
+
+/// Calculate the weighted sum of the equations in a shard, which is 0 if they all hold.
+fn shard_sum(items: &[BatchItem]) -> Result<Point, SignatureError> {
+    let mut scalars = Vec::with_capacity(2 * items.len() + 1);
+    let mut points = Vec::with_capacity(2 * items.len() + 1);
+    let mut b_coefficient = Scalar::from(0);
+    scalars.push(b_coefficient);
+    points.push(point::B);
+    for item in items {
+        let s = Scalar::try_from(&item.signature.bytes[32..])?;
+        let a = Point::try_from(&item.public_key.bytes[..])?;
+        let r_bytes = &item.signature.bytes[..32];
+        let r = Point::try_from(r_bytes)?;
+        let k = PublicKey::challenge(r_bytes, a, &[item.message]);
+        // z * ([s]B - R - [k]A)
+        b_coefficient += item.weight * s;
+        scalars.push(-item.weight);
+        points.push(r);
+        scalars.push(-(item.weight * k));
+        points.push(a);
+    }
+    scalars[0] = b_coefficient;
+    Ok(Point::vartime_multiscalar_mul(&scalars, &points))
+}
+
+fn verify_batch_sharded<R: RngCore + CryptoRng>(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+    rng: &mut R,
+    shard_size: usize,
+) -> bool {
+    if messages.len() != signatures.len() || signatures.len() != public_keys.len() {
+        return false;
+    }
+    // The randomness gets drawn up front, so that the shards don't need to share our RNG.
+    let items: Vec<BatchItem> = messages
+        .iter()
+        .zip(signatures.iter())
+        .zip(public_keys.iter())
+        .map(|((&message, &signature), &public_key)| BatchItem {
+            message,
+            signature,
+            public_key,
+            weight: random_weight(rng),
+        })
+        .collect();
+
+    #[cfg(feature = "parallel")]
+    let sums: Result<Vec<Point>, SignatureError> =
+        items.par_chunks(shard_size).map(shard_sum).collect();
+    #[cfg(not(feature = "parallel"))]
+    let sums: Result<Vec<Point>, SignatureError> =
+        items.chunks(shard_size).map(shard_sum).collect();
+
+    let total = match sums {
+        Ok(sums) => sums.into_iter().fold(Point::identity(), |acc, p| acc + p),
+        Err(_) => return false,
+    };
+    // We multiply by the cofactor, to ignore any components of small order.
+    total.has_small_order()
+}
+
+/// Verify a single signature with the cofactored equation used for batches.
+///
+/// This accepts exactly the signatures which `verify_batch` accepts, without
+/// the random weight, so that callers can fall back to it to find invalid signatures.
+#[cfg(all(feature = "std", feature = "hazmat"))]
+pub(super) fn verify_cofactored(
+    message: &[u8],
+    signature: Signature,
+    public_key: PublicKey,
+) -> bool {
+    let item = BatchItem {
+        message,
+        signature,
+        public_key,
+        weight: Scalar::from(1),
+    };
+    matches!(shard_sum(&[item]), Ok(sum) if sum.has_small_order())
+}
+
+/// Verify many signatures at once, returning true only if all of them are valid.
+///
+/// The ith signature is checked against the ith message and public key, and
+/// the batch is rejected if these slices have different lengths.
+///
+/// Unlike `PublicKey::verify`, this checks the equations after multiplying by the cofactor,
+/// so it may accept signatures with small order components that individual verification
+/// would reject. For signatures produced honestly, both methods agree.
+pub fn verify_batch<R: RngCore + CryptoRng>(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+    rng: &mut R,
+) -> bool {
+    let _span = trace::verify_batch(signatures.len());
+    let valid = verify_batch_sharded(messages, signatures, public_keys, rng, SHARD_SIZE);
+    trace::verified(valid);
+    valid
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+
+    use super::super::PrivateKey;
+    use super::*;
+
+    fn example_batch(n: usize) -> (Vec<Vec<u8>>, Vec<Signature>, Vec<PublicKey>) {
+        let mut messages = Vec::new();
+        let mut signatures = Vec::new();
+        let mut public_keys = Vec::new();
+        for i in 0..n {
+            let private = PrivateKey {
+                bytes: [i as u8; 32],
+            };
+            let message = vec![i as u8; i];
+            signatures.push(private.sign(&message));
+            public_keys.push(private.derive_public_key());
+            messages.push(message);
+        }
+        (messages, signatures, public_keys)
+    }
+
+    #[test]
+    fn test_valid_batch() {
+        let (messages, signatures, public_keys) = example_batch(10);
+        let messages: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+        assert!(verify_batch(
+            &messages,
+            &signatures,
+            &public_keys,
+            &mut OsRng
+        ));
+        // Multiple shards should agree
+        assert!(verify_batch_sharded(
+            &messages,
+            &signatures,
+            &public_keys,
+            &mut OsRng,
+            3
+        ));
+    }
+
+    #[test]
+    fn test_invalid_batch() {
+        let (messages, mut signatures, public_keys) = example_batch(10);
+        let messages: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+        signatures[7].bytes[40] ^= 1;
+        assert!(!verify_batch(
+            &messages,
+            &signatures,
+            &public_keys,
+            &mut OsRng
+        ));
+        assert!(!verify_batch_sharded(
+            &messages,
+            &signatures,
+            &public_keys,
+            &mut OsRng,
+            3
+        ));
+    }
+
+    #[test]
+    fn test_mismatched_lengths() {
+        let (messages, signatures, public_keys) = example_batch(3);
+        let messages: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+        assert!(!verify_batch(
+            &messages[..2],
+            &signatures,
+            &public_keys,
+            &mut OsRng
+        ));
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        assert!(verify_batch(&[], &[], &[], &mut OsRng));
+    }
+}
@@ -0,0 +1,307 @@
+//! This module implements BIP32-Ed25519, as described by Khovratovich and Law:
+//! https://input-output-hk.github.io/adrestia/static/Ed25519_BIP.pdf
+//!
+//! Unlike SLIP-0010, this supports non-hardened derivation, letting a public key derive
+//! the public keys of its children. This works by having private keys be extended keys,
+//! with the secret scalar used directly, instead of being hashed from a seed. Child
+//! scalars are the parent scalar plus 8 times some hash, so they stay multiples of 8.
+//!
+//! The derivation follows the variant used by Cardano, with indices encoded in little endian.
+
+use core::{convert::TryFrom, fmt};
+
+use crate::hmac::{self, HmacSha512};
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error, PrivateKey, PublicKey, Signature, PUBLIC_KEY_SIZE,
+};
+
+/// The size of an extended private key, along with its chain code.
+pub const BIP32_PRIVATE_KEY_SIZE: usize = 96;
+
+/// The size of an extended public key, along with its chain code.
+pub const BIP32_PUBLIC_KEY_SIZE: usize = 64;
+
+/// The indices from this one onwards use hardened derivation.
+const HARDENED: u32 = 0x8000_0000;
+
+/// How many bytes of each hash get added, times 8, to the parent scalar.
+const ADDED_SIZE: usize = 28;
+
+/// Add 8 times the first 28 bytes of a hash to a little endian number, without reduction.
+///
+/// Reducing modulo L would break the clamping, so this is plain integer addition,
+/// and fails if the result doesn't fit in 32 bytes.
+fn add_times_eight(bytes: &[u8; 32], hash: &[u8]) -> Result<[u8; 32], Error> {
+    let mut out = [0; 32];
+    let mut carry = 0u16;
+    for i in 0..32 {
+        // The shift by 3 moves the top bits of each byte into the next one.
+        let lo = hash.get(i).map_or(0, |&x| (u16::from(x) << 3) & 0xFF);
+        let hi = i
+            .checked_sub(1)
+            .and_then(|j| hash.get(j))
+            .map_or(0, |&x| u16::from(x) >> 5);
+        let added = lo | hi;
+        let sum = u16::from(bytes[i]) + added + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    if carry != 0 {
+        return Err(Error::InvalidDerivationPath);
+    }
+    Ok(out)
+}
+
+/// Convert the little endian bytes of a secret, or added, value into a scalar.
+fn reduce(bytes: &[u8; 32]) -> Scalar {
+    let mut wide = Zeroizing::new([0; 64]);
+    wide[..32].copy_from_slice(bytes);
+    Scalar::from(*wide)
+}
+
+/// Calculate the hash Z, and the next chain code, for some derivation.
+fn hashes(
+    chain_code: &[u8; 32],
+    tags: [u8; 2],
+    data: &[&[u8]],
+    index: u32,
+) -> ([u8; hmac::TAG_SIZE], [u8; 32]) {
+    let run = |tag| {
+        let mut mac = HmacSha512::new(chain_code);
+        mac.update(&[tag]);
+        for part in data {
+            mac.update(part);
+        }
+        mac.update(&index.to_le_bytes());
+        mac.finalize()
+    };
+    let z = run(tags[0]);
+    let code_tag = Zeroizing::new(run(tags[1]));
+    let mut code = [0; 32];
+    code.copy_from_slice(&code_tag[32..]);
+    (z, code)
+}
+
+/// An extended private key, with a chain code, for BIP32-Ed25519.
+#[derive(Clone)]
+pub struct Bip32PrivateKey {
+    /// The secret scalar, as little endian bytes, which is a multiple of 8.
+    scalar: [u8; 32],
+    /// The prefix used to derive nonces when signing.
+    prefix: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+impl Bip32PrivateKey {
+    /// Parse a key from the secret scalar, the nonce prefix, and the chain code.
+    ///
+    /// The scalar has to be clamped, like in Ed25519, and additionally have the
+    /// third highest bit cleared, as in root keys, which leaves room for derivation.
+    pub fn from_bytes(bytes: &[u8; BIP32_PRIVATE_KEY_SIZE]) -> Result<Self, Error> {
+        if bytes[0] & 0b111 != 0 || bytes[31] & 0b1110_0000 != 0b0100_0000 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut out = Bip32PrivateKey {
+            scalar: [0; 32],
+            prefix: [0; 32],
+            chain_code: [0; 32],
+        };
+        out.scalar.copy_from_slice(&bytes[..32]);
+        out.prefix.copy_from_slice(&bytes[32..64]);
+        out.chain_code.copy_from_slice(&bytes[64..]);
+        Ok(out)
+    }
+
+    pub fn to_bytes(&self) -> [u8; BIP32_PRIVATE_KEY_SIZE] {
+        let mut out = [0; BIP32_PRIVATE_KEY_SIZE];
+        out[..32].copy_from_slice(&self.scalar);
+        out[32..64].copy_from_slice(&self.prefix);
+        out[64..].copy_from_slice(&self.chain_code);
+        out
+    }
+
+    /// Derive the child key at some index, with hardened derivation from 2^31 onwards.
+    pub fn derive(&self, index: u32) -> Result<Self, Error> {
+        let (z, chain_code) = if index >= HARDENED {
+            hashes(
+                &self.chain_code,
+                [0, 1],
+                &[&self.scalar, &self.prefix],
+                index,
+            )
+        } else {
+            let public = self.public_key().public_key;
+            hashes(&self.chain_code, [2, 3], &[&public.bytes], index)
+        };
+        let z = Zeroizing::new(z);
+        let scalar = add_times_eight(&self.scalar, &z[..ADDED_SIZE]);
+        // The prefix is simply added modulo 2^256.
+        let mut prefix = [0; 32];
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = u16::from(self.prefix[i]) + u16::from(z[32 + i]) + carry;
+            prefix[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        Ok(Bip32PrivateKey {
+            scalar: scalar?,
+            prefix,
+            chain_code,
+        })
+    }
+
+    /// Derive the key at a sequence of indices below this one.
+    pub fn derive_path(&self, indices: &[u32]) -> Result<Self, Error> {
+        let mut out = self.clone();
+        for &index in indices {
+            out = out.derive(index)?;
+        }
+        Ok(out)
+    }
+
+    pub fn public_key(&self) -> Bip32PublicKey {
+        let scalar = Zeroizing::new(reduce(&self.scalar));
+        let public_key = PublicKey {
+            bytes: (point::B * *scalar).into(),
+        };
+        Bip32PublicKey {
+            public_key,
+            chain_code: self.chain_code,
+        }
+    }
+
+    /// Sign a message, producing a signature valid under our public key.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        PrivateKey::sign_with_scalar(reduce(&self.scalar), &self.prefix, &[message])
+    }
+}
+
+/// This never prints the key, or the chain code.
+impl fmt::Debug for Bip32PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bip32PrivateKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Bip32PrivateKey {
+    fn drop(&mut self) {
+        self.scalar.zeroize();
+        self.prefix.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+/// An extended public key, with a chain code, for BIP32-Ed25519.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bip32PublicKey {
+    /// The Ed25519 key, which verifies signatures.
+    pub public_key: PublicKey,
+    pub chain_code: [u8; 32],
+}
+
+impl Bip32PublicKey {
+    /// Parse a key from the public key, followed by the chain code.
+    pub fn from_bytes(bytes: &[u8; BIP32_PUBLIC_KEY_SIZE]) -> Result<Self, Error> {
+        let public_key = PublicKey::try_from(&bytes[..PUBLIC_KEY_SIZE])?;
+        let mut chain_code = [0; 32];
+        chain_code.copy_from_slice(&bytes[PUBLIC_KEY_SIZE..]);
+        Ok(Bip32PublicKey {
+            public_key,
+            chain_code,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; BIP32_PUBLIC_KEY_SIZE] {
+        let mut out = [0; BIP32_PUBLIC_KEY_SIZE];
+        out[..PUBLIC_KEY_SIZE].copy_from_slice(&self.public_key.bytes);
+        out[PUBLIC_KEY_SIZE..].copy_from_slice(&self.chain_code);
+        out
+    }
+
+    /// Derive the public key of the child at some index, which can't be hardened.
+    pub fn derive(&self, index: u32) -> Result<Self, Error> {
+        if index >= HARDENED {
+            return Err(Error::InvalidDerivationPath);
+        }
+        let (z, chain_code) = hashes(&self.chain_code, [2, 3], &[&self.public_key.bytes], index);
+        let added = add_times_eight(&[0; 32], &z[..ADDED_SIZE])?;
+        // Every public key holds a valid point, by construction.
+        let a = Point::try_from(&self.public_key.bytes[..]).unwrap();
+        let public_key = PublicKey {
+            bytes: (a + point::B * reduce(&added)).into(),
+        };
+        Ok(Bip32PublicKey {
+            public_key,
+            chain_code,
+        })
+    }
+
+    /// Derive the public key at a sequence of non-hardened indices below this one.
+    pub fn derive_path(&self, indices: &[u32]) -> Result<Self, Error> {
+        let mut out = *self;
+        for &index in indices {
+            out = out.derive(index)?;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The vectors D1, and D1_H0, from src/tests.rs in version 0.4.1 of the ed25519-bip32
+    // crate, which implements the same derivation for Cardano:
+    // https://github.com/typed-io/rust-ed25519-bip32
+    const ROOT: &str = "f8a29231ee38d6c5bf715d5bac21c750577aa3798b22d79d65bf97d6fadea15adcd1ee1abdf78bd4be64731a12deb94d3671784112eb6f364b871851fd1c9a247384db9ad6003bbd08b3b1ddc0d07a597293ff85e961bf252b331262eddfad0d";
+    const PATH: [u32; 4] = [HARDENED + 44, HARDENED + 1815, 0, 7];
+
+    fn root() -> Bip32PrivateKey {
+        let mut bytes = [0; BIP32_PRIVATE_KEY_SIZE];
+        hex::decode_to_slice(ROOT, &mut bytes).unwrap();
+        Bip32PrivateKey::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_private_derivation_vector() {
+        let child = root().derive(HARDENED).unwrap();
+        assert_eq!(
+            hex::encode(&child.to_bytes()[..]),
+            "60d399da83ef80d8d4f8d223239efdc2b8fef387e1b5219137ffb4e8fbdea15adc9366b7d003af37c11396de9a83734e30e05e851efa32745c9cd7b42712c890608763770eddf77248ab652984b21b849760d1da74a6f5bd633ce41adceef07a"
+        );
+        let signature = child.sign(b"Hello World");
+        assert_eq!(
+            hex::encode(signature.bytes),
+            "90194d57cde4fdadd01eb7cf161780c277e129fc7135b97779a3268837e4cd2e9444b9bb91c0e84d23bba870df3c4bda91a110ef735638fa7a34ea2046d4be04"
+        );
+        assert!(child
+            .public_key()
+            .public_key
+            .verify(b"Hello World", signature));
+    }
+
+    #[test]
+    fn test_public_derivation_matches_private() {
+        let account = root().derive_path(&PATH[..2]).unwrap();
+        let public = Bip32PublicKey::from_bytes(&account.public_key().to_bytes()).unwrap();
+        assert_eq!(
+            public.derive_path(&PATH[2..]).unwrap(),
+            account.derive_path(&PATH[2..]).unwrap().public_key()
+        );
+        assert_eq!(
+            public.derive(HARDENED).unwrap_err(),
+            Error::InvalidDerivationPath
+        );
+        // Keys without room for derivation get rejected
+        let mut bytes = root().to_bytes();
+        bytes[31] |= 0b0010_0000;
+        assert!(Bip32PrivateKey::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! This module implements blind Schnorr signatures over ristretto255.
+//!
+//! A user gets a signature on a message from a signer, without the signer learning
+//! the message, or being able to recognize the signature later, as in Chaum's
+//! "Blind Signatures for Untraceable Payments", adapted to Schnorr signatures.
+//!
+//! The plain protocol is only secure if the signer runs sessions one at a time. With
+//! many sessions open concurrently, the ROS attack forges an extra signature in
+//! polynomial time, as shown in "On the (in)security of ROS":
+//! https://eprint.iacr.org/2020/945
+//!
+//! The clause variant, from "Blind Schnorr Signatures and Signed ElGamal Encryption in
+//! the Algebraic Group Model", Section 5, avoids this, at the cost of twice as many
+//! commitments. The signer sends two commitments, the user blinds both, and the signer
+//! only answers one of them, picked at random:
+//! https://eprint.iacr.org/2019/877
+//!
+//! Either way, the results are plain Schnorr signatures, checked with `blind_verify`.
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::sha512::Sha512;
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+
+use super::{
+    ristretto::{Element, ELEMENT_SIZE, GENERATOR},
+    scalar::Scalar,
+    Error,
+};
+
+/// The size of a public key, or of a single commitment.
+pub const BLIND_PUBLIC_KEY_SIZE: usize = ELEMENT_SIZE;
+
+/// The size of a finished signature.
+pub const BLIND_SIGNATURE_SIZE: usize = 64;
+
+fn decode_scalar(bytes: &[u8]) -> Result<Scalar, Error> {
+    Scalar::try_from(bytes).map_err(|_| Error::NonCanonicalScalar)
+}
+
+/// The challenge c = H(X || R || m) of a signature.
+fn challenge(public: &[u8; ELEMENT_SIZE], r: &[u8; ELEMENT_SIZE], message: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(b"blind-Schnorr-ristretto255-SHA512");
+    hasher.update(public);
+    hasher.update(r);
+    hasher.update(message);
+    Scalar::from_hash(hasher)
+}
+
+/// A plain Schnorr signature over ristretto255, produced by blind signing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlindSignature {
+    pub bytes: [u8; BLIND_SIGNATURE_SIZE],
+}
+
+impl From<[u8; BLIND_SIGNATURE_SIZE]> for BlindSignature {
+    fn from(bytes: [u8; BLIND_SIGNATURE_SIZE]) -> Self {
+        BlindSignature { bytes }
+    }
+}
+
+/// Verify a signature from blind signing, checking that s * G = R + c * X.
+pub fn blind_verify(
+    public_key: &[u8; BLIND_PUBLIC_KEY_SIZE],
+    message: &[u8],
+    signature: &BlindSignature,
+) -> Result<(), Error> {
+    let x = Element::try_from_nonidentity(public_key)?;
+    let s = decode_scalar(&signature.bytes[32..])?;
+    let mut r = [0; ELEMENT_SIZE];
+    r.copy_from_slice(&signature.bytes[..32]);
+    let c = challenge(public_key, &r, message);
+    let expected = Element::vartime_double_mul(s, GENERATOR, -c, x).encode();
+    if !bool::from(expected.ct_eq(&r)) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// The key of the signer.
+pub struct BlindSigner {
+    secret: Scalar,
+    public: [u8; BLIND_PUBLIC_KEY_SIZE],
+}
+
+/// The secret nonce of the signer, for a single session.
+///
+/// This can't be cloned, and gets consumed by signing, since reusing it leaks the key.
+pub struct BlindNonce {
+    k: Scalar,
+}
+
+/// The two secret nonces of the signer, for a single session of the clause variant.
+pub struct BlindClauseNonces {
+    k: [Scalar; 2],
+}
+
+impl BlindSigner {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let secret = Scalar::random(rng);
+        BlindSigner {
+            secret,
+            public: (GENERATOR * secret).encode(),
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; BLIND_PUBLIC_KEY_SIZE] {
+        self.public
+    }
+
+    /// Start a session, producing a commitment R = k * G, to send to the user.
+    pub fn commit<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> (BlindNonce, [u8; BLIND_PUBLIC_KEY_SIZE]) {
+        let k = Scalar::random(rng);
+        (BlindNonce { k }, (GENERATOR * k).encode())
+    }
+
+    /// Answer the blinded challenge sent by the user, with s = k + c * x.
+    ///
+    /// Sessions must not run concurrently, see the module documentation.
+    pub fn sign(&self, nonce: BlindNonce, challenge: &[u8; 32]) -> Result<[u8; 32], Error> {
+        let c = decode_scalar(challenge)?;
+        Ok((nonce.k + c * self.secret).into())
+    }
+
+    /// Start a session of the clause variant, producing two commitments.
+    pub fn commit_clause<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> (BlindClauseNonces, [[u8; BLIND_PUBLIC_KEY_SIZE]; 2]) {
+        let k = [Scalar::random(rng), Scalar::random(rng)];
+        let commitments = [(GENERATOR * k[0]).encode(), (GENERATOR * k[1]).encode()];
+        (BlindClauseNonces { k }, commitments)
+    }
+
+    /// Answer one of the two blinded challenges, picked at random.
+    ///
+    /// This returns which challenge got answered, along with the answer. This variant
+    /// is safe to use with many sessions at once.
+    pub fn sign_clause<R: RngCore + CryptoRng>(
+        &self,
+        nonces: BlindClauseNonces,
+        challenges: &[[u8; 32]; 2],
+        rng: &mut R,
+    ) -> Result<(usize, [u8; 32]), Error> {
+        let c = [
+            decode_scalar(&challenges[0])?,
+            decode_scalar(&challenges[1])?,
+        ];
+        let bit = (rng.next_u32() & 1) as usize;
+        Ok((bit, (nonces.k[bit] + c[bit] * self.secret).into()))
+    }
+}
+
+/// This never prints the secret key.
+impl fmt::Debug for BlindSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindSigner")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// This never prints the nonce.
+impl fmt::Debug for BlindNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindNonce").finish_non_exhaustive()
+    }
+}
+
+/// This never prints the nonces.
+impl fmt::Debug for BlindClauseNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindClauseNonces").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindSigner {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindNonce {
+    fn drop(&mut self) {
+        self.k.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindClauseNonces {
+    fn drop(&mut self) {
+        self.k[0].zeroize();
+        self.k[1].zeroize();
+    }
+}
+
+/// The state of the user, between sending a blinded challenge, and unblinding the answer.
+pub struct BlindRequest {
+    public: [u8; BLIND_PUBLIC_KEY_SIZE],
+    alpha: Scalar,
+    c: Scalar,
+    blinded_r: [u8; ELEMENT_SIZE],
+}
+
+impl BlindRequest {
+    /// Blind a message, with the signer's commitment, producing a challenge for the signer.
+    ///
+    /// The commitment is blinded into R' = R + alpha * G + beta * X, and the challenge
+    /// sent is c' + beta, where c' is the real challenge, for R'.
+    pub fn new<R: RngCore + CryptoRng>(
+        public_key: &[u8; BLIND_PUBLIC_KEY_SIZE],
+        commitment: &[u8; BLIND_PUBLIC_KEY_SIZE],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, [u8; 32]), Error> {
+        let x = Element::try_from_nonidentity(public_key)?;
+        let r = Element::try_from_nonidentity(commitment)?;
+        let alpha = Scalar::random(rng);
+        let beta = Scalar::random(rng);
+        let blinded_r = (r + GENERATOR * alpha + x * beta).encode();
+        let c = challenge(public_key, &blinded_r, message);
+        let request = BlindRequest {
+            public: *public_key,
+            alpha,
+            c,
+            blinded_r,
+        };
+        Ok((request, (c + beta).into()))
+    }
+
+    /// Check the signer's answer, and unblind it into a signature, with s' = s + alpha.
+    pub fn finish(&self, response: &[u8; 32]) -> Result<BlindSignature, Error> {
+        let s = decode_scalar(response)? + self.alpha;
+        // If s = k + (c + beta) * x, then s' * G = R' + c * X, as a signature requires.
+        let x = Element::try_from_nonidentity(&self.public)?;
+        let expected = Element::vartime_double_mul(s, GENERATOR, -self.c, x).encode();
+        if !bool::from(expected.ct_eq(&self.blinded_r)) {
+            return Err(Error::SignatureInvalid);
+        }
+        let mut out = BlindSignature {
+            bytes: [0; BLIND_SIGNATURE_SIZE],
+        };
+        out.bytes[..32].copy_from_slice(&self.blinded_r);
+        out.bytes[32..].copy_from_slice(&<[u8; 32]>::from(s));
+        Ok(out)
+    }
+}
+
+/// This never prints the blinding factor, which would link the signature to the session.
+impl fmt::Debug for BlindRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindRequest").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindRequest {
+    fn drop(&mut self) {
+        self.alpha.zeroize();
+    }
+}
+
+/// The state of the user, in the clause variant, holding both blinded requests.
+#[derive(Debug)]
+pub struct BlindClauseRequest {
+    requests: [BlindRequest; 2],
+}
+
+impl BlindClauseRequest {
+    /// Blind a message, with both of the signer's commitments, producing two challenges.
+    pub fn new<R: RngCore + CryptoRng>(
+        public_key: &[u8; BLIND_PUBLIC_KEY_SIZE],
+        commitments: &[[u8; BLIND_PUBLIC_KEY_SIZE]; 2],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, [[u8; 32]; 2]), Error> {
+        let (first, c0) = BlindRequest::new(public_key, &commitments[0], message, rng)?;
+        let (second, c1) = BlindRequest::new(public_key, &commitments[1], message, rng)?;
+        Ok((
+            BlindClauseRequest {
+                requests: [first, second],
+            },
+            [c0, c1],
+        ))
+    }
+
+    /// Unblind the answer to whichever challenge the signer picked.
+    pub fn finish(self, bit: usize, response: &[u8; 32]) -> Result<BlindSignature, Error> {
+        self.requests
+            .get(bit)
+            .ok_or(Error::InvalidEncoding)?
+            .finish(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_blind_signing() {
+        let signer = BlindSigner::generate(&mut OsRng);
+        let public = signer.public_key();
+        let (nonce, commitment) = signer.commit(&mut OsRng);
+        let (request, c) = BlindRequest::new(&public, &commitment, b"coin", &mut OsRng).unwrap();
+        let s = signer.sign(nonce, &c).unwrap();
+        let signature = request.finish(&s).unwrap();
+        assert!(blind_verify(&public, b"coin", &signature).is_ok());
+        assert_eq!(
+            blind_verify(&public, b"other", &signature),
+            Err(Error::SignatureInvalid)
+        );
+        // The signer never saw the commitment, or response, in the final signature.
+        assert_ne!(signature.bytes[..32], commitment[..]);
+        assert_ne!(signature.bytes[32..], s[..]);
+    }
+
+    #[test]
+    fn test_rejects_bad_response() {
+        let signer = BlindSigner::generate(&mut OsRng);
+        let public = signer.public_key();
+        let (_, commitment) = signer.commit(&mut OsRng);
+        let (nonce, _) = signer.commit(&mut OsRng);
+        let (request, c) = BlindRequest::new(&public, &commitment, b"coin", &mut OsRng).unwrap();
+        // Answering with the wrong nonce gets caught by the user.
+        let s = signer.sign(nonce, &c).unwrap();
+        assert_eq!(request.finish(&s), Err(Error::SignatureInvalid));
+    }
+
+    #[test]
+    fn test_clause_blind_signing() {
+        let signer = BlindSigner::generate(&mut OsRng);
+        let public = signer.public_key();
+        for _ in 0..4 {
+            let (nonces, commitments) = signer.commit_clause(&mut OsRng);
+            let (request, challenges) =
+                BlindClauseRequest::new(&public, &commitments, b"coin", &mut OsRng).unwrap();
+            let (bit, s) = signer.sign_clause(nonces, &challenges, &mut OsRng).unwrap();
+            let signature = request.finish(bit, &s).unwrap();
+            assert!(blind_verify(&public, b"coin", &signature).is_ok());
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! This module implements the key blinding used by Tor v3 onion services, as described
+//! in Appendix A.2 of the rendezvous specification:
+//! https://spec.torproject.org/rend-spec/keyblind.html
+//!
+//! A blinded key is the master key multiplied by a factor depending on the key,
+//! and the current time period. Without knowing the master key, blinded keys from
+//! different periods can't be linked to each other, but anyone with the master
+//! public key can derive them, and check signatures made with them.
+//!
+//! Blinded signatures are standard Ed25519 signatures under the blinded public key.
+
+use core::{convert::TryFrom, fmt};
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::{
+    sha512::{self, Sha512},
+    shake::sha3_256,
+    zeroize::Zeroizing,
+};
+
+use super::{point::Point, scalar::Scalar, PrivateKey, PublicKey, Signature, PUBLIC_KEY_SIZE};
+
+const BLIND_STRING: &[u8] = b"Derive temporary signing key\x00";
+const NONCE_BLIND_STRING: &[u8] = b"Derive temporary signing key hash input";
+const NONCE_STRING: &[u8] = b"key-blind";
+/// The basepoint, written out as a string, which the specification hashes in.
+const BASEPOINT_STRING: &[u8] = b"(15112221349535400772501151409588531511454012693041857206046113283949847762202, 46316835694926478169428394003475163141307993866256225615783033603165251855960)";
+
+/// The size of the string hashed into the blinding factor.
+const BLIND_DATA_SIZE: usize =
+    BLIND_STRING.len() + PUBLIC_KEY_SIZE + BASEPOINT_STRING.len() + NONCE_STRING.len() + 16;
+
+/// Calculate the clamped blinding factor h for a public key, and a time period.
+fn blinding_factor(public: &PublicKey, period_number: u64, period_length: u64) -> Scalar {
+    let mut data = [0; BLIND_DATA_SIZE];
+    let mut rest = &mut data[..];
+    for part in &[
+        BLIND_STRING,
+        &public.bytes,
+        BASEPOINT_STRING,
+        NONCE_STRING,
+        &period_number.to_be_bytes(),
+        &period_length.to_be_bytes(),
+    ] {
+        let (head, tail) = rest.split_at_mut(part.len());
+        head.copy_from_slice(part);
+        rest = tail;
+    }
+    // Tor clears the top two bits, and sets the second highest, like Ed25519 clamping.
+    Scalar::clamped(sha3_256(&data))
+}
+
+impl PublicKey {
+    /// Derive the blinded public key for a given time period.
+    ///
+    /// The period number, and length, in minutes, usually come from the current
+    /// time, and the consensus, with 1440 minutes as the default length.
+    pub fn blind(&self, period_number: u64, period_length: u64) -> PublicKey {
+        // Every public key holds a valid point, by construction.
+        let a = Point::try_from(&self.bytes[..]).unwrap();
+        let h = blinding_factor(self, period_number, period_length);
+        PublicKey {
+            bytes: (a * h).into(),
+        }
+    }
+}
+
+impl PrivateKey {
+    /// Derive the blinded private key for a given time period.
+    ///
+    /// The public key of the result is the same as blinding our public key.
+    pub fn blind(&self, period_number: u64, period_length: u64) -> BlindedPrivateKey {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        let public = PublicKey::from_hash(&hash);
+        let secret = Zeroizing::new(<[u8; 32]>::try_from(&hash[..32]).unwrap());
+        blind_expanded(&secret, &hash[32..], &public, period_number, period_length)
+    }
+}
+
+/// Blind an expanded private key, made of a secret scalar, and a nonce prefix.
+fn blind_expanded(
+    secret: &[u8; 32],
+    prefix: &[u8],
+    public: &PublicKey,
+    period_number: u64,
+    period_length: u64,
+) -> BlindedPrivateKey {
+    let h = blinding_factor(public, period_number, period_length);
+    let a = Zeroizing::new(Scalar::clamped(*secret));
+    let scalar = h * *a;
+    let mut nonce_hasher = Sha512::new();
+    nonce_hasher.update(NONCE_BLIND_STRING);
+    nonce_hasher.update(prefix);
+    let nonce_hash = Zeroizing::new(nonce_hasher.finalize());
+    let mut blinded_prefix = [0; 32];
+    blinded_prefix.copy_from_slice(&nonce_hash[..32]);
+
+    BlindedPrivateKey {
+        scalar,
+        prefix: blinded_prefix,
+        public: public.blind(period_number, period_length),
+    }
+}
+
+/// A private key blinded for a single time period.
+///
+/// Unlike a normal private key, this has no seed, only the blinded secret scalar,
+/// and the prefix used to derive nonces.
+pub struct BlindedPrivateKey {
+    scalar: Scalar,
+    prefix: [u8; 32],
+    public: PublicKey,
+}
+
+impl BlindedPrivateKey {
+    /// The blinded public key, which verifies our signatures.
+    pub fn public_key(&self) -> PublicKey {
+        self.public
+    }
+
+    /// Sign a message, producing a signature valid under the blinded public key.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        PrivateKey::sign_with_scalar(self.scalar, &self.prefix, &[message])
+    }
+}
+
+/// This never prints the secret parts, only the public key.
+impl fmt::Debug for BlindedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindedPrivateKey")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BlindedPrivateKey {
+    fn drop(&mut self) {
+        self.scalar.zeroize();
+        self.prefix.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    fn key() -> PrivateKey {
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(SEED, &mut bytes).unwrap();
+        PrivateKey::from(bytes)
+    }
+
+    fn decode<const N: usize>(data: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(data, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_blinding_vector() {
+        // Generated with C tor, from key_blinding_testvec in src/pk.rs of version 0.47.0
+        // of the tor-hscrypto crate: https://gitlab.torproject.org/tpo/core/arti
+        let public = PublicKey {
+            bytes: decode("833990b085c1a688c1d4c8b1f6b56afaf5a2eca674449e1d704f83765ccb7bc6"),
+        };
+        let h: [u8; 32] =
+            decode("379e50db31fee6775abd0af6fb7c371e060308f4f847db09fe4cfe13af602287");
+        assert_eq!(blinding_factor(&public, 1234, 1440), Scalar::clamped(h));
+        let expected = "3a50bf210e8f9ee955ae0014f7a6917fb65ebf098a86305abb508d1a7291b6d5";
+        assert_eq!(hex::encode(public.blind(1234, 1440).bytes), expected);
+
+        let secret = decode("d8c7ff0e31295b66540d789af3e3df992038a9592eea01d8b7cba06d6e66d159");
+        let prefix: [u8; 32] =
+            decode("4d6167696320576f7264733a20737065697373636f62616c742062697669756d");
+        let blinded = blind_expanded(&secret, &prefix, &public, 1234, 1440);
+        assert_eq!(hex::encode(blinded.public_key().bytes), expected);
+        assert_eq!(
+            hex::encode(<[u8; 32]>::from(blinded.scalar)),
+            "a958dc83ac885f6814c67035de817a2c604d5d2f715282079448f789b656350b"
+        );
+        assert_eq!(
+            hex::encode(blinded.prefix),
+            "4540fe1f80aa3f7e91306b7bf7a8e367293352b14a29fdcc8c19f3558075524b"
+        );
+        assert!(blinded
+            .public_key()
+            .verify(b"hello", blinded.sign(b"hello")));
+
+        let key = key();
+        assert_eq!(
+            key.blind(1234, 1440).public_key(),
+            key.derive_public_key().blind(1234, 1440)
+        );
+    }
+
+    #[test]
+    fn test_blinded_signatures_verify() {
+        let key = key();
+        let public = key.derive_public_key();
+        let blinded = key.blind(1, 1440);
+        let signature = blinded.sign(b"message");
+        assert!(blinded.public_key().verify(b"message", signature));
+        assert!(!public.verify(b"message", signature));
+        assert!(!key
+            .blind(2, 1440)
+            .public_key()
+            .verify(b"message", signature));
+        assert_ne!(public.blind(1, 1440), public.blind(1, 720));
+    }
+}
@@ -0,0 +1,436 @@
+//! This module mirrors the most common names of curve25519-dalek, and ed25519-dalek,
+//! so that code written against them can switch over with few changes:
+//! https://github.com/dalek-cryptography/curve25519-dalek
+//!
+//! Only the `compat` feature enables this. The types here are thin wrappers over the
+//! rest of the crate, converting to, and from, them freely. Signing, and verification,
+//! are inherent methods, rather than going through the `signature` crate's traits,
+//! and all errors are this crate's `Error`, named `SignatureError` here.
+
+use core::{
+    convert::TryFrom,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use rand::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::uint::U256;
+
+use super::{
+    point::{self, Point},
+    scalar, PrivateKey, PublicKey, Signature as RawSignature,
+};
+
+pub use super::Error as SignatureError;
+
+pub const SECRET_KEY_LENGTH: usize = 32;
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+
+pub const KEYPAIR_LENGTH: usize = SECRET_KEY_LENGTH + PUBLIC_KEY_LENGTH;
+
+pub const SIGNATURE_LENGTH: usize = 64;
+
+pub type SecretKey = [u8; SECRET_KEY_LENGTH];
+
+/// An element of Z/(L), like dalek's `Scalar`.
+#[derive(Clone, Copy, Debug)]
+pub struct Scalar(scalar::Scalar);
+
+impl Scalar {
+    pub const ZERO: Self = Scalar(scalar::Scalar { value: U256::ZERO });
+
+    pub const ONE: Self = Scalar(scalar::Scalar { value: U256::ONE });
+
+    /// Reduce 32 little endian bytes modulo L.
+    pub fn from_bytes_mod_order(bytes: [u8; 32]) -> Self {
+        let mut wide = [0; 64];
+        wide[..32].copy_from_slice(&bytes);
+        Scalar(scalar::Scalar::from(wide))
+    }
+
+    /// Reduce 64 little endian bytes modulo L.
+    pub fn from_bytes_mod_order_wide(bytes: &[u8; 64]) -> Self {
+        Scalar(scalar::Scalar::from(*bytes))
+    }
+
+    /// Read 32 little endian bytes, if they're already reduced modulo L.
+    pub fn from_canonical_bytes(bytes: [u8; 32]) -> Option<Self> {
+        scalar::Scalar::from_canonical_bytes(bytes).map(Scalar)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.into()
+    }
+
+    pub fn invert(&self) -> Self {
+        Scalar(self.0.inverse())
+    }
+}
+
+impl From<u64> for Scalar {
+    fn from(x: u64) -> Self {
+        Scalar(scalar::Scalar::from(x))
+    }
+}
+
+impl From<scalar::Scalar> for Scalar {
+    fn from(x: scalar::Scalar) -> Self {
+        Scalar(x)
+    }
+}
+
+impl From<Scalar> for scalar::Scalar {
+    fn from(x: Scalar) -> Self {
+        x.0
+    }
+}
+
+impl ConstantTimeEq for Scalar {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.value.ct_eq(&other.0.value)
+    }
+}
+
+/// This compares in constant time, like `ct_eq`.
+impl PartialEq for Scalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Scalar {}
+
+impl Add for Scalar {
+    type Output = Scalar;
+
+    fn add(self, other: Scalar) -> Self::Output {
+        Scalar(self.0 + other.0)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Scalar;
+
+    fn sub(self, other: Scalar) -> Self::Output {
+        Scalar(self.0 - other.0)
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Scalar;
+
+    fn mul(self, other: Scalar) -> Self::Output {
+        Scalar(self.0 * other.0)
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Scalar;
+
+    fn neg(self) -> Self::Output {
+        Scalar(-self.0)
+    }
+}
+
+/// The encoding of a point, by its y coordinate, and the sign of x.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedEdwardsY(pub [u8; 32]);
+
+impl CompressedEdwardsY {
+    /// Decode the point, returning `None` if these bytes aren't a valid encoding.
+    pub fn decompress(&self) -> Option<EdwardsPoint> {
+        Point::try_from(&self.0[..]).ok().map(EdwardsPoint)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// A point on the Edwards curve, like dalek's `EdwardsPoint`.
+#[derive(Clone, Copy, Debug)]
+pub struct EdwardsPoint(Point);
+
+impl EdwardsPoint {
+    pub fn identity() -> Self {
+        EdwardsPoint(Point::identity())
+    }
+
+    /// Multiply the basepoint by a scalar, in constant time.
+    pub fn mul_base(scalar: &Scalar) -> Self {
+        EdwardsPoint(point::B * scalar.0)
+    }
+
+    pub fn compress(&self) -> CompressedEdwardsY {
+        CompressedEdwardsY(self.0.into())
+    }
+
+    /// Calculate a * A + b * B, in variable time, for the basepoint B.
+    pub fn vartime_double_scalar_mul_basepoint(
+        a: &Scalar,
+        big_a: &EdwardsPoint,
+        b: &Scalar,
+    ) -> Self {
+        EdwardsPoint(Point::vartime_multiscalar_mul(
+            &[a.0, b.0],
+            &[big_a.0, point::B],
+        ))
+    }
+}
+
+impl From<Point> for EdwardsPoint {
+    fn from(point: Point) -> Self {
+        EdwardsPoint(point)
+    }
+}
+
+impl From<EdwardsPoint> for Point {
+    fn from(point: EdwardsPoint) -> Self {
+        point.0
+    }
+}
+
+impl ConstantTimeEq for EdwardsPoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+/// This compares in constant time, like `ct_eq`.
+impl PartialEq for EdwardsPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for EdwardsPoint {}
+
+impl Add for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn add(self, other: EdwardsPoint) -> Self::Output {
+        EdwardsPoint(self.0 + other.0)
+    }
+}
+
+impl Sub for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn sub(self, other: EdwardsPoint) -> Self::Output {
+        EdwardsPoint(self.0 + -other.0)
+    }
+}
+
+impl Neg for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn neg(self) -> Self::Output {
+        EdwardsPoint(-self.0)
+    }
+}
+
+impl Mul<Scalar> for EdwardsPoint {
+    type Output = EdwardsPoint;
+
+    fn mul(self, scalar: Scalar) -> Self::Output {
+        EdwardsPoint(self.0 * scalar.0)
+    }
+}
+
+/// An Ed25519 signature, like the `Signature` used by ed25519-dalek.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature(RawSignature);
+
+impl Signature {
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Self {
+        Signature(RawSignature { bytes: *bytes })
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0.bytes
+    }
+}
+
+impl From<RawSignature> for Signature {
+    fn from(signature: RawSignature) -> Self {
+        Signature(signature)
+    }
+}
+
+impl From<Signature> for RawSignature {
+    fn from(signature: Signature) -> Self {
+        signature.0
+    }
+}
+
+/// A public key, like ed25519-dalek's `VerifyingKey`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifyingKey(PublicKey);
+
+impl VerifyingKey {
+    /// Load a public key, failing if it doesn't encode a point.
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LENGTH]) -> Result<Self, SignatureError> {
+        PublicKey::from_bytes(*bytes).map(VerifyingKey)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_LENGTH] {
+        self.0.as_bytes()
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        Ok(self.0.try_verify(message, signature.0)?)
+    }
+
+    /// Verify a signature, also rejecting public keys of small order.
+    ///
+    /// This crate already rejects non canonical encodings of s, and of R, when verifying.
+    pub fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let a = Point::try_from(&self.0.bytes[..]).map_err(|_| SignatureError::PointNotOnCurve)?;
+        if bool::from(a.doubled().doubled().doubled().ct_eq(&Point::identity())) {
+            return Err(SignatureError::WeakPublicKey);
+        }
+        self.verify(message, signature)
+    }
+}
+
+impl From<PublicKey> for VerifyingKey {
+    fn from(public: PublicKey) -> Self {
+        VerifyingKey(public)
+    }
+}
+
+impl From<VerifyingKey> for PublicKey {
+    fn from(key: VerifyingKey) -> Self {
+        key.0
+    }
+}
+
+/// A private key, along with its public key, like ed25519-dalek's `SigningKey`.
+#[derive(Clone, Debug)]
+pub struct SigningKey {
+    secret: PrivateKey,
+    public: VerifyingKey,
+}
+
+impl SigningKey {
+    pub fn from_bytes(secret: &SecretKey) -> Self {
+        PrivateKey::from_seed(*secret).into()
+    }
+
+    pub fn generate<R: RngCore + CryptoRng>(csprng: &mut R) -> Self {
+        super::gen_keypair(csprng).1.into()
+    }
+
+    /// Load a key from the 64 byte layout of secret key, followed by public key.
+    ///
+    /// This fails if the public key doesn't match the secret key.
+    pub fn from_keypair_bytes(bytes: &[u8; KEYPAIR_LENGTH]) -> Result<Self, SignatureError> {
+        let mut secret = [0; SECRET_KEY_LENGTH];
+        secret.copy_from_slice(&bytes[..SECRET_KEY_LENGTH]);
+        let key = Self::from_bytes(&secret);
+        if key.public.as_bytes()[..] != bytes[SECRET_KEY_LENGTH..] {
+            return Err(SignatureError::InvalidEncoding);
+        }
+        Ok(key)
+    }
+
+    pub fn to_bytes(&self) -> SecretKey {
+        self.secret.to_bytes()
+    }
+
+    pub fn to_keypair_bytes(&self) -> [u8; KEYPAIR_LENGTH] {
+        let mut out = [0; KEYPAIR_LENGTH];
+        out[..SECRET_KEY_LENGTH].copy_from_slice(self.secret.as_bytes());
+        out[SECRET_KEY_LENGTH..].copy_from_slice(self.public.as_bytes());
+        out
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.public
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        Signature(self.secret.sign(message))
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.public.verify(message, signature)
+    }
+}
+
+impl From<PrivateKey> for SigningKey {
+    fn from(secret: PrivateKey) -> Self {
+        let public = VerifyingKey(secret.derive_public_key());
+        SigningKey { secret, public }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signing_matches_crate() {
+        let private = PrivateKey::from_seed([8; 32]);
+        let key = SigningKey::from_bytes(&[8; 32]);
+        assert_eq!(key.verifying_key(), private.derive_public_key().into());
+        let signature = key.sign(b"switching over");
+        assert_eq!(signature, private.sign(b"switching over").into());
+        let bytes = signature.to_bytes();
+        assert!(key
+            .verifying_key()
+            .verify_strict(b"switching over", &Signature::from_bytes(&bytes))
+            .is_ok());
+        assert_eq!(
+            key.verify(b"switching", &signature),
+            Err(SignatureError::SignatureInvalid)
+        );
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        assert_eq!(
+            VerifyingKey::from_bytes(&identity)
+                .unwrap()
+                .verify_strict(b"switching over", &signature),
+            Err(SignatureError::WeakPublicKey)
+        );
+        let keypair = key.to_keypair_bytes();
+        assert_eq!(
+            SigningKey::from_keypair_bytes(&keypair).unwrap().to_bytes(),
+            [8; 32]
+        );
+        let mut mismatched = keypair;
+        mismatched[40] ^= 1;
+        assert!(SigningKey::from_keypair_bytes(&mismatched).is_err());
+    }
+
+    #[test]
+    fn test_points_and_scalars() {
+        let s = Scalar::from_bytes_mod_order([0xFF; 32]);
+        assert_eq!(Scalar::from_canonical_bytes(s.to_bytes()), Some(s));
+        assert_eq!(Scalar::from_canonical_bytes([0xFF; 32]), None);
+        assert_eq!(s * s.invert(), Scalar::ONE);
+        let p = EdwardsPoint::mul_base(&s);
+        assert_eq!(p.compress().decompress(), Some(p));
+        assert_eq!(p - p, EdwardsPoint::identity());
+        let two = Scalar::from(2);
+        assert_eq!(
+            EdwardsPoint::vartime_double_scalar_mul_basepoint(&two, &p, &s),
+            p * two + EdwardsPoint::mul_base(&s)
+        );
+        assert_eq!(Scalar::ZERO, Scalar::from(0));
+    }
+}
@@ -0,0 +1,261 @@
+//! This module implements COSE keys, and COSE_Sign1 messages, for Ed25519.
+//!
+//! Keys use the "OKP" key type, with the "Ed25519" curve, as per Section 7.2 of RFC 9053:
+//! https://datatracker.ietf.org/doc/html/rfc9053#section-7.2
+//!
+//! Messages use the "EdDSA" algorithm, and the structure of Section 4 of RFC 9052:
+//! https://datatracker.ietf.org/doc/html/rfc9052#section-4
+
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+use core::convert::TryFrom;
+
+use subtle::ConstantTimeEq;
+
+use crate::cbor::{self, Value};
+use crate::zeroize::{Zeroize, Zeroizing};
+
+use super::{Error, PrivateKey, PublicKey, Signature, PRIVATE_KEY_SIZE};
+
+// Labels and values, from the IANA COSE registries.
+const KTY: i128 = 1;
+const ALG: i128 = 3;
+const CRV: i128 = -1;
+const X: i128 = -2;
+const D: i128 = -4;
+const KTY_OKP: i128 = 1;
+const CRV_ED25519: i128 = 6;
+const ALG_EDDSA: i128 = -8;
+const HEADER_ALG: i128 = 1;
+const HEADER_CRIT: i128 = 2;
+/// The tag for COSE_Sign1_Tagged.
+const SIGN1_TAG: u64 = 18;
+
+fn encode_key(x: &[u8], d: Option<&[u8]>) -> Vec<u8> {
+    let mut entries = vec![
+        (Value::Int(KTY), Value::Int(KTY_OKP)),
+        (Value::Int(ALG), Value::Int(ALG_EDDSA)),
+        (Value::Int(CRV), Value::Int(CRV_ED25519)),
+        (Value::Int(X), Value::Bytes(x.to_vec())),
+    ];
+    if let Some(d) = d {
+        entries.push((Value::Int(D), Value::Bytes(d.to_vec())));
+    }
+    let key = Zeroizing::new(Value::Map(entries));
+    cbor::encode(&key)
+}
+
+impl Zeroize for Value {
+    /// Erase the byte strings of a key, one of which may be the private key.
+    fn zeroize(&mut self) {
+        if let Value::Map(entries) = self {
+            for (_, v) in entries {
+                if let Value::Bytes(b) = v {
+                    b.zeroize();
+                }
+            }
+        }
+    }
+}
+
+/// Parse an Ed25519 COSE key, returning the map, once checked.
+fn parse_key(input: &[u8]) -> Result<Value, Error> {
+    let key = cbor::decode(input)?;
+    if !matches!(key, Value::Map(_)) {
+        return Err(Error::InvalidEncoding);
+    }
+    if key.get(KTY) != Some(&Value::Int(KTY_OKP)) || key.get(CRV) != Some(&Value::Int(CRV_ED25519))
+    {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    match key.get(ALG) {
+        None => {}
+        Some(Value::Int(ALG_EDDSA)) => {}
+        Some(_) => return Err(Error::UnsupportedAlgorithm),
+    }
+    Ok(key)
+}
+
+fn get_bytes(map: &Value, label: i128) -> Result<&[u8], Error> {
+    match map.get(label) {
+        Some(Value::Bytes(b)) => Ok(b),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// The protected header of our messages, which only contains the algorithm.
+fn protected_header() -> Vec<u8> {
+    cbor::encode(&Value::Map(vec![(
+        Value::Int(HEADER_ALG),
+        Value::Int(ALG_EDDSA),
+    )]))
+}
+
+/// The Sig_structure of Section 4.4, which is what actually gets signed.
+fn to_be_signed(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Vec<u8> {
+    cbor::encode(&Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+impl PublicKey {
+    /// Encode this key as a COSE_Key, with its "alg" set to "EdDSA".
+    pub fn to_cose_key(&self) -> Vec<u8> {
+        encode_key(&self.bytes, None)
+    }
+
+    /// Parse a COSE_Key containing an Ed25519 public key.
+    ///
+    /// Like with JWKs, any private key in the map gets ignored.
+    pub fn from_cose_key(input: &[u8]) -> Result<Self, Error> {
+        let key = parse_key(input)?;
+        PublicKey::try_from(get_bytes(&key, X)?)
+    }
+
+    /// Verify a COSE_Sign1 message, made by this key, returning its payload.
+    ///
+    /// The external data has to match what was passed when signing. Both tagged, and
+    /// untagged messages are accepted, but the payload can't be detached.
+    pub fn verify_cose_sign1(&self, message: &[u8], external_aad: &[u8]) -> Result<Vec<u8>, Error> {
+        let message = match cbor::decode(message)? {
+            Value::Tag(SIGN1_TAG, inner) => *inner,
+            Value::Tag(_, _) => return Err(Error::InvalidEncoding),
+            untagged => untagged,
+        };
+        let (protected, payload, signature) = match message {
+            Value::Array(items) => match <[Value; 4]>::try_from(items) {
+                Ok([Value::Bytes(p), Value::Map(_), Value::Bytes(m), Value::Bytes(s)]) => (p, m, s),
+                _ => return Err(Error::InvalidEncoding),
+            },
+            _ => return Err(Error::InvalidEncoding),
+        };
+        let header = cbor::decode(&protected)?;
+        // The algorithm has to be protected, and we don't understand any critical headers.
+        if header.get(HEADER_ALG) != Some(&Value::Int(ALG_EDDSA))
+            || header.get(HEADER_CRIT).is_some()
+        {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let signature = Signature::try_from(&signature[..])?;
+        if !self.verify(&to_be_signed(&protected, external_aad, &payload), signature) {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(payload)
+    }
+}
+
+impl PrivateKey {
+    /// Encode this key as a COSE_Key, including both "x", and the private "d".
+    pub fn to_cose_key(&self) -> Vec<u8> {
+        encode_key(&self.derive_public_key().bytes, Some(&self.bytes))
+    }
+
+    /// Parse a COSE_Key containing an Ed25519 private key.
+    ///
+    /// If the public key "x" is present, it has to match the private key.
+    pub fn from_cose_key(input: &[u8]) -> Result<Self, Error> {
+        let key = Zeroizing::new(parse_key(input)?);
+        Self::from_parsed_key(&key)
+    }
+
+    fn from_parsed_key(key: &Value) -> Result<Self, Error> {
+        let bytes = <[u8; PRIVATE_KEY_SIZE]>::try_from(get_bytes(key, D)?)
+            .map_err(|_| Error::InvalidLength)?;
+        let out = PrivateKey::from(bytes);
+        if key.get(X).is_some()
+            && !bool::from(out.derive_public_key().bytes[..].ct_eq(get_bytes(key, X)?))
+        {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(out)
+    }
+
+    /// Sign a payload, producing a tagged COSE_Sign1 message.
+    ///
+    /// The external data isn't included in the message, but gets signed, and has to be
+    /// passed again to verify.
+    pub fn sign_cose_sign1(&self, payload: &[u8], external_aad: &[u8]) -> Vec<u8> {
+        let protected = protected_header();
+        let signature = self.sign(&to_be_signed(&protected, external_aad, payload));
+        cbor::encode(&Value::Tag(
+            SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(Vec::new()),
+                Value::Bytes(payload.to_vec()),
+                Value::Bytes(signature.bytes.to_vec()),
+            ])),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example_key() -> PrivateKey {
+        let mut bytes = [0; PRIVATE_KEY_SIZE];
+        hex::decode_to_slice(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            &mut bytes,
+        )
+        .unwrap();
+        PrivateKey::from(bytes)
+    }
+
+    #[test]
+    fn test_cose_key() {
+        let key = example_key();
+        let public = key.derive_public_key();
+        assert_eq!(
+            hex::encode(public.to_cose_key()),
+            "a4010103272006215820d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(PublicKey::from_cose_key(&public.to_cose_key()), Ok(public));
+        let decoded = PrivateKey::from_cose_key(&key.to_cose_key()).unwrap();
+        assert_eq!(decoded.bytes, key.bytes);
+        assert!(PrivateKey::from_cose_key(&public.to_cose_key()).is_err());
+        // An EC2 key, on P-256, which we don't support
+        let ec2 = hex::decode(
+            "a301022001215820d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        )
+        .unwrap();
+        assert_eq!(
+            PublicKey::from_cose_key(&ec2),
+            Err(Error::UnsupportedAlgorithm)
+        );
+    }
+
+    #[test]
+    fn test_sign1() {
+        let key = example_key();
+        let public = key.derive_public_key();
+        let message = key.sign_cose_sign1(b"payload", b"context");
+        // A tag, an array of 4 items, and the protected header, containing {1: -8}
+        assert_eq!(hex::encode(&message[..6]), "d28443a10127");
+        assert_eq!(
+            public.verify_cose_sign1(&message, b"context").unwrap(),
+            b"payload"
+        );
+        assert_eq!(
+            public.verify_cose_sign1(&message, b"other context"),
+            Err(Error::SignatureInvalid)
+        );
+
+        // The signature is over the Sig_structure, which we can check against the key directly.
+        let signature = Signature::try_from(&message[message.len() - 64..]).unwrap();
+        let mut expected =
+            hex::decode("846a5369676e61747572653143a1012747636f6e74657874477061796c6f6164")
+                .unwrap();
+        assert!(public.verify(&expected, signature));
+        expected[1] ^= 1;
+        assert!(!public.verify(&expected, signature));
+        // Untagged messages are also accepted
+        assert_eq!(
+            public.verify_cose_sign1(&message[1..], b"context").unwrap(),
+            b"payload"
+        );
+    }
+}
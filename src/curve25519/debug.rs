@@ -0,0 +1,161 @@
+//! This module contains tools for inspecting values while developing the crate.
+//!
+//! These are only available with the `debug-tools` feature, and should never be
+//! enabled in production builds. Some of the functions here format secret values,
+//! and none of them are constant-time.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{convert::TryFrom, fmt::Write};
+
+use crate::uint::{U, U256, U512};
+
+use super::{
+    field::Z25519,
+    point::Point,
+    scalar::{Scalar, L},
+    PublicKey, Signature,
+};
+
+/// Format a big integer as big-endian hexadecimal, prefixed with "0x".
+pub(crate) fn hex<const N: usize>(x: &U<N>) -> String {
+    let mut out = String::from("0x");
+    for limb in x.limbs.iter().rev() {
+        write!(out, "{:016x}", limb).unwrap();
+    }
+    out
+}
+
+/// Format a big integer in decimal.
+pub(crate) fn decimal<const N: usize>(x: &U<N>) -> String {
+    // We repeatedly divide by 10¹⁹, the largest power of 10 fitting in a limb.
+    const CHUNK: u64 = 10_000_000_000_000_000_000;
+    let mut x = *x;
+    let mut chunks = Vec::new();
+    while x.limbs.iter().any(|&l| l != 0) {
+        let mut remainder = 0u128;
+        for limb in x.limbs.iter_mut().rev() {
+            let current = (remainder << 64) | u128::from(*limb);
+            *limb = (current / u128::from(CHUNK)) as u64;
+            remainder = current % u128::from(CHUNK);
+        }
+        chunks.push(remainder as u64);
+    }
+    match chunks.split_last() {
+        None => String::from("0"),
+        Some((first, rest)) => {
+            let mut out = first.to_string();
+            for chunk in rest.iter().rev() {
+                write!(out, "{:019}", chunk).unwrap();
+            }
+            out
+        }
+    }
+}
+
+/// Format the individual limbs of a big integer, least significant first.
+pub(crate) fn limbs<const N: usize>(x: &U<N>) -> String {
+    let formatted: Vec<String> = x.limbs.iter().map(|l| format!("{:016x}", l)).collect();
+    format!("[{}]", formatted.join(", "))
+}
+
+pub(crate) fn field_element(x: &Z25519) -> String {
+    let bytes: [u8; 32] = (*x).into();
+    hex(&U256::from(bytes))
+}
+
+pub(crate) fn point(p: &Point) -> String {
+    let (x, y) = p.affine();
+    format!("(x: {}, y: {})", field_element(&x), field_element(&y))
+}
+
+pub(crate) fn scalar(s: &Scalar) -> String {
+    format!("{} ({})", hex(&s.value), decimal(&s.value))
+}
+
+/// Format the affine coordinates of a public key, or why it fails to decode.
+pub fn public_key(public: &PublicKey) -> String {
+    match Point::try_from(&public.bytes[..]) {
+        Ok(p) => point(&p),
+        Err(_) => String::from("<invalid point>"),
+    }
+}
+
+/// Format both halves of a signature, as a point R, and a scalar s.
+pub fn signature(signature: &Signature) -> String {
+    let r = match Point::try_from(&signature.bytes[..32]) {
+        Ok(p) => point(&p),
+        Err(_) => String::from("<invalid point>"),
+    };
+    let s = match Scalar::try_from(&signature.bytes[32..]) {
+        Ok(s) => scalar(&s),
+        Err(_) => String::from("<non canonical scalar>"),
+    };
+    format!("R: {}\ns: {}", r, s)
+}
+
+/// Trace the intermediate states of reducing 64 bytes modulo L.
+///
+/// This is the reduction used for hashes, when signing and verifying.
+pub fn scalar_reduction(bytes: [u8; 64]) -> String {
+    let input: U512 = Scalar::wide_input(bytes);
+    let q = Scalar::barret_quotient(input);
+    let q_l = q * L;
+    let result = Scalar::from(bytes);
+    let mut out = String::new();
+    writeln!(out, "input:  {}", limbs(&input)).unwrap();
+    writeln!(out, "q:      {}", limbs(&q)).unwrap();
+    writeln!(out, "q * L:  {}", limbs(&q_l)).unwrap();
+    write!(out, "result: {}", scalar(&result)).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::point::B;
+    use super::*;
+
+    #[test]
+    fn test_decimal_examples() {
+        assert_eq!(decimal(&U256::from(0)), "0");
+        assert_eq!(decimal(&U256::from(1234)), "1234");
+        assert_eq!(
+            decimal(&L),
+            "7237005577332262213973186563042994240857116359379907606001950938285454250989"
+        );
+        assert_eq!(
+            decimal(&U256 {
+                limbs: [0, 1, 0, 0]
+            }),
+            "18446744073709551616"
+        );
+    }
+
+    #[test]
+    fn test_hex_examples() {
+        assert_eq!(
+            hex(&L),
+            "0x1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed"
+        );
+    }
+
+    #[test]
+    fn test_basepoint_affine() {
+        let public = PublicKey { bytes: B.into() };
+        assert_eq!(
+            public_key(&public),
+            "(x: 0x216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a, \
+             y: 0x6666666666666666666666666666666666666666666666666666666666666658)"
+        );
+    }
+
+    #[test]
+    fn test_scalar_reduction_ends_with_result() {
+        let trace = scalar_reduction([0xFF; 64]);
+        assert!(trace.contains(&scalar(&Scalar::from([0xFF; 64]))));
+        assert_eq!(trace.lines().count(), 4);
+    }
+}
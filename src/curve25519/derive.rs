@@ -0,0 +1,51 @@
+//! This module implements deriving the public keys of many private keys at once.
+//!
+//! Each key still needs its own scalar multiplication, but the table of multiples
+//! of the basepoint gets shared, and the inversions needed to encode each point
+//! are batched together, using Montgomery's trick, in `Point::batch_encode`.
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use crate::{sha512, zeroize::Zeroizing};
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    PrivateKey, PublicKey,
+};
+
+impl PrivateKey {
+    /// Calculate the public keys of many private keys at once.
+    ///
+    /// This gives the same results as calling `derive_public_key` on each key,
+    /// but shares work between them, which adds up when deriving keys in bulk.
+    pub fn derive_public_keys(keys: &[PrivateKey]) -> Vec<PublicKey> {
+        let table = point::window_table(point::B);
+        let points: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                let hash = Zeroizing::new(sha512::hash(&key.bytes));
+                let scalar = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
+                point::mul_with_table(&table, &scalar)
+            })
+            .collect();
+        Point::batch_encode(&points)
+            .into_iter()
+            .map(|bytes| PublicKey { bytes })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_individual_derivation() {
+        let keys: Vec<_> = (0..20u8).map(|i| PrivateKey::from_seed([i; 32])).collect();
+        let expected: Vec<_> = keys.iter().map(|k| k.derive_public_key()).collect();
+        assert_eq!(PrivateKey::derive_public_keys(&keys), expected);
+        assert!(PrivateKey::derive_public_keys(&[]).is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! This module implements did:key identifiers for Ed25519 public keys:
+//! https://w3c-ccg.github.io/did-method-key/
+//!
+//! The key gets prefixed with its multicodec, 0xed as a varint, and then encoded
+//! as a base58btc multibase string, which always starts with "z6Mk".
+
+use alloc::{format, string::String};
+use core::convert::TryFrom;
+
+use crate::base58;
+
+use super::{Error, PublicKey};
+
+/// The multicodec for Ed25519 public keys, as an unsigned varint.
+const MULTICODEC: [u8; 2] = [0xed, 0x01];
+/// The multibase prefix for base58btc.
+const BASE58BTC: char = 'z';
+const DID_KEY: &str = "did:key:";
+
+impl PublicKey {
+    /// Encode this key as a multibase string, like "z6Mk...".
+    pub fn to_multibase(&self) -> String {
+        let mut data = MULTICODEC.to_vec();
+        data.extend_from_slice(&self.bytes);
+        format!("{}{}", BASE58BTC, base58::encode(&data))
+    }
+
+    /// Parse a key encoded as a base58btc multibase string, with the Ed25519 multicodec.
+    pub fn from_multibase(input: &str) -> Result<Self, Error> {
+        let encoded = input
+            .strip_prefix(BASE58BTC)
+            .ok_or(Error::UnsupportedAlgorithm)?;
+        let data = base58::decode(encoded).ok_or(Error::InvalidEncoding)?;
+        let key = data
+            .strip_prefix(&MULTICODEC[..])
+            .ok_or(Error::UnsupportedAlgorithm)?;
+        PublicKey::try_from(key)
+    }
+
+    /// Encode this key as a did:key identifier, like "did:key:z6Mk...".
+    pub fn to_did_key(&self) -> String {
+        format!("{}{}", DID_KEY, self.to_multibase())
+    }
+
+    /// Parse a did:key identifier, containing an Ed25519 key.
+    ///
+    /// A fragment is allowed, as in a verification method, but has to be the key itself.
+    pub fn from_did_key(input: &str) -> Result<Self, Error> {
+        let rest = input.strip_prefix(DID_KEY).ok_or(Error::InvalidEncoding)?;
+        let (encoded, fragment) = match rest.split_once('#') {
+            Some((encoded, fragment)) => (encoded, Some(fragment)),
+            None => (rest, None),
+        };
+        if fragment.is_some() && fragment != Some(encoded) {
+            return Err(Error::InvalidEncoding);
+        }
+        Self::from_multibase(encoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_did_key() {
+        // This is the first example of the did:key specification
+        let did = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        let public = PublicKey::from_did_key(did).unwrap();
+        assert_eq!(
+            hex::encode(public.bytes),
+            "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29"
+        );
+        assert_eq!(public.to_did_key(), did);
+        let method = format!("{}#{}", did, &did[DID_KEY.len()..]);
+        assert_eq!(PublicKey::from_did_key(&method), Ok(public));
+        assert!(PublicKey::from_did_key(&format!("{}#key-1", did)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_keys() {
+        // An X25519 key, with the 0xec multicodec
+        let mut data = vec![0xec, 0x01];
+        data.extend_from_slice(&[9; 32]);
+        let x25519 = format!("z{}", base58::encode(&data));
+        assert_eq!(
+            PublicKey::from_multibase(&x25519),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            PublicKey::from_multibase("m7QE"),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            PublicKey::from_did_key("did:web:example.com"),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! This module implements Pedersen's distributed key generation, from "A Threshold
+//! Cryptosystem without a Trusted Party", with the complaint handling of Gennaro et al.'s
+//! "Secure Distributed Key Generation for Discrete-Log Based Cryptosystems":
+//! https://link.springer.com/content/pdf/10.1007/3-540-46416-6_47.pdf
+//!
+//! Every participant deals a Feldman sharing of a random secret to all the others,
+//! and the key is the sum of all these secrets, which nobody ever learns. The result
+//! is a set of `FrostKeyShare`s, just like those from a trusted dealer.
+//!
+//! Each dealer also proves knowledge of its secret, as in FROST's key generation, which
+//! stops a dealer from choosing its commitment based on the others, to cancel them out.
+//!
+//! This assumes a broadcast channel: every participant has to see the same commitments,
+//! complaints, and responses. The shares themselves have to be sent privately.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    frost::FrostKeyShare,
+    point::Point,
+    scalar::Scalar,
+    schnorr::{self, SchnorrProof},
+    vss::{self, FeldmanCommitment, SecretShare},
+    Error, PublicKey,
+};
+
+/// The commitments of one dealer, broadcast in the first round.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DkgCommitment {
+    pub dealer: u16,
+    pub coefficients: Vec<[u8; 32]>,
+    /// A proof of knowledge of the secret behind the first coefficient.
+    pub proof: SchnorrProof,
+}
+
+const PROOF_CONTEXT: &[u8] = b"Pedersen DKG commitment";
+
+/// A share dealt to one participant, sent privately in the first round, or
+/// broadcast after a complaint.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DkgShare {
+    pub dealer: u16,
+    pub recipient: u16,
+    pub value: [u8; 32],
+}
+
+/// This never prints the share itself.
+impl fmt::Debug for DkgShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DkgShare")
+            .field("dealer", &self.dealer)
+            .field("recipient", &self.recipient)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DkgShare {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// A complaint, by some participant, that a dealer sent it no share, or an invalid one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DkgComplaint {
+    pub dealer: u16,
+    pub accuser: u16,
+}
+
+/// The result of the key generation, for one participant.
+#[derive(Debug)]
+pub struct DkgOutput {
+    pub key_share: FrostKeyShare,
+    /// The dealers whose sharings make up the key, in increasing order.
+    pub qualified: Vec<u16>,
+}
+
+/// The state of one participant, through the rounds of the key generation.
+pub struct DkgParticipant {
+    identifier: u16,
+    min_signers: u16,
+    max_signers: u16,
+    dealt: Vec<SecretShare>,
+    coefficients: Vec<[u8; 32]>,
+    proof: SchnorrProof,
+    commitments: BTreeMap<u16, FeldmanCommitment>,
+    received: BTreeMap<u16, Scalar>,
+}
+
+impl DkgParticipant {
+    /// Start taking part, with identifier between 1 and `max_signers`, dealing a random secret.
+    pub fn new<R: RngCore + CryptoRng>(
+        identifier: u16,
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        if min_signers < 2 || identifier == 0 || identifier > max_signers {
+            return Err(Error::InvalidLength);
+        }
+        let secret = Zeroizing::new(Scalar::random(rng));
+        let (dealt, commitment) = vss::share_secret(*secret, min_signers, max_signers, rng)?;
+        let coefficients = commitment.to_bytes();
+        let proof = schnorr::prove(
+            *secret,
+            &coefficients[0],
+            &identifier.to_be_bytes(),
+            PROOF_CONTEXT,
+            rng,
+        );
+        Ok(DkgParticipant {
+            identifier,
+            min_signers,
+            max_signers,
+            dealt,
+            coefficients,
+            proof,
+            commitments: BTreeMap::new(),
+            received: BTreeMap::new(),
+        })
+    }
+
+    /// The commitment to broadcast to everyone in the first round.
+    pub fn commitment(&self) -> DkgCommitment {
+        DkgCommitment {
+            dealer: self.identifier,
+            coefficients: self.coefficients.clone(),
+            proof: self.proof,
+        }
+    }
+
+    /// The shares to send privately to each other participant in the first round.
+    pub fn shares(&self) -> Vec<DkgShare> {
+        self.dealt
+            .iter()
+            .filter(|s| s.index != self.identifier)
+            .map(|s| self.dealt_share(s.index))
+            .collect()
+    }
+
+    fn dealt_share(&self, recipient: u16) -> DkgShare {
+        DkgShare {
+            dealer: self.identifier,
+            recipient,
+            value: self.dealt[usize::from(recipient) - 1].value.into(),
+        }
+    }
+
+    /// Check a share against the commitment of its dealer, returning the value if it's valid.
+    fn check(&self, share: &DkgShare) -> Option<Scalar> {
+        let commitment = self.commitments.get(&share.dealer)?;
+        let value = Scalar::try_from(&share.value[..]).ok()?;
+        let share = SecretShare {
+            index: share.recipient,
+            value,
+        };
+        commitment.verify(&share).ok()?;
+        Some(value)
+    }
+
+    /// Receive the commitments of all the dealers, and the shares sent to us.
+    ///
+    /// This returns the complaints to broadcast, against the dealers whose shares were
+    /// missing, or didn't match their commitment. Malformed commitments, or those
+    /// with an invalid proof, get ignored, which excludes their dealers from the key.
+    pub fn receive(
+        &mut self,
+        commitments: &[DkgCommitment],
+        shares: &[DkgShare],
+    ) -> Vec<DkgComplaint> {
+        for commitment in commitments {
+            if commitment.dealer == 0
+                || commitment.dealer > self.max_signers
+                || commitment.coefficients.len() != usize::from(self.min_signers)
+                || self.commitments.contains_key(&commitment.dealer)
+                || schnorr::verify(
+                    &commitment.coefficients[0],
+                    &commitment.proof,
+                    &commitment.dealer.to_be_bytes(),
+                    PROOF_CONTEXT,
+                )
+                .is_err()
+            {
+                continue;
+            }
+            if let Ok(decoded) = FeldmanCommitment::from_bytes(&commitment.coefficients) {
+                self.commitments.insert(commitment.dealer, decoded);
+            }
+        }
+        let own = self.dealt[usize::from(self.identifier) - 1].value;
+        self.received.insert(self.identifier, own);
+        let identifier = self.identifier;
+        for share in shares.iter().filter(|s| s.recipient == identifier) {
+            if let Some(value) = self.check(share) {
+                self.received.entry(share.dealer).or_insert(value);
+            }
+        }
+        self.commitments
+            .keys()
+            .filter(|dealer| !self.received.contains_key(dealer))
+            .map(|&dealer| DkgComplaint {
+                dealer,
+                accuser: self.identifier,
+            })
+            .collect()
+    }
+
+    /// Answer the complaints against us, by revealing the shares in question to everyone.
+    pub fn respond(&self, complaints: &[DkgComplaint]) -> Vec<DkgShare> {
+        complaints
+            .iter()
+            .filter(|c| {
+                c.dealer == self.identifier && c.accuser >= 1 && c.accuser <= self.max_signers
+            })
+            .map(|c| self.dealt_share(c.accuser))
+            .collect()
+    }
+
+    /// Finish, with all the complaints, and the responses from the accused dealers.
+    ///
+    /// Dealers which failed to answer a complaint with a valid share are disqualified.
+    pub fn finish(
+        mut self,
+        complaints: &[DkgComplaint],
+        responses: &[DkgShare],
+    ) -> Result<DkgOutput, Error> {
+        let mut disqualified = Vec::new();
+        for complaint in complaints {
+            let answer = responses
+                .iter()
+                .filter(|r| r.dealer == complaint.dealer && r.recipient == complaint.accuser)
+                .find_map(|r| self.check(r));
+            match answer {
+                Some(value) if complaint.accuser == self.identifier => {
+                    self.received.insert(complaint.dealer, value);
+                }
+                Some(_) => {}
+                None => disqualified.push(complaint.dealer),
+            }
+        }
+        let qualified: Vec<u16> = self
+            .commitments
+            .keys()
+            .cloned()
+            .filter(|dealer| !disqualified.contains(dealer))
+            .collect();
+        if !qualified.contains(&self.identifier) {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut secret = Scalar::from(0);
+        let mut public = Point::identity();
+        for dealer in qualified.iter() {
+            secret += *self.received.get(dealer).ok_or(Error::InvalidEncoding)?;
+            public = public + self.commitments[dealer].public_key();
+        }
+        let public: [u8; 32] = public.into();
+        if public == <[u8; 32]>::from(Point::identity()) {
+            return Err(Error::WeakPublicKey);
+        }
+        let group_public_key = PublicKey { bytes: public };
+        Ok(DkgOutput {
+            key_share: FrostKeyShare::from_parts(
+                self.identifier,
+                self.min_signers,
+                secret,
+                group_public_key,
+            ),
+            qualified,
+        })
+    }
+}
+
+/// This never prints the secrets being dealt.
+impl fmt::Debug for DkgParticipant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DkgParticipant")
+            .field("identifier", &self.identifier)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DkgParticipant {
+    fn drop(&mut self) {
+        for share in self.dealt.iter_mut() {
+            share.value.zeroize();
+        }
+        for value in self.received.values_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    use crate::curve25519::frost::{frost_aggregate, FrostCommitment};
+
+    /// Run the whole protocol, letting `tamper` modify the private shares in transit.
+    fn run(n: u16, t: u16, tamper: impl Fn(&mut DkgShare)) -> Vec<DkgOutput> {
+        let mut participants: Vec<_> = (1..=n)
+            .map(|i| DkgParticipant::new(i, t, n, &mut OsRng).unwrap())
+            .collect();
+        let commitments: Vec<_> = participants.iter().map(|p| p.commitment()).collect();
+        let mut shares: Vec<_> = participants.iter().flat_map(|p| p.shares()).collect();
+        shares.iter_mut().for_each(tamper);
+        let complaints: Vec<_> = participants
+            .iter_mut()
+            .flat_map(|p| p.receive(&commitments, &shares))
+            .collect();
+        let responses: Vec<_> = participants
+            .iter()
+            .flat_map(|p| p.respond(&complaints))
+            .collect();
+        participants
+            .into_iter()
+            .map(|p| p.finish(&complaints, &responses).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_generated_key_signs() {
+        let outputs = run(3, 2, |_| {});
+        let public = outputs[0].key_share.group_public_key();
+        assert!(outputs
+            .iter()
+            .all(|o| o.key_share.group_public_key() == public && o.qualified == [1, 2, 3]));
+
+        let signers = [&outputs[0].key_share, &outputs[2].key_share];
+        let (nonces, commitments): (Vec<_>, Vec<FrostCommitment>) =
+            signers.iter().map(|s| s.commit(&mut OsRng)).unzip();
+        let shares: Vec<_> = signers
+            .iter()
+            .zip(nonces)
+            .map(|(s, n)| s.sign(n, b"message", &commitments).unwrap())
+            .collect();
+        let signature = frost_aggregate(&public, b"message", &commitments, &shares).unwrap();
+        assert!(public.verify(b"message", signature));
+    }
+
+    #[test]
+    fn test_complaints() {
+        // A share corrupted in transit gets revealed, and the dealer stays qualified.
+        let outputs = run(3, 2, |s| {
+            if s.dealer == 1 && s.recipient == 2 {
+                s.value[0] ^= 1;
+            }
+        });
+        assert!(outputs.iter().all(|o| o.qualified == [1, 2, 3]));
+        let public = outputs[0].key_share.group_public_key();
+        assert!(outputs
+            .iter()
+            .all(|o| o.key_share.group_public_key() == public));
+
+        // A dealer which can't answer a complaint with a valid share gets disqualified.
+        let mut participants: Vec<_> = (1..=3)
+            .map(|i| DkgParticipant::new(i, 2, 3, &mut OsRng).unwrap())
+            .collect();
+        let commitments: Vec<_> = participants.iter().map(|p| p.commitment()).collect();
+        let shares: Vec<_> = participants
+            .iter()
+            .flat_map(|p| p.shares())
+            .filter(|s| s.dealer != 3)
+            .collect();
+        let complaints: Vec<_> = participants
+            .iter_mut()
+            .flat_map(|p| p.receive(&commitments, &shares))
+            .collect();
+        assert_eq!(complaints.len(), 2);
+        let output = participants.remove(0).finish(&complaints, &[]).unwrap();
+        assert_eq!(output.qualified, [1, 2]);
+
+        // So does a dealer whose proof doesn't match its commitment.
+        let mut participants: Vec<_> = (1..=3)
+            .map(|i| DkgParticipant::new(i, 2, 3, &mut OsRng).unwrap())
+            .collect();
+        let mut commitments: Vec<_> = participants.iter().map(|p| p.commitment()).collect();
+        commitments[1].proof = commitments[2].proof;
+        let shares: Vec<_> = participants.iter().flat_map(|p| p.shares()).collect();
+        assert!(participants[0].receive(&commitments, &shares).is_empty());
+        let output = participants.remove(0).finish(&[], &[]).unwrap();
+        assert_eq!(output.qualified, [1, 3]);
+        assert!(DkgParticipant::new(0, 2, 3, &mut OsRng).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! This module implements Chaum-Pedersen proofs of discrete log equality, from
+//! "Wallet Databases with Observers":
+//! https://link.springer.com/content/pdf/10.1007/3-540-48071-4_7.pdf
+//!
+//! A proof shows that log_B(A) = log_C(D), without revealing the logarithm. This is
+//! the core of VRFs, verifiable OPRFs, and verifiable shuffles, which is why it's exposed
+//! on its own, under `hazmat`.
+//!
+//! Many pairs sharing the same logarithm can be proven at once, by combining them
+//! with random weights derived from a hash of all of them, as in RFC 9497. The
+//! points are assumed to be in the prime order subgroup, which isn't checked.
+// Nothing outside of hazmat uses this yet.
+#![cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+
+use alloc::{vec, vec::Vec};
+use core::convert::TryFrom;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::sha512::Sha512;
+use crate::zeroize::Zeroizing;
+
+use super::{point::Point, scalar::Scalar, Error};
+
+/// The size of a proof, containing a challenge, and a response.
+pub const DLEQ_PROOF_SIZE: usize = 64;
+
+const CONTEXT: &[u8] = b"DLEQ-edwards25519-SHA512";
+
+/// A proof that two pairs of points share the same discrete logarithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DleqProof {
+    pub bytes: [u8; DLEQ_PROOF_SIZE],
+}
+
+impl From<[u8; DLEQ_PROOF_SIZE]> for DleqProof {
+    fn from(bytes: [u8; DLEQ_PROOF_SIZE]) -> Self {
+        DleqProof { bytes }
+    }
+}
+
+fn hash_points(tag: &[u8], points: &[Point]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(CONTEXT);
+    hasher.update(tag);
+    for encoded in Point::batch_encode(points) {
+        hasher.update(&encoded);
+    }
+    Scalar::from_hash(hasher)
+}
+
+fn challenge(b: Point, a: Point, c: Point, d: Point, r1: Point, r2: Point) -> Scalar {
+    hash_points(b"challenge", &[b, a, c, d, r1, r2])
+}
+
+/// Combine the pairs (C_i, D_i) into a single pair (M, Z), with weights from a hash.
+fn composite(b: Point, a: Point, pairs: &[(Point, Point)]) -> (Point, Point) {
+    let mut points = vec![b, a];
+    for &(c, d) in pairs {
+        points.push(c);
+        points.push(d);
+    }
+    let seed = hash_points(b"seed", &points);
+    let seed: [u8; 32] = seed.into();
+    let weights: Vec<Scalar> = (0..pairs.len() as u64)
+        .map(|i| {
+            let mut hasher = Sha512::new();
+            hasher.update(CONTEXT);
+            hasher.update(b"weight");
+            hasher.update(&seed);
+            hasher.update(&i.to_le_bytes());
+            Scalar::from_hash(hasher)
+        })
+        .collect();
+    let cs: Vec<Point> = pairs.iter().map(|&(c, _)| c).collect();
+    let ds: Vec<Point> = pairs.iter().map(|&(_, d)| d).collect();
+    (
+        Point::vartime_multiscalar_mul(&weights, &cs),
+        Point::vartime_multiscalar_mul(&weights, &ds),
+    )
+}
+
+/// Prove that log_B(A) = log_C(D) = x, with A = x * B, and D = x * C.
+pub fn prove_dleq<R: RngCore + CryptoRng>(x: Scalar, b: Point, c: Point, rng: &mut R) -> DleqProof {
+    let k = Zeroizing::new(Scalar::random(rng));
+    let e = challenge(b, b * x, c, c * x, b * *k, c * *k);
+    let s = *k + e * x;
+    let mut out = DleqProof {
+        bytes: [0; DLEQ_PROOF_SIZE],
+    };
+    out.bytes[..32].copy_from_slice(&<[u8; 32]>::from(e));
+    out.bytes[32..].copy_from_slice(&<[u8; 32]>::from(s));
+    out
+}
+
+/// Verify a proof that log_B(A) = log_C(D).
+pub fn verify_dleq(b: Point, a: Point, c: Point, d: Point, proof: &DleqProof) -> Result<(), Error> {
+    let e = Scalar::try_from(&proof.bytes[..32]).map_err(|_| Error::NonCanonicalScalar)?;
+    let s = Scalar::try_from(&proof.bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+    let r1 = Point::vartime_multiscalar_mul(&[s, -e], &[b, a]);
+    let r2 = Point::vartime_multiscalar_mul(&[s, -e], &[c, d]);
+    let expected: [u8; 32] = challenge(b, a, c, d, r1, r2).into();
+    if expected[..] != proof.bytes[..32] {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// Prove that D_i = x * C_i, for every pair, along with A = x * B, in a single proof.
+pub fn prove_dleq_batch<R: RngCore + CryptoRng>(
+    x: Scalar,
+    b: Point,
+    pairs: &[(Point, Point)],
+    rng: &mut R,
+) -> DleqProof {
+    let (m, _) = composite(b, b * x, pairs);
+    prove_dleq(x, b, m, rng)
+}
+
+/// Verify a proof from `prove_dleq_batch`, with the same pairs, in the same order.
+pub fn verify_dleq_batch(
+    b: Point,
+    a: Point,
+    pairs: &[(Point, Point)],
+    proof: &DleqProof,
+) -> Result<(), Error> {
+    if pairs.is_empty() {
+        return Err(Error::InvalidLength);
+    }
+    let (m, z) = composite(b, a, pairs);
+    verify_dleq(b, a, m, z, proof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    use crate::curve25519::point::B;
+
+    #[test]
+    fn test_dleq() {
+        let x = Scalar::from(42);
+        let c = B * Scalar::from(1000);
+        let proof = prove_dleq(x, B, c, &mut OsRng);
+        assert!(verify_dleq(B, B * x, c, c * x, &proof).is_ok());
+        assert_eq!(
+            verify_dleq(B, B * x, c, c * Scalar::from(43), &proof),
+            Err(Error::SignatureInvalid)
+        );
+        assert!(verify_dleq(B, B * x, B, c * x, &proof).is_err());
+    }
+
+    #[test]
+    fn test_dleq_batch() {
+        let x = Scalar::from(7);
+        let mut pairs: Vec<(Point, Point)> = (1..5u64)
+            .map(|i| {
+                let c = B * Scalar::from(i * 31);
+                (c, c * x)
+            })
+            .collect();
+        let proof = prove_dleq_batch(x, B, &pairs, &mut OsRng);
+        assert!(verify_dleq_batch(B, B * x, &pairs, &proof).is_ok());
+        pairs[2].1 = pairs[2].1 + B;
+        assert_eq!(
+            verify_dleq_batch(B, B * x, &pairs, &proof),
+            Err(Error::SignatureInvalid)
+        );
+        assert!(verify_dleq_batch(B, B * x, &[], &proof).is_err());
+    }
+}
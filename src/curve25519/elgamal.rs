@@ -0,0 +1,249 @@
+//! This module implements ElGamal encryption over the curve, from "A Public Key
+//! Cryptosystem and a Signature Scheme Based on Discrete Logarithms":
+//! https://link.springer.com/content/pdf/10.1007/3-540-39568-7_2.pdf
+//!
+//! Ciphertexts of points can be rerandomized, and added together, which is what
+//! mixnets and voting schemes rely on. Exponential ElGamal encrypts a small number m
+//! as the point m * B, so that sums of ciphertexts decrypt to sums of numbers, at the
+//! cost of a discrete log, which we find with baby step giant step.
+//!
+//! Hashed ElGamal encrypts arbitrary bytes, by masking them with SHAKE256 of the shared
+//! point. None of these ciphertexts are authenticated, so they're malleable by design.
+// Nothing outside of hazmat uses this.
+#![cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Add;
+use std::collections::HashMap;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::shake::Shake256;
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error,
+};
+
+/// Mask a message, in place, with SHAKE256 of the ephemeral point, and the shared point.
+fn mask(ephemeral: &[u8; 32], shared: Point, message: &mut [u8]) {
+    let mut shake = Shake256::new();
+    shake.update(b"hashed ElGamal");
+    shake.update(ephemeral);
+    shake.update(&shared.encode());
+    let mut stream = Zeroizing::new(vec![0; message.len()]);
+    shake.finalize().read(&mut stream);
+    for (m, s) in message.iter_mut().zip(stream.iter()) {
+        *m ^= s;
+    }
+}
+
+/// An ElGamal ciphertext, (r * B, M + r * Y), of a point M.
+#[derive(Clone, Copy, Debug)]
+pub struct ElGamalCiphertext {
+    pub ephemeral: Point,
+    pub masked: Point,
+}
+
+/// Adding ciphertexts produces a ciphertext of the sum of their points.
+impl Add for ElGamalCiphertext {
+    type Output = ElGamalCiphertext;
+
+    fn add(self, other: ElGamalCiphertext) -> Self::Output {
+        ElGamalCiphertext {
+            ephemeral: self.ephemeral + other.ephemeral,
+            masked: self.masked + other.masked,
+        }
+    }
+}
+
+/// The public key anyone can encrypt to.
+#[derive(Clone, Copy, Debug)]
+pub struct ElGamalPublicKey {
+    pub point: Point,
+}
+
+impl ElGamalPublicKey {
+    /// Encrypt a point, with fresh randomness.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        message: Point,
+        rng: &mut R,
+    ) -> ElGamalCiphertext {
+        let r = Zeroizing::new(Scalar::random(rng));
+        ElGamalCiphertext {
+            ephemeral: point::B * *r,
+            masked: message + self.point * *r,
+        }
+    }
+
+    /// Encrypt a number m, as the point m * B.
+    pub fn encrypt_exponent<R: RngCore + CryptoRng>(
+        &self,
+        m: u64,
+        rng: &mut R,
+    ) -> ElGamalCiphertext {
+        self.encrypt(point::B * Scalar::from(m), rng)
+    }
+
+    /// Produce a fresh ciphertext of the same point, unlinkable to the original one.
+    pub fn rerandomize<R: RngCore + CryptoRng>(
+        &self,
+        ciphertext: &ElGamalCiphertext,
+        rng: &mut R,
+    ) -> ElGamalCiphertext {
+        *ciphertext + self.encrypt(Point::identity(), rng)
+    }
+
+    /// Encrypt some bytes with hashed ElGamal, producing a ciphertext 32 bytes longer.
+    pub fn encrypt_hashed<R: RngCore + CryptoRng>(&self, message: &[u8], rng: &mut R) -> Vec<u8> {
+        let r = Zeroizing::new(Scalar::random(rng));
+        let ephemeral = (point::B * *r).encode();
+        let mut out = ephemeral.to_vec();
+        out.extend_from_slice(message);
+        mask(&ephemeral, self.point * *r, &mut out[32..]);
+        out
+    }
+}
+
+/// The secret key, which decrypts ciphertexts sent to its public key.
+pub struct ElGamalSecretKey {
+    x: Scalar,
+}
+
+impl ElGamalSecretKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        ElGamalSecretKey {
+            x: Scalar::random(rng),
+        }
+    }
+
+    pub fn public_key(&self) -> ElGamalPublicKey {
+        ElGamalPublicKey {
+            point: point::B * self.x,
+        }
+    }
+
+    /// Decrypt a ciphertext, recovering its point.
+    pub fn decrypt(&self, ciphertext: &ElGamalCiphertext) -> Point {
+        ciphertext.masked + ciphertext.ephemeral * -self.x
+    }
+
+    /// Decrypt a ciphertext of a number m, as long as m < max.
+    ///
+    /// This takes time, and memory, proportional to the square root of max, and
+    /// isn't constant time, so it should only be used on public results, like tallies.
+    pub fn decrypt_exponent(&self, ciphertext: &ElGamalCiphertext, max: u64) -> Option<u64> {
+        let target = self.decrypt(ciphertext);
+        let mut step = 1;
+        while step * step < max {
+            step += 1;
+        }
+        let mut baby_steps = HashMap::with_capacity(step as usize);
+        let mut current = Point::identity();
+        for j in 0..step {
+            baby_steps.entry(current.encode()).or_insert(j);
+            current = current + point::B;
+        }
+        // current is now step * B, and we subtract it repeatedly from the target.
+        let giant = current * -Scalar::from(1);
+        let mut current = target;
+        let mut i = 0;
+        while i * step < max {
+            if let Some(&j) = baby_steps.get(&current.encode()) {
+                let m = i * step + j;
+                return if m < max { Some(m) } else { None };
+            }
+            current = current + giant;
+            i += 1;
+        }
+        None
+    }
+
+    /// Decrypt some bytes encrypted with hashed ElGamal.
+    pub fn decrypt_hashed(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        if ciphertext.len() < 32 {
+            return Err(Error::InvalidLength);
+        }
+        let mut ephemeral = [0; 32];
+        ephemeral.copy_from_slice(&ciphertext[..32]);
+        let point = Point::try_from(&ephemeral[..]).map_err(|_| Error::PointNotOnCurve)?;
+        let mut out = ciphertext[32..].to_vec();
+        mask(&ephemeral, point * self.x, &mut out);
+        Ok(out)
+    }
+}
+
+/// This never prints the secret key.
+impl fmt::Debug for ElGamalSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElGamalSecretKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ElGamalSecretKey {
+    fn drop(&mut self) {
+        self.x.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_encrypt_and_rerandomize() {
+        let secret = ElGamalSecretKey::generate(&mut OsRng);
+        let public = secret.public_key();
+        let message = point::B * Scalar::from(1234);
+        let ciphertext = public.encrypt(message, &mut OsRng);
+        assert_eq!(secret.decrypt(&ciphertext).encode(), message.encode());
+        let rerandomized = public.rerandomize(&ciphertext, &mut OsRng);
+        assert_ne!(
+            rerandomized.ephemeral.encode(),
+            ciphertext.ephemeral.encode()
+        );
+        assert_eq!(secret.decrypt(&rerandomized).encode(), message.encode());
+    }
+
+    #[test]
+    fn test_exponential_tally() {
+        let secret = ElGamalSecretKey::generate(&mut OsRng);
+        let public = secret.public_key();
+        let tally = [3, 0, 1, 1, 995]
+            .iter()
+            .map(|&vote| public.encrypt_exponent(vote, &mut OsRng))
+            .fold(public.encrypt_exponent(0, &mut OsRng), |acc, c| acc + c);
+        assert_eq!(secret.decrypt_exponent(&tally, 1001), Some(1000));
+        assert_eq!(secret.decrypt_exponent(&tally, 1000), None);
+        let zero = public.encrypt_exponent(0, &mut OsRng);
+        assert_eq!(secret.decrypt_exponent(&zero, 1), Some(0));
+    }
+
+    #[test]
+    fn test_hashed() {
+        let secret = ElGamalSecretKey::generate(&mut OsRng);
+        let ciphertext = secret
+            .public_key()
+            .encrypt_hashed(b"attack at dawn", &mut OsRng);
+        assert_eq!(ciphertext.len(), 32 + 14);
+        assert_eq!(
+            secret.decrypt_hashed(&ciphertext).unwrap(),
+            b"attack at dawn"
+        );
+        let other = ElGamalSecretKey::generate(&mut OsRng);
+        assert_ne!(
+            other.decrypt_hashed(&ciphertext).unwrap(),
+            b"attack at dawn"
+        );
+        assert!(secret.decrypt_hashed(&ciphertext[..31]).is_err());
+    }
+}
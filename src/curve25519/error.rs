@@ -1,6 +1,162 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum SignatureError {
     InvalidPoint,
     InvalidFieldElement,
     InvalidScalar,
     InvalidEquation,
 }
+
+impl SignatureError {
+    #[cfg(feature = "debug-tools")]
+    fn describe(self) -> &'static str {
+        match self {
+            SignatureError::InvalidPoint => "invalid point encoding",
+            SignatureError::InvalidFieldElement => "invalid field element encoding",
+            SignatureError::InvalidScalar => "non canonical scalar",
+            SignatureError::InvalidEquation => "verification equation failed",
+        }
+    }
+}
+
+/// The error returned when a signature fails to check out.
+///
+/// This deliberately says nothing about why the check failed. Telling apart a
+/// malformed encoding from a failed equation lets errors act as an oracle, so all
+/// failures look the same, both as values, and when displayed.
+///
+/// With the `debug-tools` feature, the underlying reason is kept, for diagnostics.
+#[derive(Clone, Copy, Debug)]
+pub struct VerificationError {
+    #[cfg(feature = "debug-tools")]
+    reason: SignatureError,
+}
+
+impl VerificationError {
+    /// A description of why the check failed, for debugging only.
+    #[cfg(feature = "debug-tools")]
+    pub fn reason(&self) -> &'static str {
+        self.reason.describe()
+    }
+}
+
+impl From<SignatureError> for VerificationError {
+    #[cfg_attr(not(feature = "debug-tools"), allow(unused_variables))]
+    fn from(reason: SignatureError) -> Self {
+        VerificationError {
+            #[cfg(feature = "debug-tools")]
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "verification failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerificationError {}
+
+/// The errors returned by fallible operations throughout this crate.
+///
+/// Signature verification is the exception, failing with a `VerificationError`,
+/// which doesn't say why. That error converts into `Error::SignatureInvalid`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Some text contained something other than hex digits.
+    InvalidHex,
+    /// Some input had the wrong number of bytes.
+    InvalidLength,
+    /// A scalar wasn't reduced modulo L.
+    NonCanonicalScalar,
+    /// Some bytes weren't a valid encoding of a point on the curve.
+    PointNotOnCurve,
+    /// A public key has small order, so exchanging with it doesn't produce a secret.
+    WeakPublicKey,
+    /// A signature failed to verify.
+    SignatureInvalid,
+    /// Some structured data, like DER, was malformed.
+    InvalidEncoding,
+    /// Some encoded key was for an algorithm other than the one expected.
+    UnsupportedAlgorithm,
+    /// A key derivation path was malformed, or used an index the scheme can't derive.
+    InvalidDerivationPath,
+    /// A ciphertext failed to authenticate, so it wasn't decrypted.
+    DecryptionFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Error::InvalidHex => "invalid hex",
+            Error::InvalidLength => "invalid length",
+            Error::NonCanonicalScalar => "non canonical scalar",
+            Error::PointNotOnCurve => "point not on curve",
+            Error::WeakPublicKey => "weak public key",
+            Error::SignatureInvalid => "invalid signature",
+            Error::InvalidEncoding => "invalid encoding",
+            Error::UnsupportedAlgorithm => "unsupported algorithm",
+            Error::InvalidDerivationPath => "invalid derivation path",
+            Error::DecryptionFailed => "decryption failed",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl From<VerificationError> for Error {
+    fn from(_: VerificationError) -> Self {
+        Error::SignatureInvalid
+    }
+}
+
+/// Decode a hex string into a fixed number of bytes.
+pub(crate) fn parse_hex<const N: usize>(s: &str) -> Result<[u8; N], Error> {
+    let mut out = [0; N];
+    hex::decode_to_slice(s, &mut out).map_err(|e| match e {
+        hex::FromHexError::InvalidHexCharacter { .. } => Error::InvalidHex,
+        _ => Error::InvalidLength,
+    })?;
+    Ok(out)
+}
+
+/// Represents the ways committing to a nonce counter value can fail.
+#[derive(Debug)]
+pub enum CounterError<E> {
+    /// The counter has reached its maximum value, and can no longer be used.
+    Exhausted,
+    /// Persisting the next counter value failed, with some underlying error.
+    Persist(E),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verification_errors_are_uniform() {
+        let point = VerificationError::from(SignatureError::InvalidPoint);
+        let equation = VerificationError::from(SignatureError::InvalidEquation);
+        assert_eq!(point.to_string(), equation.to_string());
+    }
+
+    #[test]
+    fn test_verification_error_converts() {
+        let err = VerificationError::from(SignatureError::InvalidPoint);
+        assert_eq!(Error::from(err), Error::SignatureInvalid);
+    }
+
+    #[cfg(feature = "debug-tools")]
+    #[test]
+    fn test_verification_error_reason() {
+        let err = VerificationError::from(SignatureError::InvalidScalar);
+        assert_eq!(err.reason(), "non canonical scalar");
+    }
+}
@@ -1,13 +1,53 @@
-use std::{
+//! This module implements arithmetic in the field Z/(2^255 - 19).
+//!
+//! The representation of field elements depends on the backend. By default,
+//! we use 5 limbs of 51 bits, which needs 64 x 64 -> 128 bit multiplication.
+//! On 32 bit targets, or with the `u32_backend` feature, we use 10 limbs of
+//! 25.5 bits instead, which only needs 32 x 32 -> 64 bit multiplication.
+//! With the `fiat_backend` feature, we use the formally verified arithmetic
+//! generated by fiat-crypto, taking precedence over the other two.
+//!
+//! Each backend provides the core arithmetic operations, and this module
+//! builds everything else on top of them.
+
+use core::{
     convert::{TryFrom, TryInto},
-    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    ops::{Add, Mul, Neg, Sub},
 };
 
 use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
-use crate::arch::adc;
-
-use super::{arithmetic::U256, error::SignatureError};
+use crate::uint::U256;
+
+use super::error::SignatureError;
+
+#[cfg(feature = "fiat_backend")]
+mod fiat;
+#[cfg(all(
+    not(feature = "fiat_backend"),
+    any(feature = "u32_backend", target_pointer_width = "32")
+))]
+mod u32;
+#[cfg(not(any(
+    feature = "fiat_backend",
+    feature = "u32_backend",
+    target_pointer_width = "32"
+)))]
+mod u64;
+
+#[cfg(feature = "fiat_backend")]
+pub use self::fiat::Z25519;
+#[cfg(all(
+    not(feature = "fiat_backend"),
+    any(feature = "u32_backend", target_pointer_width = "32")
+))]
+pub use self::u32::Z25519;
+#[cfg(not(any(
+    feature = "fiat_backend",
+    feature = "u32_backend",
+    target_pointer_width = "32"
+)))]
+pub use self::u64::Z25519;
 
 const P: U256 = U256 {
     limbs: [
@@ -18,102 +58,30 @@ const P: U256 = U256 {
     ],
 };
 
-const TWO_P_MINUS_1_OVER_4: Z25519 = Z25519 {
-    value: U256 {
-        limbs: [
-            0xc4ee1b274a0ea0b0,
-            0x2f431806ad2fe478,
-            0x2b4d00993dfbd7a7,
-            0x2b8324804fc1df0b,
-        ],
-    },
-};
-
-/// Represents an element in the field Z/(2^255 - 19).
-///
-/// The operations in this field are defined through arithmetic modulo
-/// P := 2^255 - 19
-///
-/// # Creation
-///
-/// Elements in the field can be created from `u64`.
-#[derive(Clone, Copy, Debug)]
-// Only implement equality for tests. This is to avoid the temptation to introduce
-// a timing leak through equality comparison in other situations.
-#[cfg_attr(test, derive(PartialEq))]
-pub struct Z25519 {
-    pub value: U256,
-}
+/// A square root of -1, which is 2^((p - 1) / 4).
+pub const SQRT_M1: Z25519 = Z25519::from_limbs64([
+    0xc4ee1b274a0ea0b0,
+    0x2f431806ad2fe478,
+    0x2b4d00993dfbd7a7,
+    0x2b8324804fc1df0b,
+]);
 
 impl Z25519 {
-    /// reduce_after_addition reduces this element modulo P, after an addition.
-    ///
-    /// After an addition, we have at most 2P - 2, so at most one subtraction of P suffices.
-    fn reduce_after_addition(&mut self, carry: u8) {
-        let mut m_removed = *self;
-        // The largest result we've just calculated is 2P - 2. Therefore, we might
-        // need to subtract P once, if we have a result >= P.
-        let borrow = m_removed.value.sub_with_borrow(P);
-        // A few cases here:
-        //
-        // carry = 1, borrow = 0:
-        //    Impossible: we would need a result ≥ 2²⁵⁶ + P
-        // carry = 1, borrow = 1:
-        //     We produced a result larger than 2^256, with an extra bit, so certainly
-        //     we should subtract P. This will always produce a borrow, given our input ranges.
-        // carry = 0, borrow = 1:
-        //     Our result fits over 4 limbs, but is < P.
-        //     We don't want to choose the subtraction
-        // carry = 0, borrow = 0:
-        //     Our result fits over 4 limbs, but is ≥ P.
-        //     We want to choose the subtraction.
-        self.conditional_assign(&m_removed, borrow.ct_eq(&carry))
-    }
-
-    /// reduce_after_scaling reduces this element modulo P, after a scaling.
-    ///
-    /// After a scaling, this number fits over 5 limbs, and there's an efficient way
-    /// to reduce it modulo P.
-    fn reduce_after_scaling(&mut self, carry: u64) {
-        // Let's say that:
-        //     A = q⋅2²⁵⁵ + R
-        // This means that:
-        //     A = q⋅P + R + 19q
-        // Modulo P, this entails:
-        //     A ≡ R + 19q mod P
-        // We can efficiently calculate q and R using shifting and masking.
-
-        // We pull in one bit from the top limb, in order to calculate the quotient
-        let q = (carry << 1) | (self.value.limbs[3] >> 63);
-        // Clear the top bit, thus calculating R
-        self.value.limbs[3] &= 0x7FFF_FFFF_FFFF_FFFF;
-        // Now we add in 19q
-        let full_res = 19 * u128::from(q);
-        let mut carry = 0;
-        carry = adc(
-            carry,
-            full_res as u64,
-            self.value.limbs[0],
-            &mut self.value.limbs[0],
-        );
-        carry = adc(
-            carry,
-            (full_res >> 64) as u64,
-            self.value.limbs[1],
-            &mut self.value.limbs[1],
-        );
-        carry = adc(carry, 0, self.value.limbs[2], &mut self.value.limbs[2]);
-        carry = adc(carry, 0, self.value.limbs[3], &mut self.value.limbs[3]);
-        // Now remove P if necessary
-        self.reduce_after_addition(carry);
-    }
-
-    /// calculate z <- z * z mod P.
+    /// Return 1 if the canonical form of this element is odd, and 0 otherwise.
     ///
-    /// This is equivalent to z *= z, but is a bit more efficient, because it takes
-    /// advantage of the extra symmetry of this operation compared to the general case.
-    pub fn square(&mut self) {
-        *self *= *self;
+    /// RFC 8032 calls odd elements "negative", for the purposes of encoding points.
+    pub fn parity(&self) -> u8 {
+        (self.to_limbs64()[0] & 1) as u8
+    }
+
+    /// Check whether or not this element is negative, i.e. odd, in constant time.
+    pub fn is_negative(&self) -> Choice {
+        Choice::from(self.parity())
+    }
+
+    /// Check whether or not this element is zero, in constant time.
+    pub fn is_zero(&self) -> Choice {
+        self.ct_eq(&Z25519::from(0))
     }
 
     /// calculates z * z mod P
@@ -151,14 +119,13 @@ impl Z25519 {
         out
     }
 
-    pub fn fraction_root(u: Self, v: Self) -> Option<Self> {
-        let v_2 = v.squared();
-        let v_3 = v * v_2;
-        let v_7 = v_3 * v_2.squared();
-        let u_v_7 = u * v_7;
-        // powering by (p - 5) ** 8, which is 0xFF...FD
+    /// Calculate self^((p - 5) / 8), which is the core of our square roots.
+    ///
+    /// Since (p - 5) / 8 = 2^252 - 3, this is also known as `pow22523`.
+    pub fn pow_p58(self) -> Self {
+        // (p - 5) / 8 is 250 one bits, followed by 0b01
         let mut powered = Self::from(1);
-        let mut current_power = u_v_7;
+        let mut current_power = self;
         // Handling 0b01
         powered *= current_power;
         current_power.square();
@@ -168,21 +135,80 @@ impl Z25519 {
             powered *= current_power;
             current_power.square();
         }
+        powered
+    }
+
+    pub fn fraction_root(u: Self, v: Self) -> Option<Self> {
+        let v_2 = v.squared();
+        let v_3 = v * v_2;
+        let v_7 = v_3 * v_2.squared();
+        let powered = (u * v_7).pow_p58();
         let x = u * v_3 * powered;
         let v_x_2 = v * x.squared();
-        if v_x_2.value.eq(u.value) {
+        if bool::from(v_x_2.ct_eq(&u)) {
             return Some(x);
         }
-        if v_x_2.value.eq((-u).value) {
-            return Some(x * TWO_P_MINUS_1_OVER_4);
+        if bool::from(v_x_2.ct_eq(&-u)) {
+            return Some(x * SQRT_M1);
         }
         None
     }
+
+    /// Replace each element with its inverse, using a single field inversion.
+    ///
+    /// This uses Montgomery's trick, as described in Section 10.3.1 of "Speeding the
+    /// Pollard and Elliptic Curve Methods of Factorization", trading each inversion
+    /// for 3 multiplications: https://doi.org/10.1090/S0025-5718-1987-0866113-7
+    ///
+    /// Every element must be non zero.
+    #[cfg(feature = "alloc")]
+    pub fn batch_invert(elements: &mut [Self]) {
+        // prefixes[i] holds the product of all the elements before i.
+        let mut prefixes = alloc::vec::Vec::with_capacity(elements.len());
+        let mut acc = Self::from(1);
+        for &x in elements.iter() {
+            prefixes.push(acc);
+            acc *= x;
+        }
+        // acc^-1 = (x_0 ... x_n)^-1, and we peel off one element at a time.
+        let mut acc_inv = acc.inverse();
+        for (x, prefix) in elements.iter_mut().zip(prefixes).rev() {
+            let x_inv = acc_inv * prefix;
+            acc_inv *= *x;
+            *x = x_inv;
+        }
+    }
+
+    /// Return this element, or its negation, whichever is even.
+    pub fn abs(self) -> Self {
+        Self::conditional_select(&self, &-self, self.is_negative())
+    }
+
+    /// Calculate the non-negative square root of u / v, in constant time.
+    ///
+    /// If u / v isn't square, this returns the root of SQRT_M1 * u / v instead,
+    /// along with a false choice. This follows Section 4.2 of RFC 9496:
+    /// https://datatracker.ietf.org/doc/html/rfc9496#section-4.2
+    pub fn sqrt_ratio_m1(u: Self, v: Self) -> (Choice, Self) {
+        let v_2 = v.squared();
+        let v_3 = v * v_2;
+        let v_7 = v_3 * v_2.squared();
+        let r = u * v_3 * (u * v_7).pow_p58();
+        let check = v * r.squared();
+        let correct_sign = check.ct_eq(&u);
+        let flipped_sign = check.ct_eq(&-u);
+        let flipped_sign_i = check.ct_eq(&(-u * SQRT_M1));
+        let r = Self::conditional_select(&r, &(r * SQRT_M1), flipped_sign | flipped_sign_i);
+        (correct_sign | flipped_sign, r.abs())
+    }
 }
 
-impl Into<[u8; 32]> for Z25519 {
-    fn into(self) -> [u8; 32] {
-        self.value.into()
+impl From<Z25519> for [u8; 32] {
+    fn from(x: Z25519) -> [u8; 32] {
+        U256 {
+            limbs: x.to_limbs64(),
+        }
+        .into()
     }
 }
 
@@ -198,38 +224,31 @@ impl<'a> TryFrom<&'a [u8]> for Z25519 {
         if value.geq(P) {
             return Err(SignatureError::InvalidScalar);
         }
-        Ok(Z25519 { value })
-    }
-}
-
-impl From<u64> for Z25519 {
-    fn from(x: u64) -> Self {
-        Z25519 {
-            value: U256::from(x),
-        }
+        Ok(Z25519::from(value.limbs))
     }
 }
 
 impl From<[u64; 4]> for Z25519 {
+    /// Convert 4 limbs of 64 bits into a field element.
+    ///
+    /// The top bit of the last limb gets ignored.
     fn from(limbs: [u64; 4]) -> Self {
-        Z25519 {
-            value: U256 { limbs },
-        }
+        Z25519::from_limbs64(limbs)
     }
 }
 
-impl ConditionallySelectable for Z25519 {
-    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
-        Z25519 {
-            value: U256::conditional_select(&a.value, &b.value, choice),
-        }
+impl ConstantTimeEq for Z25519 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_limbs64()[..].ct_eq(&other.to_limbs64()[..])
     }
 }
 
-impl AddAssign for Z25519 {
-    fn add_assign(&mut self, other: Self) {
-        let carry = self.value.add_with_carry(other.value);
-        self.reduce_after_addition(carry);
+// Only implement equality for tests. This is to avoid the temptation to introduce
+// a timing leak through equality comparison in other situations.
+#[cfg(test)]
+impl PartialEq for Z25519 {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
     }
 }
 
@@ -242,14 +261,6 @@ impl Add for Z25519 {
     }
 }
 
-impl SubAssign for Z25519 {
-    fn sub_assign(&mut self, other: Z25519) {
-        // We perform the subtraction, and then add back P if we underflowed.
-        let borrow = self.value.sub_with_borrow(other.value);
-        self.value.cond_add(P, borrow.ct_eq(&1));
-    }
-}
-
 impl Sub for Z25519 {
     type Output = Self;
 
@@ -262,20 +273,13 @@ impl Sub for Z25519 {
 impl Neg for Z25519 {
     type Output = Self;
 
-    fn neg(self) -> Self::Output {
-        // NOTE: Hopefully Rust inlines things, to avoid materializing 4 zeros in memory
+    fn neg(mut self) -> Self::Output {
+        // Subtraction needs its right hand side to be loosely reduced.
+        self.weak_reduce();
         Self::from(0) - self
     }
 }
 
-impl MulAssign<u64> for Z25519 {
-    fn mul_assign(&mut self, small: u64) {
-        let (carry, lo) = self.value * small;
-        self.value = lo;
-        self.reduce_after_scaling(carry);
-    }
-}
-
 impl Mul<u64> for Z25519 {
     type Output = Z25519;
 
@@ -285,25 +289,6 @@ impl Mul<u64> for Z25519 {
     }
 }
 
-impl MulAssign for Z25519 {
-    fn mul_assign(&mut self, other: Self) {
-        let res = self.value * other.value;
-        // At this point, we've multiplied things out, and have:
-        //     hi⋅2²⁵⁶ + lo
-        // Observe that 2²⁵⁶ = 2⋅(2²⁵⁵ - 19) + 38, so mod P, we have:
-        //     hi + 38⋅lo
-        // All that's left is to multiply hi by 38, and then add in lo
-        let mut carry = 0u64;
-        for i in 0..4 {
-            let full_res =
-                u128::from(carry) + u128::from(res.limbs[i]) + 38 * u128::from(res.limbs[i + 4]);
-            self.value.limbs[i] = full_res as u64;
-            carry = (full_res >> 64) as u64;
-        }
-        self.reduce_after_scaling(carry);
-    }
-}
-
 impl Mul for Z25519 {
     type Output = Self;
 
@@ -315,8 +300,6 @@ impl Mul for Z25519 {
 
 #[cfg(test)]
 mod test {
-    use super::super::arithmetic::U256;
-
     use super::Z25519;
     use proptest::prelude::*;
 
@@ -326,9 +309,7 @@ mod test {
             z1 in any::<u64>(),
             z2 in any::<u64>(),
             z3 in 0..((1u64 << 63) - 19)) -> Z25519 {
-            Z25519 {
-                value: U256 { limbs: [z0, z1, z2, z3] }
-            }
+            Z25519::from([z0, z1, z2, z3])
         }
     }
 
@@ -453,84 +434,89 @@ mod test {
         }
     }
 
+    proptest! {
+        #[test]
+        fn test_pow_p58(a in arb_z25519()) {
+            // p = 8 * (p - 5) / 8 + 5, and a^p = a.
+            let mut powered = a.pow_p58();
+            for _ in 0..3 {
+                powered.square();
+            }
+            assert_eq!(powered * a.squared().squared() * a, a);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_sqrt_ratio_m1(
+            a in arb_z25519(),
+            b in arb_z25519()
+                .prop_filter(
+                    "zero cannot be divided by".to_owned(),
+                    |x: &Z25519| *x != 0.into()
+                )
+        ) {
+            let (was_square, root) = Z25519::sqrt_ratio_m1(a.squared(), b.squared());
+            assert!(bool::from(was_square));
+            assert!(!bool::from(root.is_negative()));
+            assert_eq!(root.squared() * b.squared(), a.squared());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_batch_invert() {
+        let mut elements: Vec<_> = (1..10u64).map(Z25519::from).collect();
+        let expected: Vec<_> = elements.iter().map(|x| x.inverse()).collect();
+        Z25519::batch_invert(&mut elements);
+        assert_eq!(elements, expected);
+        Z25519::batch_invert(&mut []);
+    }
+
     #[test]
     fn test_addition_examples() {
-        let z1 = Z25519 {
-            value: U256 {
-                limbs: [1, 1, 1, 1],
-            },
-        };
-        let z2 = Z25519 {
-            value: U256 {
-                limbs: [2, 2, 2, 2],
-            },
-        };
-        let z3 = Z25519 {
-            value: U256 {
-                limbs: [3, 3, 3, 3],
-            },
-        };
+        let z1 = Z25519::from([1, 1, 1, 1]);
+        let z2 = Z25519::from([2, 2, 2, 2]);
+        let z3 = Z25519::from([3, 3, 3, 3]);
         assert_eq!(z3, z1 + z2);
 
-        let two_254 = Z25519 {
-            value: U256 {
-                limbs: [0, 0, 0, 1 << 62],
-            },
-        };
+        let two_254 = Z25519::from([0, 0, 0, 1 << 62]);
         assert_eq!(two_254 + two_254, Z25519::from(19));
     }
 
     #[test]
     fn test_subtraction_examples() {
-        let mut z1 = Z25519 {
-            value: U256 {
-                limbs: [1, 1, 1, 1],
-            },
-        };
+        let mut z1 = Z25519::from([1, 1, 1, 1]);
         z1 -= z1;
         assert_eq!(z1, 0.into());
         z1 -= 1.into();
-        let p_minus_one = Z25519 {
-            value: U256 {
-                limbs: [
-                    0xFFFF_FFFF_FFFF_FFEC,
-                    0xFFFF_FFFF_FFFF_FFFF,
-                    0xFFFF_FFFF_FFFF_FFFF,
-                    0x7FFF_FFFF_FFFF_FFFF,
-                ],
-            },
-        };
+        let p_minus_one = Z25519::from([
+            0xFFFF_FFFF_FFFF_FFEC,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0x7FFF_FFFF_FFFF_FFFF,
+        ]);
         assert_eq!(z1, p_minus_one);
     }
 
     #[test]
     fn test_small_multiplication_examples() {
-        let z1 = Z25519 {
-            value: U256 { limbs: [1; 4] },
-        };
+        let z1 = Z25519::from([1; 4]);
         assert_eq!(z1 + z1, z1 * 2);
         assert_eq!(z1 + z1 + z1, z1 * 3);
-        let p_minus_one = Z25519 {
-            value: U256 {
-                limbs: [
-                    0xFFFF_FFFF_FFFF_FFEC,
-                    0xFFFF_FFFF_FFFF_FFFF,
-                    0xFFFF_FFFF_FFFF_FFFF,
-                    0x7FFF_FFFF_FFFF_FFFF,
-                ],
-            },
-        };
+        let p_minus_one = Z25519::from([
+            0xFFFF_FFFF_FFFF_FFEC,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0x7FFF_FFFF_FFFF_FFFF,
+        ]);
         assert_eq!(p_minus_one * 2, p_minus_one - 1.into());
         assert_eq!(p_minus_one * 3, p_minus_one - 2.into());
     }
 
     #[test]
     fn test_2192_times_zero() {
-        let two192 = Z25519 {
-            value: U256 {
-                limbs: [0, 0, 0, 1],
-            },
-        };
+        let two192 = Z25519::from([0, 0, 0, 1]);
         assert_eq!(two192 * Z25519::from(0), 0.into());
     }
 
@@ -543,11 +529,7 @@ mod test {
 
     #[test]
     fn test_two_255() {
-        let two_254 = Z25519 {
-            value: U256 {
-                limbs: [0, 0, 0, 0x4000000000000000],
-            },
-        };
+        let two_254 = Z25519::from([0, 0, 0, 0x4000000000000000]);
         assert_eq!(two_254 * Z25519::from(2), 19.into());
     }
 }
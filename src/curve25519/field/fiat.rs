@@ -0,0 +1,152 @@
+//! The formally verified backend for field arithmetic, using fiat-crypto:
+//! https://github.com/mit-plv/fiat-crypto
+//!
+//! The carrying and reduction logic here is generated, and proven correct, by
+//! fiat-crypto, rather than written by hand. Like the 64 bit backend, this uses
+//! 5 limbs of 51 bits, but every operation carries, keeping the limbs tight.
+
+use core::ops::{AddAssign, MulAssign, SubAssign};
+
+use fiat_crypto::curve25519_64::{
+    fiat_25519_add, fiat_25519_carry, fiat_25519_carry_mul, fiat_25519_carry_square,
+    fiat_25519_loose_field_element, fiat_25519_relax, fiat_25519_selectznz, fiat_25519_sub,
+    fiat_25519_tight_field_element, fiat_25519_to_bytes,
+};
+use subtle::{Choice, ConditionallySelectable};
+
+/// A mask for the lower 51 bits of a limb.
+const LOW_51: u64 = (1 << 51) - 1;
+
+/// Represents an element in the field Z/(2^255 - 19).
+///
+/// The limbs always satisfy the tight bounds of fiat-crypto, so that every
+/// element can be passed to any of its operations, without checking.
+#[derive(Clone, Copy, Debug)]
+pub struct Z25519 {
+    limbs: [u64; 5],
+}
+
+impl Z25519 {
+    fn tight(&self) -> fiat_25519_tight_field_element {
+        fiat_25519_tight_field_element(self.limbs)
+    }
+
+    fn loose(&self) -> fiat_25519_loose_field_element {
+        let mut out = fiat_25519_loose_field_element([0; 5]);
+        fiat_25519_relax(&mut out, &self.tight());
+        out
+    }
+
+    fn carried(loose: &fiat_25519_loose_field_element) -> Z25519 {
+        let mut out = fiat_25519_tight_field_element([0; 5]);
+        fiat_25519_carry(&mut out, loose);
+        Z25519 { limbs: out.0 }
+    }
+
+    /// Every operation already carries, so this does nothing.
+    #[inline]
+    pub fn weak_reduce(&mut self) {}
+
+    /// Convert 4 limbs of 64 bits into a field element, ignoring the top bit.
+    pub const fn from_limbs64(limbs: [u64; 4]) -> Z25519 {
+        Z25519 {
+            limbs: [
+                limbs[0] & LOW_51,
+                ((limbs[0] >> 51) | (limbs[1] << 13)) & LOW_51,
+                ((limbs[1] >> 38) | (limbs[2] << 26)) & LOW_51,
+                ((limbs[2] >> 25) | (limbs[3] << 39)) & LOW_51,
+                (limbs[3] >> 12) & LOW_51,
+            ],
+        }
+    }
+
+    /// Convert this element into 4 limbs of 64 bits, in canonical form.
+    pub fn to_limbs64(self) -> [u64; 4] {
+        let mut bytes = [0; 32];
+        fiat_25519_to_bytes(&mut bytes, &self.tight());
+        let mut out = [0; 4];
+        for (limb, chunk) in out.iter_mut().zip(bytes.chunks_exact(8)) {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
+        }
+        out
+    }
+
+    /// calculate z <- z * z mod P.
+    pub fn square(&mut self) {
+        let mut out = fiat_25519_tight_field_element([0; 5]);
+        fiat_25519_carry_square(&mut out, &self.loose());
+        self.limbs = out.0;
+    }
+}
+
+impl From<u64> for Z25519 {
+    fn from(x: u64) -> Self {
+        Z25519 {
+            limbs: [x & LOW_51, x >> 51, 0, 0, 0],
+        }
+    }
+}
+
+impl ConditionallySelectable for Z25519 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut limbs = [0; 5];
+        fiat_25519_selectznz(&mut limbs, choice.unwrap_u8(), &a.limbs, &b.limbs);
+        Z25519 { limbs }
+    }
+}
+
+impl AddAssign for Z25519 {
+    fn add_assign(&mut self, other: Self) {
+        let mut sum = fiat_25519_loose_field_element([0; 5]);
+        fiat_25519_add(&mut sum, &self.tight(), &other.tight());
+        *self = Self::carried(&sum);
+    }
+}
+
+impl SubAssign for Z25519 {
+    fn sub_assign(&mut self, other: Z25519) {
+        let mut difference = fiat_25519_loose_field_element([0; 5]);
+        fiat_25519_sub(&mut difference, &self.tight(), &other.tight());
+        *self = Self::carried(&difference);
+    }
+}
+
+impl MulAssign<u64> for Z25519 {
+    fn mul_assign(&mut self, small: u64) {
+        *self *= Z25519::from(small);
+    }
+}
+
+impl MulAssign for Z25519 {
+    fn mul_assign(&mut self, other: Self) {
+        let mut out = fiat_25519_tight_field_element([0; 5]);
+        fiat_25519_carry_mul(&mut out, &self.loose(), &other.loose());
+        self.limbs = out.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LOW_51, Z25519};
+
+    #[test]
+    fn test_non_canonical_limbs() {
+        let p = Z25519 {
+            limbs: [LOW_51 - 18, LOW_51, LOW_51, LOW_51, LOW_51],
+        };
+        assert_eq!(p, 0.into());
+        let p_plus_5 = Z25519 {
+            limbs: [LOW_51 - 13, LOW_51, LOW_51, LOW_51, LOW_51],
+        };
+        assert_eq!(p_plus_5.to_limbs64(), [5, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_sqrt_m1_squares_to_minus_one() {
+        let minus_one = Z25519::from(0) - Z25519::from(1);
+        assert_eq!(super::super::SQRT_M1.squared(), minus_one);
+        assert_eq!(minus_one * minus_one, Z25519::from(1));
+    }
+}
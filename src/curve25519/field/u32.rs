@@ -0,0 +1,272 @@
+//! The 32 bit backend for field arithmetic, using 10 limbs of 25.5 bits.
+
+use core::ops::{AddAssign, MulAssign, SubAssign};
+
+use subtle::{Choice, ConditionallySelectable};
+
+/// A mask for the lower 26 bits of a limb.
+const LOW_26: u32 = (1 << 26) - 1;
+/// A mask for the lower 25 bits of a limb.
+const LOW_25: u32 = (1 << 25) - 1;
+
+/// The bit offset of each limb, inside of a 255 bit element.
+const OFFSETS: [usize; 10] = [0, 26, 51, 77, 102, 128, 153, 179, 204, 230];
+
+/// The limbs of 8P, which we add before subtracting, to avoid underflow.
+const EIGHT_P: [u32; 10] = [
+    8 * ((1 << 26) - 19),
+    8 * LOW_25,
+    8 * LOW_26,
+    8 * LOW_25,
+    8 * LOW_26,
+    8 * LOW_25,
+    8 * LOW_26,
+    8 * LOW_25,
+    8 * LOW_26,
+    8 * LOW_25,
+];
+
+/// The largest limb we accept as input to a multiplication.
+///
+/// With limbs below this bound, multiplying a limb by 19 fits in 32 bits,
+/// and the intermediate sums in a multiplication fit in 64 bits.
+const MAX_INPUT_LIMB: u32 = 3 << 26;
+
+/// The number of bits in a given limb.
+#[inline]
+const fn width(i: usize) -> u32 {
+    26 - (i as u32 & 1)
+}
+
+/// Represents an element in the field Z/(2^255 - 19).
+///
+/// The operations in this field are defined through arithmetic modulo
+/// P := 2^255 - 19
+///
+/// # Representation
+///
+/// We use 10 limbs, alternating between 26 and 25 bits, stored in 32 bit words.
+/// This lets us multiply with only 32 x 32 -> 64 bit products, which is what
+/// 32 bit targets provide natively.
+///
+/// Like the 64 bit backend, the limbs are only loosely reduced: additions
+/// don't carry at all, and each multiplication carries once at the end.
+/// Unlike it, subtractions carry too, since adding 8P would already take
+/// the limbs past what a multiplication accepts.
+///
+/// # Creation
+///
+/// Elements in the field can be created from `u64`.
+#[derive(Clone, Copy, Debug)]
+pub struct Z25519 {
+    pub limbs: [u32; 10],
+}
+
+impl Z25519 {
+    /// Carry the limbs of this element, so that each fits in its width, plus a small excess.
+    ///
+    /// The result has its second limb < 2^25 + 2^18, and every other limb within its width.
+    #[inline]
+    fn carry(mut c: [u64; 10]) -> Z25519 {
+        for i in 0..9 {
+            c[i + 1] += c[i] >> width(i);
+            c[i] &= u64::from((1u32 << width(i)) - 1);
+        }
+        // 2^255 = 19 mod P, so the top carry wraps around, multiplied by 19
+        c[0] += 19 * (c[9] >> 25);
+        c[9] &= u64::from(LOW_25);
+        c[1] += c[0] >> 26;
+        c[0] &= u64::from(LOW_26);
+        let mut limbs = [0; 10];
+        for (limb, &c) in limbs.iter_mut().zip(c.iter()) {
+            *limb = c as u32;
+        }
+        Z25519 { limbs }
+    }
+
+    /// Carry the limbs of this element, so that each fits in its width, plus a small excess.
+    #[inline]
+    pub fn weak_reduce(&mut self) {
+        let mut c = [0; 10];
+        for (c, &limb) in c.iter_mut().zip(self.limbs.iter()) {
+            *c = u64::from(limb);
+        }
+        *self = Self::carry(c);
+    }
+
+    /// Calculate the unique representation of this element, with each limb within
+    /// its width, and the whole value < P.
+    pub fn canonical_limbs(&self) -> [u32; 10] {
+        let mut reduced = *self;
+        reduced.weak_reduce();
+        let mut l = reduced.limbs;
+        // Now the value is < 2P, so we need to subtract P at most once. The value
+        // is ≥ P exactly when adding 19 makes it overflow 2^255.
+        let mut q = (l[0] + 19) >> 26;
+        for (i, &limb) in l.iter().enumerate().skip(1) {
+            q = (limb + q) >> width(i);
+        }
+        // Subtracting P is the same as adding 19, and removing 2^255
+        l[0] += 19 * q;
+        for i in 0..9 {
+            l[i + 1] += l[i] >> width(i);
+            l[i] &= (1 << width(i)) - 1;
+        }
+        l[9] &= LOW_25;
+        l
+    }
+
+    /// Convert 4 limbs of 64 bits into a field element, ignoring the top bit.
+    pub const fn from_limbs64(limbs: [u64; 4]) -> Z25519 {
+        let mut out = [0; 10];
+        let mut i = 0;
+        while i < 10 {
+            let (word, shift) = (OFFSETS[i] / 64, OFFSETS[i] % 64);
+            let mut bits = limbs[word] >> shift;
+            if shift + width(i) as usize > 64 {
+                bits |= limbs[word + 1] << (64 - shift);
+            }
+            out[i] = (bits as u32) & ((1 << width(i)) - 1);
+            i += 1;
+        }
+        Z25519 { limbs: out }
+    }
+
+    /// Convert this element into 4 limbs of 64 bits, in canonical form.
+    pub fn to_limbs64(self) -> [u64; 4] {
+        let mut out = [0u64; 4];
+        for (i, &limb) in self.canonical_limbs().iter().enumerate() {
+            let (word, shift) = (OFFSETS[i] / 64, OFFSETS[i] % 64);
+            out[word] |= u64::from(limb) << shift;
+            if shift + width(i) as usize > 64 {
+                out[word + 1] |= u64::from(limb) >> (64 - shift);
+            }
+        }
+        out
+    }
+
+    /// calculate z <- z * z mod P.
+    ///
+    /// This backend has no dedicated squaring routine, so this is the same as z *= z.
+    pub fn square(&mut self) {
+        let copy = *self;
+        *self *= copy;
+    }
+}
+
+impl From<u64> for Z25519 {
+    fn from(x: u64) -> Self {
+        Z25519::from_limbs64([x, 0, 0, 0])
+    }
+}
+
+impl ConditionallySelectable for Z25519 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut limbs = [0; 10];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = u32::conditional_select(&a.limbs[i], &b.limbs[i], choice);
+        }
+        Z25519 { limbs }
+    }
+}
+
+impl AddAssign for Z25519 {
+    fn add_assign(&mut self, other: Self) {
+        // We don't carry here, relying on the slack in our limbs instead.
+        for (limb, o) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *limb += o;
+        }
+        debug_assert!(self.limbs.iter().all(|&x| x < MAX_INPUT_LIMB));
+    }
+}
+
+impl SubAssign for Z25519 {
+    fn sub_assign(&mut self, other: Z25519) {
+        // We add 8P first, which makes sure that each limb stays positive.
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            *limb = (*limb + EIGHT_P[i]) - other.limbs[i];
+        }
+        self.weak_reduce();
+    }
+}
+
+impl MulAssign<u64> for Z25519 {
+    fn mul_assign(&mut self, small: u64) {
+        // The products here might not fit in 64 bits, so we carry in 128 bits,
+        // before handing the small result to the normal carry routine.
+        let mut c = [0u128; 10];
+        for (c, &limb) in c.iter_mut().zip(self.limbs.iter()) {
+            *c = u128::from(limb) * u128::from(small);
+        }
+        for i in 0..9 {
+            c[i + 1] += c[i] >> width(i);
+            c[i] &= (1 << width(i)) - 1;
+        }
+        c[0] += 19 * (c[9] >> 25);
+        c[9] &= u128::from(LOW_25);
+        c[1] += c[0] >> 26;
+        c[0] &= u128::from(LOW_26);
+        let mut wide = [0u64; 10];
+        for (w, &c) in wide.iter_mut().zip(c.iter()) {
+            *w = c as u64;
+        }
+        *self = Self::carry(wide);
+    }
+}
+
+impl MulAssign for Z25519 {
+    fn mul_assign(&mut self, other: Self) {
+        let a = self.limbs;
+        let b = other.limbs;
+        debug_assert!(a.iter().all(|&x| x < MAX_INPUT_LIMB));
+        debug_assert!(b.iter().all(|&x| x < MAX_INPUT_LIMB));
+        // When both limbs have odd indices, their offsets add up to one more
+        // than the offset of their product's limb, so we multiply by 2.
+        let a2: [u32; 10] = {
+            let mut a2 = a;
+            for limb in a2.iter_mut().skip(1).step_by(2) {
+                *limb *= 2;
+            }
+            a2
+        };
+        // Products past 2^255 wrap around to the bottom, multiplied by 19.
+        let mut b19 = [0u32; 10];
+        for (x, &y) in b19.iter_mut().zip(b.iter()) {
+            *x = 19 * y;
+        }
+        let mut c = [0u64; 10];
+        for i in 0..10 {
+            for j in 0..10 {
+                let x = if i & j & 1 == 1 { a2[i] } else { a[i] };
+                if i + j < 10 {
+                    c[i + j] += u64::from(x) * u64::from(b[j]);
+                } else {
+                    c[i + j - 10] += u64::from(x) * u64::from(b19[j]);
+                }
+            }
+        }
+        *self = Self::carry(c);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LOW_25, LOW_26, Z25519};
+
+    #[test]
+    fn test_non_canonical_limbs() {
+        let mut limbs = [
+            LOW_26, LOW_25, LOW_26, LOW_25, LOW_26, LOW_25, LOW_26, LOW_25, LOW_26, LOW_25,
+        ];
+        limbs[0] -= 18;
+        let p = Z25519 { limbs };
+        assert_eq!(p, 0.into());
+        assert_eq!(p.canonical_limbs(), [0; 10]);
+        limbs[0] += 5;
+        let p_plus_5 = Z25519 { limbs };
+        assert_eq!(p_plus_5.canonical_limbs(), [5, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let bytes: [u8; 32] = p_plus_5.into();
+        assert_eq!(bytes[0], 5);
+        assert!(bytes[1..].iter().all(|&b| b == 0));
+    }
+}
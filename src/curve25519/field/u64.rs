@@ -0,0 +1,282 @@
+//! The 64 bit backend for field arithmetic, using 5 limbs of 51 bits.
+
+use core::ops::{AddAssign, MulAssign, SubAssign};
+
+use subtle::{Choice, ConditionallySelectable};
+
+/// A mask for the lower 51 bits of a limb.
+const LOW_51: u64 = (1 << 51) - 1;
+
+/// The limbs of 16P, which we add before subtracting, to avoid underflow.
+///
+/// This means that the element being subtracted needs limbs no larger than these.
+const SIXTEEN_P: [u64; 5] = [
+    16 * ((1 << 51) - 19),
+    16 * LOW_51,
+    16 * LOW_51,
+    16 * LOW_51,
+    16 * LOW_51,
+];
+
+/// The largest limb we accept as input to a multiplication.
+///
+/// With limbs below 2^58, multiplying a limb by 19 fits in 64 bits, and the
+/// intermediate sums in a multiplication stay below 2^124. Additions, and
+/// subtractions, check that their results stay below this bound, so that no
+/// chain of them can overflow a limb without being caught in debug builds.
+const MAX_INPUT_LIMB: u64 = 1 << 58;
+
+/// Represents an element in the field Z/(2^255 - 19).
+///
+/// The operations in this field are defined through arithmetic modulo
+/// P := 2^255 - 19
+///
+/// # Representation
+///
+/// We use 5 limbs of 51 bits, stored in 64 bit words. The extra space
+/// lets us delay carrying: additions, and subtractions, don't carry at all,
+/// and multiplications accumulate their products in 128 bits, carrying once
+/// at the end.
+///
+/// This means that the limbs are only loosely reduced, and the same element
+/// can have multiple representations. Elements are only fully reduced
+/// when converting them to bytes, or comparing them.
+///
+/// The element being subtracted needs limbs below those of 16P, which holds for
+/// the outputs of multiplications, and short sums of them, but not necessarily
+/// for the outputs of other subtractions. Those can be brought back into range
+/// with `weak_reduce`.
+///
+/// # Creation
+///
+/// Elements in the field can be created from `u64`.
+#[derive(Clone, Copy, Debug)]
+pub struct Z25519 {
+    pub limbs: [u64; 5],
+}
+
+impl Z25519 {
+    /// Carry the limbs of this element, so that each fits in 51 bits, plus a small excess.
+    ///
+    /// This accepts 128 bit limbs, below 2^124, and the result has limbs < 2^51 + 2^28.
+    #[inline]
+    fn carry(mut c: [u128; 5]) -> Z25519 {
+        c[1] += c[0] >> 51;
+        c[2] += c[1] >> 51;
+        c[3] += c[2] >> 51;
+        c[4] += c[3] >> 51;
+        // 2^255 = 19 mod P, so the top carry wraps around, multiplied by 19
+        let mut l0 = (c[0] & u128::from(LOW_51)) + 19 * (c[4] >> 51);
+        let l1 = (c[1] & u128::from(LOW_51)) + (l0 >> 51);
+        l0 &= u128::from(LOW_51);
+        Z25519 {
+            limbs: [
+                l0 as u64,
+                l1 as u64,
+                (c[2] as u64) & LOW_51,
+                (c[3] as u64) & LOW_51,
+                (c[4] as u64) & LOW_51,
+            ],
+        }
+    }
+
+    /// Carry the limbs of this element, so that each fits in 51 bits, plus a small excess.
+    #[inline]
+    pub fn weak_reduce(&mut self) {
+        let l = self.limbs;
+        *self = Self::carry([
+            u128::from(l[0]),
+            u128::from(l[1]),
+            u128::from(l[2]),
+            u128::from(l[3]),
+            u128::from(l[4]),
+        ]);
+    }
+
+    /// Calculate the unique representation of this element, with each limb < 2^51,
+    /// and the whole value < P.
+    pub fn canonical_limbs(&self) -> [u64; 5] {
+        let mut reduced = *self;
+        reduced.weak_reduce();
+        let mut l = reduced.limbs;
+        // Now the value is < 2P, so we need to subtract P at most once. The value
+        // is ≥ P exactly when adding 19 makes it overflow 2^255.
+        let mut q = (l[0] + 19) >> 51;
+        q = (l[1] + q) >> 51;
+        q = (l[2] + q) >> 51;
+        q = (l[3] + q) >> 51;
+        q = (l[4] + q) >> 51;
+        // Subtracting P is the same as adding 19, and removing 2^255
+        l[0] += 19 * q;
+        l[1] += l[0] >> 51;
+        l[0] &= LOW_51;
+        l[2] += l[1] >> 51;
+        l[1] &= LOW_51;
+        l[3] += l[2] >> 51;
+        l[2] &= LOW_51;
+        l[4] += l[3] >> 51;
+        l[3] &= LOW_51;
+        l[4] &= LOW_51;
+        l
+    }
+
+    /// Convert 4 limbs of 64 bits into a field element, ignoring the top bit.
+    pub const fn from_limbs64(limbs: [u64; 4]) -> Z25519 {
+        Z25519 {
+            limbs: [
+                limbs[0] & LOW_51,
+                ((limbs[0] >> 51) | (limbs[1] << 13)) & LOW_51,
+                ((limbs[1] >> 38) | (limbs[2] << 26)) & LOW_51,
+                ((limbs[2] >> 25) | (limbs[3] << 39)) & LOW_51,
+                (limbs[3] >> 12) & LOW_51,
+            ],
+        }
+    }
+
+    /// Convert this element into 4 limbs of 64 bits, in canonical form.
+    pub fn to_limbs64(self) -> [u64; 4] {
+        let l = self.canonical_limbs();
+        [
+            l[0] | (l[1] << 51),
+            (l[1] >> 13) | (l[2] << 38),
+            (l[2] >> 26) | (l[3] << 25),
+            (l[3] >> 39) | (l[4] << 12),
+        ]
+    }
+
+    /// calculate z <- z * z mod P.
+    ///
+    /// This is equivalent to z *= z, but is a bit more efficient, because it takes
+    /// advantage of the extra symmetry of this operation compared to the general case.
+    pub fn square(&mut self) {
+        let a = self.limbs;
+        debug_assert!(a.iter().all(|&x| x < MAX_INPUT_LIMB));
+        let m = |x: u64, y: u64| u128::from(x) * u128::from(y);
+        let a3_19 = 19 * a[3];
+        let a4_19 = 19 * a[4];
+        // Each cross product appears twice, and wrapped products get multiplied by 19
+        let c0 = m(a[0], a[0]) + 2 * (m(a[1], a4_19) + m(a[2], a3_19));
+        let c1 = m(a[3], a3_19) + 2 * (m(a[0], a[1]) + m(a[2], a4_19));
+        let c2 = m(a[1], a[1]) + 2 * (m(a[0], a[2]) + m(a[4], a3_19));
+        let c3 = m(a[4], a4_19) + 2 * (m(a[0], a[3]) + m(a[1], a[2]));
+        let c4 = m(a[2], a[2]) + 2 * (m(a[0], a[4]) + m(a[1], a[3]));
+        *self = Self::carry([c0, c1, c2, c3, c4]);
+    }
+}
+
+impl From<u64> for Z25519 {
+    fn from(x: u64) -> Self {
+        Z25519 {
+            limbs: [x & LOW_51, x >> 51, 0, 0, 0],
+        }
+    }
+}
+
+impl ConditionallySelectable for Z25519 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut limbs = [0; 5];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = u64::conditional_select(&a.limbs[i], &b.limbs[i], choice);
+        }
+        Z25519 { limbs }
+    }
+}
+
+impl AddAssign for Z25519 {
+    fn add_assign(&mut self, other: Self) {
+        // We don't carry here, relying on the slack in our limbs instead.
+        for (limb, o) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *limb += o;
+        }
+        debug_assert!(self.limbs.iter().all(|&x| x < MAX_INPUT_LIMB));
+    }
+}
+
+impl SubAssign for Z25519 {
+    fn sub_assign(&mut self, other: Z25519) {
+        debug_assert!(other
+            .limbs
+            .iter()
+            .zip(SIXTEEN_P.iter())
+            .all(|(x, p)| x <= p));
+        // We add 16P first, which makes sure that each limb stays positive.
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            *limb = (*limb + SIXTEEN_P[i]) - other.limbs[i];
+        }
+        debug_assert!(self.limbs.iter().all(|&x| x < MAX_INPUT_LIMB));
+    }
+}
+
+impl MulAssign<u64> for Z25519 {
+    fn mul_assign(&mut self, small: u64) {
+        let l = self.limbs;
+        let small = u128::from(small);
+        *self = Self::carry([
+            u128::from(l[0]) * small,
+            u128::from(l[1]) * small,
+            u128::from(l[2]) * small,
+            u128::from(l[3]) * small,
+            u128::from(l[4]) * small,
+        ]);
+    }
+}
+
+impl MulAssign for Z25519 {
+    fn mul_assign(&mut self, other: Self) {
+        let a = self.limbs;
+        let b = other.limbs;
+        debug_assert!(a.iter().all(|&x| x < MAX_INPUT_LIMB));
+        debug_assert!(b.iter().all(|&x| x < MAX_INPUT_LIMB));
+        let m = |x: u64, y: u64| u128::from(x) * u128::from(y);
+        // Products past 2^255 wrap around to the bottom, multiplied by 19.
+        // Since our limbs are small, we can multiply by 19 before the product.
+        let b1_19 = 19 * b[1];
+        let b2_19 = 19 * b[2];
+        let b3_19 = 19 * b[3];
+        let b4_19 = 19 * b[4];
+        let c0 = m(a[0], b[0]) + m(a[1], b4_19) + m(a[2], b3_19) + m(a[3], b2_19) + m(a[4], b1_19);
+        let c1 = m(a[0], b[1]) + m(a[1], b[0]) + m(a[2], b4_19) + m(a[3], b3_19) + m(a[4], b2_19);
+        let c2 = m(a[0], b[2]) + m(a[1], b[1]) + m(a[2], b[0]) + m(a[3], b4_19) + m(a[4], b3_19);
+        let c3 = m(a[0], b[3]) + m(a[1], b[2]) + m(a[2], b[1]) + m(a[3], b[0]) + m(a[4], b4_19);
+        let c4 = m(a[0], b[4]) + m(a[1], b[3]) + m(a[2], b[2]) + m(a[3], b[1]) + m(a[4], b[0]);
+        *self = Self::carry([c0, c1, c2, c3, c4]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LOW_51, MAX_INPUT_LIMB, SIXTEEN_P, Z25519};
+
+    #[test]
+    fn test_non_canonical_limbs() {
+        let p = Z25519 {
+            limbs: [LOW_51 - 18, LOW_51, LOW_51, LOW_51, LOW_51],
+        };
+        assert_eq!(p, 0.into());
+        assert_eq!(p.canonical_limbs(), [0; 5]);
+        let p_plus_5 = Z25519 {
+            limbs: [LOW_51 - 13, LOW_51, LOW_51, LOW_51, LOW_51],
+        };
+        assert_eq!(p_plus_5.canonical_limbs(), [5, 0, 0, 0, 0]);
+        let bytes: [u8; 32] = p_plus_5.into();
+        assert_eq!(bytes[0], 5);
+        assert!(bytes[1..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_lazy_subtraction() {
+        let a = Z25519::from(1234567);
+        let b = Z25519::from(7654321);
+        // Subtracting 16P itself, the largest allowed right hand side, changes nothing.
+        let sixteen_p = Z25519 { limbs: SIXTEEN_P };
+        assert_eq!(a - sixteen_p, a);
+        // The difference is left unreduced, but can still be used after carrying.
+        let mut diff = a - b;
+        assert!(diff.limbs.iter().any(|&x| x > LOW_51));
+        assert!(diff.limbs.iter().all(|&x| x < MAX_INPUT_LIMB));
+        assert_eq!(-diff, b - a);
+        // Subtracting it needs a carry first, since its limbs are too large.
+        diff.weak_reduce();
+        assert_eq!(a - diff, b);
+    }
+}
@@ -0,0 +1,262 @@
+//! This module provides forward secure signatures, with keys that evolve over time.
+//!
+//! Each signature uses the key of a different epoch, and the private key moves on
+//! to the next epoch after signing, forgetting the previous one. The seed for each
+//! epoch is derived from the previous one through HKDF, which is one-way, so stealing
+//! the current key doesn't allow forging signatures for earlier epochs. This makes
+//! these signatures useful for things like audit logs.
+//!
+//! The public key is the root of a Merkle tree over the public keys of every epoch,
+//! and each signature contains the epoch's public key, along with the path to this root.
+//! Because of this, all the epoch keys get derived up front, when generating a key.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::{hkdf, sha512::Sha512, zeroize::Zeroizing};
+
+use super::{PrivateKey, PublicKey, Signature};
+
+/// The largest depth of tree we support, allowing for 2^20 epochs.
+///
+/// Generating a key takes time, and memory, proportional to the number of epochs.
+pub const MAX_FORWARD_SECURE_DEPTH: u32 = 20;
+
+/// The salt we use when extracting keys from the seed of an epoch.
+const SEED_SALT: &[u8] = b"eddo forward secure seed";
+
+/// The size of the nodes in our Merkle tree.
+const NODE_SIZE: usize = 32;
+
+/// Derive the signing key for an epoch, and the seed for the next epoch.
+fn split_seed(seed: &[u8; 32]) -> (PrivateKey, [u8; 32]) {
+    let prk = Zeroizing::new(hkdf::extract(SEED_SALT, seed));
+    let mut key = PrivateKey { bytes: [0; 32] };
+    hkdf::expand(&prk, b"epoch key", &mut key.bytes);
+    let mut next = [0; 32];
+    hkdf::expand(&prk, b"next seed", &mut next);
+    (key, next)
+}
+
+fn leaf_hash(epoch: u32, public: &PublicKey) -> [u8; NODE_SIZE] {
+    let mut hasher = Sha512::new();
+    hasher.update(&[0]);
+    hasher.update(&epoch.to_be_bytes());
+    hasher.update(&public.bytes);
+    let mut out = [0; NODE_SIZE];
+    out.copy_from_slice(&hasher.finalize()[..NODE_SIZE]);
+    out
+}
+
+fn node_hash(left: &[u8; NODE_SIZE], right: &[u8; NODE_SIZE]) -> [u8; NODE_SIZE] {
+    let mut hasher = Sha512::new();
+    hasher.update(&[1]);
+    hasher.update(left);
+    hasher.update(right);
+    let mut out = [0; NODE_SIZE];
+    out.copy_from_slice(&hasher.finalize()[..NODE_SIZE]);
+    out
+}
+
+/// The public key for a forward secure signer, covering every epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForwardSecurePublicKey {
+    /// The root of the tree of epoch public keys.
+    pub root: [u8; NODE_SIZE],
+    /// The depth of this tree, with 2^depth epochs in total.
+    pub depth: u32,
+}
+
+/// A signature made during one epoch of a forward secure key.
+#[derive(Clone, Debug)]
+pub struct ForwardSecureSignature {
+    /// The epoch this signature was made in.
+    pub epoch: u32,
+    /// The public key specific to this epoch.
+    pub public_key: PublicKey,
+    /// The siblings on the path from this epoch's leaf up to the root.
+    pub path: Vec<[u8; NODE_SIZE]>,
+    /// A standard Ed25519 signature, using the key of this epoch.
+    pub signature: Signature,
+}
+
+impl ForwardSecurePublicKey {
+    /// Verify a signature over a message, made in any epoch of this key.
+    pub fn verify(&self, message: &[u8], signature: &ForwardSecureSignature) -> bool {
+        if self.depth > MAX_FORWARD_SECURE_DEPTH
+            || u64::from(signature.epoch) >= 1 << self.depth
+            || signature.path.len() != self.depth as usize
+        {
+            return false;
+        }
+        let mut node = leaf_hash(signature.epoch, &signature.public_key);
+        for (level, sibling) in signature.path.iter().enumerate() {
+            node = if (signature.epoch >> level) & 1 == 0 {
+                node_hash(&node, sibling)
+            } else {
+                node_hash(sibling, &node)
+            };
+        }
+        if !bool::from(node.ct_eq(&self.root)) {
+            return false;
+        }
+        signature.public_key.verify(message, signature.signature)
+    }
+}
+
+/// A private key which evolves after every signature.
+///
+/// Since the tree over every epoch is kept, this uses 2^(depth + 1) * 32 bytes of memory.
+pub struct ForwardSecureKey {
+    seed: [u8; 32],
+    epoch: u32,
+    depth: u32,
+    // Each level of the tree, starting from the leaves. This is all public.
+    levels: Vec<Vec<[u8; NODE_SIZE]>>,
+}
+
+impl ForwardSecureKey {
+    /// Generate a new key, for 2^depth epochs.
+    ///
+    /// This will panic if depth is larger than `MAX_FORWARD_SECURE_DEPTH`.
+    pub fn generate<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        depth: u32,
+    ) -> (ForwardSecureKey, ForwardSecurePublicKey) {
+        let mut seed = Zeroizing::new([0; 32]);
+        rng.fill_bytes(&mut *seed);
+        Self::from_seed(&seed, depth)
+    }
+
+    fn from_seed(seed: &[u8; 32], depth: u32) -> (ForwardSecureKey, ForwardSecurePublicKey) {
+        assert!(
+            depth <= MAX_FORWARD_SECURE_DEPTH,
+            "forward secure key too deep"
+        );
+        let mut leaves = Vec::with_capacity(1 << depth);
+        let mut current = Zeroizing::new(*seed);
+        for epoch in 0..(1u32 << depth) {
+            let (key, next) = split_seed(&current);
+            leaves.push(leaf_hash(epoch, &key.derive_public_key()));
+            *current = next;
+        }
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks_exact(2)
+                .map(|pair| node_hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+        let public = ForwardSecurePublicKey {
+            root: levels[levels.len() - 1][0],
+            depth,
+        };
+        let key = ForwardSecureKey {
+            seed: *seed,
+            epoch: 0,
+            depth,
+            levels,
+        };
+        (key, public)
+    }
+
+    /// The epoch the next signature will be made in.
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Sign a message with the key of the current epoch, and then move to the next epoch.
+    ///
+    /// This returns `None` once every epoch has been used.
+    pub fn sign(&mut self, message: &[u8]) -> Option<ForwardSecureSignature> {
+        if u64::from(self.epoch) >= 1 << self.depth {
+            return None;
+        }
+        let (key, next) = split_seed(&self.seed);
+        let path = (0..self.depth as usize)
+            .map(|level| self.levels[level][((self.epoch >> level) ^ 1) as usize])
+            .collect();
+        let signature = ForwardSecureSignature {
+            epoch: self.epoch,
+            public_key: key.derive_public_key(),
+            path,
+            signature: key.sign(message),
+        };
+        self.seed = next;
+        self.epoch += 1;
+        Some(signature)
+    }
+}
+
+/// This never prints the seed, only the current epoch.
+impl fmt::Debug for ForwardSecureKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForwardSecureKey")
+            .field("epoch", &self.epoch)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ForwardSecureKey {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signatures_verify_in_every_epoch() {
+        let (mut key, public) = ForwardSecureKey::from_seed(&[1; 32], 3);
+        for epoch in 0..8 {
+            assert_eq!(key.epoch(), epoch);
+            let message = [epoch as u8; 10];
+            let sig = key.sign(&message).unwrap();
+            assert_eq!(sig.epoch, epoch);
+            assert!(public.verify(&message, &sig));
+            assert!(!public.verify(b"other", &sig));
+        }
+        assert!(key.sign(b"exhausted").is_none());
+    }
+
+    #[test]
+    fn test_rejects_wrong_epoch_or_path() {
+        let (mut key, public) = ForwardSecureKey::from_seed(&[2; 32], 2);
+        let sig = key.sign(b"message").unwrap();
+        let mut wrong_epoch = sig.clone();
+        wrong_epoch.epoch = 1;
+        assert!(!public.verify(b"message", &wrong_epoch));
+        let mut wrong_path = sig.clone();
+        wrong_path.path[1][0] ^= 1;
+        assert!(!public.verify(b"message", &wrong_path));
+        let mut short_path = sig;
+        short_path.path.pop();
+        assert!(!public.verify(b"message", &short_path));
+    }
+
+    #[test]
+    fn test_later_keys_cannot_sign_earlier_epochs() {
+        let (mut key, public) = ForwardSecureKey::from_seed(&[3; 32], 2);
+        key.sign(b"first").unwrap();
+        // Even with the evolved state, we can only produce keys for later epochs
+        let (stolen, _) = split_seed(&key.seed);
+        let forged = ForwardSecureSignature {
+            epoch: 0,
+            public_key: stolen.derive_public_key(),
+            path: key.sign(b"probe").unwrap().path,
+            signature: stolen.sign(b"forged"),
+        };
+        assert!(!public.verify(b"forged", &forged));
+    }
+}
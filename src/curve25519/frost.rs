@@ -0,0 +1,602 @@
+//! This module implements FROST threshold signatures, with the FROST(Ed25519, SHA-512)
+//! ciphersuite from RFC 9591:
+//! https://datatracker.ietf.org/doc/html/rfc9591
+//!
+//! A key is split into shares, so that any `min_signers` of them can produce a
+//! signature together, in two rounds, without ever reconstructing the key. The result
+//! is a standard Ed25519 signature, which `PublicKey::verify` accepts.
+//!
+//! Keys are generated by a trusted dealer, following Appendix C. Signers send their
+//! commitments to a coordinator in the first round, and their shares in the second.
+
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::sha512::{self, Sha512};
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    vss::{self, lagrange_coefficient},
+    Error, PrivateKey, PublicKey, Signature, SIGNATURE_SIZE,
+};
+
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+/// Hash some parts into a scalar, as H1, and H3, in Section 6.1 of RFC 9591.
+///
+/// The RFC concatenates everything without length prefixes, unlike `Scalar::from_tagged_hash`,
+/// so this has to stay separate to match the test vectors.
+fn hash_to_scalar(tag: &[u8], parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(CONTEXT);
+    hasher.update(tag);
+    for part in parts {
+        hasher.update(part);
+    }
+    Scalar::from_hash(hasher)
+}
+
+fn hash(tag: &[u8], message: &[u8]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(CONTEXT);
+    hasher.update(tag);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// Decode an element, which can't be the identity, and has to be in the prime order subgroup.
+fn decode(bytes: &[u8; 32]) -> Result<Point, Error> {
+    let point = Point::try_from(&bytes[..]).map_err(|_| Error::PointNotOnCurve)?;
+    if point.encode() == Point::identity().encode() || !point.is_torsion_free() {
+        return Err(Error::PointNotOnCurve);
+    }
+    Ok(point)
+}
+
+fn identifier_scalar(identifier: u16) -> Scalar {
+    Scalar::from(u64::from(identifier))
+}
+
+/// The share of a key held by one signer.
+pub struct FrostKeyShare {
+    identifier: u16,
+    min_signers: u16,
+    secret: Scalar,
+    group_public_key: PublicKey,
+}
+
+impl FrostKeyShare {
+    pub(super) fn from_parts(
+        identifier: u16,
+        min_signers: u16,
+        secret: Scalar,
+        group_public_key: PublicKey,
+    ) -> Self {
+        FrostKeyShare {
+            identifier,
+            min_signers,
+            secret,
+            group_public_key,
+        }
+    }
+
+    fn deal_secret<R: RngCore + CryptoRng>(
+        secret: Scalar,
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<Vec<Self>, Error> {
+        if min_signers < 2 || min_signers > max_signers {
+            return Err(Error::InvalidLength);
+        }
+        let (shares, commitment) = vss::share_secret(secret, min_signers, max_signers, rng)?;
+        let group_public_key = PublicKey {
+            bytes: commitment.public_key().into(),
+        };
+        let shares = shares
+            .into_iter()
+            .map(|share| Self::from_parts(share.index, min_signers, share.value, group_public_key))
+            .collect();
+        Ok(shares)
+    }
+
+    /// Generate a new key, split into `max_signers` shares, any `min_signers` of which can sign.
+    ///
+    /// Each share has to be sent to its signer privately, and then deleted by the dealer.
+    pub fn deal<R: RngCore + CryptoRng>(
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<Vec<Self>, Error> {
+        let secret = Scalar::random(rng);
+        Self::deal_secret(secret, min_signers, max_signers, rng)
+    }
+
+    /// Split an existing key into shares, which sign for the same public key.
+    pub fn split<R: RngCore + CryptoRng>(
+        private: &PrivateKey,
+        min_signers: u16,
+        max_signers: u16,
+        rng: &mut R,
+    ) -> Result<Vec<Self>, Error> {
+        let hash = Zeroizing::new(sha512::hash(&private.bytes));
+        let secret = Scalar::clamped(hash[..32].try_into().unwrap());
+        Self::deal_secret(secret, min_signers, max_signers, rng)
+    }
+
+    /// The identifier of this signer, between 1 and `max_signers`.
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// The number of signers needed to produce a signature.
+    pub fn min_signers(&self) -> u16 {
+        self.min_signers
+    }
+
+    /// The public key that the signers produce signatures for.
+    pub fn group_public_key(&self) -> PublicKey {
+        self.group_public_key
+    }
+
+    /// The public counterpart of this share, which lets a coordinator check signature shares.
+    pub fn verifying_share(&self) -> [u8; 32] {
+        (point::B * self.secret).encode()
+    }
+
+    /// Derive a nonce from fresh randomness, and the secret share, following Section 4.1.
+    fn nonce_from_randomness(&self, random: &[u8; 32]) -> Scalar {
+        let secret = Zeroizing::new(<[u8; 32]>::from(self.secret));
+        hash_to_scalar(b"nonce", &[random, &*secret])
+    }
+
+    fn generate_nonce<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Scalar {
+        let mut random = Zeroizing::new([0; 32]);
+        rng.fill_bytes(&mut *random);
+        self.nonce_from_randomness(&random)
+    }
+
+    fn commit_with_nonces(
+        &self,
+        hiding: Scalar,
+        binding: Scalar,
+    ) -> (FrostNonces, FrostCommitment) {
+        let commitment = FrostCommitment {
+            identifier: self.identifier,
+            hiding: (point::B * hiding).encode(),
+            binding: (point::B * binding).encode(),
+        };
+        let nonces = FrostNonces {
+            identifier: self.identifier,
+            hiding,
+            binding,
+        };
+        (nonces, commitment)
+    }
+
+    /// Run the first round, generating nonces, and the commitment to send to the coordinator.
+    ///
+    /// The nonces have to be kept secret, and used for a single signature.
+    pub fn commit<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (FrostNonces, FrostCommitment) {
+        let hiding = self.generate_nonce(rng);
+        let binding = self.generate_nonce(rng);
+        self.commit_with_nonces(hiding, binding)
+    }
+
+    /// Run the second round, producing a share of the signature on a message.
+    ///
+    /// The commitments are those of all the signers chosen by the coordinator,
+    /// which have to include the commitment matching these nonces.
+    pub fn sign(
+        &self,
+        nonces: FrostNonces,
+        message: &[u8],
+        commitments: &[FrostCommitment],
+    ) -> Result<FrostSignatureShare, Error> {
+        let package = SigningPackage::new(&self.group_public_key, message, commitments)?;
+        if package.signers.len() < usize::from(self.min_signers) {
+            return Err(Error::InvalidLength);
+        }
+        let ours = commitments
+            .iter()
+            .find(|c| c.identifier == self.identifier)
+            .ok_or(Error::InvalidEncoding)?;
+        if nonces.identifier != self.identifier
+            || ours.hiding != (point::B * nonces.hiding).encode()
+            || ours.binding != (point::B * nonces.binding).encode()
+        {
+            return Err(Error::InvalidEncoding);
+        }
+        let rho = package.binding_factor(self.identifier);
+        let lambda = lagrange_coefficient(self.identifier, &package.signers);
+        let share = nonces.hiding + nonces.binding * rho + lambda * self.secret * package.challenge;
+        Ok(FrostSignatureShare {
+            identifier: self.identifier,
+            share: share.into(),
+        })
+    }
+}
+
+/// This never prints the secret share.
+impl fmt::Debug for FrostKeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostKeyShare")
+            .field("identifier", &self.identifier)
+            .field("min_signers", &self.min_signers)
+            .field("group_public_key", &self.group_public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for FrostKeyShare {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// The secret nonces of one signer, for a single signature.
+///
+/// These can't be cloned, and get consumed by signing, since reusing them leaks the share.
+pub struct FrostNonces {
+    identifier: u16,
+    hiding: Scalar,
+    binding: Scalar,
+}
+
+/// This never prints the nonces.
+impl fmt::Debug for FrostNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostNonces")
+            .field("identifier", &self.identifier)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for FrostNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+/// The commitment to the nonces of one signer, sent in the first round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrostCommitment {
+    pub identifier: u16,
+    pub hiding: [u8; 32],
+    pub binding: [u8; 32],
+}
+
+/// The share of a signature produced by one signer, sent in the second round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrostSignatureShare {
+    pub identifier: u16,
+    pub share: [u8; 32],
+}
+
+/// Everything derived from the commitments, the message, and the key, following Section 4.
+struct SigningPackage {
+    signers: Vec<u16>,
+    rho_prefix: Vec<u8>,
+    group_commitment: Point,
+    challenge: Scalar,
+}
+
+impl SigningPackage {
+    fn new(
+        group_public_key: &PublicKey,
+        message: &[u8],
+        commitments: &[FrostCommitment],
+    ) -> Result<Self, Error> {
+        let mut sorted = commitments.to_vec();
+        sorted.sort_by_key(|c| c.identifier);
+        if sorted
+            .windows(2)
+            .any(|w| w[0].identifier == w[1].identifier)
+            || sorted.first().is_some_and(|c| c.identifier == 0)
+        {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut encoded = Vec::new();
+        for c in sorted.iter() {
+            encoded.extend_from_slice(&<[u8; 32]>::from(identifier_scalar(c.identifier)));
+            encoded.extend_from_slice(&c.hiding);
+            encoded.extend_from_slice(&c.binding);
+        }
+        let mut rho_prefix = group_public_key.bytes.to_vec();
+        rho_prefix.extend_from_slice(&hash(b"msg", message));
+        rho_prefix.extend_from_slice(&hash(b"com", &encoded));
+
+        let mut package = SigningPackage {
+            signers: sorted.iter().map(|c| c.identifier).collect(),
+            rho_prefix,
+            group_commitment: Point::identity(),
+            challenge: Scalar::from(0),
+        };
+        for c in sorted.iter() {
+            let rho = package.binding_factor(c.identifier);
+            package.group_commitment =
+                package.group_commitment + decode(&c.hiding)? + decode(&c.binding)? * rho;
+        }
+        let a = Point::try_from(&group_public_key.bytes[..]).map_err(|_| Error::PointNotOnCurve)?;
+        package.challenge = PublicKey::challenge(&package.group_commitment.encode(), a, &[message]);
+        Ok(package)
+    }
+
+    fn binding_factor(&self, identifier: u16) -> Scalar {
+        let identifier: [u8; 32] = identifier_scalar(identifier).into();
+        hash_to_scalar(b"rho", &[&self.rho_prefix, &identifier])
+    }
+}
+
+/// Check a share of the signature, against the verifying share of its signer.
+///
+/// A coordinator can use this to find out which signers misbehaved, when aggregation fails.
+pub fn frost_verify_share(
+    verifying_share: &[u8; 32],
+    group_public_key: &PublicKey,
+    message: &[u8],
+    commitments: &[FrostCommitment],
+    share: &FrostSignatureShare,
+) -> Result<(), Error> {
+    let package = SigningPackage::new(group_public_key, message, commitments)?;
+    let commitment = commitments
+        .iter()
+        .find(|c| c.identifier == share.identifier)
+        .ok_or(Error::InvalidEncoding)?;
+    let z = Scalar::try_from(&share.share[..]).map_err(|_| Error::NonCanonicalScalar)?;
+    let rho = package.binding_factor(share.identifier);
+    let lambda = lagrange_coefficient(share.identifier, &package.signers);
+    let expected = decode(&commitment.hiding)?
+        + decode(&commitment.binding)? * rho
+        + decode(verifying_share)? * (package.challenge * lambda);
+    if (point::B * z).encode() != expected.encode() {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// Aggregate the shares of all the signers into a signature, following Section 5.3.
+///
+/// The result is checked against the group public key, so an invalid share makes this fail.
+pub fn frost_aggregate(
+    group_public_key: &PublicKey,
+    message: &[u8],
+    commitments: &[FrostCommitment],
+    shares: &[FrostSignatureShare],
+) -> Result<Signature, Error> {
+    let package = SigningPackage::new(group_public_key, message, commitments)?;
+    let mut identifiers: Vec<u16> = shares.iter().map(|s| s.identifier).collect();
+    identifiers.sort_unstable();
+    if identifiers != package.signers {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut z = Scalar::from(0);
+    for share in shares {
+        z += Scalar::try_from(&share.share[..]).map_err(|_| Error::NonCanonicalScalar)?;
+    }
+    let mut signature = Signature {
+        bytes: [0; SIGNATURE_SIZE],
+    };
+    signature.bytes[..32].copy_from_slice(&package.group_commitment.encode());
+    signature.bytes[32..].copy_from_slice(&<[u8; 32]>::from(z));
+    if !group_public_key.verify(message, signature) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    fn decode_hex<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(s, &mut out).unwrap();
+        out
+    }
+
+    fn scalar(s: &str) -> Scalar {
+        Scalar::try_from(&decode_hex::<32>(s)[..]).unwrap()
+    }
+
+    fn sign_with(
+        shares: &[&FrostKeyShare],
+        message: &[u8],
+    ) -> (Vec<FrostCommitment>, Vec<FrostSignatureShare>) {
+        let (nonces, commitments): (Vec<_>, Vec<_>) =
+            shares.iter().map(|s| s.commit(&mut OsRng)).unzip();
+        let signature_shares = shares
+            .iter()
+            .zip(nonces)
+            .map(|(s, n)| s.sign(n, message, &commitments).unwrap())
+            .collect();
+        (commitments, signature_shares)
+    }
+
+    #[test]
+    fn test_threshold_signing() {
+        let shares = FrostKeyShare::deal(2, 3, &mut OsRng).unwrap();
+        let public = shares[0].group_public_key();
+        for signers in [[0, 1], [0, 2], [1, 2]].iter() {
+            let chosen: Vec<_> = shares
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| signers.contains(i))
+                .map(|(_, s)| s)
+                .collect();
+            let (commitments, signature_shares) = sign_with(&chosen, b"message");
+            for (key, share) in chosen.iter().zip(signature_shares.iter()) {
+                assert!(frost_verify_share(
+                    &key.verifying_share(),
+                    &public,
+                    b"message",
+                    &commitments,
+                    share
+                )
+                .is_ok());
+            }
+            let signature =
+                frost_aggregate(&public, b"message", &commitments, &signature_shares).unwrap();
+            assert!(public.verify(b"message", signature));
+        }
+    }
+
+    #[test]
+    fn test_split_and_bad_shares() {
+        let private = PrivateKey::from([3; 32]);
+        let public = private.derive_public_key();
+        let shares = FrostKeyShare::split(&private, 3, 5, &mut OsRng).unwrap();
+        assert!(shares.iter().all(|s| s.group_public_key() == public));
+
+        let (commitments, mut signature_shares) =
+            sign_with(&shares[1..4].iter().collect::<Vec<_>>(), b"message");
+        let signature =
+            frost_aggregate(&public, b"message", &commitments, &signature_shares).unwrap();
+        assert!(public.verify(b"message", signature));
+
+        signature_shares[1].share[0] ^= 1;
+        assert_eq!(
+            frost_verify_share(
+                &shares[2].verifying_share(),
+                &public,
+                b"message",
+                &commitments,
+                &signature_shares[1]
+            ),
+            Err(Error::SignatureInvalid)
+        );
+        assert!(frost_aggregate(&public, b"message", &commitments, &signature_shares).is_err());
+
+        // Too few signers, or missing commitments for these nonces, get rejected.
+        let (nonces, commitment) = shares[0].commit(&mut OsRng);
+        assert_eq!(
+            shares[0]
+                .sign(nonces, b"message", &[commitment])
+                .unwrap_err(),
+            Error::InvalidLength
+        );
+        let (nonces, _) = shares[0].commit(&mut OsRng);
+        assert_eq!(
+            shares[0]
+                .sign(nonces, b"message", &commitments)
+                .unwrap_err(),
+            Error::InvalidEncoding
+        );
+        assert!(FrostKeyShare::deal(4, 3, &mut OsRng).is_err());
+    }
+
+    #[test]
+    fn test_rfc_9591_vectors() {
+        // The FROST(Ed25519, SHA-512) vectors from Appendix E.1 of RFC 9591.
+        let group_secret =
+            scalar("7b1c33d3f5291d85de664833beb1ad469f7fb6025a0ec78b3a790c6e13a98304");
+        let group_public_key = PublicKey {
+            bytes: decode_hex("15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673"),
+        };
+        assert_eq!((point::B * group_secret).encode(), group_public_key.bytes);
+        let coefficient =
+            scalar("178199860edd8c62f5212ee91eff1295d0d670ab4ed4506866bae57e7030b204");
+        let message = b"test";
+
+        let shares: Vec<FrostKeyShare> = [
+            "929dcc590407aae7d388761cddb0c0db6f5627aea8e217f4a033f2ec83d93509",
+            "a91e66e012e4364ac9aaa405fcafd370402d9859f7b6685c07eed76bf409e80d",
+            "d3cb090a075eb154e82fdb4b3cb507f110040905468bb9c46da8bdea643a9a02",
+        ]
+        .iter()
+        .zip(1..)
+        .map(|(share, identifier)| {
+            let secret = scalar(share);
+            assert_eq!(
+                secret,
+                group_secret + coefficient * identifier_scalar(identifier)
+            );
+            FrostKeyShare::from_parts(identifier, 2, secret, group_public_key)
+        })
+        .collect();
+
+        // For each of P1 and P3: the hiding and binding randomness, the nonces, their
+        // commitments, the binding factor, and the signature share.
+        let signers = [
+            (
+                &shares[0],
+                [
+                    "0fd2e39e111cdc266f6c0f4d0fd45c947761f1f5d3cb583dfcb9bbaf8d4c9fec",
+                    "69cd85f631d5f7f2721ed5e40519b1366f340a87c2f6856363dbdcda348a7501",
+                ],
+                [
+                    "812d6104142944d5a55924de6d49940956206909f2acaeedecda2b726e630407",
+                    "b1110165fc2334149750b28dd813a39244f315cff14d4e89e6142f262ed83301",
+                ],
+                [
+                    "b5aa8ab305882a6fc69cbee9327e5a45e54c08af61ae77cb8207be3d2ce13de3",
+                    "67e98ab55aa310c3120418e5050c9cf76cf387cb20ac9e4b6fdb6f82a469f932",
+                ],
+                "f2cb9d7dd9beff688da6fcc83fa89046b3479417f47f55600b106760eb3b5603",
+                "001719ab5a53ee1a12095cd088fd149702c0720ce5fd2f29dbecf24b7281b603",
+            ),
+            (
+                &shares[2],
+                [
+                    "86d64a260059e495d0fb4fcc17ea3da7452391baa494d4b00321098ed2a0062f",
+                    "13e6b25afb2eba51716a9a7d44130c0dbae0004a9ef8d7b5550c8a0e07c61775",
+                ],
+                [
+                    "c256de65476204095ebdc01bd11dc10e57b36bc96284595b8215222374f99c0e",
+                    "243d71944d929063bc51205714ae3c2218bd3451d0214dfb5aeec2a90c35180d",
+                ],
+                [
+                    "cfbdb165bd8aad6eb79deb8d287bcc0ab6658ae57fdcc98ed12c0669e90aec91",
+                    "7487bc41a6e712eea2f2af24681b58b1cf1da278ea11fe4e8b78398965f13552",
+                ],
+                "b087686bf35a13f3dc78e780a34b0fe8a77fef1b9938c563f5573d71d8d7890f",
+                "bd86125de990acc5e1f13781d8e32c03a9bbd4c53539bbc106058bfd14326007",
+            ),
+        ];
+
+        let mut nonces = Vec::new();
+        let mut commitments = Vec::new();
+        for (share, randomness, expected_nonces, expected_commitments, _, _) in signers.iter() {
+            let hiding = share.nonce_from_randomness(&decode_hex(randomness[0]));
+            let binding = share.nonce_from_randomness(&decode_hex(randomness[1]));
+            assert_eq!(hiding, scalar(expected_nonces[0]));
+            assert_eq!(binding, scalar(expected_nonces[1]));
+            let (nonce, commitment) = share.commit_with_nonces(hiding, binding);
+            assert_eq!(commitment.hiding, decode_hex(expected_commitments[0]));
+            assert_eq!(commitment.binding, decode_hex(expected_commitments[1]));
+            nonces.push(nonce);
+            commitments.push(commitment);
+        }
+
+        let package = SigningPackage::new(&group_public_key, message, &commitments).unwrap();
+        let mut signature_shares = Vec::new();
+        for ((share, _, _, _, binding_factor, expected), nonce) in signers.iter().zip(nonces) {
+            assert_eq!(
+                package.binding_factor(share.identifier),
+                scalar(binding_factor)
+            );
+            let signature_share = share.sign(nonce, message, &commitments).unwrap();
+            assert_eq!(signature_share.share, decode_hex(expected));
+            signature_shares.push(signature_share);
+        }
+
+        let signature =
+            frost_aggregate(&group_public_key, message, &commitments, &signature_shares).unwrap();
+        assert_eq!(
+            signature.bytes,
+            decode_hex::<64>("36282629c383bb820a88b71cae937d41f2f2adfcc3d02e55507e2fb9e2dd3cbebd9d2b0844e49ae0f3fa935161e1419aab7b47d21a37ebeae1f17d4987b3160b")
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! This module exposes the raw building blocks underneath our signatures.
+//!
+//! Nothing here is covered by semver: these types may change, or disappear, in
+//! any release. They're provided for research, and for building new protocols,
+//! and are only available with the `hazmat` feature.
+//!
+//! Unlike the rest of the crate, these APIs make it easy to shoot yourself in the foot.
+//! Not every operation is constant-time, and nothing checks that values are used
+//! in a way which makes sense cryptographically.
+//!
+//! The protocols built on top of signatures, like blind, ring, and threshold signatures,
+//! key derivation schemes, OPRFs, and PAKEs, also live here, until their APIs settle.
+//!
+//! Besides the raw types, this has the escape hatches between them and the rest of the
+//! crate: expanding a private key into its secret scalar, and nonce prefix, as in
+//! Section 5.1.5 of RFC 8032, signing with those directly, and converting public keys
+//! to, and from, points:
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.5
+
+use core::convert::{TryFrom, TryInto};
+
+pub use super::adaptor::{AdaptorPoint, AdaptorSecret, PreSignature, PRE_SIGNATURE_SIZE};
+#[cfg(feature = "std")]
+pub use super::audit::{
+    verify_log, BrokenLink, BrokenLinkReason, LogEntry, LogWriter, ENTRY_HASH_SIZE, GENESIS_HASH,
+};
+pub use super::bip32::{
+    Bip32PrivateKey, Bip32PublicKey, BIP32_PRIVATE_KEY_SIZE, BIP32_PUBLIC_KEY_SIZE,
+};
+pub use super::blind::{
+    blind_verify, BlindClauseNonces, BlindClauseRequest, BlindNonce, BlindRequest, BlindSignature,
+    BlindSigner, BLIND_PUBLIC_KEY_SIZE, BLIND_SIGNATURE_SIZE,
+};
+pub use super::blinding::BlindedPrivateKey;
+#[cfg(feature = "alloc")]
+pub use super::dkg::{DkgCommitment, DkgComplaint, DkgOutput, DkgParticipant, DkgShare};
+#[cfg(feature = "alloc")]
+pub use super::dleq::{
+    prove_dleq, prove_dleq_batch, verify_dleq, verify_dleq_batch, DleqProof, DLEQ_PROOF_SIZE,
+};
+#[cfg(feature = "std")]
+pub use super::elgamal::{ElGamalCiphertext, ElGamalPublicKey, ElGamalSecretKey};
+pub use super::field::{SQRT_M1, Z25519};
+#[cfg(feature = "alloc")]
+pub use super::forward::{
+    ForwardSecureKey, ForwardSecurePublicKey, ForwardSecureSignature, MAX_FORWARD_SECURE_DEPTH,
+};
+#[cfg(feature = "alloc")]
+pub use super::frost::{
+    frost_aggregate, frost_verify_share, FrostCommitment, FrostKeyShare, FrostNonces,
+    FrostSignatureShare,
+};
+pub use super::montgomery::{ProjectiveU, BASE_U};
+#[cfg(feature = "alloc")]
+pub use super::musig2::{
+    musig2_verify, Musig2AggregateKey, Musig2Commitment, Musig2Nonces, Musig2Signature,
+    Musig2Signer, MUSIG2_COMMITMENT_SIZE, MUSIG2_PUBLIC_KEY_SIZE, MUSIG2_SECRET_KEY_SIZE,
+    MUSIG2_SIGNATURE_SIZE,
+};
+#[cfg(feature = "alloc")]
+pub use super::oprf::{
+    OprfClient, OprfEvaluation, OprfServer, OPRF_ELEMENT_SIZE, OPRF_OUTPUT_SIZE, OPRF_PROOF_SIZE,
+};
+pub use super::point::{AffinePoint, Point, ProjectiveNiels, B};
+#[cfg(feature = "alloc")]
+pub use super::poly::{evaluate_many, interpolate, Polynomial};
+#[cfg(feature = "std")]
+pub use super::quorum::{QuorumResult, QuorumVerifier};
+#[cfg(feature = "alloc")]
+pub use super::ring::{
+    ring_verify, ring_verify_linkable, LinkableRingSignature, RingSignature, RingSigner,
+    KEY_IMAGE_SIZE, RING_KEY_SIZE,
+};
+pub use super::scalar::{MontgomeryScalar, Scalar, WideDigest, L};
+pub use super::schnorr::{SchnorrProof, SCHNORR_PROOF_SIZE};
+pub use super::slip10::{Slip10Key, CHAIN_CODE_SIZE, HARDENED_INDEX};
+#[cfg(feature = "alloc")]
+pub use super::spake2::{
+    Spake2, Spake2Keys, SPAKE2_CONFIRMATION_SIZE, SPAKE2_KEY_SIZE, SPAKE2_MESSAGE_SIZE,
+};
+pub use super::vrf::{VrfProof, VRF_OUTPUT_SIZE, VRF_PROOF_SIZE};
+#[cfg(feature = "alloc")]
+pub use super::vss::{
+    lagrange_coefficient, reconstruct_secret, share_secret, FeldmanCommitment, SecretShare,
+};
+
+use super::{PrivateKey, PublicKey, Signature};
+use crate::sha512;
+use crate::zeroize::Zeroizing;
+
+/// Expand a private key into its secret scalar, and the prefix used to derive nonces.
+///
+/// The public key is B * scalar.
+pub fn expand_private_key(private: &PrivateKey) -> (Scalar, [u8; 32]) {
+    let hash = Zeroizing::new(sha512::hash(&private.bytes));
+    let scalar = Scalar::clamped(hash[..32].try_into().unwrap());
+    let prefix = hash[32..].try_into().unwrap();
+    (scalar, prefix)
+}
+
+/// Sign a message with a secret scalar, and a nonce prefix, directly.
+///
+/// With the output of `expand_private_key`, this is the same as `PrivateKey::sign`.
+/// Since nonces are derived from the prefix, and the message, the prefix needs to
+/// be as secret as the scalar, and must never be shared with another scalar.
+pub fn raw_sign(scalar: Scalar, prefix: &[u8; 32], message: &[u8]) -> Signature {
+    PrivateKey::sign_with_scalar(scalar, prefix, &[message])
+}
+
+/// Decode a public key into the point it encodes.
+pub fn public_key_to_point(public: &PublicKey) -> Point {
+    // Public keys are always checked to encode a point when created.
+    Point::try_from(&public.bytes[..]).unwrap()
+}
+
+/// Encode a point as a public key.
+///
+/// Nothing stops this from producing a key of small order, or the identity.
+pub fn point_to_public_key(point: Point) -> PublicKey {
+    PublicKey {
+        bytes: point.into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use subtle::ConstantTimeEq;
+
+    #[test]
+    fn test_raw_sign_matches_sign() {
+        let private = PrivateKey::from_seed([5; 32]);
+        let (scalar, prefix) = expand_private_key(&private);
+        let signature = raw_sign(scalar, &prefix, b"low level");
+        assert_eq!(signature, private.sign(b"low level"));
+        assert_eq!(point_to_public_key(B * scalar), private.derive_public_key());
+    }
+
+    #[test]
+    fn test_public_key_point_roundtrip() {
+        let public = PrivateKey::from_seed([6; 32]).derive_public_key();
+        let point = public_key_to_point(&public);
+        assert_eq!(point_to_public_key(point), public);
+        assert!(bool::from((point + -point).ct_eq(&Point::identity())));
+    }
+}
@@ -0,0 +1,182 @@
+//! This module implements JSON Web Keys for our keys, as described in RFC 8037:
+//! https://datatracker.ietf.org/doc/html/rfc8037#section-2
+//!
+//! Thumbprints follow RFC 7638, using SHA-256, like most implementations do:
+//! https://datatracker.ietf.org/doc/html/rfc7638
+
+use core::convert::{TryFrom, TryInto};
+
+use subtle::ConstantTimeEq;
+
+use crate::{base64, json, sha256, zeroize::Zeroizing};
+
+use super::{Error, ExchangePublicKey, PrivateKey, PublicKey, PRIVATE_KEY_SIZE};
+
+const ED25519: &str = "Ed25519";
+const X25519: &str = "X25519";
+
+/// Encode a key, with its members in lexicographic order, which makes this usable for thumbprints.
+fn encode_public(curve: &str, x: &[u8]) -> String {
+    format!(
+        r#"{{"crv":"{}","kty":"OKP","x":"{}"}}"#,
+        curve,
+        base64::encode_url(x)
+    )
+}
+
+fn thumbprint(curve: &str, x: &[u8]) -> String {
+    base64::encode_url(&sha256::hash(encode_public(curve, x).as_bytes()))
+}
+
+/// Parse an "OKP" key for a given curve, returning the decoded "x", and "d", if present.
+fn parse(input: &str, curve: &str) -> Result<(Vec<u8>, Option<Vec<u8>>), Error> {
+    let members = json::parse_object(input)?;
+    if json::get_string(&members, "kty")? != Some("OKP")
+        || json::get_string(&members, "crv")? != Some(curve)
+    {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    let decode = |name| -> Result<Option<Vec<u8>>, Error> {
+        json::get_string(&members, name)?
+            .map(|x| base64::decode_url(x.as_bytes()).map_err(|_| Error::InvalidEncoding))
+            .transpose()
+    };
+    let x = decode("x")?.ok_or(Error::InvalidEncoding)?;
+    Ok((x, decode("d")?))
+}
+
+impl PublicKey {
+    /// Encode this key as a JWK, with "kty" set to "OKP", and "crv" set to "Ed25519".
+    pub fn to_jwk(&self) -> String {
+        encode_public(ED25519, &self.bytes)
+    }
+
+    /// Parse a JWK containing an Ed25519 key.
+    ///
+    /// Members other than "kty", "crv", and "x", are ignored, including "d", so this
+    /// also accepts private keys.
+    pub fn from_jwk(input: &str) -> Result<Self, Error> {
+        let (x, _) = parse(input, ED25519)?;
+        PublicKey::try_from(&x[..])
+    }
+
+    /// Calculate the JWK thumbprint of this key, encoded as Base64url.
+    pub fn jwk_thumbprint(&self) -> String {
+        thumbprint(ED25519, &self.bytes)
+    }
+}
+
+impl PrivateKey {
+    /// Encode this key as a JWK, including both the public "x", and the private "d".
+    pub fn to_jwk(&self) -> String {
+        let public = self.derive_public_key();
+        let d = Zeroizing::new(base64::encode_url(&self.bytes));
+        format!(
+            r#"{{"kty":"OKP","crv":"{}","x":"{}","d":"{}"}}"#,
+            ED25519,
+            base64::encode_url(&public.bytes),
+            d.as_str()
+        )
+    }
+
+    /// Parse a JWK containing an Ed25519 private key.
+    ///
+    /// The public key in "x" has to match the private key in "d".
+    pub fn from_jwk(input: &str) -> Result<Self, Error> {
+        let (x, d) = parse(input, ED25519)?;
+        let d = Zeroizing::new(d.ok_or(Error::InvalidEncoding)?);
+        let key = <[u8; PRIVATE_KEY_SIZE]>::try_from(&d[..])
+            .map(PrivateKey::from)
+            .map_err(|_| Error::InvalidLength)?;
+        if !bool::from(key.derive_public_key().bytes[..].ct_eq(&x)) {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(key)
+    }
+}
+
+impl ExchangePublicKey {
+    /// Encode this key as a JWK, with "kty" set to "OKP", and "crv" set to "X25519".
+    pub fn to_jwk(&self) -> String {
+        encode_public(X25519, &self.bytes)
+    }
+
+    /// Parse a JWK containing an X25519 public key.
+    pub fn from_jwk(input: &str) -> Result<Self, Error> {
+        let (x, _) = parse(input, X25519)?;
+        let bytes = x[..].try_into().map_err(|_| Error::InvalidLength)?;
+        Ok(ExchangePublicKey { bytes })
+    }
+
+    /// Calculate the JWK thumbprint of this key, encoded as Base64url.
+    pub fn jwk_thumbprint(&self) -> String {
+        thumbprint(X25519, &self.bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rfc_8037_vectors() {
+        // From Appendix A.1, and A.3
+        let input = r#"{"kty":"OKP","crv":"Ed25519",
+   "d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A",
+   "x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#;
+        let private = PrivateKey::from_jwk(input).unwrap();
+        let public = PublicKey::from_jwk(input).unwrap();
+        assert_eq!(private.derive_public_key(), public);
+        assert_eq!(
+            public.jwk_thumbprint(),
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"
+        );
+        assert_eq!(
+            PrivateKey::from_jwk(&private.to_jwk()).unwrap().bytes,
+            private.bytes
+        );
+        assert_eq!(PublicKey::from_jwk(&public.to_jwk()), Ok(public));
+        // The public key alone isn't enough
+        assert!(PrivateKey::from_jwk(&public.to_jwk()).is_err());
+
+        // From Appendix A.6
+        let exchange = ExchangePublicKey::from_jwk(
+            r#"{"kty":"OKP","crv":"X25519","x":"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(exchange.bytes),
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"
+        );
+        assert_eq!(
+            ExchangePublicKey::from_jwk(&exchange.to_jwk()),
+            Ok(exchange)
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_keys() {
+        let key = PrivateKey::from([1; PRIVATE_KEY_SIZE]);
+        let wrong_x = key.to_jwk().replace(
+            &base64::encode_url(&key.derive_public_key().bytes),
+            "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo",
+        );
+        assert_eq!(
+            PrivateKey::from_jwk(&wrong_x).unwrap_err(),
+            Error::InvalidEncoding
+        );
+        let exchange = ExchangePublicKey { bytes: [9; 32] };
+        assert_eq!(
+            PublicKey::from_jwk(&exchange.to_jwk()),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            PublicKey::from_jwk(r#"{"kty":"EC","crv":"P-256"}"#),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            PublicKey::from_jwk(r#"{"kty":"OKP","crv":"Ed25519"}"#),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
@@ -1,19 +1,123 @@
-use std::convert::{TryFrom, TryInto};
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    ops::Deref,
+    str::FromStr,
+};
+#[cfg(feature = "std")]
+use std::io::IoSlice;
 
 use rand::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
 
 use crate::{
-    curve25519::{point::Point, scalar::Scalar},
-    sha512,
+    curve25519::{field::Z25519, montgomery::ProjectiveU, point::Point, scalar::Scalar},
+    sha512::{self, Sha512},
+    zeroize::{Zeroize, Zeroizing},
 };
 
-use self::error::SignatureError;
+use self::error::{parse_hex, SignatureError};
 
-mod arithmetic;
+#[cfg(feature = "hazmat")]
+mod adaptor;
+mod attached;
+#[cfg(all(feature = "std", feature = "hazmat"))]
+mod audit;
+#[cfg(feature = "alloc")]
+mod batch;
+#[cfg(feature = "hazmat")]
+mod bip32;
+#[cfg(feature = "hazmat")]
+mod blind;
+#[cfg(feature = "hazmat")]
+mod blinding;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "alloc")]
+mod cose;
+mod cost;
+#[cfg(feature = "debug-tools")]
+pub mod debug;
+#[cfg(feature = "alloc")]
+mod derive;
+#[cfg(feature = "alloc")]
+mod did;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod dkg;
+#[cfg(feature = "alloc")]
+mod dleq;
+#[cfg(feature = "std")]
+mod elgamal;
 mod error;
 mod field;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod forward;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod frost;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
+#[cfg(feature = "std")]
+mod jwk;
+mod montgomery;
+mod msm;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod musig2;
+mod noise;
+mod nonce;
+#[cfg(feature = "alloc")]
+mod onion;
+#[cfg(feature = "std")]
+mod openssh;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod oprf;
+#[cfg(feature = "pkcs8")]
+mod pkcs8;
 mod point;
+#[cfg(feature = "alloc")]
+mod poly;
+#[cfg(all(feature = "std", feature = "hazmat"))]
+mod quorum;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod ring;
+#[cfg(feature = "hazmat")]
+mod ristretto;
 mod scalar;
+#[cfg(feature = "hazmat")]
+mod schnorr;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "signature")]
+mod signer;
+#[cfg(feature = "std")]
+mod signify;
+#[cfg(feature = "hazmat")]
+mod slip10;
+pub mod sodium;
+#[cfg(all(feature = "alloc", feature = "hazmat"))]
+mod spake2;
+#[cfg(feature = "std")]
+mod stream;
+mod trace;
+mod usage;
+mod verifying;
+#[cfg(feature = "hazmat")]
+mod vrf;
+#[cfg(feature = "alloc")]
+mod vss;
+
+#[cfg(feature = "alloc")]
+pub use batch::verify_batch;
+pub use cost::VerificationCost;
+pub use error::{CounterError, Error, VerificationError};
+pub use montgomery::x25519;
+pub use noise::{Dh25519, NOISE_DH_LEN};
+pub use nonce::{CommittedCounter, NonceCounter};
+#[cfg(feature = "std")]
+pub use signify::SIGNIFY_KEY_ID_SIZE;
+#[cfg(feature = "std")]
+pub use stream::{SigningStream, VerifyingStream};
+pub use usage::{ExchangeOnlyKey, ExchangePublicKey, SigningOnlyKey, EXCHANGE_PUBLIC_KEY_SIZE};
+pub use verifying::VerifyingKey;
 
 pub const SIGNATURE_SIZE: usize = 64;
 
@@ -22,76 +126,474 @@ pub struct Signature {
     pub bytes: [u8; SIGNATURE_SIZE],
 }
 
+impl ConstantTimeEq for Signature {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+/// This compares in constant time, so comparing against an expected signature is safe.
+impl PartialEq for Signature {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Signature {}
+
+impl fmt::LowerHex for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in &self.bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// This displays the signature in lowercase hex.
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+/// This parses a signature from hex, rejecting signatures with a non canonical scalar.
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(&parse_hex::<SIGNATURE_SIZE>(s)?[..])
+    }
+}
+
+/// This doesn't check the encoding, which happens when verifying.
+impl From<[u8; SIGNATURE_SIZE]> for Signature {
+    fn from(bytes: [u8; SIGNATURE_SIZE]) -> Self {
+        Signature { bytes }
+    }
+}
+
+/// This rejects signatures with the wrong length, or with a non canonical scalar.
+impl<'a> TryFrom<&'a [u8]> for Signature {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; SIGNATURE_SIZE] = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Scalar::try_from(&bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+        Ok(Signature { bytes })
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Signature {
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SIZE] {
+        self.bytes
+    }
+}
+
 pub const PUBLIC_KEY_SIZE: usize = 32;
 
 #[derive(Debug, Clone, Copy)]
 pub struct PublicKey {
-    pub bytes: [u8; PUBLIC_KEY_SIZE],
+    bytes: [u8; PUBLIC_KEY_SIZE],
+}
+
+impl ConstantTimeEq for PublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+/// This compares in constant time, like `ct_eq`.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in &self.bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// This displays the public key in lowercase hex.
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+/// This parses a public key from hex, rejecting keys which aren't valid points.
+impl FromStr for PublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(parse_hex::<PUBLIC_KEY_SIZE>(s)?)
+    }
+}
+
+/// This rejects keys with the wrong length, or which aren't valid points.
+impl<'a> TryFrom<&'a [u8]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; PUBLIC_KEY_SIZE] = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Point::try_from(&bytes[..]).map_err(|_| Error::PointNotOnCurve)?;
+        Ok(PublicKey { bytes })
+    }
+}
+
+/// Unlike the other key types, there's no infallible conversion from bytes,
+/// since not every 32 bytes encode a point on the curve.
+impl TryFrom<[u8; PUBLIC_KEY_SIZE]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: [u8; PUBLIC_KEY_SIZE]) -> Result<Self, Self::Error> {
+        Self::try_from(&value[..])
+    }
+}
+
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
 }
 
 impl PublicKey {
+    /// Load a public key, checking that it encodes a point on the curve.
+    ///
+    /// This fails with `Error::PointNotOnCurve`, like `try_from`.
+    pub fn from_bytes(bytes: [u8; PUBLIC_KEY_SIZE]) -> Result<Self, Error> {
+        Self::try_from(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_SIZE] {
+        &self.bytes
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.bytes
+    }
+
     fn from_hash(hash: &[u8; 64]) -> Self {
-        let scalar = Scalar::clamped(hash[..32].try_into().unwrap());
+        let scalar = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
         PublicKey {
-            bytes: (point::B * scalar).into(),
+            bytes: (point::B * *scalar).into(),
         }
     }
 
-    fn verify_result(&self, message: &[u8], signature: Signature) -> Result<(), SignatureError> {
+    /// Calculate the challenge k := H(R || A || M) of a signature.
+    ///
+    /// The message M is the concatenation of all the parts passed in.
+    fn challenge<M: Deref<Target = [u8]>>(r_bytes: &[u8], a: Point, message: &[M]) -> Scalar {
+        let a_bytes: [u8; 32] = a.into();
+        Self::challenge_encoded(r_bytes, &a_bytes, message)
+    }
+
+    /// Calculate the challenge of a signature, with A already encoded.
+    fn challenge_encoded<M: Deref<Target = [u8]>>(
+        r_bytes: &[u8],
+        a_bytes: &[u8; 32],
+        message: &[M],
+    ) -> Scalar {
+        let mut hasher = Sha512::new();
+        hasher.update(r_bytes);
+        hasher.update(a_bytes);
+        for part in message {
+            hasher.update(part);
+        }
+        Scalar::from_hash(hasher)
+    }
+
+    fn verify_result<M: Deref<Target = [u8]>>(
+        &self,
+        message: &[M],
+        signature: Signature,
+        cost: &mut VerificationCost,
+    ) -> Result<(), SignatureError> {
+        let message_len: usize = message.iter().map(|part| part.len()).sum();
+        let _span = trace::verify(message_len);
+        let result = self.verify_equation(message_len, message, signature, cost);
+        trace::verified(result.is_ok());
+        result
+    }
+
+    fn verify_equation<M: Deref<Target = [u8]>>(
+        &self,
+        message_len: usize,
+        message: &[M],
+        signature: Signature,
+        cost: &mut VerificationCost,
+    ) -> Result<(), SignatureError> {
         let s = Scalar::try_from(&signature.bytes[32..])?;
+        *cost += VerificationCost::decompression();
         let a = Point::try_from(&self.bytes[..])?;
-        let mut to_hash = Vec::with_capacity(64 + message.len());
         let r_bytes = &signature.bytes[..32];
-        to_hash.extend_from_slice(&r_bytes);
-        let a_bytes: [u8; 32] = a.into();
-        to_hash.extend_from_slice(&a_bytes);
-        to_hash.extend_from_slice(message);
-        let k = Scalar::from(sha512::hash(&to_hash));
-        let check_encoded: [u8; 32] = (point::B * s + (a * -k)).into();
-        if r_bytes != &check_encoded {
+        *cost += VerificationCost::hash(64 + message_len);
+        let k = Self::challenge(r_bytes, a, message);
+        *cost += VerificationCost::multiscalar_mul(2);
+        verifying::check_equation(r_bytes, s, k, a, None)
+    }
+
+    fn verify_low_memory_result(
+        &self,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<(), SignatureError> {
+        let _span = trace::verify(message.len());
+        let result = self.verify_low_memory_equation(message, signature);
+        trace::verified(result.is_ok());
+        result
+    }
+
+    fn verify_low_memory_equation(
+        &self,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<(), SignatureError> {
+        let s = Scalar::try_from(&signature.bytes[32..])?;
+        let a = Point::try_from(&self.bytes[..])?;
+        let a_u = a.montgomery_u();
+        // The ladder can't handle these points, and they're trivially forgeable anyways.
+        if bool::from(a_u.is_degenerate()) {
+            return Err(SignatureError::InvalidPoint);
+        }
+        let r_bytes = &signature.bytes[..32];
+        let mut r_y_bytes: [u8; 32] = r_bytes.try_into().unwrap();
+        r_y_bytes[31] &= 0x7F;
+        let r_y = Z25519::try_from(&r_y_bytes[..])?;
+        let r_u = ProjectiveU {
+            u: Z25519::from(1) + r_y,
+            w: Z25519::from(1) - r_y,
+        };
+        let k = Self::challenge(r_bytes, a, &[message]);
+        let s_b = point::B.montgomery_u().ladder(&s.into());
+        let k_a = a_u.ladder(&k.into());
+        if !bool::from(r_u.is_sum_or_difference(&s_b, &k_a)) {
             return Err(SignatureError::InvalidEquation);
         }
         Ok(())
     }
 
+    /// Verify a signature.
+    ///
+    /// When verifying many signatures under one key, a `VerifyingKey` avoids
+    /// decoding the key again each time.
     pub fn verify(&self, message: &[u8], signature: Signature) -> bool {
-        self.verify_result(message, signature).is_ok()
+        self.verify_metered(message, signature).0
+    }
+
+    /// Verify a signature, returning an error if it's invalid.
+    ///
+    /// This is the same as `verify`, but returns a `VerificationError` instead of a bool,
+    /// for use with `?`.
+    pub fn try_verify(
+        &self,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<(), VerificationError> {
+        let mut cost = VerificationCost::default();
+        Ok(self.verify_result(&[message], signature, &mut cost)?)
+    }
+
+    /// Verify a signature, also reporting the work done to verify it.
+    ///
+    /// The cost follows a fixed schedule, and is the same on every machine,
+    /// for the same inputs. Invalid signatures may be rejected early, in which
+    /// case the cost only includes the work done up to that point.
+    pub fn verify_metered(&self, message: &[u8], signature: Signature) -> (bool, VerificationCost) {
+        let mut cost = VerificationCost::default();
+        let ok = self.verify_result(&[message], signature, &mut cost).is_ok();
+        (ok, cost)
+    }
+
+    /// Verify a signature over a message scattered across several buffers.
+    ///
+    /// This is the same as verifying the concatenation of all the buffers,
+    /// but without copying them into one place first.
+    #[cfg(feature = "std")]
+    pub fn verify_vectored(&self, message: &[IoSlice], signature: Signature) -> bool {
+        let mut cost = VerificationCost::default();
+        self.verify_result(message, signature, &mut cost).is_ok()
+    }
+
+    /// Verify a signature, using only x-only arithmetic on the Montgomery curve.
+    ///
+    /// This uses a constant, small amount of memory, without any tables of points,
+    /// which makes it suitable for constrained devices, at the cost of being slower.
+    ///
+    /// Since Montgomery u coordinates don't determine the sign of a point, this checks
+    /// that R = ±[s]B ± [k]A, rather than R = [s]B - [k]A. Satisfying this weaker equation
+    /// still requires knowledge of the private key. Public keys of order 1 or 2 are rejected.
+    pub fn verify_low_memory(&self, message: &[u8], signature: Signature) -> bool {
+        self.verify_low_memory_result(message, signature).is_ok()
     }
 }
 
 pub const PRIVATE_KEY_SIZE: usize = 32;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PrivateKey {
-    pub bytes: [u8; PRIVATE_KEY_SIZE],
+    bytes: [u8; PRIVATE_KEY_SIZE],
+}
+
+/// Any 32 bytes make a valid private key.
+impl From<[u8; PRIVATE_KEY_SIZE]> for PrivateKey {
+    fn from(bytes: [u8; PRIVATE_KEY_SIZE]) -> Self {
+        PrivateKey::from_seed(bytes)
+    }
+}
+
+/// This fails with `Error::InvalidLength`, unless given exactly 32 bytes.
+impl<'a> TryFrom<&'a [u8]> for PrivateKey {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Ok(PrivateKey { bytes })
+    }
+}
+
+impl AsRef<[u8]> for PrivateKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// This never prints the key itself, so that secrets don't end up in logs.
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
 }
 
 impl PrivateKey {
-    fn derive_public_key(&self) -> PublicKey {
-        let hash = sha512::hash(&self.bytes);
+    /// Load a private key from its 32 byte seed, as defined in Section 5.1.5 of RFC 8032:
+    /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.5
+    ///
+    /// This is deterministic, and needs no randomness, so it's how stored keys get
+    /// loaded again. The seed is exactly what `to_bytes` returns.
+    pub fn from_seed(seed: [u8; PRIVATE_KEY_SIZE]) -> Self {
+        PrivateKey { bytes: seed }
+    }
+
+    /// Generate a new private key, using the randomness of the operating system.
+    ///
+    /// This calls getrandom directly, so no RNG needs to be constructed first.
+    ///
+    /// # Panics
+    ///
+    /// Like `OsRng`, this panics if the operating system can't provide randomness.
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Self {
+        let _span = trace::keygen();
+        let mut bytes = [0; PRIVATE_KEY_SIZE];
+        getrandom::getrandom(&mut bytes).expect("failed to get randomness");
+        PrivateKey::from_seed(bytes)
+    }
+
+    /// Load a private key from its bytes, which is the same as `from_seed`.
+    ///
+    /// Any 32 bytes make a valid private key, so this can't fail.
+    pub fn from_bytes(bytes: [u8; PRIVATE_KEY_SIZE]) -> Self {
+        PrivateKey::from_seed(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PRIVATE_KEY_SIZE] {
+        &self.bytes
+    }
+
+    pub fn to_bytes(&self) -> [u8; PRIVATE_KEY_SIZE] {
+        self.bytes
+    }
+
+    /// Calculate the public key corresponding to this private key.
+    pub fn derive_public_key(&self) -> PublicKey {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
         PublicKey::from_hash(&hash)
     }
 
     pub fn sign(&self, message: &[u8]) -> Signature {
-        let hash = sha512::hash(&self.bytes);
-        let s = Scalar::clamped(hash[..32].try_into().unwrap());
-        let a: [u8; 32] = (point::B * s).into();
-        let prefix = &hash[32..];
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        self.sign_with_nonce_prefix(&hash, &hash[32..], &[message])
+    }
 
-        let mut to_hash = Vec::with_capacity(64 + message.len());
-        to_hash.extend_from_slice(prefix);
-        to_hash.extend_from_slice(message);
-        let r = Scalar::from(sha512::hash(&to_hash));
+    /// Sign a message scattered across several buffers.
+    ///
+    /// This produces the same signature as signing the concatenation of all the buffers,
+    /// but without copying them into one place first.
+    #[cfg(feature = "std")]
+    pub fn sign_vectored(&self, message: &[IoSlice]) -> Signature {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        self.sign_with_nonce_prefix(&hash, &hash[32..], message)
+    }
 
-        let big_r: [u8; 32] = (point::B * r).into();
+    /// Sign a message, mixing a committed counter value into the nonce.
+    ///
+    /// This produces a standard Ed25519 signature, verifiable as usual, but with
+    /// a nonce that depends on the counter as well as the key and message.
+    /// See `NonceCounter` for how to obtain a committed value.
+    pub fn sign_with_counter(&self, counter: CommittedCounter, message: &[u8]) -> Signature {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        let prefix = Zeroizing::new(counter.nonce_prefix(&hash[32..]));
+        self.sign_with_nonce_prefix(&hash, &*prefix, &[message])
+    }
 
-        to_hash.clear();
-        to_hash.extend_from_slice(&big_r);
-        to_hash.extend_from_slice(&a);
-        to_hash.extend_from_slice(message);
-        let k = Scalar::from(sha512::hash(&to_hash));
+    fn sign_with_nonce_prefix<M: Deref<Target = [u8]>>(
+        &self,
+        hash: &[u8; 64],
+        prefix: &[u8],
+        message: &[M],
+    ) -> Signature {
+        Self::sign_with_scalar(
+            Scalar::clamped(hash[..32].try_into().unwrap()),
+            prefix,
+            message,
+        )
+    }
 
-        let big_s: [u8; 32] = (r + k * s).into();
+    /// Sign a message with the secret scalar s directly, and a given nonce prefix.
+    ///
+    /// This is the core of signing, shared with keys whose scalar isn't derived from a seed.
+    fn sign_with_scalar<M: Deref<Target = [u8]>>(
+        s: Scalar,
+        prefix: &[u8],
+        message: &[M],
+    ) -> Signature {
+        let message_len: usize = message.iter().map(|part| part.len()).sum();
+        let _span = trace::sign(message_len);
+        let s = Zeroizing::new(s);
+        let a: [u8; 32] = (point::B * *s).into();
+
+        let mut hasher = Sha512::new();
+        hasher.update(prefix);
+        for part in message {
+            hasher.update(part);
+        }
+        let r = Zeroizing::new(Scalar::from_hash(hasher));
+
+        let big_r: [u8; 32] = (point::B * *r).into();
+
+        let mut hasher = Sha512::new();
+        hasher.update(&big_r);
+        hasher.update(&a);
+        for part in message {
+            hasher.update(part);
+        }
+        let k = Scalar::from_hash(hasher);
+
+        let big_s: [u8; 32] = (*r + k * *s).into();
 
         let mut out = Signature { bytes: [0; 64] };
         out.bytes[..32].copy_from_slice(&big_r);
@@ -101,12 +603,38 @@ impl PrivateKey {
     }
 }
 
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 pub fn gen_keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, PrivateKey) {
+    let _span = trace::keygen();
     let mut private = PrivateKey { bytes: [0u8; 32] };
     rng.fill_bytes(&mut private.bytes);
     (private.derive_public_key(), private)
 }
 
+/// Generate a key pair deterministically, from a 32 byte seed.
+///
+/// The seed becomes the private key, so the same seed gives the same keys, on every
+/// platform, and in every version of this crate. This is meant for tests, and fixtures,
+/// where keys need to be reproducible; real keys should come from `gen_keypair`.
+/// Fixtures needing many keys can use distinct seeds, e.g. a hash of each key's name.
+pub fn gen_keypair_from_seed(seed: [u8; PRIVATE_KEY_SIZE]) -> (PublicKey, PrivateKey) {
+    let _span = trace::keygen();
+    let private = PrivateKey::from_seed(seed);
+    (private.derive_public_key(), private)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -165,4 +693,244 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_counter_signatures_verify() {
+        let private = PrivateKey { bytes: [1; 32] };
+        let public = private.derive_public_key();
+        let mut counter = NonceCounter::restore(0);
+        let message = b"hello";
+        let persist = |_| -> Result<(), ()> { Ok(()) };
+        let sig0 = private.sign_with_counter(counter.commit(persist).unwrap(), message);
+        let sig1 = private.sign_with_counter(counter.commit(persist).unwrap(), message);
+        assert!(public.verify(message, sig0));
+        assert!(public.verify(message, sig1));
+        assert_ne!(sig0.bytes[..], sig1.bytes[..]);
+        assert_ne!(sig0.bytes[..], private.sign(message).bytes[..]);
+    }
+
+    #[test]
+    fn test_metered_verification() {
+        let private = PrivateKey { bytes: [2; 32] };
+        let public = private.derive_public_key();
+        let message = [0xAA; 100];
+        let sig = private.sign(&message);
+        let (ok, cost) = public.verify_metered(&message, sig);
+        assert!(ok);
+        // 64 + 100 bytes need 2 blocks, with padding
+        assert_eq!(cost.hash_blocks, 2);
+        assert_eq!(cost.point_decompressions, 1);
+        assert_eq!(
+            cost,
+            VerificationCost::hash(164)
+                + VerificationCost::decompression()
+                + VerificationCost::multiscalar_mul(2)
+        );
+
+        let mut bad = sig;
+        bad.bytes[0] ^= 1;
+        let (ok, bad_cost) = public.verify_metered(&message, bad);
+        assert!(!ok);
+        assert_eq!(cost, bad_cost);
+
+        // A non canonical scalar gets rejected before doing any work
+        bad.bytes[63] = 0xFF;
+        let (ok, bad_cost) = public.verify_metered(&message, bad);
+        assert!(!ok);
+        assert_eq!(bad_cost, VerificationCost::default());
+    }
+
+    #[test]
+    fn test_low_memory_verification() {
+        for b in 0..4u8 {
+            let private = PrivateKey { bytes: [b; 32] };
+            let public = private.derive_public_key();
+            let message = [b; 10];
+            let sig = private.sign(&message);
+            assert!(public.verify_low_memory(&message, sig));
+            assert!(!public.verify_low_memory(&message[1..], sig));
+            let mut bad = sig;
+            bad.bytes[40] ^= 1;
+            assert!(!public.verify_low_memory(&message, bad));
+        }
+    }
+
+    #[test]
+    fn test_low_memory_rejects_identity_key() {
+        // With A = 0, [s]B = R would be a valid signature for anyone
+        let mut identity = PublicKey { bytes: [0; 32] };
+        identity.bytes[0] = 1;
+        let mut sig = Signature { bytes: [0; 64] };
+        sig.bytes[..32].copy_from_slice(&identity.bytes);
+        assert!(!identity.verify_low_memory(b"forged", sig));
+    }
+
+    #[test]
+    fn test_try_verify() {
+        let private = PrivateKey { bytes: [4; 32] };
+        let public = private.derive_public_key();
+        let sig = private.sign(b"message");
+        assert!(public.try_verify(b"message", sig).is_ok());
+        let mut bad_scalar = sig;
+        bad_scalar.bytes[63] = 0xFF;
+        let mut bad_point = sig;
+        bad_point.bytes[0] ^= 1;
+        let scalar_err = public.try_verify(b"message", bad_scalar).unwrap_err();
+        let point_err = public.try_verify(b"message", bad_point).unwrap_err();
+        assert_eq!(scalar_err.to_string(), point_err.to_string());
+    }
+
+    #[test]
+    fn test_constant_time_equality() {
+        let private = PrivateKey { bytes: [3; 32] };
+        let public = private.derive_public_key();
+        let sig = private.sign(b"message");
+        assert_eq!(public, PrivateKey { bytes: [3; 32] }.derive_public_key());
+        assert_ne!(public, PrivateKey { bytes: [4; 32] }.derive_public_key());
+        assert_eq!(sig, private.sign(b"message"));
+        let mut other = sig;
+        other.bytes[63] ^= 1;
+        assert!(!bool::from(sig.ct_eq(&other)));
+        assert_ne!(sig, other);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let private = PrivateKey { bytes: [8; 32] };
+        let public = private.derive_public_key();
+        let sig = private.sign(b"message");
+        let public_hex = public.to_string();
+        assert_eq!(public_hex, hex::encode(public.bytes));
+        assert_eq!(format!("{:x}", sig), hex::encode(&sig.bytes[..]));
+        assert_eq!(public_hex.parse::<PublicKey>(), Ok(public));
+        assert_eq!(sig.to_string().parse::<Signature>(), Ok(sig));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert_eq!("zz".repeat(32).parse::<PublicKey>(), Err(Error::InvalidHex));
+        assert_eq!("00".parse::<PublicKey>(), Err(Error::InvalidLength));
+        // y = 2 isn't on the curve
+        let mut not_point = [0u8; 32];
+        not_point[0] = 2;
+        assert_eq!(
+            hex::encode(not_point).parse::<PublicKey>(),
+            Err(Error::PointNotOnCurve)
+        );
+        let mut sig = PrivateKey { bytes: [8; 32] }.sign(b"message");
+        sig.bytes[63] = 0xFF;
+        assert_eq!(
+            sig.to_string().parse::<Signature>(),
+            Err(Error::NonCanonicalScalar)
+        );
+    }
+
+    #[test]
+    fn test_byte_conversions() {
+        let private = PrivateKey::from([8; 32]);
+        let public = private.derive_public_key();
+        let sig = private.sign(b"message");
+        assert_eq!(
+            PrivateKey::try_from(private.as_ref()).unwrap().bytes,
+            private.bytes
+        );
+        assert_eq!(PublicKey::try_from(public.as_ref()), Ok(public));
+        assert_eq!(PublicKey::try_from(public.to_bytes()), Ok(public));
+        assert_eq!(Signature::try_from(sig.as_ref()), Ok(sig));
+        assert_eq!(Signature::from(sig.to_bytes()), sig);
+
+        assert_eq!(
+            PrivateKey::try_from(&[0u8; 31][..]).unwrap_err(),
+            Error::InvalidLength
+        );
+        assert_eq!(
+            PublicKey::try_from(&[0u8; 33][..]),
+            Err(Error::InvalidLength)
+        );
+        let mut not_point = [0u8; 32];
+        not_point[0] = 2;
+        assert_eq!(PublicKey::try_from(not_point), Err(Error::PointNotOnCurve));
+        assert_eq!(
+            PublicKey::from_bytes(not_point),
+            Err(Error::PointNotOnCurve)
+        );
+        assert_eq!(PublicKey::from_bytes(public.to_bytes()), Ok(public));
+        assert_eq!(
+            PrivateKey::from_bytes([8; 32]).as_bytes(),
+            private.as_bytes()
+        );
+        let mut bad_sig = sig.to_bytes();
+        bad_sig[63] = 0xFF;
+        assert_eq!(
+            Signature::try_from(&bad_sig[..]),
+            Err(Error::NonCanonicalScalar)
+        );
+    }
+
+    #[test]
+    fn test_gen_keypair_from_seed() {
+        let (public, private) = gen_keypair_from_seed([9; 32]);
+        let (again, _) = gen_keypair_from_seed([9; 32]);
+        assert_eq!(public, again);
+        assert_eq!(private.to_bytes(), [9; 32]);
+        assert!(public.verify(b"fixture", private.sign(b"fixture")));
+        assert_ne!(gen_keypair_from_seed([10; 32]).0, public);
+    }
+
+    #[test]
+    fn test_from_seed() {
+        // The key from test 1 of RFC 8032, section 7.1.
+        let mut seed = [0; 32];
+        hex::decode_to_slice(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            &mut seed,
+        )
+        .unwrap();
+        let private = PrivateKey::from_seed(seed);
+        assert_eq!(
+            hex::encode(private.derive_public_key().bytes),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(private.to_bytes(), seed);
+        let loaded = PrivateKey::try_from(&seed[..]).unwrap();
+        assert_eq!(loaded.sign(b"stored").bytes, private.sign(b"stored").bytes);
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_generate() {
+        let private = PrivateKey::generate();
+        let public = private.derive_public_key();
+        assert!(public.verify(b"generated", private.sign(b"generated")));
+        assert_ne!(PrivateKey::generate().bytes, private.bytes);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_vectored_matches_contiguous() {
+        let private = PrivateKey { bytes: [9; 32] };
+        let public = private.derive_public_key();
+        let message = b"a message split across several buffers";
+        let parts = [
+            IoSlice::new(&message[..3]),
+            IoSlice::new(&[]),
+            IoSlice::new(&message[3..20]),
+            IoSlice::new(&message[20..]),
+        ];
+        let sig = private.sign_vectored(&parts);
+        assert_eq!(sig.bytes, private.sign(message).bytes);
+        assert!(public.verify_vectored(&parts, sig));
+        assert!(!public.verify_vectored(&parts[..3], sig));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_secrets() {
+        let mut private = PrivateKey { bytes: [7; 32] };
+        private.zeroize();
+        assert_eq!(private.bytes, [0; 32]);
+        let mut scalar = Scalar::from(1234);
+        scalar.zeroize();
+        assert_eq!(scalar, Scalar::from(0));
+    }
 }
@@ -0,0 +1,313 @@
+//! This module implements x-only arithmetic on the Montgomery form of our curve.
+//!
+//! Our Edward's curve is birationally equivalent to the Montgomery curve
+//!     v² = u³ + 486662u² + u
+//! of RFC 7748 (https://datatracker.ietf.org/doc/html/rfc7748), through the map
+//!     u = (1 + y) / (1 - y)
+//!
+//! Working with u alone loses the sign of a point, but lets us multiply points
+//! with a ladder using only a handful of field elements, and no tables.
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::{uint::U256, zeroize::Zeroizing};
+
+use super::field::Z25519;
+
+/// The coefficient A of the Montgomery curve.
+const A: u64 = 486662;
+
+/// (A - 2) / 4, as used in the ladder of RFC 7748.
+const A24: u64 = 121665;
+
+/// The encoded u coordinate of the standard base point, i.e. u = 9.
+pub const BASE_U: [u8; 32] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// The u coordinate of a point on the Montgomery curve, in projective form.
+///
+/// This represents the coordinate u / w. The point at infinity, i.e. the identity,
+/// has w = 0.
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectiveU {
+    pub u: Z25519,
+    pub w: Z25519,
+}
+
+impl ProjectiveU {
+    /// Calculate the u coordinate of [scalar] P, where P has this u coordinate.
+    ///
+    /// This uses the Montgomery ladder, described in Section 5 of RFC 7748:
+    /// https://datatracker.ietf.org/doc/html/rfc7748#section-5
+    ///
+    /// The scalar is read in little endian order, ignoring its top bit. This runs in constant time.
+    /// The result is only correct if P has neither u = 0, nor u = ∞.
+    pub fn ladder(&self, scalar: &[u8; 32]) -> ProjectiveU {
+        let mut x2 = Z25519::from(1);
+        let mut z2 = Z25519::from(0);
+        let mut x3 = self.u;
+        let mut z3 = self.w;
+        let mut swap = Choice::from(0);
+        for t in (0..255).rev() {
+            let k_t = Choice::from((scalar[t >> 3] >> (t & 7)) & 1);
+            swap ^= k_t;
+            Z25519::conditional_swap(&mut x2, &mut x3, swap);
+            Z25519::conditional_swap(&mut z2, &mut z3, swap);
+            swap = k_t;
+
+            let a = x2 + z2;
+            let aa = a.squared();
+            let b = x2 - z2;
+            let bb = b.squared();
+            let e = aa - bb;
+            let c = x3 + z3;
+            let d = x3 - z3;
+            let da = d * a;
+            let cb = c * b;
+            // Since our difference is projective, we scale by its denominator, instead
+            // of dividing by it.
+            x3 = self.w * (da + cb).squared();
+            z3 = self.u * (da - cb).squared();
+            x2 = aa * bb;
+            z2 = e * (aa + e * A24);
+        }
+        Z25519::conditional_swap(&mut x2, &mut x3, swap);
+        Z25519::conditional_swap(&mut z2, &mut z3, swap);
+        ProjectiveU { u: x2, w: z2 }
+    }
+
+    /// Check whether or not this is the u coordinate of P + Q or P - Q, given those of P and Q.
+    ///
+    /// For u coordinates u₁ and u₂ of P and Q, the coordinates u₃ and u₄ of P + Q and P - Q
+    /// are the roots of:
+    ///     (u₁ - u₂)² X² - 2((u₁u₂ + 1)(u₁ + u₂) + 2Au₁u₂) X + (u₁u₂ - 1)²
+    /// so we check that this coordinate is a root, in projective form.
+    pub fn is_sum_or_difference(&self, p: &ProjectiveU, q: &ProjectiveU) -> Choice {
+        let (x1, z1, x2, z2) = (p.u, p.w, q.u, q.w);
+        let x1x2 = x1 * x2;
+        let z1z2 = z1 * z2;
+        let x1z2 = x1 * z2;
+        let x2z1 = x2 * z1;
+        let a = (x1z2 - x2z1).squared();
+        let b = ((x1x2 + z1z2) * (x1z2 + x2z1) + x1x2 * z1z2 * (2 * A)) * 2;
+        let c = (x1x2 - z1z2).squared();
+        let lhs = a * self.u.squared() + c * self.w.squared();
+        let rhs = b * self.u * self.w;
+        lhs.ct_eq(&rhs)
+    }
+
+    /// Check whether or not this coordinate is either 0, or ∞.
+    ///
+    /// These correspond to the points of order 2 and 1 respectively.
+    pub fn is_degenerate(&self) -> Choice {
+        self.u.is_zero() | self.w.is_zero()
+    }
+}
+
+/// Calculate the X25519 function, multiplying an encoded u coordinate by a scalar.
+///
+/// This follows Section 5 of RFC 7748, clamping the scalar, and ignoring the
+/// top bit of the coordinate:
+/// https://datatracker.ietf.org/doc/html/rfc7748#section-5
+///
+/// Coordinates which aren't reduced, i.e. between p and 2^255 - 1, are accepted,
+/// and treated as if they had been reduced modulo p, as the RFC requires.
+pub fn x25519(scalar: [u8; 32], u: [u8; 32]) -> [u8; 32] {
+    let mut scalar = Zeroizing::new(scalar);
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    // Converting from limbs ignores the top bit for us.
+    let u = Z25519::from(U256::from(u).limbs);
+    let out = ProjectiveU {
+        u,
+        w: Z25519::from(1),
+    }
+    .ladder(&scalar);
+    // For the point at infinity, inverting 0 gives us 0, as the RFC expects.
+    (out.u * out.w.inverse()).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{
+        point::{Point, B},
+        scalar::Scalar,
+    };
+    use super::*;
+
+    fn affine(p: &ProjectiveU) -> Z25519 {
+        p.u * p.w.inverse()
+    }
+
+    #[test]
+    fn test_x25519_vectors() {
+        // These are the test vectors from Section 5.2 of RFC 7748:
+        // https://datatracker.ietf.org/doc/html/rfc7748#section-5.2
+        let mut scalar = [0; 32];
+        let mut u = [0; 32];
+        let mut expected = [0; 32];
+        hex::decode_to_slice(
+            "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+            &mut scalar,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+            &mut u,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(x25519(scalar, u), expected);
+
+        hex::decode_to_slice(
+            "4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+            &mut scalar,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+            &mut u,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(x25519(scalar, u), expected);
+    }
+
+    #[test]
+    fn test_x25519_diffie_hellman() {
+        // This is the example from Section 6.1 of RFC 7748:
+        // https://datatracker.ietf.org/doc/html/rfc7748#section-6.1
+        let mut alice = [0; 32];
+        let mut bob = [0; 32];
+        let mut alice_public = [0; 32];
+        let mut shared = [0; 32];
+        hex::decode_to_slice(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+            &mut alice,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+            &mut bob,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a",
+            &mut alice_public,
+        )
+        .unwrap();
+        hex::decode_to_slice(
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742",
+            &mut shared,
+        )
+        .unwrap();
+        assert_eq!(x25519(alice, BASE_U), alice_public);
+        let bob_public = x25519(bob, BASE_U);
+        assert_eq!(x25519(alice, bob_public), shared);
+        assert_eq!(x25519(bob, alice_public), shared);
+    }
+
+    fn decode(hex_str: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        hex::decode_to_slice(hex_str, &mut out).unwrap();
+        out
+    }
+
+    const SCALAR: &str = "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4";
+
+    #[test]
+    fn test_x25519_ignores_high_bit() {
+        let scalar = decode(SCALAR);
+        let u = decode("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        let mut high = u;
+        high[31] |= 0x80;
+        assert_eq!(x25519(scalar, high), x25519(scalar, u));
+        let mut low = u;
+        low[31] &= 0x7F;
+        assert_eq!(x25519(scalar, low), x25519(scalar, u));
+    }
+
+    #[test]
+    fn test_x25519_reduces_non_canonical_coordinates() {
+        let scalar = decode(SCALAR);
+        // p + 9, which should act just like the base point
+        let p_plus_9 = decode("f6ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert_eq!(x25519(scalar, p_plus_9), x25519(scalar, BASE_U));
+        // 2^255 - 1 = p + 18, and 2^256 - 1, with the high bit ignored
+        let mut eighteen = [0; 32];
+        eighteen[0] = 18;
+        let expected = x25519(scalar, eighteen);
+        let mut all_ones = [0xFF; 32];
+        assert_eq!(x25519(scalar, all_ones), expected);
+        all_ones[31] = 0x7F;
+        assert_eq!(x25519(scalar, all_ones), expected);
+    }
+
+    #[test]
+    fn test_x25519_small_order_coordinates() {
+        // These coordinates, and their non canonical forms, all have order dividing 8,
+        // so clamping makes the output 0.
+        let scalar = decode(SCALAR);
+        let small_order = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+            "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ];
+        for u in small_order.iter() {
+            assert_eq!(x25519(scalar, decode(u)), [0; 32], "{}", u);
+        }
+    }
+
+    #[test]
+    fn test_base_point_u() {
+        assert_eq!(affine(&B.montgomery_u()), Z25519::from(9));
+    }
+
+    #[test]
+    fn test_ladder_matches_edwards() {
+        let base = B.montgomery_u();
+        for &s in &[1u64, 2, 3, 17, 0xDEAD_BEEF] {
+            let scalar = Scalar::from(s);
+            let expected = (B * scalar).montgomery_u();
+            let actual = base.ladder(&scalar.into());
+            assert_eq!(affine(&actual), affine(&expected));
+        }
+    }
+
+    #[test]
+    fn test_ladder_zero_is_infinity() {
+        let out = B.montgomery_u().ladder(&[0; 32]);
+        assert!(bool::from(out.w.is_zero()));
+    }
+
+    #[test]
+    fn test_sum_or_difference() {
+        let p = B * Scalar::from(5);
+        let q = B * Scalar::from(3);
+        let (pu, qu) = (p.montgomery_u(), q.montgomery_u());
+        let sum = (B * Scalar::from(8)).montgomery_u();
+        let difference = (B * Scalar::from(2)).montgomery_u();
+        let other = (B * Scalar::from(7)).montgomery_u();
+        assert!(bool::from(sum.is_sum_or_difference(&pu, &qu)));
+        assert!(bool::from(difference.is_sum_or_difference(&pu, &qu)));
+        assert!(!bool::from(other.is_sum_or_difference(&pu, &qu)));
+        // With P at infinity, this checks that the coordinate matches Q
+        let infinity = Point::identity().montgomery_u();
+        assert!(bool::from(qu.is_sum_or_difference(&infinity, &qu)));
+        assert!(!bool::from(pu.is_sum_or_difference(&infinity, &qu)));
+    }
+}
@@ -0,0 +1,254 @@
+//! This module implements multiscalar multiplication, i.e. calculating
+//!     s₀⋅P₀ + s₁⋅P₁ + … + sₙ⋅Pₙ
+//! faster than by doing each multiplication separately.
+//!
+//! None of the functions here are constant-time, so they should only be
+//! used with public scalars and points, like in signature verification.
+
+use super::{
+    point::{Point, ProjectiveNiels},
+    scalar::Scalar,
+};
+
+/// Above this many terms, we switch from Straus's method to Pippenger's.
+///
+/// This threshold was chosen empirically, and isn't very sensitive.
+#[cfg(feature = "alloc")]
+const PIPPENGER_THRESHOLD: usize = 190;
+
+/// The width of the windows used by Straus's method.
+const WINDOW_SIZE: usize = 4;
+
+/// Without an allocator, Straus's method builds tables for this many terms at a time.
+#[cfg(not(feature = "alloc"))]
+const STACK_TERMS: usize = 4;
+
+/// Extract `width` bits of a scalar, starting at bit `start`.
+///
+/// Bits past the end of the scalar are treated as 0.
+pub(super) fn window(scalar: &Scalar, start: usize, width: usize) -> usize {
+    let limbs = &scalar.value.limbs;
+    let limb = start / 64;
+    let shift = start % 64;
+    let mut bits = limbs[limb] >> shift;
+    if shift + width > 64 && limb + 1 < limbs.len() {
+        bits |= limbs[limb + 1] << (64 - shift);
+    }
+    (bits & ((1 << width) - 1)) as usize
+}
+
+/// Calculate 2^k * point, through repeated doubling.
+fn double_times(mut point: Point, k: usize) -> Point {
+    for _ in 0..k {
+        point = point.doubled();
+    }
+    point
+}
+
+/// The small multiples of a point, used by Straus's method.
+pub(super) type StrausTable = [ProjectiveNiels; 1 << WINDOW_SIZE];
+
+/// Calculate the small multiples 0 * p, 1 * p, …, 15 * p.
+pub(super) fn straus_table(p: Point) -> StrausTable {
+    let niels = p.niels();
+    let mut table = [ProjectiveNiels::identity(); 1 << WINDOW_SIZE];
+    let mut multiple = Point::identity();
+    for entry in table.iter_mut().skip(1) {
+        multiple = multiple + &niels;
+        *entry = multiple.niels();
+    }
+    table
+}
+
+/// The main loop of Straus's method, going through the scalars one window at a time.
+pub(super) fn straus_with_tables(scalars: &[Scalar], tables: &[StrausTable]) -> Point {
+    let mut out = Point::identity();
+    for start in (0..256).step_by(WINDOW_SIZE).rev() {
+        out = double_times(out, WINDOW_SIZE);
+        for (scalar, table) in scalars.iter().zip(tables.iter()) {
+            let w = window(scalar, start, WINDOW_SIZE);
+            if w != 0 {
+                out = out + &table[w];
+            }
+        }
+    }
+    out
+}
+
+impl Point {
+    /// Calculate the sum of scalars[i] * points[i], in variable time.
+    ///
+    /// For small inputs, this uses Straus's method, and for larger inputs,
+    /// this uses Pippenger's bucket method. Without the `alloc` feature, this
+    /// always uses Straus's method, on a few terms at a time.
+    ///
+    /// This will panic if the number of scalars and points differ.
+    pub fn vartime_multiscalar_mul(scalars: &[Scalar], points: &[Point]) -> Point {
+        assert_eq!(
+            scalars.len(),
+            points.len(),
+            "mismatched number of scalars and points"
+        );
+        #[cfg(feature = "alloc")]
+        if scalars.len() >= PIPPENGER_THRESHOLD {
+            return Self::pippenger(scalars, points);
+        }
+        Self::straus(scalars, points)
+    }
+
+    /// Straus's method, which shares doublings across all the terms.
+    ///
+    /// Each point gets a table of small multiples, and we go through the scalars
+    /// 4 bits at a time, in parallel.
+    fn straus(scalars: &[Scalar], points: &[Point]) -> Point {
+        #[cfg(feature = "alloc")]
+        {
+            let tables: alloc::vec::Vec<_> = points.iter().map(|&p| straus_table(p)).collect();
+            straus_with_tables(scalars, &tables)
+        }
+        #[cfg(not(feature = "alloc"))]
+        {
+            let mut out = Point::identity();
+            for (scalars, points) in scalars.chunks(STACK_TERMS).zip(points.chunks(STACK_TERMS)) {
+                let mut tables = [[ProjectiveNiels::identity(); 1 << WINDOW_SIZE]; STACK_TERMS];
+                for (table, &p) in tables.iter_mut().zip(points.iter()) {
+                    *table = straus_table(p);
+                }
+                out = out + straus_with_tables(scalars, &tables[..points.len()]);
+            }
+            out
+        }
+    }
+
+    /// Pippenger's bucket method, which is asymptotically faster for many terms.
+    ///
+    /// For each window of c bits, we sort the points into buckets, based on
+    /// their digit in that window, and then sum up the buckets with the
+    /// right weights, using only additions.
+    #[cfg(feature = "alloc")]
+    fn pippenger(scalars: &[Scalar], points: &[Point]) -> Point {
+        // Roughly log2(n), which balances bucket work against point work
+        let c = match scalars.len() {
+            0..=499 => 6,
+            500..=799 => 7,
+            _ => 8,
+        };
+        let bucket_count = (1 << c) - 1;
+        let mut buckets = alloc::vec![Point::identity(); bucket_count];
+
+        let mut out = Point::identity();
+        // The highest window starting below bit 256.
+        let mut start = (255 / c) * c;
+        loop {
+            for bucket in buckets.iter_mut() {
+                *bucket = Point::identity();
+            }
+            for (scalar, &point) in scalars.iter().zip(points.iter()) {
+                let w = window(scalar, start, c);
+                if w != 0 {
+                    buckets[w - 1] = buckets[w - 1] + point;
+                }
+            }
+            // We want Σ i⋅bucket[i]. Using a running sum, we add bucket[i]
+            // into the total once for every j ≤ i.
+            let mut running = Point::identity();
+            let mut window_sum = Point::identity();
+            for &bucket in buckets.iter().rev() {
+                running = running + bucket;
+                window_sum = window_sum + running;
+            }
+            out = out + window_sum;
+
+            if start == 0 {
+                break;
+            }
+            start -= c;
+            out = double_times(out, c);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::point::B;
+    use super::*;
+
+    fn example_terms(n: usize) -> (Vec<Scalar>, Vec<Point>) {
+        let scalars: Vec<Scalar> = (0..n)
+            .map(|i| {
+                let mut bytes = [0u8; 64];
+                bytes[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+                let mixed = 0x9E37_79B9_7F4A_7C15u64.wrapping_mul(i as u64 + 3);
+                bytes[8..16].copy_from_slice(&mixed.to_le_bytes());
+                bytes[63] = 0xFF;
+                Scalar::from(bytes)
+            })
+            .collect();
+        let points = (0..n).map(|i| B * Scalar::from(i as u64 + 7)).collect();
+        (scalars, points)
+    }
+
+    fn naive(scalars: &[Scalar], points: &[Point]) -> Point {
+        scalars
+            .iter()
+            .zip(points.iter())
+            .fold(Point::identity(), |acc, (&s, &p)| acc + p * s)
+    }
+
+    fn encode(p: Point) -> [u8; 32] {
+        p.into()
+    }
+
+    #[test]
+    fn test_empty_is_identity() {
+        let out = Point::vartime_multiscalar_mul(&[], &[]);
+        assert_eq!(encode(out), encode(Point::identity()));
+    }
+
+    #[test]
+    fn test_straus_matches_naive() {
+        for &n in &[1, 2, 5, 16] {
+            let (scalars, points) = example_terms(n);
+            assert_eq!(
+                encode(Point::straus(&scalars, &points)),
+                encode(naive(&scalars, &points))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_pippenger_matches_naive() {
+        for &n in &[1, 3, 40] {
+            let (scalars, points) = example_terms(n);
+            assert_eq!(
+                encode(Point::pippenger(&scalars, &points)),
+                encode(naive(&scalars, &points))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_pippenger_window_widths() {
+        // These sizes use windows of 7, and 8 bits, where 8 divides 256 exactly.
+        for &n in &[799, 800] {
+            let (scalars, points) = example_terms(n);
+            assert_eq!(
+                encode(Point::vartime_multiscalar_mul(&scalars, &points)),
+                encode(Point::straus(&scalars, &points))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_large_inputs_agree() {
+        let (scalars, points) = example_terms(PIPPENGER_THRESHOLD + 10);
+        assert_eq!(
+            encode(Point::vartime_multiscalar_mul(&scalars, &points)),
+            encode(Point::straus(&scalars, &points))
+        );
+    }
+}
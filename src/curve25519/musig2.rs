@@ -0,0 +1,414 @@
+//! This module implements MuSig2 multisignatures over ristretto255, following
+//! "MuSig2: Simple Two-Round Schnorr Multi-Signatures", by Nick, Ruffing, and Seurin:
+//! https://eprint.iacr.org/2020/1261
+//!
+//! A group of signers aggregates their keys into a single key, and then produces
+//! a single Schnorr signature for it together, in two rounds. All of the signers
+//! have to take part, unlike with threshold signatures.
+//!
+//! The first round, exchanging nonce commitments, doesn't depend on the message,
+//! so it can be done ahead of time. The hashes are SHA-512, with a separate prefix
+//! for each use, and signatures are plain Schnorr signatures over ristretto255,
+//! not Ed25519 signatures.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+
+use super::{
+    ristretto::{Element, ELEMENT_SIZE, GENERATOR},
+    scalar::Scalar,
+    Error, PrivateKey,
+};
+
+/// The size of the secret key of one signer.
+pub const MUSIG2_SECRET_KEY_SIZE: usize = 32;
+
+/// The size of a public key, taking part in the aggregation, or aggregated.
+pub const MUSIG2_PUBLIC_KEY_SIZE: usize = ELEMENT_SIZE;
+
+/// The size of the commitment each signer sends in the first round.
+pub const MUSIG2_COMMITMENT_SIZE: usize = 2 * ELEMENT_SIZE;
+
+/// The size of an aggregate signature.
+pub const MUSIG2_SIGNATURE_SIZE: usize = 64;
+
+const CONTEXT: &[u8] = b"MuSig2-ristretto255-SHA512/";
+
+fn challenge(public: &[u8; ELEMENT_SIZE], r: &[u8; ELEMENT_SIZE], message: &[u8]) -> Scalar {
+    Scalar::from_tagged_hash(CONTEXT, b"challenge", &[public, r, message])
+}
+
+/// A key belonging to one signer.
+pub struct Musig2Signer {
+    secret: Scalar,
+    public: [u8; MUSIG2_PUBLIC_KEY_SIZE],
+}
+
+impl Musig2Signer {
+    fn from_scalar(secret: Scalar) -> Self {
+        Musig2Signer {
+            secret,
+            public: (GENERATOR * secret).encode(),
+        }
+    }
+
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::from_scalar(Scalar::random(rng))
+    }
+
+    /// Load a key stored with `to_bytes`.
+    ///
+    /// This fails with `Error::NonCanonicalScalar`, unless the bytes encode a scalar
+    /// below the order of the group.
+    pub fn from_bytes(bytes: [u8; MUSIG2_SECRET_KEY_SIZE]) -> Result<Self, Error> {
+        Scalar::from_canonical_bytes(bytes)
+            .map(Self::from_scalar)
+            .ok_or(Error::NonCanonicalScalar)
+    }
+
+    /// The secret key, for storing it, and loading it again with `from_bytes`.
+    pub fn to_bytes(&self) -> [u8; MUSIG2_SECRET_KEY_SIZE] {
+        self.secret.into()
+    }
+
+    /// The public key of this signer, which the others need to aggregate keys.
+    pub fn public_key(&self) -> [u8; MUSIG2_PUBLIC_KEY_SIZE] {
+        self.public
+    }
+
+    /// Run the first round, generating nonces, and the commitment to send to the others.
+    ///
+    /// The nonces have to be kept secret, and used for a single signature.
+    pub fn commit<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (Musig2Nonces, Musig2Commitment) {
+        let nonces = Musig2Nonces {
+            first: Scalar::random(rng),
+            second: Scalar::random(rng),
+        };
+        let mut commitment = Musig2Commitment {
+            bytes: [0; MUSIG2_COMMITMENT_SIZE],
+        };
+        commitment.bytes[..ELEMENT_SIZE].copy_from_slice(&(GENERATOR * nonces.first).encode());
+        commitment.bytes[ELEMENT_SIZE..].copy_from_slice(&(GENERATOR * nonces.second).encode());
+        (nonces, commitment)
+    }
+
+    /// Run the second round, producing a partial signature on a message.
+    ///
+    /// The commitments have to come from all the signers, in the same order as their keys.
+    pub fn sign(
+        &self,
+        nonces: Musig2Nonces,
+        key: &Musig2AggregateKey,
+        commitments: &[Musig2Commitment],
+        message: &[u8],
+    ) -> Result<[u8; 32], Error> {
+        let index = key
+            .keys
+            .iter()
+            .position(|k| *k == self.public)
+            .ok_or(Error::InvalidEncoding)?;
+        let ours = &commitments.get(index).ok_or(Error::InvalidLength)?.bytes;
+        let first = (GENERATOR * nonces.first).encode();
+        let second = (GENERATOR * nonces.second).encode();
+        if !bool::from(ours[..ELEMENT_SIZE].ct_eq(&first) & ours[ELEMENT_SIZE..].ct_eq(&second)) {
+            return Err(Error::InvalidEncoding);
+        }
+        let (b, _, c) = key.session(commitments, message)?;
+        let s = nonces.first + b * nonces.second + c * key.coefficients[index] * self.secret;
+        Ok(s.into())
+    }
+}
+
+/// Derive a signer from an Ed25519 key, so that existing keys can take part.
+///
+/// The secret scalar is a hash of the seed, separate from the one used to make
+/// Ed25519 signatures, so the same scalar is never used by both protocols.
+impl From<PrivateKey> for Musig2Signer {
+    fn from(private: PrivateKey) -> Self {
+        Self::from_scalar(Scalar::from_tagged_hash(
+            CONTEXT,
+            b"key",
+            &[private.as_bytes()],
+        ))
+    }
+}
+
+/// This never prints the secret key.
+impl fmt::Debug for Musig2Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Musig2Signer")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Musig2Signer {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// The secret nonces of one signer, for a single signature.
+///
+/// These can't be cloned, and get consumed by signing, since reusing them leaks the key.
+pub struct Musig2Nonces {
+    first: Scalar,
+    second: Scalar,
+}
+
+/// This never prints the nonces.
+impl fmt::Debug for Musig2Nonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Musig2Nonces").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Musig2Nonces {
+    fn drop(&mut self) {
+        self.first.zeroize();
+        self.second.zeroize();
+    }
+}
+
+/// The commitment to the two nonces of one signer, sent in the first round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Musig2Commitment {
+    pub bytes: [u8; MUSIG2_COMMITMENT_SIZE],
+}
+
+impl From<[u8; MUSIG2_COMMITMENT_SIZE]> for Musig2Commitment {
+    fn from(bytes: [u8; MUSIG2_COMMITMENT_SIZE]) -> Self {
+        Musig2Commitment { bytes }
+    }
+}
+
+/// A signature produced by all the signers together, for their aggregate key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Musig2Signature {
+    pub bytes: [u8; MUSIG2_SIGNATURE_SIZE],
+}
+
+impl From<[u8; MUSIG2_SIGNATURE_SIZE]> for Musig2Signature {
+    fn from(bytes: [u8; MUSIG2_SIGNATURE_SIZE]) -> Self {
+        Musig2Signature { bytes }
+    }
+}
+
+/// The aggregation of the keys of a group of signers.
+///
+/// The order of the keys matters, and every signer has to use the same one.
+#[derive(Clone, Debug)]
+pub struct Musig2AggregateKey {
+    keys: Vec<[u8; MUSIG2_PUBLIC_KEY_SIZE]>,
+    coefficients: Vec<Scalar>,
+    public: [u8; MUSIG2_PUBLIC_KEY_SIZE],
+}
+
+impl Musig2AggregateKey {
+    /// Aggregate the public keys of all the signers, which have to be distinct.
+    pub fn new(keys: &[[u8; MUSIG2_PUBLIC_KEY_SIZE]]) -> Result<Self, Error> {
+        if keys.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].contains(key) {
+                return Err(Error::InvalidEncoding);
+            }
+        }
+        let elements = keys
+            .iter()
+            .map(|k| Element::try_from_nonidentity(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Each coefficient commits to the whole list, which prevents rogue key attacks.
+        let list = keys.concat();
+        let coefficients: Vec<Scalar> = keys
+            .iter()
+            .map(|k| Scalar::from_tagged_hash(CONTEXT, b"coefficient", &[&list, k]))
+            .collect();
+        let aggregate = elements
+            .iter()
+            .zip(coefficients.iter())
+            .fold(Element::identity(), |acc, (&x, &a)| acc + x * a);
+        Ok(Musig2AggregateKey {
+            keys: keys.to_vec(),
+            coefficients,
+            public: aggregate.encode(),
+        })
+    }
+
+    /// The aggregate public key, which signatures get verified against.
+    pub fn public_key(&self) -> [u8; MUSIG2_PUBLIC_KEY_SIZE] {
+        self.public
+    }
+
+    /// Calculate the nonce coefficient b, the final nonce R, and the challenge c.
+    fn session(
+        &self,
+        commitments: &[Musig2Commitment],
+        message: &[u8],
+    ) -> Result<(Scalar, [u8; ELEMENT_SIZE], Scalar), Error> {
+        if commitments.len() != self.keys.len() {
+            return Err(Error::InvalidLength);
+        }
+        let mut first = Element::identity();
+        let mut second = Element::identity();
+        for commitment in commitments {
+            first = first + Element::try_from(&commitment.bytes[..ELEMENT_SIZE])?;
+            second = second + Element::try_from(&commitment.bytes[ELEMENT_SIZE..])?;
+        }
+        let b = Scalar::from_tagged_hash(
+            CONTEXT,
+            b"nonce",
+            &[&self.public, &first.encode(), &second.encode(), message],
+        );
+        let r = (first + second * b).encode();
+        Ok((b, r, challenge(&self.public, &r, message)))
+    }
+
+    /// Combine the partial signatures of all the signers, in the same order as their keys.
+    ///
+    /// The result is checked against the aggregate key, so an invalid partial
+    /// signature makes this fail.
+    pub fn aggregate(
+        &self,
+        commitments: &[Musig2Commitment],
+        partials: &[[u8; 32]],
+        message: &[u8],
+    ) -> Result<Musig2Signature, Error> {
+        if partials.len() != self.keys.len() {
+            return Err(Error::InvalidLength);
+        }
+        let (_, r, _) = self.session(commitments, message)?;
+        let mut s = Scalar::from(0);
+        for partial in partials {
+            s += Scalar::try_from(&partial[..]).map_err(|_| Error::NonCanonicalScalar)?;
+        }
+        let mut signature = Musig2Signature {
+            bytes: [0; MUSIG2_SIGNATURE_SIZE],
+        };
+        signature.bytes[..32].copy_from_slice(&r);
+        signature.bytes[32..].copy_from_slice(&<[u8; 32]>::from(s));
+        musig2_verify(&self.public, message, &signature)?;
+        Ok(signature)
+    }
+}
+
+/// Verify a signature against an aggregate key, checking that s * G = R + c * X.
+pub fn musig2_verify(
+    public_key: &[u8; MUSIG2_PUBLIC_KEY_SIZE],
+    message: &[u8],
+    signature: &Musig2Signature,
+) -> Result<(), Error> {
+    let x = Element::try_from_nonidentity(public_key)?;
+    let s = Scalar::try_from(&signature.bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+    let mut r = [0; ELEMENT_SIZE];
+    r.copy_from_slice(&signature.bytes[..32]);
+    let c = challenge(public_key, &r, message);
+    let expected = Element::vartime_double_mul(s, GENERATOR, -c, x).encode();
+    if !bool::from(expected.ct_eq(&r)) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    fn sign_all(
+        signers: &[Musig2Signer],
+        key: &Musig2AggregateKey,
+        message: &[u8],
+    ) -> (Vec<Musig2Commitment>, Vec<[u8; 32]>) {
+        let (nonces, commitments): (Vec<_>, Vec<_>) =
+            signers.iter().map(|s| s.commit(&mut OsRng)).unzip();
+        let partials = signers
+            .iter()
+            .zip(nonces)
+            .map(|(s, n)| s.sign(n, key, &commitments, message).unwrap())
+            .collect();
+        (commitments, partials)
+    }
+
+    #[test]
+    fn test_aggregate_signature() {
+        let signers: Vec<_> = (0..3).map(|_| Musig2Signer::generate(&mut OsRng)).collect();
+        let keys: Vec<_> = signers.iter().map(|s| s.public_key()).collect();
+        let key = Musig2AggregateKey::new(&keys).unwrap();
+        let (commitments, partials) = sign_all(&signers, &key, b"message");
+        let signature = key.aggregate(&commitments, &partials, b"message").unwrap();
+        assert!(musig2_verify(&key.public_key(), b"message", &signature).is_ok());
+        assert_eq!(
+            musig2_verify(&key.public_key(), b"other", &signature),
+            Err(Error::SignatureInvalid)
+        );
+
+        // The order of the keys changes the aggregate key.
+        let reversed: Vec<_> = keys.iter().rev().cloned().collect();
+        let other = Musig2AggregateKey::new(&reversed).unwrap();
+        assert_ne!(other.public_key(), key.public_key());
+        assert!(musig2_verify(&other.public_key(), b"message", &signature).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_partials() {
+        let signers: Vec<_> = (1..=2u64)
+            .map(|i| Musig2Signer::from_scalar(Scalar::from(i)))
+            .collect();
+        let keys: Vec<_> = signers.iter().map(|s| s.public_key()).collect();
+        let key = Musig2AggregateKey::new(&keys).unwrap();
+        let (commitments, mut partials) = sign_all(&signers, &key, b"message");
+        partials[0][0] ^= 1;
+        assert!(key.aggregate(&commitments, &partials, b"message").is_err());
+        assert!(key
+            .aggregate(&commitments, &partials[..1], b"message")
+            .is_err());
+
+        // Nonces have to match the commitment in the signer's position.
+        let (nonces, _) = signers[0].commit(&mut OsRng);
+        assert_eq!(
+            signers[0]
+                .sign(nonces, &key, &commitments, b"message")
+                .unwrap_err(),
+            Error::InvalidEncoding
+        );
+        assert!(Musig2AggregateKey::new(&[keys[0], keys[0]]).is_err());
+        assert!(Musig2AggregateKey::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_stored_keys_sign_again() {
+        let stored = Musig2Signer::generate(&mut OsRng).to_bytes();
+        let signers = [
+            Musig2Signer::from_bytes(stored).unwrap(),
+            Musig2Signer::from(PrivateKey::from_seed([7; 32])),
+        ];
+        let keys: Vec<_> = signers.iter().map(|s| s.public_key()).collect();
+        let key = Musig2AggregateKey::new(&keys).unwrap();
+
+        // After a restart, the same keys still make valid signatures for the aggregate key.
+        let reloaded = [
+            Musig2Signer::from_bytes(stored).unwrap(),
+            Musig2Signer::from(PrivateKey::from_seed([7; 32])),
+        ];
+        let (commitments, partials) = sign_all(&reloaded, &key, b"message");
+        let signature = key.aggregate(&commitments, &partials, b"message").unwrap();
+        assert!(musig2_verify(&key.public_key(), b"message", &signature).is_ok());
+
+        assert_eq!(
+            Musig2Signer::from_bytes([0xFF; 32]).unwrap_err(),
+            Error::NonCanonicalScalar
+        );
+    }
+}
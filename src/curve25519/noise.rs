@@ -0,0 +1,133 @@
+//! This module provides the "25519" DH functions of the Noise Protocol Framework:
+//! https://noiseprotocol.org/noise.html#the-25519-dh-functions
+//!
+//! The methods mirror the DH interface in Section 4.1, as well as the shape of resolvers
+//! like `snow`'s `Dh` trait, so that a small adapter is all it takes to back a Noise
+//! handshake with this crate.
+
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+
+use super::{
+    error::Error,
+    montgomery::{self, BASE_U},
+};
+
+/// The DHLEN constant from the Noise specification, the size of keys and outputs.
+pub const NOISE_DH_LEN: usize = 32;
+
+/// A key pair for the 25519 DH functions in Noise.
+pub struct Dh25519 {
+    private: [u8; NOISE_DH_LEN],
+    public: [u8; NOISE_DH_LEN],
+}
+
+impl Dh25519 {
+    /// The name of these DH functions, as used in protocol names.
+    pub fn name() -> &'static str {
+        "25519"
+    }
+
+    /// GENERATE_KEYPAIR(), creating a new random key pair.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut private = [0; NOISE_DH_LEN];
+        rng.fill_bytes(&mut private);
+        Self::set(private)
+    }
+
+    /// Use an existing private key, calculating the matching public key.
+    pub fn set(private: [u8; NOISE_DH_LEN]) -> Self {
+        let public = montgomery::x25519(private, BASE_U);
+        Dh25519 { private, public }
+    }
+
+    /// The public key, sent to the other party during a handshake.
+    pub fn pubkey(&self) -> &[u8; NOISE_DH_LEN] {
+        &self.public
+    }
+
+    /// The private key, e.g. to store a static key.
+    pub fn privkey(&self) -> &[u8; NOISE_DH_LEN] {
+        &self.private
+    }
+
+    /// DH(key_pair, public_key), the X25519 function from RFC 7748.
+    ///
+    /// The specification allows rejecting invalid public keys, so we do that for
+    /// keys of small order, i.e. whenever the output would be all zeros.
+    pub fn dh(&self, public: &[u8]) -> Result<[u8; NOISE_DH_LEN], Error> {
+        if public.len() != NOISE_DH_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let mut u = [0; NOISE_DH_LEN];
+        u.copy_from_slice(public);
+        let out = montgomery::x25519(self.private, u);
+        if bool::from(out.ct_eq(&[0; NOISE_DH_LEN])) {
+            return Err(Error::WeakPublicKey);
+        }
+        Ok(out)
+    }
+}
+
+/// Like with `PrivateKey`, this never prints the private key.
+impl fmt::Debug for Dh25519 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut public = [0; 2 * NOISE_DH_LEN];
+        hex::encode_to_slice(self.public, &mut public).map_err(|_| fmt::Error)?;
+        f.debug_struct("Dh25519")
+            .field("public", &core::str::from_utf8(&public).unwrap())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Dh25519 {
+    fn drop(&mut self) {
+        self.private.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    fn decode(data: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        hex::decode_to_slice(data, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_rfc_7748_exchange() {
+        // From Section 6.1
+        let alice = Dh25519::set(decode(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        let bob = Dh25519::set(decode(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ));
+        assert_eq!(
+            hex::encode(alice.pubkey()),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"
+        );
+        let shared = "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742";
+        assert_eq!(hex::encode(alice.dh(bob.pubkey()).unwrap()), shared);
+        assert_eq!(hex::encode(bob.dh(alice.pubkey()).unwrap()), shared);
+    }
+
+    #[test]
+    fn test_rejects_bad_public_keys() {
+        let key = Dh25519::generate(&mut OsRng);
+        assert_eq!(Dh25519::set(*key.privkey()).pubkey(), key.pubkey());
+        assert_eq!(key.dh(&[0; 32]), Err(Error::WeakPublicKey));
+        assert_eq!(key.dh(&[9; 31]), Err(Error::InvalidLength));
+        assert!(!format!("{:?}", key).contains(&hex::encode(key.privkey())));
+    }
+}
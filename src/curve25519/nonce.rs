@@ -0,0 +1,137 @@
+//! This module provides counter-based nonce derivation for signing.
+//!
+//! Standard Ed25519 signing derives its nonce from the private key and the message.
+//! Some devices want to mix additional state into this nonce, but lack a good
+//! source of randomness to do so. Instead, these devices can keep a persistent,
+//! monotonic counter, which we mix into the nonce through HKDF.
+//!
+//! The main danger with counters is rolling them back, e.g. after a crash,
+//! or restoring a backup. To make this harder to get wrong, the API forces the
+//! next counter value to be persisted before a nonce can be derived from the current one.
+
+use crate::{hkdf, sha512::HASH_SIZE, zeroize::Zeroizing};
+
+use super::error::CounterError;
+
+/// The salt we use when extracting a nonce key from the private key material.
+const NONCE_KEY_SALT: &[u8] = b"eddo counter nonce key";
+
+/// A persistent, monotonic counter used to derive signing nonces.
+///
+/// The value of this counter should be stored in non-volatile memory, and loaded
+/// with `NonceCounter::restore`.
+#[derive(Debug)]
+pub struct NonceCounter {
+    next: u64,
+}
+
+impl NonceCounter {
+    /// Restore a counter from its persisted state.
+    ///
+    /// For a fresh device, this state should be 0.
+    pub fn restore(state: u64) -> Self {
+        NonceCounter { next: state }
+    }
+
+    /// The current state of this counter, i.e. the next value that will be used.
+    pub fn state(&self) -> u64 {
+        self.next
+    }
+
+    /// Commit to using the current counter value, advancing this counter.
+    ///
+    /// The `persist` callback receives the new state of the counter, and should
+    /// store it durably before returning `Ok`. Only if it succeeds is the
+    /// counter advanced, and a `CommittedCounter` produced, which can then
+    /// be used exactly once for signing.
+    pub fn commit<E>(
+        &mut self,
+        persist: impl FnOnce(u64) -> Result<(), E>,
+    ) -> Result<CommittedCounter, CounterError<E>> {
+        let value = self.next;
+        let next = value.checked_add(1).ok_or(CounterError::Exhausted)?;
+        persist(next).map_err(CounterError::Persist)?;
+        self.next = next;
+        Ok(CommittedCounter { value })
+    }
+}
+
+/// A counter value whose successor has been durably persisted.
+///
+/// This can only be created by `NonceCounter::commit`, and gets consumed
+/// when signing, so each value gets used at most once.
+#[derive(Debug)]
+pub struct CommittedCounter {
+    value: u64,
+}
+
+impl CommittedCounter {
+    /// The counter value this commitment allows using.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Derive the prefix to hash along with the message, producing a nonce.
+    ///
+    /// The hierarchy here is:
+    ///     nonce key   <- HKDF-Extract(salt, prefix)
+    ///     nonce seed  <- HKDF-Expand(nonce key, counter)
+    /// where prefix is the second half of the hashed private key.
+    pub(super) fn nonce_prefix(self, prefix: &[u8]) -> [u8; HASH_SIZE] {
+        let nonce_key = Zeroizing::new(hkdf::extract(NONCE_KEY_SALT, prefix));
+        let mut out = [0; HASH_SIZE];
+        hkdf::expand(&nonce_key, &self.value.to_be_bytes(), &mut out);
+        out
+    }
+}
+
+impl From<CommittedCounter> for u64 {
+    fn from(counter: CommittedCounter) -> u64 {
+        counter.value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_commit_advances_after_persisting() {
+        let mut counter = NonceCounter::restore(7);
+        let mut stored = 0;
+        let committed = counter
+            .commit(|next| -> Result<(), ()> {
+                stored = next;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(committed.value(), 7);
+        assert_eq!(stored, 8);
+        assert_eq!(counter.state(), 8);
+    }
+
+    #[test]
+    fn test_failed_persist_does_not_advance() {
+        let mut counter = NonceCounter::restore(3);
+        let res = counter.commit(|_| Err("disk full"));
+        assert!(matches!(res, Err(CounterError::Persist("disk full"))));
+        assert_eq!(counter.state(), 3);
+    }
+
+    #[test]
+    fn test_exhausted_counter() {
+        let mut counter = NonceCounter::restore(u64::MAX);
+        let res = counter.commit(|_| -> Result<(), ()> { Ok(()) });
+        assert!(matches!(res, Err(CounterError::Exhausted)));
+    }
+
+    #[test]
+    fn test_different_counters_give_different_prefixes() {
+        let prefix = [0xAB; 32];
+        let a = CommittedCounter { value: 0 }.nonce_prefix(&prefix);
+        let b = CommittedCounter { value: 1 }.nonce_prefix(&prefix);
+        assert_ne!(a[..], b[..]);
+        let a_again = CommittedCounter { value: 0 }.nonce_prefix(&prefix);
+        assert_eq!(a[..], a_again[..]);
+    }
+}
@@ -0,0 +1,119 @@
+//! This module implements Tor v3 onion service addresses, as described in Section 6 of
+//! the rendezvous specification:
+//! https://spec.torproject.org/rend-spec/encoding-onion-addresses.html
+//!
+//! An address is the lowercase Base32 encoding of the public key, a 2 byte checksum,
+//! and a version byte, followed by ".onion".
+
+use alloc::{string::String, vec::Vec};
+use core::convert::{TryFrom, TryInto};
+
+use crate::shake::sha3_256;
+
+use super::{Error, PublicKey, PUBLIC_KEY_SIZE};
+
+const VERSION: u8 = 3;
+const CHECKSUM_PREFIX: &[u8] = b".onion checksum";
+const SUFFIX: &str = ".onion";
+const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+/// The size of the decoded address, which is a multiple of 5, avoiding Base32 padding.
+const ADDRESS_BYTES: usize = PUBLIC_KEY_SIZE + 2 + 1;
+const ADDRESS_CHARS: usize = ADDRESS_BYTES / 5 * 8;
+
+fn checksum(public: &[u8; PUBLIC_KEY_SIZE]) -> [u8; 2] {
+    let mut data = CHECKSUM_PREFIX.to_vec();
+    data.extend_from_slice(public);
+    data.push(VERSION);
+    sha3_256(&data)[..2].try_into().unwrap()
+}
+
+impl PublicKey {
+    /// Derive the v3 onion address of a service with this key, like "xxx.onion".
+    pub fn to_onion_address(&self) -> String {
+        let mut data = [0; ADDRESS_BYTES];
+        data[..PUBLIC_KEY_SIZE].copy_from_slice(&self.bytes);
+        data[PUBLIC_KEY_SIZE..PUBLIC_KEY_SIZE + 2].copy_from_slice(&checksum(&self.bytes));
+        data[ADDRESS_BYTES - 1] = VERSION;
+
+        let mut out = String::with_capacity(ADDRESS_CHARS + SUFFIX.len());
+        for chunk in data.chunks_exact(5) {
+            let mut group = [0; 8];
+            group[3..].copy_from_slice(chunk);
+            let group = u64::from_be_bytes(group);
+            for i in (0..8).rev() {
+                out.push(char::from(ALPHABET[(group >> (5 * i)) as usize & 0x1F]));
+            }
+        }
+        out.push_str(SUFFIX);
+        out
+    }
+
+    /// Parse a v3 onion address, checking its version, and checksum.
+    ///
+    /// The ".onion" suffix is optional, since it's often left out.
+    pub fn from_onion_address(address: &str) -> Result<Self, Error> {
+        let encoded = address.strip_suffix(SUFFIX).unwrap_or(address);
+        if encoded.len() != ADDRESS_CHARS {
+            return Err(Error::InvalidLength);
+        }
+        let mut data = Vec::with_capacity(ADDRESS_BYTES);
+        for chunk in encoded.as_bytes().chunks_exact(8) {
+            let mut group = 0u64;
+            for &c in chunk {
+                let value = ALPHABET
+                    .iter()
+                    .position(|&a| a == c)
+                    .ok_or(Error::InvalidEncoding)?;
+                group = (group << 5) | value as u64;
+            }
+            data.extend_from_slice(&group.to_be_bytes()[3..]);
+        }
+        if data[ADDRESS_BYTES - 1] != VERSION {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let public = PublicKey::try_from(&data[..PUBLIC_KEY_SIZE])?;
+        if data[PUBLIC_KEY_SIZE..PUBLIC_KEY_SIZE + 2] != checksum(&public.bytes) {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(public)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_address_vector() {
+        // This comes from Tor's own tests, for the public key of RFC 8032's first vector
+        let public: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            .parse()
+            .unwrap();
+        let address = "25njqamcweflpvkl73j4szahhihoc4xt3ktcgjnpaingr5yhkenl5sid.onion";
+        assert_eq!(public.to_onion_address(), address);
+        assert_eq!(PublicKey::from_onion_address(address), Ok(public));
+        assert_eq!(PublicKey::from_onion_address(&address[..56]), Ok(public));
+    }
+
+    #[test]
+    fn test_rejects_invalid_addresses() {
+        // A broken checksum
+        assert_eq!(
+            PublicKey::from_onion_address(
+                "25njqamcweflpvkl73j4szahhihoc4xt3ktcgjnpaingr5yhkenl5aid"
+            ),
+            Err(Error::InvalidEncoding)
+        );
+        // A v2 address
+        assert_eq!(
+            PublicKey::from_onion_address("expyuzz4wqqyqhjn.onion"),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            PublicKey::from_onion_address(
+                "25NJQAMCWEFLPVKL73J4SZAHHIHOC4XT3KTCGJNPAINGR5YHKENL5SID"
+            ),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
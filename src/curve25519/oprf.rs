@@ -0,0 +1,427 @@
+//! This module implements oblivious pseudorandom functions over ristretto255, as
+//! described in RFC 9497, with the ristretto255-SHA512 ciphersuite:
+//! https://datatracker.ietf.org/doc/html/rfc9497
+//!
+//! A client blinds its input, and the server evaluates its PRF on the blinded input,
+//! letting the client compute the output without the server ever seeing the input,
+//! or the client learning the key. In the verifiable mode, the server also proves that
+//! it used the key matching its public key, so that clients can't be singled out.
+//!
+//! Only the base, and verifiable modes are implemented, not the partially oblivious one.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::sha512::{self, expand_message_xmd, Sha512};
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    ristretto::{Element, GENERATOR},
+    scalar::Scalar,
+    Error,
+};
+
+/// The size of the elements exchanged between client and server.
+pub const OPRF_ELEMENT_SIZE: usize = 32;
+
+/// The size of the output of the PRF.
+pub const OPRF_OUTPUT_SIZE: usize = sha512::HASH_SIZE;
+
+/// The size of a proof in the verifiable mode, containing two scalars.
+pub const OPRF_PROOF_SIZE: usize = 64;
+
+const MODE_BASE: u8 = 0x00;
+const MODE_VERIFIABLE: u8 = 0x01;
+
+fn context(mode: u8) -> Vec<u8> {
+    let mut out = b"OPRFV1-".to_vec();
+    out.push(mode);
+    out.extend_from_slice(b"-ristretto255-SHA512");
+    out
+}
+
+fn dst(prefix: &[u8], mode: u8) -> Vec<u8> {
+    let mut out = prefix.to_vec();
+    out.extend_from_slice(&context(mode));
+    out
+}
+
+/// Append some data to a transcript, prefixed with its 2 byte length.
+fn push_prefixed(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+fn hash_to_scalar(message: &[u8], dst: &[u8]) -> Scalar {
+    let mut uniform = [0; 64];
+    expand_message_xmd(message, dst, &mut uniform);
+    Scalar::from(uniform)
+}
+
+/// Combine elements C, and D, into M, and Z, following Section 2.2.1.
+///
+/// When the server knows the key, it saves work by calculating Z as k * M.
+fn composites(
+    mode: u8,
+    public: Element,
+    c: Element,
+    d: Element,
+    key: Option<Scalar>,
+) -> (Element, Element) {
+    let mut transcript = Vec::new();
+    push_prefixed(&mut transcript, &public.encode());
+    push_prefixed(&mut transcript, &dst(b"Seed-", mode));
+    let seed = sha512::hash(&transcript);
+
+    // We only ever have a single element, but keep the transcript of a batch.
+    let mut transcript = Vec::new();
+    push_prefixed(&mut transcript, &seed);
+    transcript.extend_from_slice(&0u16.to_be_bytes());
+    push_prefixed(&mut transcript, &c.encode());
+    push_prefixed(&mut transcript, &d.encode());
+    transcript.extend_from_slice(b"Composite");
+    let di = hash_to_scalar(&transcript, &dst(b"HashToScalar-", mode));
+    let m = c * di;
+    let z = match key {
+        Some(k) => m * k,
+        None => d * di,
+    };
+    (m, z)
+}
+
+fn challenge(mode: u8, elements: [Element; 5]) -> Scalar {
+    let mut transcript = Vec::new();
+    for element in elements.iter() {
+        push_prefixed(&mut transcript, &(*element).encode());
+    }
+    transcript.extend_from_slice(b"Challenge");
+    hash_to_scalar(&transcript, &dst(b"HashToScalar-", mode))
+}
+
+/// Prove that log_G(public) = log_c(d), following Section 2.2.1.
+fn prove(key: Scalar, public: Element, c: Element, d: Element, r: Scalar) -> [u8; OPRF_PROOF_SIZE] {
+    let r = Zeroizing::new(r);
+    let (m, z) = composites(MODE_VERIFIABLE, public, c, d, Some(key));
+    let challenge = challenge(MODE_VERIFIABLE, [public, m, z, GENERATOR * *r, m * *r]);
+    let s = *r - challenge * key;
+    let mut out = [0; OPRF_PROOF_SIZE];
+    out[..32].copy_from_slice(&<[u8; 32]>::from(challenge));
+    out[32..].copy_from_slice(&<[u8; 32]>::from(s));
+    out
+}
+
+fn verify_proof(
+    public: Element,
+    c: Element,
+    d: Element,
+    proof: &[u8; OPRF_PROOF_SIZE],
+) -> Result<(), Error> {
+    let challenge = Scalar::try_from(&proof[..32]).map_err(|_| Error::NonCanonicalScalar)?;
+    let s = Scalar::try_from(&proof[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+    let (m, z) = composites(MODE_VERIFIABLE, public, c, d, None);
+    let t2 = Element::vartime_double_mul(s, GENERATOR, challenge, public);
+    let t3 = Element::vartime_double_mul(s, m, challenge, z);
+    let expected: [u8; 32] = self::challenge(MODE_VERIFIABLE, [public, m, z, t2, t3]).into();
+    if !bool::from(expected.ct_eq(&proof[..32])) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// The response of the server to a blinded input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OprfEvaluation {
+    /// The blinded input, with the PRF applied.
+    pub element: [u8; OPRF_ELEMENT_SIZE],
+    /// The proof that the right key was used, in the verifiable mode.
+    pub proof: Option<[u8; OPRF_PROOF_SIZE]>,
+}
+
+/// A server, holding the key of the PRF.
+pub struct OprfServer {
+    mode: u8,
+    key: Scalar,
+    public: Element,
+}
+
+impl OprfServer {
+    fn new(mode: u8, key: Scalar) -> Self {
+        OprfServer {
+            mode,
+            key,
+            public: GENERATOR * key,
+        }
+    }
+
+    /// Generate a random key for the base mode.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::new(MODE_BASE, Scalar::random(rng))
+    }
+
+    /// Generate a random key for the verifiable mode.
+    pub fn generate_verifiable<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self::new(MODE_VERIFIABLE, Scalar::random(rng))
+    }
+
+    fn derive_mode(mode: u8, seed: &[u8; 32], info: &[u8]) -> Result<Self, Error> {
+        if info.len() > usize::from(u16::MAX) {
+            return Err(Error::InvalidLength);
+        }
+        let mut input = Zeroizing::new(seed.to_vec());
+        push_prefixed(&mut input, info);
+        input.push(0);
+        let dst = dst(b"DeriveKeyPair", mode);
+        for counter in 0..=255 {
+            *input.last_mut().unwrap() = counter;
+            let key = hash_to_scalar(&input, &dst);
+            if !bool::from(<[u8; 32]>::from(key).ct_eq(&[0; 32])) {
+                return Ok(Self::new(mode, key));
+            }
+        }
+        // This happens with probability about 2^-2000.
+        Err(Error::InvalidEncoding)
+    }
+
+    /// Derive a key for the base mode, deterministically, from a seed, and some info.
+    pub fn derive(seed: &[u8; 32], info: &[u8]) -> Result<Self, Error> {
+        Self::derive_mode(MODE_BASE, seed, info)
+    }
+
+    /// Derive a key for the verifiable mode, deterministically, from a seed, and some info.
+    pub fn derive_verifiable(seed: &[u8; 32], info: &[u8]) -> Result<Self, Error> {
+        Self::derive_mode(MODE_VERIFIABLE, seed, info)
+    }
+
+    /// The public key, which clients use to check evaluations, in the verifiable mode.
+    pub fn public_key(&self) -> [u8; OPRF_ELEMENT_SIZE] {
+        self.public.encode()
+    }
+
+    fn evaluate_with_nonce(
+        &self,
+        blinded: &[u8; OPRF_ELEMENT_SIZE],
+        r: Scalar,
+    ) -> Result<OprfEvaluation, Error> {
+        let c = Element::try_from_nonidentity(blinded)?;
+        let d = c * self.key;
+        let proof = if self.mode == MODE_VERIFIABLE {
+            Some(prove(self.key, self.public, c, d, r))
+        } else {
+            None
+        };
+        Ok(OprfEvaluation {
+            element: d.encode(),
+            proof,
+        })
+    }
+
+    /// Evaluate the PRF on a blinded input, producing a proof in the verifiable mode.
+    pub fn evaluate<R: RngCore + CryptoRng>(
+        &self,
+        blinded: &[u8; OPRF_ELEMENT_SIZE],
+        rng: &mut R,
+    ) -> Result<OprfEvaluation, Error> {
+        self.evaluate_with_nonce(blinded, Scalar::random(rng))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for OprfServer {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// The state a client keeps between blinding its input, and finalizing the output.
+pub struct OprfClient {
+    blind: Scalar,
+    blinded: Element,
+    /// The public key of the server, in the verifiable mode.
+    server: Option<Element>,
+}
+
+impl OprfClient {
+    fn blind_mode(
+        mode: u8,
+        server: Option<Element>,
+        input: &[u8],
+        blind: Scalar,
+    ) -> Result<(Self, [u8; OPRF_ELEMENT_SIZE]), Error> {
+        let element = Element::hash(input, &dst(b"HashToGroup-", mode));
+        if bool::from(element.is_identity()) {
+            return Err(Error::InvalidEncoding);
+        }
+        let blinded = element * blind;
+        let client = OprfClient {
+            blind,
+            blinded,
+            server,
+        };
+        Ok((client, blinded.encode()))
+    }
+
+    /// Blind an input, for the base mode, returning the element to send to the server.
+    pub fn blind<R: RngCore + CryptoRng>(
+        input: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, [u8; OPRF_ELEMENT_SIZE]), Error> {
+        Self::blind_mode(MODE_BASE, None, input, Scalar::random(rng))
+    }
+
+    /// Blind an input, for the verifiable mode, with the public key of the server.
+    pub fn blind_verifiable<R: RngCore + CryptoRng>(
+        server: &[u8; OPRF_ELEMENT_SIZE],
+        input: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, [u8; OPRF_ELEMENT_SIZE]), Error> {
+        let server = Element::try_from_nonidentity(server)?;
+        Self::blind_mode(MODE_VERIFIABLE, Some(server), input, Scalar::random(rng))
+    }
+
+    /// Compute the output of the PRF, from the same input, and the server's response.
+    ///
+    /// In the verifiable mode, this fails unless the response has a valid proof.
+    pub fn finalize(
+        &self,
+        input: &[u8],
+        evaluation: &OprfEvaluation,
+    ) -> Result<[u8; OPRF_OUTPUT_SIZE], Error> {
+        if input.len() > usize::from(u16::MAX) {
+            return Err(Error::InvalidLength);
+        }
+        let evaluated = Element::try_from_nonidentity(&evaluation.element)?;
+        if let Some(server) = self.server {
+            let proof = evaluation.proof.as_ref().ok_or(Error::SignatureInvalid)?;
+            verify_proof(server, self.blinded, evaluated, proof)?;
+        }
+        let unblinded = (evaluated * self.blind.inverse()).encode();
+        let mut hasher = Sha512::new();
+        hasher.update(&(input.len() as u16).to_be_bytes());
+        hasher.update(input);
+        hasher.update(&(unblinded.len() as u16).to_be_bytes());
+        hasher.update(&unblinded);
+        hasher.update(b"Finalize");
+        Ok(hasher.finalize())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for OprfClient {
+    fn drop(&mut self) {
+        self.blind.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: [u8; 32] = [0xa3; 32];
+    const INFO: &[u8] = b"test key";
+
+    fn scalar(hex: &str) -> Scalar {
+        Scalar::try_from(&hex::decode(hex).unwrap()[..]).unwrap()
+    }
+
+    #[test]
+    fn test_base_mode_vectors() {
+        // From Appendix A.1.1, Test Vectors 1, and 2
+        let server = OprfServer::derive(&SEED, INFO).unwrap();
+        assert_eq!(
+            hex::encode(<[u8; 32]>::from(server.key)),
+            "5ebcea5ee37023ccb9fc2d2019f9d7737be85591ae8652ffa9ef0f4d37063b0e"
+        );
+        let blind = scalar("64d37aed22a27f5191de1c1d69fadb899d8862b58eb4220029e036ec4c1f6706");
+        let vectors = [
+            (
+                &[0u8][..],
+                "609a0ae68c15a3cf6903766461307e5c8bb2f95e7e6550e1ffa2dc99e412803c",
+                "7ec6578ae5120958eb2db1745758ff379e77cb64fe77b0b2d8cc917ea0869c7e",
+                "527759c3d9366f277d8c6020418d96bb393ba2afb20ff90df23fb7708264e2f3ab9135e3bd69955851de4b1f9fe8a0973396719b7912ba9ee8aa7d0b5e24bcf6",
+            ),
+            (
+                &[0x5a; 17][..],
+                "da27ef466870f5f15296299850aa088629945a17d1f5b7f5ff043f76b3c06418",
+                "b4cbf5a4f1eeda5a63ce7b77c7d23f461db3fcab0dd28e4e17cecb5c90d02c25",
+                "f4a74c9c592497375e796aa837e907b1a045d34306a749db9f34221f7e750cb4f2a6413a6bf6fa5e19ba6348eb673934a722a7ede2e7621306d18951e7cf2c73",
+            ),
+        ];
+        for (input, blinded_hex, evaluated_hex, output) in vectors.iter() {
+            let (client, blinded) = OprfClient::blind_mode(MODE_BASE, None, input, blind).unwrap();
+            assert_eq!(hex::encode(blinded), *blinded_hex);
+            let evaluation = server.evaluate(&blinded, &mut rand::rngs::OsRng).unwrap();
+            assert_eq!(hex::encode(evaluation.element), *evaluated_hex);
+            assert_eq!(evaluation.proof, None);
+            assert_eq!(
+                hex::encode(&client.finalize(input, &evaluation).unwrap()[..]),
+                *output
+            );
+        }
+    }
+
+    #[test]
+    fn test_verifiable_mode_vectors() {
+        // From Appendix A.1.2, Test Vectors 1, and 2
+        let server = OprfServer::derive_verifiable(&SEED, INFO).unwrap();
+        assert_eq!(
+            hex::encode(server.public_key()),
+            "c803e2cc6b05fc15064549b5920659ca4a77b2cca6f04f6b357009335476ad4e"
+        );
+        let blind = scalar("64d37aed22a27f5191de1c1d69fadb899d8862b58eb4220029e036ec4c1f6706");
+        let r = scalar("222a5e897cf59db8145db8d16e597e8facb80ae7d4e26d9881aa6f61d645fc0e");
+        let public = Element::try_from_nonidentity(&server.public_key()).unwrap();
+        let vectors = [
+            (
+                &[0u8][..],
+                "863f330cc1a1259ed5a5998a23acfd37fb4351a793a5b3c090b642ddc439b945",
+                "aa8fa048764d5623868679402ff6108d2521884fa138cd7f9c7669a9a014267e",
+                "ddef93772692e535d1a53903db24367355cc2cc78de93b3be5a8ffcc6985dd066d4346421d17bf5117a2a1ff0fcb2a759f58a539dfbe857a40bce4cf49ec600d",
+                "b58cfbe118e0cb94d79b5fd6a6dafb98764dff49c14e1770b566e42402da1a7da4d8527693914139caee5bd03903af43a491351d23b430948dd50cde10d32b3c",
+            ),
+            (
+                &[0x5a; 17][..],
+                "cc0b2a350101881d8a4cba4c80241d74fb7dcbfde4a61fde2f91443c2bf9ef0c",
+                "60a59a57208d48aca71e9e850d22674b611f752bed48b36f7a91b372bd7ad468",
+                "401a0da6264f8cf45bb2f5264bc31e109155600babb3cd4e5af7d181a2c9dc0a67154fabf031fd936051dec80b0b6ae29c9503493dde7393b722eafdf5a50b02",
+                "8a9a2f3c7f085b65933594309041fc1898d42d0858e59f90814ae90571a6df60356f4610bf816f27afdd84f47719e480906d27ecd994985890e5f539e7ea74b6",
+            ),
+        ];
+        for (input, blinded_hex, evaluated_hex, proof, output) in vectors.iter() {
+            let (client, blinded) =
+                OprfClient::blind_mode(MODE_VERIFIABLE, Some(public), input, blind).unwrap();
+            assert_eq!(hex::encode(blinded), *blinded_hex);
+            let evaluation = server.evaluate_with_nonce(&blinded, r).unwrap();
+            assert_eq!(hex::encode(evaluation.element), *evaluated_hex);
+            assert_eq!(hex::encode(&evaluation.proof.unwrap()[..]), *proof);
+            assert_eq!(
+                hex::encode(&client.finalize(input, &evaluation).unwrap()[..]),
+                *output
+            );
+        }
+    }
+
+    #[test]
+    fn test_verifiable_mode_rejects_bad_evaluations() {
+        let server = OprfServer::derive_verifiable(&SEED, INFO).unwrap();
+        let (client, blinded) =
+            OprfClient::blind_verifiable(&server.public_key(), &[0], &mut rand::rngs::OsRng)
+                .unwrap();
+        let evaluation = server.evaluate(&blinded, &mut rand::rngs::OsRng).unwrap();
+        assert!(client.finalize(&[0], &evaluation).is_ok());
+
+        // Evaluations with another key, or without a proof, get rejected.
+        let other = OprfServer::generate_verifiable(&mut rand::rngs::OsRng);
+        let wrong = other.evaluate(&blinded, &mut rand::rngs::OsRng).unwrap();
+        assert_eq!(client.finalize(&[0], &wrong), Err(Error::SignatureInvalid));
+        let unproven = OprfEvaluation {
+            proof: None,
+            ..evaluation
+        };
+        assert!(client.finalize(&[0], &unproven).is_err());
+    }
+}
@@ -2,62 +2,45 @@
 //! This follows sections of RFC 8032:
 //! https://datatracker.ietf.org/doc/html/rfc8032
 
-use std::{
+use core::{
     convert::{TryFrom, TryInto},
-    ops::{Add, Mul},
+    ops::{Add, Mul, Neg, Sub},
 };
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
-use super::{arithmetic::U256, error::SignatureError, field::Z25519, scalar::Scalar};
-
-const D: Z25519 = Z25519 {
-    value: U256 {
-        limbs: [
-            0x75eb4dca135978a3,
-            0x00700a4d4141d8ab,
-            0x8cc740797779e898,
-            0x52036cee2b6ffe73,
-        ],
-    },
-};
+use super::{error::SignatureError, field::Z25519, montgomery::ProjectiveU, scalar::Scalar};
+
+pub const D: Z25519 = Z25519::from_limbs64([
+    0x75eb4dca135978a3,
+    0x00700a4d4141d8ab,
+    0x8cc740797779e898,
+    0x52036cee2b6ffe73,
+]);
 
 pub const B: Point = Point {
-    x: Z25519 {
-        value: U256 {
-            limbs: [
-                0xc9562d608f25d51a,
-                0x692cc7609525a7b2,
-                0xc0a4e231fdd6dc5c,
-                0x216936d3cd6e53fe,
-            ],
-        },
-    },
-    y: Z25519 {
-        value: U256 {
-            limbs: [
-                0x6666666666666658,
-                0x6666666666666666,
-                0x6666666666666666,
-                0x6666666666666666,
-            ],
-        },
-    },
-    z: Z25519 {
-        value: U256 {
-            limbs: [1, 0, 0, 0],
-        },
-    },
-    t: Z25519 {
-        value: U256 {
-            limbs: [
-                0x6dde8ab3a5b7dda3,
-                0x20f09f80775152f5,
-                0x66ea4e8e64abe37d,
-                0x67875f0fd78b7665,
-            ],
-        },
-    },
+    x: Z25519::from_limbs64([
+        0xc9562d608f25d51a,
+        0x692cc7609525a7b2,
+        0xc0a4e231fdd6dc5c,
+        0x216936d3cd6e53fe,
+    ]),
+    y: Z25519::from_limbs64([
+        0x6666666666666658,
+        0x6666666666666666,
+        0x6666666666666666,
+        0x6666666666666666,
+    ]),
+    z: Z25519::from_limbs64([1, 0, 0, 0]),
+    t: Z25519::from_limbs64([
+        0x6dde8ab3a5b7dda3,
+        0x20f09f80775152f5,
+        0x66ea4e8e64abe37d,
+        0x67875f0fd78b7665,
+    ]),
 };
 
 /// Represents a point on our Edward's Curve.
@@ -75,12 +58,12 @@ pub struct Point {
 
 impl Point {
     // Return the identity element of this group.
-    fn identity() -> Point {
+    pub const fn identity() -> Point {
         Point {
-            x: Z25519::from(0),
-            y: Z25519::from(1),
-            z: Z25519::from(1),
-            t: Z25519::from(0),
+            x: Z25519::from_limbs64([0, 0, 0, 0]),
+            y: Z25519::from_limbs64([1, 0, 0, 0]),
+            z: Z25519::from_limbs64([1, 0, 0, 0]),
+            t: Z25519::from_limbs64([0, 0, 0, 0]),
         }
     }
 
@@ -95,9 +78,55 @@ impl Point {
         }
     }
 
+    /// Create a point from extended coordinates, assumed to be on the curve.
+    pub const fn from_extended_unchecked(x: Z25519, y: Z25519, z: Z25519, t: Z25519) -> Point {
+        Point { x, y, z, t }
+    }
+
+    /// Return the extended coordinates (X, Y, Z, T) of this point.
+    pub fn extended(&self) -> (Z25519, Z25519, Z25519, Z25519) {
+        (self.x, self.y, self.z, self.t)
+    }
+
+    /// Calculate the affine coordinates (x, y) of this point.
+    pub fn affine(&self) -> (Z25519, Z25519) {
+        let zinv = self.z.inverse();
+        (self.x * zinv, self.y * zinv)
+    }
+
+    /// Calculate the u coordinate of this point, on the equivalent Montgomery curve.
+    pub fn montgomery_u(&self) -> ProjectiveU {
+        // u = (1 + y) / (1 - y), and y = Y / Z
+        ProjectiveU {
+            u: self.z + self.y,
+            w: self.z - self.y,
+        }
+    }
+
+    /// Encode this point, as 32 bytes.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+    pub(super) fn encode(self) -> [u8; 32] {
+        self.into()
+    }
+
+    /// Check if 8 times this point is the identity, i.e. it has small order.
+    pub(super) fn has_small_order(&self) -> bool {
+        let cleared = self.doubled().doubled().doubled();
+        cleared.ct_eq(&Point::identity()).into()
+    }
+
+    /// Check if L times this point is the identity, i.e. it has no component of small order.
+    pub(super) fn is_torsion_free(&self) -> bool {
+        // The scalar -1 is L - 1, so adding the point once more gives L times it.
+        (*self * -Scalar::from(1) + *self)
+            .ct_eq(&Point::identity())
+            .into()
+    }
+
     // this calculates self + self, but in a more efficient way, exploiting symmetry.
     #[must_use]
-    fn doubled(&self) -> Point {
+    pub fn doubled(&self) -> Point {
         // This is taken from the second routine in section 5.1.4:
         // https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.4
         let a = self.x.squared();
@@ -127,17 +156,130 @@ impl ConditionallySelectable for Point {
     }
 }
 
-impl Into<[u8; 32]> for Point {
-    fn into(self) -> [u8; 32] {
-        let zinv = self.z.inverse();
-        let x = self.x * zinv;
-        let y = self.y * zinv;
-        let mut out: [u8; 32] = y.into();
-        out[31] |= ((x.value.limbs[0] & 1) as u8) << 7;
+/// Two points are equal when X1 / Z1 = X2 / Z2, and Y1 / Z1 = Y2 / Z2, which we check
+/// by cross multiplying, without any inversions, or branches.
+impl ConstantTimeEq for Point {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (self.x * other.z).ct_eq(&(other.x * self.z))
+            & (self.y * other.z).ct_eq(&(other.y * self.z))
+    }
+}
+
+/// The negation of (x, y) is (-x, y), which also negates t.
+impl Neg for &Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Point {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+            t: -self.t,
+        }
+    }
+}
+
+/// Along with `ConditionallySelectable`, this makes points `ConditionallyNegatable`.
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+/// A point in affine coordinates, i.e. with Z = 1.
+///
+/// Converting a point to affine coordinates needs an inversion, which
+/// `Point::to_affine_batch` shares between many points.
+#[derive(Clone, Copy, Debug)]
+pub struct AffinePoint {
+    x: Z25519,
+    y: Z25519,
+}
+
+impl AffinePoint {
+    pub fn x(&self) -> Z25519 {
+        self.x
+    }
+
+    pub fn y(&self) -> Z25519 {
+        self.y
+    }
+}
+
+impl ConstantTimeEq for AffinePoint {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.x.ct_eq(&other.x) & self.y.ct_eq(&other.y)
+    }
+}
+
+impl ConditionallySelectable for AffinePoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        AffinePoint {
+            x: Z25519::conditional_select(&a.x, &b.x, choice),
+            y: Z25519::conditional_select(&a.y, &b.y, choice),
+        }
+    }
+}
+
+impl From<Point> for AffinePoint {
+    fn from(point: Point) -> Self {
+        let (x, y) = point.affine();
+        AffinePoint { x, y }
+    }
+}
+
+impl From<AffinePoint> for Point {
+    fn from(point: AffinePoint) -> Self {
+        Point::from_affine_unchecked(point.x, point.y)
+    }
+}
+
+/// Encoding affine points needs no inversion.
+impl From<AffinePoint> for [u8; 32] {
+    fn from(point: AffinePoint) -> [u8; 32] {
+        let mut out: [u8; 32] = point.y.into();
+        out[31] |= point.x.parity() << 7;
         out
     }
 }
 
+impl From<Point> for [u8; 32] {
+    fn from(point: Point) -> [u8; 32] {
+        AffinePoint::from(point).into()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Point {
+    /// Convert many points to affine coordinates, sharing a single inversion between them.
+    ///
+    /// This gives the same results as converting each point separately.
+    pub fn to_affine_batch(points: &[Point]) -> Vec<AffinePoint> {
+        let mut z_invs: Vec<_> = points.iter().map(|p| p.z).collect();
+        Z25519::batch_invert(&mut z_invs);
+        points
+            .iter()
+            .zip(z_invs)
+            .map(|(p, z_inv)| AffinePoint {
+                x: p.x * z_inv,
+                y: p.y * z_inv,
+            })
+            .collect()
+    }
+
+    /// Encode many points at once, sharing a single inversion between them.
+    ///
+    /// This gives the same results as converting each point into bytes.
+    pub fn batch_encode(points: &[Point]) -> Vec<[u8; 32]> {
+        Self::to_affine_batch(points)
+            .into_iter()
+            .map(|p| p.into())
+            .collect()
+    }
+}
+
 impl<'a> TryFrom<&'a [u8]> for Point {
     type Error = SignatureError;
 
@@ -153,24 +295,96 @@ impl<'a> TryFrom<&'a [u8]> for Point {
         let u = y_2 - Z25519::from(1);
         let v = D * y_2 + Z25519::from(1);
         let mut x = Z25519::fraction_root(u, v).ok_or(SignatureError::InvalidPoint)?;
-        if x_0 == 1 && x.value.eq(U256::from(0)) {
+        if x_0 == 1 && bool::from(x.is_zero()) {
             return Err(SignatureError::InvalidPoint);
         }
-        if x_0 != x.value.limbs[0] % 2 {
+        if x_0 != u64::from(x.parity()) {
             x = -x;
         }
         Ok(Point::from_affine_unchecked(x, y))
     }
 }
 
-impl Add for Point {
+/// A point, cached in the form most convenient for adding it to other points.
+///
+/// This holds (Y + X, Y - X, 2Z, 2dT), which saves a multiplication, along with some
+/// doublings, on every addition, as in Section 3.1 of "Twisted Edwards Curves Revisited":
+/// https://eprint.iacr.org/2008/522
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectiveNiels {
+    y_plus_x: Z25519,
+    y_minus_x: Z25519,
+    z2: Z25519,
+    t2d: Z25519,
+}
+
+impl ProjectiveNiels {
+    /// Return the identity element, in cached form.
+    pub const fn identity() -> Self {
+        ProjectiveNiels {
+            y_plus_x: Z25519::from_limbs64([1, 0, 0, 0]),
+            y_minus_x: Z25519::from_limbs64([1, 0, 0, 0]),
+            z2: Z25519::from_limbs64([2, 0, 0, 0]),
+            t2d: Z25519::from_limbs64([0, 0, 0, 0]),
+        }
+    }
+}
+
+impl ConditionallySelectable for ProjectiveNiels {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        ProjectiveNiels {
+            y_plus_x: Z25519::conditional_select(&a.y_plus_x, &b.y_plus_x, choice),
+            y_minus_x: Z25519::conditional_select(&a.y_minus_x, &b.y_minus_x, choice),
+            z2: Z25519::conditional_select(&a.z2, &b.z2, choice),
+            t2d: Z25519::conditional_select(&a.t2d, &b.t2d, choice),
+        }
+    }
+}
+
+/// Negating x swaps Y + X with Y - X, and negates T.
+impl Neg for &ProjectiveNiels {
+    type Output = ProjectiveNiels;
+
+    fn neg(self) -> Self::Output {
+        ProjectiveNiels {
+            y_plus_x: self.y_minus_x,
+            y_minus_x: self.y_plus_x,
+            z2: self.z2,
+            t2d: -self.t2d,
+        }
+    }
+}
+
+impl Neg for ProjectiveNiels {
+    type Output = ProjectiveNiels;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl Point {
+    /// Convert this point into the cached form used for additions.
+    pub fn niels(&self) -> ProjectiveNiels {
+        ProjectiveNiels {
+            y_plus_x: self.y + self.x,
+            y_minus_x: self.y - self.x,
+            z2: self.z * 2,
+            t2d: self.t * D * 2,
+        }
+    }
+}
+
+impl Add<&ProjectiveNiels> for Point {
     type Output = Point;
 
-    fn add(self, other: Point) -> Self::Output {
-        let a = (self.y - self.x) * (other.y - other.x);
-        let b = (self.y + self.x) * (other.y + other.x);
-        let c = self.t * D * other.t * 2;
-        let d = self.z * other.z * 2;
+    fn add(self, other: &ProjectiveNiels) -> Self::Output {
+        // This is the addition routine in section 5.1.4:
+        // https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.4
+        let a = (self.y - self.x) * other.y_minus_x;
+        let b = (self.y + self.x) * other.y_plus_x;
+        let c = self.t * other.t2d;
+        let d = self.z * other.z2;
         let e = b - a;
         let f = d - c;
         let g = d + c;
@@ -184,32 +398,134 @@ impl Add for Point {
     }
 }
 
+impl Sub<&ProjectiveNiels> for Point {
+    type Output = Point;
+
+    fn sub(self, other: &ProjectiveNiels) -> Self::Output {
+        self + &-other
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Self::Output {
+        self + &other.niels()
+    }
+}
+
+/// The width of the windows used by `serial_mul`.
+const WINDOW_SIZE: usize = 4;
+
+/// The multiples point, 2 * point, …, 15 * point, used by `serial_mul`.
+pub(super) type WindowTable = [ProjectiveNiels; (1 << WINDOW_SIZE) - 1];
+
+/// Calculate the table of small multiples of a point, for `mul_with_table`.
+pub(super) fn window_table(point: Point) -> WindowTable {
+    let niels = point.niels();
+    let mut window = [niels; (1 << WINDOW_SIZE) - 1];
+    let mut multiple = point;
+    for entry in window.iter_mut().skip(1) {
+        multiple = multiple + &niels;
+        *entry = multiple.niels();
+    }
+    window
+}
+
+/// Calculate point * scalar, in constant time, given the table for that point.
+///
+/// Sharing a table saves work when multiplying the same point by many scalars.
+pub(super) fn mul_with_table(window: &WindowTable, scalar: &Scalar) -> Point {
+    let mut out = Point::identity();
+    for x in scalar.value.limbs.iter().rev() {
+        for i in (0..64).step_by(WINDOW_SIZE).rev() {
+            out = out.doubled();
+            out = out.doubled();
+            out = out.doubled();
+            out = out.doubled();
+
+            let w = ((x >> i) & ((1 << WINDOW_SIZE) - 1)) as usize;
+            let mut selected = ProjectiveNiels::identity();
+            for (i, p) in window.iter().enumerate() {
+                selected.conditional_assign(p, w.ct_eq(&(i + 1)));
+            }
+            out = out + &selected;
+        }
+    }
+    out
+}
+
+/// Calculate point * scalar, in constant time.
 impl Mul<Scalar> for Point {
     type Output = Point;
 
     fn mul(self, other: Scalar) -> Self::Output {
-        let mut out = Point::identity();
-        const WINDOW_SIZE: usize = 4;
-        let mut window = [Point::identity(); (1 << WINDOW_SIZE) - 1];
-        window[0] = self;
-        for i in 1..window.len() {
-            window[i] = self + window[i - 1];
-        }
-        for x in other.value.limbs.iter().rev() {
-            for i in (0..64).step_by(WINDOW_SIZE).rev() {
-                out = out.doubled();
-                out = out.doubled();
-                out = out.doubled();
-                out = out.doubled();
-
-                let w = ((x >> i) & ((1 << WINDOW_SIZE) - 1)) as usize;
-                let mut selected = Point::identity();
-                for i in 0..window.len() {
-                    selected.conditional_assign(&window[i], w.ct_eq(&(i + 1)));
-                }
-                out = out + selected;
-            }
+        mul_with_table(&window_table(self), &other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use subtle::ConditionallyNegatable;
+
+    #[test]
+    fn test_ct_eq_ignores_representation() {
+        let scaled = Point::from_extended_unchecked(B.x * 7, B.y * 7, B.z * 7, B.t * 7);
+        assert!(bool::from(scaled.ct_eq(&B)));
+        assert!(bool::from((B + B).ct_eq(&B.doubled())));
+        assert!(!bool::from(B.ct_eq(&B.doubled())));
+        assert!(!bool::from(B.ct_eq(&-B)));
+        assert!(bool::from((B + -B).ct_eq(&Point::identity())));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_batch_encode() {
+        let points = [
+            B,
+            B.doubled(),
+            -B,
+            Point::identity(),
+            B * Scalar::from(12345),
+        ];
+        let expected: Vec<[u8; 32]> = points.iter().map(|&p| p.into()).collect();
+        assert_eq!(Point::batch_encode(&points), expected);
+        assert!(Point::batch_encode(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_niels_addition() {
+        let p = B.doubled();
+        assert!(bool::from((p + &B.niels()).ct_eq(&(B + p))));
+        assert!(bool::from((p - &B.niels()).ct_eq(&B)));
+        let identity = ProjectiveNiels::identity();
+        assert!(bool::from((p + &identity).ct_eq(&p)));
+        assert!(bool::from((p + &-p.niels()).ct_eq(&Point::identity())));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_affine_batch() {
+        let points = [B.doubled(), -B, Point::identity()];
+        for (&p, affine) in points.iter().zip(Point::to_affine_batch(&points)) {
+            assert!(bool::from(affine.ct_eq(&AffinePoint::from(p))));
+            assert!(bool::from(Point::from(affine).ct_eq(&p)));
         }
-        out
+        let affine = AffinePoint::from(B);
+        assert!(bool::from(affine.x().ct_eq(&B.x)));
+        assert!(bool::from(affine.y().ct_eq(&B.y)));
+    }
+
+    #[test]
+    fn test_conditional_negation() {
+        let mut p = B;
+        p.conditional_negate(Choice::from(0));
+        assert!(bool::from(p.ct_eq(&B)));
+        p.conditional_negate(Choice::from(1));
+        assert!(bool::from(p.ct_eq(&-B)));
+        let selected = Point::conditional_select(&B, &p, Choice::from(1));
+        assert!(bool::from(selected.ct_eq(&p)));
     }
 }
@@ -0,0 +1,321 @@
+//! This module provides fast arithmetic on polynomials over the scalars, i.e. Z/L.
+//!
+//! Large secret sharing committees need to evaluate, and interpolate, polynomials
+//! at many points. Doing this naively takes quadratic time.
+//!
+//! Over many fields, one would use a number theoretic transform, but this needs
+//! roots of unity of large, smooth, order. Here, L - 1 = 2^2 * 3 * 11 * p1 * p2,
+//! for two large primes p1 and p2, so the largest smooth transform has size 132,
+//! which isn't worth it. Instead, we use subproduct trees, along with Karatsuba
+//! multiplication, and Newton iteration for division, as described in Chapter 10 of
+//! "Modern Computer Algebra", by von zur Gathen and Gerhard.
+// Secret sharing is the main user of this module, and this is all tested here.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use super::scalar::Scalar;
+use alloc::{vec, vec::Vec};
+
+/// Below this size, schoolbook multiplication is faster than Karatsuba.
+const KARATSUBA_THRESHOLD: usize = 32;
+
+fn is_zero(x: &Scalar) -> bool {
+    <[u8; 32]>::from(*x) == [0; 32]
+}
+
+/// A polynomial, with coefficients stored from the lowest degree to the highest.
+///
+/// The representation is normalized, so that the last coefficient is never 0.
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Polynomial {
+    coefficients: Vec<Scalar>,
+}
+
+impl Polynomial {
+    /// Create a polynomial from its coefficients, starting with the constant term.
+    pub fn new(coefficients: Vec<Scalar>) -> Self {
+        let mut out = Polynomial { coefficients };
+        out.normalize();
+        out
+    }
+
+    fn normalize(&mut self) {
+        while self.coefficients.last().is_some_and(is_zero) {
+            self.coefficients.pop();
+        }
+    }
+
+    /// The degree of this polynomial, with the zero polynomial having no degree.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    /// Evaluate this polynomial at a single point, using Horner's method.
+    pub fn evaluate(&self, x: Scalar) -> Scalar {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Scalar::from(0), |acc, &c| acc * x + c)
+    }
+
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let mut out = vec![Scalar::from(0); self.coefficients.len().max(other.coefficients.len())];
+        add_into(&mut out, &self.coefficients);
+        add_into(&mut out, &other.coefficients);
+        Polynomial::new(out)
+    }
+
+    pub fn mul(&self, other: &Polynomial) -> Polynomial {
+        Polynomial::new(mul_slices(&self.coefficients, &other.coefficients))
+    }
+
+    /// Divide this polynomial by a monic polynomial, returning the quotient, and remainder.
+    ///
+    /// This reverses the coefficients, turning the quotient into a power series,
+    /// which we calculate with Newton iteration, in the time of a few multiplications.
+    pub fn div_rem_monic(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        let n = divisor.coefficients.len();
+        assert!(n > 0, "division by zero polynomial");
+        debug_assert!(is_zero(&(divisor.coefficients[n - 1] - Scalar::from(1))));
+        if self.coefficients.len() < n {
+            return (Polynomial::new(Vec::new()), self.clone());
+        }
+        let quotient_len = self.coefficients.len() - n + 1;
+        let rev_divisor: Vec<Scalar> = divisor.coefficients.iter().rev().copied().collect();
+        let rev_self: Vec<Scalar> = self.coefficients.iter().rev().copied().collect();
+        let inverse = inverse_series(&rev_divisor, quotient_len);
+        let mut rev_quotient = mul_slices(&rev_self[..quotient_len], &inverse);
+        rev_quotient.truncate(quotient_len);
+        rev_quotient.reverse();
+        let quotient = Polynomial::new(rev_quotient);
+
+        let product = mul_slices(&quotient.coefficients, &divisor.coefficients);
+        let mut remainder = self.coefficients[..n - 1].to_vec();
+        for (r, &p) in remainder.iter_mut().zip(product.iter()) {
+            *r -= p;
+        }
+        (quotient, Polynomial::new(remainder))
+    }
+}
+
+fn add_into(out: &mut [Scalar], a: &[Scalar]) {
+    for (o, &x) in out.iter_mut().zip(a.iter()) {
+        *o += x;
+    }
+}
+
+fn sub_into(out: &mut [Scalar], a: &[Scalar]) {
+    for (o, &x) in out.iter_mut().zip(a.iter()) {
+        *o -= x;
+    }
+}
+
+fn schoolbook_mul(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    let mut out = vec![Scalar::from(0); a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// Multiply two polynomials, given as slices of coefficients.
+///
+/// The result isn't normalized, and has exactly a.len() + b.len() - 1 coefficients.
+fn mul_slices(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+        return schoolbook_mul(a, b);
+    }
+    // a = a0 + x^m a1, b = b0 + x^m b1, and then
+    // ab = a0 b0 + x^m ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) + x^2m a1 b1
+    let m = a.len().max(b.len()) / 2;
+    let (a0, a1) = a.split_at(m.min(a.len()));
+    let (b0, b1) = b.split_at(m.min(b.len()));
+    let low = mul_slices(a0, b0);
+    let high = mul_slices(a1, b1);
+    let mut a_sum = a0.to_vec();
+    a_sum.resize(a0.len().max(a1.len()), Scalar::from(0));
+    add_into(&mut a_sum, a1);
+    let mut b_sum = b0.to_vec();
+    b_sum.resize(b0.len().max(b1.len()), Scalar::from(0));
+    add_into(&mut b_sum, b1);
+    let mut middle = mul_slices(&a_sum, &b_sum);
+    sub_into(&mut middle, &low);
+    sub_into(&mut middle, &high);
+
+    let mut out = vec![Scalar::from(0); a.len() + b.len() - 1];
+    add_into(&mut out, &low);
+    add_into(&mut out[m..], &middle);
+    add_into(&mut out[2 * m..], &high);
+    out
+}
+
+/// Calculate the first n coefficients of 1 / f, as a power series, where f(0) = 1.
+fn inverse_series(f: &[Scalar], n: usize) -> Vec<Scalar> {
+    // g <- g (2 - f g) doubles the number of correct coefficients each time.
+    let mut g = vec![Scalar::from(1)];
+    let mut len = 1;
+    while len < n {
+        len = (2 * len).min(n);
+        let mut fg = mul_slices(&f[..len.min(f.len())], &g);
+        fg.truncate(len);
+        for c in fg.iter_mut() {
+            *c = -*c;
+        }
+        fg[0] += Scalar::from(2);
+        g = mul_slices(&g, &fg);
+        g.truncate(len);
+    }
+    g
+}
+
+/// A tree of the products of (x - x_i), for a set of points.
+///
+/// Each level holds the products of pairs of nodes from the level below,
+/// with the leaves being (x - x_i), and the root being the product of all of them.
+struct SubproductTree {
+    levels: Vec<Vec<Polynomial>>,
+}
+
+impl SubproductTree {
+    fn new(points: &[Scalar]) -> Self {
+        let leaves: Vec<Polynomial> = points
+            .iter()
+            .map(|&x| Polynomial::new(vec![-x, Scalar::from(1)]))
+            .collect();
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => a.mul(b),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        SubproductTree { levels }
+    }
+
+    /// Evaluate a polynomial at each leaf, by taking remainders down the tree.
+    fn evaluate(&self, p: &Polynomial) -> Vec<Scalar> {
+        let top = self.levels.len() - 1;
+        let mut remainders = vec![p.div_rem_monic(&self.levels[top][0]).1];
+        for level in self.levels[..top].iter().rev() {
+            remainders = level
+                .iter()
+                .enumerate()
+                .map(|(i, node)| remainders[i / 2].div_rem_monic(node).1)
+                .collect();
+        }
+        remainders
+            .iter()
+            .map(|r| r.evaluate(Scalar::from(0)))
+            .collect()
+    }
+}
+
+/// Evaluate a polynomial at many points at once.
+pub fn evaluate_many(p: &Polynomial, points: &[Scalar]) -> Vec<Scalar> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    SubproductTree::new(points).evaluate(p)
+}
+
+/// Find the polynomial of lowest degree taking the given values at the given points.
+///
+/// This returns `None` if the same point appears twice.
+pub fn interpolate(points: &[Scalar], values: &[Scalar]) -> Option<Polynomial> {
+    assert_eq!(points.len(), values.len());
+    if points.is_empty() {
+        return Some(Polynomial::new(Vec::new()));
+    }
+    let tree = SubproductTree::new(points);
+    // With M the product of all (x - x_i), the Lagrange weight of x_i is 1 / M'(x_i)
+    let root = &tree.levels[tree.levels.len() - 1][0];
+    let derivative = Polynomial::new(
+        root.coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| Scalar::from(i as u64) * c)
+            .collect(),
+    );
+    let denominators = tree.evaluate(&derivative);
+    if denominators.iter().any(is_zero) {
+        return None;
+    }
+    let mut combined: Vec<Polynomial> = denominators
+        .iter()
+        .zip(values.iter())
+        .map(|(&d, &y)| Polynomial::new(vec![y * d.inverse()]))
+        .collect();
+    // Each parent combines its children as left * right_product + right * left_product
+    for level in tree.levels[..tree.levels.len() - 1].iter() {
+        combined = combined
+            .chunks(2)
+            .zip(level.chunks(2))
+            .map(|(values, nodes)| match (values, nodes) {
+                ([a, b], [node_a, node_b]) => a.mul(node_b).add(&b.mul(node_a)),
+                ([a], [_]) => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    Some(combined.pop().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example_polynomial(n: u64) -> Polynomial {
+        Polynomial::new((0..n).map(|i| Scalar::from(i * i + 7)).collect())
+    }
+
+    #[test]
+    fn test_karatsuba_matches_schoolbook() {
+        let a: Vec<Scalar> = (0..100).map(|i| Scalar::from(3 * i + 1)).collect();
+        let b: Vec<Scalar> = (0..77).map(|i| Scalar::from(i ^ 0x55)).collect();
+        assert_eq!(mul_slices(&a, &b), schoolbook_mul(&a, &b));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let divisor = Polynomial::new(
+            (0..40)
+                .map(|i| Scalar::from(i + 1))
+                .chain(core::iter::once(Scalar::from(1)))
+                .collect(),
+        );
+        let p = example_polynomial(150);
+        let (q, r) = p.div_rem_monic(&divisor);
+        assert!(r.degree() < divisor.degree());
+        assert_eq!(q.mul(&divisor).add(&r), p);
+    }
+
+    #[test]
+    fn test_evaluate_many() {
+        let p = example_polynomial(70);
+        let points: Vec<Scalar> = (0..100).map(|i| Scalar::from(5 * i + 2)).collect();
+        let expected: Vec<Scalar> = points.iter().map(|&x| p.evaluate(x)).collect();
+        assert_eq!(evaluate_many(&p, &points), expected);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let p = example_polynomial(65);
+        let points: Vec<Scalar> = (1..=65).map(Scalar::from).collect();
+        let values = evaluate_many(&p, &points);
+        assert_eq!(interpolate(&points, &values), Some(p));
+        let mut repeated = points.clone();
+        repeated[10] = repeated[20];
+        assert_eq!(interpolate(&repeated, &values), None);
+    }
+}
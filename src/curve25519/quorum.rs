@@ -0,0 +1,251 @@
+//! This module provides a helper for checking that enough members of a group signed a message.
+//!
+//! Federated and consensus systems often accept a message once some threshold of
+//! known parties have signed it. Checking this by hand is easy to get subtly wrong,
+//! e.g. by counting the same signer twice, or counting keys outside of the group.
+
+use core::convert::TryFrom;
+use std::collections::HashMap;
+
+use rand::{CryptoRng, RngCore};
+
+use super::{
+    batch::{verify_batch, verify_cofactored},
+    point::Point,
+    Error, PublicKey, Signature,
+};
+
+/// The outcome of checking a set of signatures against a quorum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumResult {
+    /// Whether or not at least threshold members signed the message.
+    pub reached: bool,
+    /// The indices, into the group of keys, of each member with a valid signature.
+    ///
+    /// These are sorted, and contain no duplicates.
+    pub signers: Vec<usize>,
+}
+
+/// Verifies that a threshold of members from a fixed group of keys signed a message.
+#[derive(Clone, Debug)]
+pub struct QuorumVerifier {
+    members: HashMap<[u8; 32], usize>,
+    threshold: usize,
+}
+
+impl QuorumVerifier {
+    /// Create a verifier for a group of keys, requiring threshold of them to sign.
+    ///
+    /// If the same key appears multiple times in the group, it still only counts once.
+    ///
+    /// The threshold has to be between 1 and the number of distinct keys. Keys of small
+    /// order are rejected, since the cofactored equation accepts almost any signature for them.
+    pub fn new(public_keys: &[PublicKey], threshold: usize) -> Result<Self, Error> {
+        let mut members = HashMap::with_capacity(public_keys.len());
+        for (i, public) in public_keys.iter().enumerate() {
+            let point = Point::try_from(&public.bytes[..]).map_err(|_| Error::PointNotOnCurve)?;
+            if point.has_small_order() {
+                return Err(Error::WeakPublicKey);
+            }
+            members.entry(public.bytes).or_insert(i);
+        }
+        if threshold == 0 || threshold > members.len() {
+            return Err(Error::InvalidLength);
+        }
+        Ok(QuorumVerifier { members, threshold })
+    }
+
+    /// Check which members of the group signed a message, and whether that's a quorum.
+    ///
+    /// Signatures from keys outside of the group are ignored, and a member
+    /// with several valid signatures only gets counted once. An invalid signature
+    /// from a member doesn't stop another, valid, signature of theirs from counting.
+    ///
+    /// All the signatures are first checked together, as a batch. If that fails,
+    /// we fall back to checking each of them individually, to find out which ones
+    /// are valid. This makes the common case, where every signature is valid, fast.
+    /// Both ways use the cofactored equation of `verify_batch`, so the same signatures
+    /// count either way, even those with components of small order.
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8],
+        signatures: &[(PublicKey, Signature)],
+        rng: &mut R,
+    ) -> QuorumResult {
+        let candidates: Vec<(usize, PublicKey, Signature)> = signatures
+            .iter()
+            .filter_map(|&(public, sig)| Some((*self.members.get(&public.bytes)?, public, sig)))
+            .collect();
+
+        let messages = vec![message; candidates.len()];
+        let keys: Vec<PublicKey> = candidates.iter().map(|&(_, public, _)| public).collect();
+        let sigs: Vec<Signature> = candidates.iter().map(|&(_, _, sig)| sig).collect();
+        let mut signers: Vec<usize> = if verify_batch(&messages, &sigs, &keys, rng) {
+            candidates.iter().map(|&(i, _, _)| i).collect()
+        } else {
+            candidates
+                .iter()
+                .filter(|&&(_, public, sig)| verify_cofactored(message, sig, public))
+                .map(|&(i, _, _)| i)
+                .collect()
+        };
+        // A member signing more than once still only counts once.
+        signers.sort_unstable();
+        signers.dedup();
+
+        QuorumResult {
+            reached: signers.len() >= self.threshold,
+            signers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::convert::{TryFrom, TryInto};
+
+    use rand::rngs::OsRng;
+
+    use super::super::{
+        point::{self, Point},
+        scalar::Scalar,
+        PrivateKey,
+    };
+    use super::*;
+    use crate::sha512;
+
+    fn example_group(n: usize) -> (Vec<PrivateKey>, Vec<PublicKey>) {
+        let privates: Vec<PrivateKey> = (0..n)
+            .map(|i| PrivateKey {
+                bytes: [i as u8 + 1; 32],
+            })
+            .collect();
+        let publics = privates.iter().map(|p| p.derive_public_key()).collect();
+        (privates, publics)
+    }
+
+    #[test]
+    fn test_quorum_reached() {
+        let (privates, publics) = example_group(5);
+        let verifier = QuorumVerifier::new(&publics, 3).unwrap();
+        let message = b"block 1234";
+        let signatures: Vec<_> = [4, 0, 2]
+            .iter()
+            .map(|&i| (publics[i], privates[i].sign(message)))
+            .collect();
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert!(result.reached);
+        assert_eq!(result.signers, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_invalid_duplicate_and_outside_signatures_dont_count() {
+        let (privates, publics) = example_group(5);
+        let verifier = QuorumVerifier::new(&publics, 3).unwrap();
+        let message = b"block 1234";
+        let outsider = PrivateKey { bytes: [0xFF; 32] };
+        let mut bad = privates[3].sign(message);
+        bad.bytes[40] ^= 1;
+        let signatures = vec![
+            (publics[1], privates[1].sign(message)),
+            (publics[1], privates[1].sign(message)),
+            (publics[3], bad),
+            (publics[0], bad),
+            (outsider.derive_public_key(), outsider.sign(message)),
+        ];
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert!(!result.reached);
+        assert_eq!(result.signers, vec![1]);
+        // A valid signature still counts, despite an earlier invalid one
+        let mut signatures = signatures;
+        signatures.push((publics[3], privates[3].sign(message)));
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert_eq!(result.signers, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_rejects_bad_thresholds() {
+        let (_, mut publics) = example_group(3);
+        assert_eq!(
+            QuorumVerifier::new(&publics, 0).unwrap_err(),
+            Error::InvalidLength
+        );
+        assert_eq!(
+            QuorumVerifier::new(&publics, 4).unwrap_err(),
+            Error::InvalidLength
+        );
+        assert!(QuorumVerifier::new(&publics, 3).is_ok());
+        // Duplicates only count once, so there aren't enough keys here
+        publics[2] = publics[1];
+        assert_eq!(
+            QuorumVerifier::new(&publics, 3).unwrap_err(),
+            Error::InvalidLength
+        );
+    }
+
+    #[test]
+    fn test_rejects_small_order_members() {
+        let (privates, mut publics) = example_group(3);
+        let identity = PublicKey {
+            bytes: Point::identity().into(),
+        };
+        publics.push(identity);
+        assert_eq!(
+            QuorumVerifier::new(&publics, 2).unwrap_err(),
+            Error::WeakPublicKey
+        );
+        // With the cofactored equation, this forges a signature for the identity
+        let forged = Signature {
+            bytes: {
+                let mut bytes = [0; 64];
+                bytes[..32].copy_from_slice(&<[u8; 32]>::from(Point::identity()));
+                bytes
+            },
+        };
+        assert!(verify_cofactored(b"block 1234", forged, identity));
+        publics.pop();
+        let verifier = QuorumVerifier::new(&publics, 2).unwrap();
+        let message = b"block 1234";
+        let signatures = vec![(publics[0], privates[0].sign(message)), (identity, forged)];
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert!(!result.reached);
+    }
+
+    /// Sign a message with a nonce commitment R shifted by the point of order 2.
+    ///
+    /// Only the cofactored equation accepts this.
+    fn small_order_signature(private: &PrivateKey, message: &[u8]) -> Signature {
+        let mut order_2 = [0xFF; 32];
+        order_2[0] = 0xEC;
+        order_2[31] = 0x7F;
+        let t = Point::try_from(&order_2[..]).unwrap();
+        let a = Scalar::clamped(sha512::hash(&private.bytes)[..32].try_into().unwrap());
+        let r = Scalar::from(7);
+        let big_r: [u8; 32] = (point::B * r + t).into();
+        let k = PublicKey::challenge(&big_r, point::B * a, &[message]);
+        let mut signature = Signature { bytes: [0; 64] };
+        signature.bytes[..32].copy_from_slice(&big_r);
+        signature.bytes[32..].copy_from_slice(&<[u8; 32]>::from(r + k * a));
+        signature
+    }
+
+    #[test]
+    fn test_small_order_components_count_on_both_paths() {
+        let (privates, publics) = example_group(3);
+        let verifier = QuorumVerifier::new(&publics, 2).unwrap();
+        let message = b"block 1234";
+        let edge = small_order_signature(&privates[2], message);
+        assert!(!publics[2].verify(message, edge));
+        let mut signatures = vec![(publics[0], privates[0].sign(message)), (publics[2], edge)];
+        // The batch passes here
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert_eq!(result.signers, vec![0, 2]);
+        // And fails here, falling back to individual checks
+        let mut bad = privates[1].sign(message);
+        bad.bytes[40] ^= 1;
+        signatures.push((publics[1], bad));
+        let result = verifier.verify(message, &signatures, &mut OsRng);
+        assert_eq!(result.signers, vec![0, 2]);
+        assert!(result.reached);
+    }
+}
@@ -0,0 +1,356 @@
+//! This module implements ring signatures over ristretto255, following the SAG, and bLSAG,
+//! schemes of "Zero to Monero", Sections 3.2 and 3.3:
+//! https://www.getmonero.org/library/Zero-to-Monero-2-0-0.pdf
+//!
+//! A ring signature shows that the signer holds the secret key behind one of the public
+//! keys in a ring, without revealing which one. Linkable signatures also carry a key
+//! image, x * H(P), which is the same for every signature made by the same key, regardless
+//! of the ring, so that signing twice can be detected, as with double spending.
+//!
+//! Signatures grow linearly with the size of the ring, and so does the time to make
+//! and verify them.
+
+use alloc::{vec, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::sha512::Sha512;
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{
+    ristretto::{Element, ELEMENT_SIZE, GENERATOR},
+    scalar::Scalar,
+    Error,
+};
+
+/// The size of each public key in a ring.
+pub const RING_KEY_SIZE: usize = ELEMENT_SIZE;
+
+/// The size of the key image attached to linkable signatures.
+pub const KEY_IMAGE_SIZE: usize = ELEMENT_SIZE;
+
+const CONTEXT: &[u8] = b"ring-ristretto255-SHA512";
+
+const KEY_IMAGE_DST: &[u8] = b"ring-ristretto255-SHA512-key-image";
+
+/// Hash a public key to an element, H_p(P), whose discrete log nobody knows.
+fn hash_key(public: &[u8; RING_KEY_SIZE]) -> Element {
+    Element::hash(public, KEY_IMAGE_DST)
+}
+
+/// The ring, decoded, along with the hash of everything the challenges commit to.
+struct Ring {
+    keys: Vec<Element>,
+    hashed: Vec<Element>,
+    prefix: Sha512,
+}
+
+impl Ring {
+    fn new(
+        ring: &[[u8; RING_KEY_SIZE]],
+        key_image: Option<&[u8; KEY_IMAGE_SIZE]>,
+        message: &[u8],
+    ) -> Result<Self, Error> {
+        if ring.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        let keys = ring
+            .iter()
+            .map(|k| Element::try_from_nonidentity(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut prefix = Sha512::new();
+        prefix.update(CONTEXT);
+        prefix.update(&(ring.len() as u64).to_le_bytes());
+        for key in ring {
+            prefix.update(key);
+        }
+        let hashed = match key_image {
+            Some(image) => {
+                prefix.update(&[1]);
+                prefix.update(image);
+                ring.iter().map(hash_key).collect()
+            }
+            None => {
+                prefix.update(&[0]);
+                Vec::new()
+            }
+        };
+        prefix.update(message);
+        Ok(Ring {
+            keys,
+            hashed,
+            prefix,
+        })
+    }
+
+    /// Calculate the next challenge, c_{i + 1} = H(prefix || L_i || R_i).
+    fn challenge(&self, l: Element, r: Option<Element>) -> Scalar {
+        let mut hasher = self.prefix.clone();
+        hasher.update(&l.encode());
+        if let Some(r) = r {
+            hasher.update(&r.encode());
+        }
+        Scalar::from_hash(hasher)
+    }
+
+    /// Calculate L_i = r_i * G + c_i * P_i, and R_i = r_i * H_p(P_i) + c_i * I, if linkable.
+    fn round(&self, i: usize, r: Scalar, c: Scalar, image: Option<Element>) -> Scalar {
+        let l = Element::vartime_double_mul(r, GENERATOR, c, self.keys[i]);
+        let r = image.map(|image| Element::vartime_double_mul(r, self.hashed[i], c, image));
+        self.challenge(l, r)
+    }
+}
+
+/// A ring signature, containing the first challenge, followed by one response per key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingSignature {
+    pub bytes: Vec<u8>,
+}
+
+impl From<Vec<u8>> for RingSignature {
+    fn from(bytes: Vec<u8>) -> Self {
+        RingSignature { bytes }
+    }
+}
+
+/// A ring signature, along with the key image of its signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkableRingSignature {
+    pub key_image: [u8; KEY_IMAGE_SIZE],
+    pub signature: RingSignature,
+}
+
+impl LinkableRingSignature {
+    /// Check whether two signatures were made with the same key.
+    ///
+    /// This is only meaningful for signatures which have been verified.
+    pub fn is_linked(&self, other: &LinkableRingSignature) -> bool {
+        self.key_image == other.key_image
+    }
+}
+
+/// A key which can sign on behalf of any ring containing its public key.
+pub struct RingSigner {
+    secret: Scalar,
+    public: [u8; RING_KEY_SIZE],
+}
+
+impl RingSigner {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let secret = Scalar::random(rng);
+        RingSigner {
+            secret,
+            public: (GENERATOR * secret).encode(),
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; RING_KEY_SIZE] {
+        self.public
+    }
+
+    /// The key image, x * H_p(P), attached to every linkable signature made by this key.
+    pub fn key_image(&self) -> [u8; KEY_IMAGE_SIZE] {
+        (hash_key(&self.public) * self.secret).encode()
+    }
+
+    fn sign_inner<R: RngCore + CryptoRng>(
+        &self,
+        ring: &[[u8; RING_KEY_SIZE]],
+        message: &[u8],
+        key_image: Option<&[u8; KEY_IMAGE_SIZE]>,
+        rng: &mut R,
+    ) -> Result<RingSignature, Error> {
+        let index = ring
+            .iter()
+            .position(|k| *k == self.public)
+            .ok_or(Error::InvalidEncoding)?;
+        let ring_data = Ring::new(ring, key_image, message)?;
+        let image = key_image
+            .map(|image| Element::try_from_nonidentity(image))
+            .transpose()?;
+        let n = ring.len();
+
+        let alpha = Zeroizing::new(Scalar::random(rng));
+        let mut challenges = vec![Scalar::from(0); n];
+        let mut responses = vec![Scalar::from(0); n];
+        challenges[(index + 1) % n] = ring_data.challenge(
+            GENERATOR * *alpha,
+            image.map(|_| ring_data.hashed[index] * *alpha),
+        );
+        for j in 1..n {
+            let i = (index + j) % n;
+            responses[i] = Scalar::random(rng);
+            challenges[(i + 1) % n] = ring_data.round(i, responses[i], challenges[i], image);
+        }
+        responses[index] = *alpha - challenges[index] * self.secret;
+
+        let mut bytes = Vec::with_capacity((n + 1) * 32);
+        bytes.extend_from_slice(&<[u8; 32]>::from(challenges[0]));
+        for response in responses {
+            bytes.extend_from_slice(&<[u8; 32]>::from(response));
+        }
+        Ok(RingSignature { bytes })
+    }
+
+    /// Sign a message on behalf of a ring, which has to contain our public key.
+    ///
+    /// Nothing links this signature to other signatures made with the same key.
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        ring: &[[u8; RING_KEY_SIZE]],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<RingSignature, Error> {
+        self.sign_inner(ring, message, None, rng)
+    }
+
+    /// Sign a message on behalf of a ring, attaching our key image.
+    pub fn sign_linkable<R: RngCore + CryptoRng>(
+        &self,
+        ring: &[[u8; RING_KEY_SIZE]],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<LinkableRingSignature, Error> {
+        let key_image = self.key_image();
+        let signature = self.sign_inner(ring, message, Some(&key_image), rng)?;
+        Ok(LinkableRingSignature {
+            key_image,
+            signature,
+        })
+    }
+}
+
+/// This never prints the secret key.
+impl fmt::Debug for RingSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingSigner")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for RingSigner {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+fn verify_inner(
+    ring: &[[u8; RING_KEY_SIZE]],
+    message: &[u8],
+    key_image: Option<&[u8; KEY_IMAGE_SIZE]>,
+    signature: &RingSignature,
+) -> Result<(), Error> {
+    if signature.bytes.len() != (ring.len() + 1) * 32 {
+        return Err(Error::InvalidLength);
+    }
+    let ring_data = Ring::new(ring, key_image, message)?;
+    let image = key_image
+        .map(|image| Element::try_from_nonidentity(image))
+        .transpose()?;
+    let scalars = signature
+        .bytes
+        .chunks(32)
+        .map(|chunk| Scalar::try_from(chunk).map_err(|_| Error::NonCanonicalScalar))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut c = scalars[0];
+    for (i, &r) in scalars[1..].iter().enumerate() {
+        c = ring_data.round(i, r, c, image);
+    }
+    // The ring has to close, with the last challenge matching the first.
+    if !bool::from(<[u8; 32]>::from(c).ct_eq(&signature.bytes[..32])) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// Verify that a message was signed by the holder of one of the keys in a ring.
+pub fn ring_verify(
+    ring: &[[u8; RING_KEY_SIZE]],
+    message: &[u8],
+    signature: &RingSignature,
+) -> Result<(), Error> {
+    verify_inner(ring, message, None, signature)
+}
+
+/// Verify a linkable signature, including its key image.
+pub fn ring_verify_linkable(
+    ring: &[[u8; RING_KEY_SIZE]],
+    message: &[u8],
+    signature: &LinkableRingSignature,
+) -> Result<(), Error> {
+    verify_inner(
+        ring,
+        message,
+        Some(&signature.key_image),
+        &signature.signature,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    fn make_ring(n: usize) -> (Vec<RingSigner>, Vec<[u8; RING_KEY_SIZE]>) {
+        let signers: Vec<RingSigner> = (0..n).map(|_| RingSigner::generate(&mut OsRng)).collect();
+        let ring = signers.iter().map(|s| s.public_key()).collect();
+        (signers, ring)
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (signers, ring) = make_ring(5);
+        for signer in &signers {
+            let signature = signer.sign(&ring, b"message", &mut OsRng).unwrap();
+            assert_eq!(signature.bytes.len(), 6 * 32);
+            assert!(ring_verify(&ring, b"message", &signature).is_ok());
+            assert_eq!(
+                ring_verify(&ring, b"other", &signature),
+                Err(Error::SignatureInvalid)
+            );
+            assert!(ring_verify(&ring[1..], b"message", &signature).is_err());
+        }
+        let (alone, single) = make_ring(1);
+        let signature = alone[0].sign(&single, b"message", &mut OsRng).unwrap();
+        assert!(ring_verify(&single, b"message", &signature).is_ok());
+        let outsider = RingSigner::generate(&mut OsRng);
+        assert!(outsider.sign(&ring, b"message", &mut OsRng).is_err());
+    }
+
+    #[test]
+    fn test_linkable() {
+        let (signers, ring) = make_ring(4);
+        let (_, other_ring) = make_ring(3);
+        let mut other_ring = other_ring;
+        other_ring.push(signers[2].public_key());
+
+        let first = signers[2].sign_linkable(&ring, b"one", &mut OsRng).unwrap();
+        let second = signers[2]
+            .sign_linkable(&other_ring, b"two", &mut OsRng)
+            .unwrap();
+        let third = signers[1].sign_linkable(&ring, b"one", &mut OsRng).unwrap();
+        assert!(ring_verify_linkable(&ring, b"one", &first).is_ok());
+        assert!(ring_verify_linkable(&other_ring, b"two", &second).is_ok());
+        assert!(ring_verify_linkable(&ring, b"one", &third).is_ok());
+        assert!(first.is_linked(&second));
+        assert!(!first.is_linked(&third));
+
+        // Swapping in another key image breaks the signature.
+        let mut forged = first.clone();
+        forged.key_image = third.key_image;
+        assert_eq!(
+            ring_verify_linkable(&ring, b"one", &forged),
+            Err(Error::SignatureInvalid)
+        );
+        // Linkable, and plain signatures, can't be mixed up.
+        assert!(ring_verify(&ring, b"one", &first.signature).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! This module implements the ristretto255 group, as described in RFC 9496:
+//! https://datatracker.ietf.org/doc/html/rfc9496
+//!
+//! Ristretto builds a group of prime order out of our curve, by identifying points
+//! differing by a point of small order. Every element has a single canonical encoding,
+//! which avoids having to think about the cofactor in protocols built on top.
+//!
+//! Elements are represented by any point in their class, which is why equality
+//! can't just compare coordinates.
+
+use core::{
+    convert::TryFrom,
+    ops::{Add, Mul},
+};
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::sha512::expand_message_xmd;
+use crate::uint::U256;
+
+use super::{
+    error::Error,
+    field::{SQRT_M1, Z25519},
+    point::{self, Point, D},
+    scalar::Scalar,
+};
+
+/// The size of an encoded element.
+pub const ELEMENT_SIZE: usize = 32;
+
+const SQRT_AD_MINUS_ONE: Z25519 = Z25519::from_limbs64([
+    0x7e97f6a0497b2e1b,
+    0xaf9d8e0c1b7854bd,
+    0x0f3cfcc931f5d1fd,
+    0x376931bf2b8348ac,
+]);
+
+const INVSQRT_A_MINUS_D: Z25519 = Z25519::from_limbs64([
+    0x99c8fdaa805d40ea,
+    0x9d2f16175a4172be,
+    0x16c27b91fe01d840,
+    0x786c8905cfaffca2,
+]);
+
+const ONE_MINUS_D_SQ: Z25519 = Z25519::from_limbs64([
+    0xe27c09c1945fc176,
+    0x2c81a138cd5e350f,
+    0x9994abddbe70dfe4,
+    0x029072a8b2b3e0d7,
+]);
+
+const D_MINUS_ONE_SQ: Z25519 = Z25519::from_limbs64([
+    0x31ad5aaa44ed4d20,
+    0xd29e4a2cb01e1999,
+    0x4cdcd32f529b4eeb,
+    0x5968b37af66c2241,
+]);
+
+/// An element of the ristretto255 group.
+#[derive(Clone, Copy, Debug)]
+pub struct Element(Point);
+
+/// The canonical generator, which is the same as the Ed25519 basepoint.
+pub const GENERATOR: Element = Element(point::B);
+
+impl Element {
+    pub fn identity() -> Self {
+        Element(Point::identity())
+    }
+
+    /// The map from field elements to the group, from Section 4.3.4.
+    fn map(bytes: &[u8; 32]) -> Self {
+        let t = Z25519::from(U256::from(*bytes).limbs);
+        let one = Z25519::from(1);
+        let r = SQRT_M1 * t.squared();
+        let u = (r + one) * ONE_MINUS_D_SQ;
+        let v = (-one - r * D) * (r + D);
+        let (was_square, s) = Z25519::sqrt_ratio_m1(u, v);
+        let s_prime = -(s * t).abs();
+        let s = Z25519::conditional_select(&s_prime, &s, was_square);
+        let c = Z25519::conditional_select(&r, &-one, was_square);
+        let n = c * (r - one) * D_MINUS_ONE_SQ - v;
+        let w0 = (s * v) * 2;
+        let w1 = n * SQRT_AD_MINUS_ONE;
+        let w2 = one - s.squared();
+        let w3 = one + s.squared();
+        Element(Point::from_extended_unchecked(
+            w0 * w3,
+            w2 * w1,
+            w1 * w3,
+            w0 * w2,
+        ))
+    }
+
+    /// Map 64 uniformly random bytes to an element, following Section 4.3.4.
+    pub fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        let mut lo = [0; 32];
+        let mut hi = [0; 32];
+        lo.copy_from_slice(&bytes[..32]);
+        hi.copy_from_slice(&bytes[32..]);
+        Self::map(&lo) + Self::map(&hi)
+    }
+
+    /// Hash a message to an element, as in hash_to_ristretto255 from RFC 9380.
+    pub fn hash(message: &[u8], dst: &[u8]) -> Self {
+        let mut uniform = [0; 64];
+        expand_message_xmd(message, dst, &mut uniform);
+        Self::from_uniform_bytes(&uniform)
+    }
+
+    pub fn is_identity(&self) -> Choice {
+        self.ct_eq(&Self::identity())
+    }
+
+    /// Encode this element, as in `From<Element> for [u8; ELEMENT_SIZE]`.
+    pub(super) fn encode(self) -> [u8; ELEMENT_SIZE] {
+        self.into()
+    }
+
+    /// Decode an element, rejecting the identity, which protocols never accept as
+    /// a key, or a commitment, since it carries no secret.
+    pub(super) fn try_from_nonidentity(bytes: &[u8]) -> Result<Element, Error> {
+        let element = Element::try_from(bytes)?;
+        if bool::from(element.is_identity()) {
+            return Err(Error::WeakPublicKey);
+        }
+        Ok(element)
+    }
+
+    /// Calculate a * A + b * B, in variable time.
+    pub fn vartime_double_mul(a: Scalar, big_a: Element, b: Scalar, big_b: Element) -> Element {
+        Element(Point::vartime_multiscalar_mul(&[a, b], &[big_a.0, big_b.0]))
+    }
+}
+
+impl ConstantTimeEq for Element {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // The representatives don't have to be the same, only in the same class.
+        let (x1, y1, _, _) = self.0.extended();
+        let (x2, y2, _, _) = other.0.extended();
+        (x1 * y2).ct_eq(&(y1 * x2)) | (y1 * y2).ct_eq(&(x1 * x2))
+    }
+}
+
+/// Decode an element, following Section 4.3.1.
+impl<'a> TryFrom<&'a [u8]> for Element {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() != ELEMENT_SIZE {
+            return Err(Error::InvalidLength);
+        }
+        let s = Z25519::try_from(value).map_err(|_| Error::PointNotOnCurve)?;
+        let one = Z25519::from(1);
+        let ss = s.squared();
+        let u1 = one - ss;
+        let u2 = one + ss;
+        let u2_sqr = u2.squared();
+        let v = -(D * u1.squared()) - u2_sqr;
+        let (was_square, invsqrt) = Z25519::sqrt_ratio_m1(one, v * u2_sqr);
+        let den_x = invsqrt * u2;
+        let den_y = invsqrt * den_x * v;
+        let x = (s * den_x * 2).abs();
+        let y = u1 * den_y;
+        let t = x * y;
+        let invalid = s.is_negative() | !was_square | t.is_negative() | y.is_zero();
+        if bool::from(invalid) {
+            return Err(Error::PointNotOnCurve);
+        }
+        Ok(Element(Point::from_extended_unchecked(x, y, one, t)))
+    }
+}
+
+/// Encode an element, following Section 4.3.2.
+impl From<Element> for [u8; ELEMENT_SIZE] {
+    fn from(element: Element) -> Self {
+        let (x0, y0, z0, t0) = element.0.extended();
+        let u1 = (z0 + y0) * (z0 - y0);
+        let u2 = x0 * y0;
+        let (_, invsqrt) = Z25519::sqrt_ratio_m1(Z25519::from(1), u1 * u2.squared());
+        let den1 = invsqrt * u1;
+        let den2 = invsqrt * u2;
+        let z_inv = den1 * den2 * t0;
+        let ix0 = x0 * SQRT_M1;
+        let iy0 = y0 * SQRT_M1;
+        let enchanted_denominator = den1 * INVSQRT_A_MINUS_D;
+        let rotate = (t0 * z_inv).is_negative();
+        let x = Z25519::conditional_select(&x0, &iy0, rotate);
+        let y = Z25519::conditional_select(&y0, &ix0, rotate);
+        let den_inv = Z25519::conditional_select(&den2, &enchanted_denominator, rotate);
+        let y = Z25519::conditional_select(&y, &-y, (x * z_inv).is_negative());
+        (den_inv * (z0 - y)).abs().into()
+    }
+}
+
+impl Add for Element {
+    type Output = Element;
+
+    fn add(self, other: Element) -> Self::Output {
+        Element(self.0 + other.0)
+    }
+}
+
+impl Mul<Scalar> for Element {
+    type Output = Element;
+
+    fn mul(self, other: Scalar) -> Self::Output {
+        Element(self.0 * other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generator_multiples() {
+        // From Appendix A.1
+        let expected = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+            "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919",
+            "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259",
+            "da80862773358b466ffadfe0b3293ab3d9fd53c5ea6c955358f568322daf6a57",
+        ];
+        let mut element = Element::identity();
+        for encoded in expected.iter() {
+            let bytes: [u8; ELEMENT_SIZE] = element.into();
+            assert_eq!(hex::encode(bytes), *encoded);
+            let decoded = Element::try_from(&bytes[..]).unwrap();
+            assert!(bool::from(decoded.ct_eq(&element)));
+            element = element + GENERATOR;
+        }
+        // Adding a point of small order doesn't change the element.
+        let torsion = Point::try_from(&[0; 32][..]).unwrap();
+        let moved = Element(GENERATOR.0 + torsion);
+        assert!(bool::from(moved.ct_eq(&GENERATOR)));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        // From Appendix A.2, non canonical, negative, and non square encodings
+        for encoded in [
+            "00ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "26948d35ca62e643e26a83177332e6b6afeb9d08e4268b650f1f5bbd8d81d371",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ]
+        .iter()
+        {
+            let bytes = hex::decode(encoded).unwrap();
+            assert!(Element::try_from(&bytes[..]).is_err(), "{}", encoded);
+        }
+    }
+
+    #[test]
+    fn test_from_uniform_bytes() {
+        // From Appendix A.3
+        let mut input = [0; 64];
+        hex::decode_to_slice("5d1be09e3d0c82fc538112490e35701979d99e06ca3e2b5b54bffe8b4dc772c14d98b696a1bbfb5ca32c436cc61c16563790306c79eaca7705668b47dffe5bb6", &mut input).unwrap();
+        let output: [u8; ELEMENT_SIZE] = Element::from_uniform_bytes(&input).into();
+        assert_eq!(
+            hex::encode(output),
+            "3066f82a1a747d45120d1740f14358531a8f04bbffe6a819f86dfe50f44a0a46"
+        );
+    }
+}
@@ -1,16 +1,31 @@
-use std::{
+use core::{
     convert::{TryFrom, TryInto},
-    ops::{Add, AddAssign, Mul, MulAssign, Neg},
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
+use rand::{CryptoRng, RngCore};
 use subtle::{ConditionallySelectable, ConstantTimeEq};
 
-use super::{
-    arithmetic::{U256, U512},
-    error::SignatureError,
+use crate::{
+    sha512::Sha512,
+    shake::Shake256,
+    uint::{U256, U512},
+    zeroize::Zeroizing,
 };
 
-const L: U256 = U256 {
+use super::error::SignatureError;
+
+#[cfg(any(feature = "u32_backend", target_pointer_width = "32"))]
+mod scalar29;
+#[cfg(any(feature = "u32_backend", target_pointer_width = "32"))]
+use scalar29 as montgomery;
+
+#[cfg(not(any(feature = "u32_backend", target_pointer_width = "32")))]
+mod scalar52;
+#[cfg(not(any(feature = "u32_backend", target_pointer_width = "32")))]
+use scalar52 as montgomery;
+
+pub const L: U256 = U256 {
     limbs: [
         0x5812631a5cf5d3ed,
         0x14def9dea2f79cd6,
@@ -37,6 +52,28 @@ const R: U256 = U256 {
     ],
 };
 
+/// A hash function producing at least 64 bytes, which can be reduced into a scalar.
+///
+/// Implementing this for another hash lets it be used with `Scalar::from_hash`.
+pub trait WideDigest {
+    /// Finish hashing, returning 64 bytes of output.
+    fn finalize_wide(self) -> [u8; 64];
+}
+
+impl WideDigest for Sha512 {
+    fn finalize_wide(self) -> [u8; 64] {
+        self.finalize()
+    }
+}
+
+impl WideDigest for Shake256 {
+    fn finalize_wide(self) -> [u8; 64] {
+        let mut out = [0; 64];
+        self.finalize().read(&mut out);
+        out
+    }
+}
+
 /// Represents a scalar in Z/(L) the order of our curve group.
 ///
 /// The operations in this ring are defined through arithmetic modulo
@@ -55,15 +92,58 @@ impl Scalar {
     /// This will apply a standard clamping procedure to the bytes, as described
     /// in Section 5.1.5:
     /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.5
-    pub fn clamped(mut bytes: [u8; 32]) -> Scalar {
+    pub const fn clamped(mut bytes: [u8; 32]) -> Scalar {
         bytes[0] &= 248;
         bytes[31] &= 127;
         bytes[31] |= 64;
-        let mut value = U256::from(0);
-        for (i, chunk) in bytes.chunks_exact(8).enumerate() {
-            value.limbs[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+        Scalar {
+            value: U256::from_le_bytes(bytes),
+        }
+    }
+
+    /// Creates a scalar from its 32 byte little endian encoding, if it's reduced modulo L.
+    ///
+    /// Being a const function, this can define scalar constants, by matching on the
+    /// result, and panicking if it's `None`, which fails at compile time.
+    ///
+    /// This method is not constant-time.
+    pub const fn from_canonical_bytes(bytes: [u8; 32]) -> Option<Scalar> {
+        let value = U256::from_le_bytes(bytes);
+        if value.geq(L) {
+            return None;
+        }
+        Some(Scalar { value })
+    }
+
+    /// Finish a hash, and reduce its 64 bytes of output modulo L.
+    ///
+    /// This is how challenges, and deterministic nonces, are derived, and the
+    /// reduction introduces a negligible bias, since 512 bits is far more than log(L).
+    pub fn from_hash<D: WideDigest>(hasher: D) -> Scalar {
+        Scalar::from(hasher.finalize_wide())
+    }
+
+    /// Hash a domain separator, a tag, and some parts, with SHA-512, into a scalar.
+    ///
+    /// Protocols built on this curve use this to derive their challenges, and nonces.
+    /// Each input is preceded by its length, as 8 little endian bytes, so that splitting
+    /// the same bytes into parts differently gives a different hash.
+    #[cfg(all(feature = "alloc", feature = "hazmat"))]
+    pub(super) fn from_tagged_hash(domain: &[u8], tag: &[u8], parts: &[&[u8]]) -> Scalar {
+        let mut hasher = Sha512::new();
+        for input in [domain, tag].iter().chain(parts) {
+            hasher.update(&(input.len() as u64).to_le_bytes());
+            hasher.update(input);
         }
-        Scalar { value }
+        Scalar::from_hash(hasher)
+    }
+
+    /// Generate a uniformly random scalar, by reducing 64 random bytes.
+    #[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+    pub(super) fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+        let mut bytes = Zeroizing::new([0; 64]);
+        rng.fill_bytes(&mut *bytes);
+        Scalar::from(*bytes)
     }
 
     fn reduce_after_addition(&mut self) {
@@ -72,17 +152,44 @@ impl Scalar {
         self.conditional_assign(&l_removed, borrow.ct_eq(&0));
     }
 
-    fn reduce_barret(large: U512) -> Self {
+    /// Estimate the quotient of a large number by L, as part of Barrett reduction.
+    ///
+    /// This estimate is either exact, or one less than the real quotient.
+    pub fn barret_quotient(large: U512) -> U256 {
         let (hi, lo) = large * R;
-        let q = U256 {
+        Self::shift_quotient(hi, lo)
+    }
+
+    /// Divide the product of a large number, and R, by 2^570, giving the quotient estimate.
+    #[inline]
+    fn shift_quotient(hi: U256, lo: U512) -> U256 {
+        U256 {
             limbs: [
                 (hi.limbs[0] << 6) | (lo.limbs[7] >> 58),
                 (hi.limbs[1] << 6) | (hi.limbs[0] >> 58),
                 (hi.limbs[2] << 6) | (hi.limbs[1] >> 58),
                 (hi.limbs[3] << 6) | (hi.limbs[2] >> 58),
             ],
-        };
-        let to_subtract = q * L;
+        }
+    }
+
+    // The 32 bit backend, and the montgomery_scalar feature, only use Montgomery reduction
+    #[cfg_attr(
+        any(
+            feature = "u32_backend",
+            target_pointer_width = "32",
+            feature = "montgomery_scalar"
+        ),
+        allow(dead_code)
+    )]
+    fn reduce_barret(large: U512) -> Self {
+        let q = Self::barret_quotient(large);
+        Self::subtract_quotient(large, q * L)
+    }
+
+    /// Finish Barrett reduction, given the quotient estimate multiplied by L.
+    #[inline]
+    fn subtract_quotient(large: U512, to_subtract: U512) -> Self {
         let mut scalar = Scalar {
             value: large.lo() - to_subtract.lo(),
         };
@@ -91,6 +198,38 @@ impl Scalar {
     }
 }
 
+// Checking for support once per operation, rather than once per 256 bit multiplication,
+// lets the accelerated multiplications get inlined into the rest of the reduction.
+#[cfg(all(
+    feature = "adx",
+    target_arch = "x86_64",
+    not(any(feature = "u32_backend", feature = "montgomery_scalar"))
+))]
+impl Scalar {
+    /// The same as `reduce_barret`, but multiplying with BMI2, and ADX.
+    #[target_feature(enable = "bmi2,adx")]
+    unsafe fn reduce_barret_adx(large: U512) -> Self {
+        let product: [u64; 12] = crate::uint::adx::mul(&large.limbs, &R.limbs);
+        let mut lo = U512 { limbs: [0; 8] };
+        let mut hi = U256 { limbs: [0; 4] };
+        lo.limbs.copy_from_slice(&product[..8]);
+        hi.limbs.copy_from_slice(&product[8..]);
+        let q = Self::shift_quotient(hi, lo);
+        let to_subtract = U512 {
+            limbs: crate::uint::adx::mul(&q.limbs, &L.limbs),
+        };
+        Self::subtract_quotient(large, to_subtract)
+    }
+
+    #[target_feature(enable = "bmi2,adx")]
+    unsafe fn mul_adx(a: U256, b: U256) -> Self {
+        let large = U512 {
+            limbs: crate::uint::adx::mul(&a.limbs, &b.limbs),
+        };
+        Self::reduce_barret_adx(large)
+    }
+}
+
 impl From<u64> for Scalar {
     fn from(x: u64) -> Self {
         Scalar {
@@ -99,8 +238,18 @@ impl From<u64> for Scalar {
     }
 }
 
-impl From<[u8; 64]> for Scalar {
-    fn from(mut bytes: [u8; 64]) -> Self {
+impl crate::zeroize::Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        crate::zeroize::wipe(&mut self.value.limbs);
+    }
+}
+
+impl Scalar {
+    /// Convert 64 bytes into a 512 bit number, partially reduced modulo L.
+    ///
+    /// The top byte gets folded in using 2⁵⁰⁴ mod L, so that the result is
+    /// small enough for Barrett reduction.
+    pub fn wide_input(mut bytes: [u8; 64]) -> U512 {
         let hi = u64::from(bytes[63]);
         bytes[63] = 0;
         let mut lo = U512 { limbs: [0; 8] };
@@ -120,13 +269,43 @@ impl From<[u8; 64]> for Scalar {
                 0,
             ],
         };
-        Scalar::reduce_barret(lo + hi_reduced)
+        lo + hi_reduced
     }
 }
 
-impl Into<[u8; 32]> for Scalar {
-    fn into(self) -> [u8; 32] {
-        self.value.into()
+impl From<[u8; 64]> for Scalar {
+    fn from(bytes: [u8; 64]) -> Self {
+        #[cfg(any(
+            feature = "u32_backend",
+            target_pointer_width = "32",
+            feature = "montgomery_scalar"
+        ))]
+        {
+            Scalar {
+                value: montgomery::from_bytes_wide(bytes),
+            }
+        }
+        #[cfg(not(any(
+            feature = "u32_backend",
+            target_pointer_width = "32",
+            feature = "montgomery_scalar"
+        )))]
+        {
+            #[cfg(all(feature = "adx", target_arch = "x86_64"))]
+            {
+                if crate::cpu::enabled().adx {
+                    // Safe, since we've just checked that the CPU supports this
+                    return unsafe { Scalar::reduce_barret_adx(Scalar::wide_input(bytes)) };
+                }
+            }
+            Scalar::reduce_barret(Scalar::wide_input(bytes))
+        }
+    }
+}
+
+impl From<Scalar> for [u8; 32] {
+    fn from(x: Scalar) -> [u8; 32] {
+        x.value.into()
     }
 }
 
@@ -137,12 +316,8 @@ impl<'a> TryFrom<&'a [u8]> for Scalar {
         if value.len() < 32 {
             return Err(SignatureError::InvalidScalar);
         }
-        let value_bytes: [u8; 32] = value[..32].try_into().unwrap();
-        let value = U256::from(value_bytes);
-        if value.geq(L) {
-            return Err(SignatureError::InvalidScalar);
-        }
-        Ok(Scalar { value })
+        Self::from_canonical_bytes(value[..32].try_into().unwrap())
+            .ok_or(SignatureError::InvalidScalar)
     }
 }
 
@@ -183,10 +358,48 @@ impl Add for Scalar {
     }
 }
 
+impl SubAssign for Scalar {
+    fn sub_assign(&mut self, other: Self) {
+        *self += -other;
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self::Output {
+        self -= other;
+        self
+    }
+}
+
 impl MulAssign for Scalar {
     fn mul_assign(&mut self, other: Self) {
-        let large = self.value * other.value;
-        *self = Scalar::reduce_barret(large);
+        #[cfg(any(
+            feature = "u32_backend",
+            target_pointer_width = "32",
+            feature = "montgomery_scalar"
+        ))]
+        {
+            self.value = montgomery::mul(&self.value, &other.value);
+        }
+        #[cfg(not(any(
+            feature = "u32_backend",
+            target_pointer_width = "32",
+            feature = "montgomery_scalar"
+        )))]
+        {
+            #[cfg(all(feature = "adx", target_arch = "x86_64"))]
+            {
+                if crate::cpu::enabled().adx {
+                    // Safe, since we've just checked that the CPU supports this
+                    *self = unsafe { Scalar::mul_adx(self.value, other.value) };
+                    return;
+                }
+            }
+            let large = self.value * other.value;
+            *self = Scalar::reduce_barret(large);
+        }
     }
 }
 
@@ -199,13 +412,102 @@ impl Mul for Scalar {
     }
 }
 
+impl Scalar {
+    /// Calculate the multiplicative inverse of this scalar, or 0, if this scalar is 0.
+    ///
+    /// This uses Fermat's little theorem, raising the scalar to L - 2. Since
+    /// the exponent is public, this takes the same time for every scalar.
+    ///
+    /// The exponentiation happens in the Montgomery domain, to avoid converting
+    /// in and out for each of the hundreds of multiplications.
+    pub fn inverse(&self) -> Scalar {
+        let mut exponent = L;
+        exponent.limbs[0] -= 2;
+        let x = self.to_montgomery();
+        let mut out = Scalar::from(1).to_montgomery();
+        for &limb in exponent.limbs.iter().rev() {
+            for i in (0..64).rev() {
+                out = out.square();
+                if (limb >> i) & 1 == 1 {
+                    out *= x;
+                }
+            }
+        }
+        out.to_scalar()
+    }
+
+    /// Move this scalar into the Montgomery domain.
+    pub fn to_montgomery(self) -> MontgomeryScalar {
+        MontgomeryScalar {
+            value: montgomery::to_montgomery(&self.value),
+        }
+    }
+}
+
+/// Represents a scalar in the Montgomery domain, i.e. x * R mod L.
+///
+/// R is 2^260 with the 64 bit backend, and 2^261 with the 32 bit one. Converting
+/// in and out costs about as much as a multiplication, but multiplications inside
+/// the domain need no conversions, unlike those of `Scalar`. This makes long chains
+/// of multiplications, like exponentiation, cheaper.
+///
+/// See Montgomery's "Modular Multiplication Without Trial Division":
+/// https://doi.org/10.1090/S0025-5718-1985-0777282-X
+#[derive(Clone, Copy, Debug)]
+pub struct MontgomeryScalar {
+    value: U256,
+}
+
+impl MontgomeryScalar {
+    /// Move this scalar out of the Montgomery domain.
+    pub fn to_scalar(self) -> Scalar {
+        Scalar {
+            value: montgomery::from_montgomery(&self.value),
+        }
+    }
+
+    /// Calculate the square of this scalar.
+    pub fn square(&self) -> Self {
+        *self * *self
+    }
+}
+
+impl crate::zeroize::Zeroize for MontgomeryScalar {
+    fn zeroize(&mut self) {
+        crate::zeroize::wipe(&mut self.value.limbs);
+    }
+}
+
+impl ConditionallySelectable for MontgomeryScalar {
+    fn conditional_select(a: &Self, b: &Self, choice: subtle::Choice) -> Self {
+        MontgomeryScalar {
+            value: U256::conditional_select(&a.value, &b.value, choice),
+        }
+    }
+}
+
+impl MulAssign for MontgomeryScalar {
+    fn mul_assign(&mut self, other: Self) {
+        self.value = montgomery::montgomery_product(&self.value, &other.value);
+    }
+}
+
+impl Mul for MontgomeryScalar {
+    type Output = Self;
+
+    fn mul(mut self, other: Self) -> Self::Output {
+        self *= other;
+        self
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::curve25519::scalar::L;
 
-    use super::super::arithmetic::U256;
+    use crate::uint::U256;
 
-    use super::Scalar;
+    use super::{Scalar, Sha512, Shake256};
     use proptest::prelude::*;
 
     prop_compose! {
@@ -280,6 +582,27 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_inverse() {
+        for &x in [1, 2, 3, 1234567, u64::MAX].iter() {
+            let x = Scalar::from(x);
+            assert_eq!(x * x.inverse(), Scalar::from(1));
+        }
+        assert_eq!(Scalar::from(0).inverse(), Scalar::from(0));
+        assert_eq!(Scalar::from(7) - Scalar::from(9), -Scalar::from(2));
+    }
+
+    proptest! {
+        #[test]
+        fn test_montgomery_multiplication_matches(a in arb_scalar(), b in arb_scalar()) {
+            let a = a * Scalar::from(1);
+            let b = b * Scalar::from(1);
+            assert_eq!(a.to_montgomery().to_scalar(), a);
+            assert_eq!((a.to_montgomery() * b.to_montgomery()).to_scalar(), a * b);
+            assert_eq!(a.to_montgomery().square().to_scalar(), a * a);
+        }
+    }
+
     #[test]
     fn test_addition_examples() {
         let z1 = Scalar {
@@ -335,4 +658,54 @@ mod test {
         };
         assert_eq!(Scalar::from(bytes), expected);
     }
+
+    #[test]
+    fn test_const_construction() {
+        const TWO: Option<Scalar> = Scalar::from_canonical_bytes({
+            let mut bytes = [0; 32];
+            bytes[0] = 2;
+            bytes
+        });
+        const CLAMPED: Scalar = Scalar::clamped([0xFF; 32]);
+        assert_eq!(TWO, Some(Scalar::from(2)));
+        assert_eq!(CLAMPED.value.limbs, [!7, !0, !0, !0 >> 1]);
+        assert_eq!(Scalar::from_canonical_bytes(L.into()), None);
+        let mut l_minus_1: [u8; 32] = L.into();
+        l_minus_1[0] -= 1;
+        assert!(Scalar::from_canonical_bytes(l_minus_1).is_some());
+    }
+
+    #[test]
+    fn test_from_hash() {
+        let mut sha512 = Sha512::new();
+        sha512.update(b"transcript");
+        assert_eq!(
+            Scalar::from_hash(sha512),
+            Scalar::from(crate::sha512::hash(b"transcript"))
+        );
+        let mut shake = Shake256::new();
+        shake.update(b"transcript");
+        let mut expected = [0; 64];
+        crate::shake::shake256(b"transcript", &mut expected);
+        assert_eq!(Scalar::from_hash(shake), Scalar::from(expected));
+    }
+
+    #[cfg(all(feature = "alloc", feature = "hazmat"))]
+    #[test]
+    fn test_tagged_hash_separates_parts() {
+        let hash = Scalar::from_tagged_hash(b"domain", b"tag", &[b"ab", b"c"]);
+        assert_ne!(
+            hash,
+            Scalar::from_tagged_hash(b"domain", b"tag", &[b"a", b"bc"])
+        );
+        assert_ne!(hash, Scalar::from_tagged_hash(b"domain", b"tagab", &[b"c"]));
+        assert_ne!(
+            hash,
+            Scalar::from_tagged_hash(b"domaintag", b"", &[b"ab", b"c"])
+        );
+        assert_eq!(
+            hash,
+            Scalar::from_tagged_hash(b"domain", b"tag", &[b"ab", b"c"])
+        );
+    }
 }
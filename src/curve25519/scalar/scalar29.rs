@@ -0,0 +1,210 @@
+//! Scalar multiplication and reduction using 9 limbs of 29 bits.
+//!
+//! Barrett reduction relies on 64 x 64 -> 128 bit products, which 32 bit targets
+//! have to emulate. Instead, we use Montgomery multiplication, with R := 2^261,
+//! which only needs 32 x 32 -> 64 bit products.
+//!
+//! The values going in and out of this module are ordinary 256 bit numbers,
+//! and the conversion to and from the Montgomery domain happens internally.
+
+use core::convert::TryInto;
+
+use subtle::{Choice, ConditionallySelectable};
+
+use crate::uint::U256;
+
+/// A mask for the lower 29 bits of a limb.
+const LOW_29: u32 = (1 << 29) - 1;
+
+/// The limbs of L.
+const L: [u32; 9] = [
+    0x1cf5d3ed, 0x009318d2, 0x1de73596, 0x1df3bd45, 0x0000014d, 0, 0, 0, 0x00100000,
+];
+
+/// -L^-1 mod 2^29, used to clear the bottom limb during reduction.
+const LFACTOR: u32 = 0x12547e1b;
+
+/// R mod L.
+const R: [u32; 9] = [
+    0x114df9ed, 0x1a617303, 0x0f7c098c, 0x16793167, 0x1ffd656e, 0x1fffffff, 0x1fffffff, 0x1fffffff,
+    0x000fffff,
+];
+
+/// R^2 mod L.
+const RR: [u32; 9] = [
+    0x0b5f9d12, 0x1e141b17, 0x158d7f3d, 0x143f3757, 0x1972d781, 0x042feb7c, 0x1ceec73d, 0x1e184d1e,
+    0x0005046d,
+];
+
+/// Split a 256 bit number into 9 limbs of 29 bits.
+fn from_u256(x: &U256) -> [u32; 9] {
+    let mut out = [0; 9];
+    for (i, limb) in out.iter_mut().enumerate() {
+        let (word, shift) = ((29 * i) / 64, (29 * i) % 64);
+        let mut bits = x.limbs[word] >> shift;
+        if shift + 29 > 64 && word + 1 < 4 {
+            bits |= x.limbs[word + 1] << (64 - shift);
+        }
+        *limb = (bits as u32) & LOW_29;
+    }
+    out
+}
+
+/// Combine 9 limbs of 29 bits, holding a number < 2^256, into a 256 bit number.
+fn to_u256(x: &[u32; 9]) -> U256 {
+    let mut out = U256::from(0);
+    for (i, &limb) in x.iter().enumerate() {
+        let (word, shift) = ((29 * i) / 64, (29 * i) % 64);
+        out.limbs[word] |= u64::from(limb) << shift;
+        if shift + 29 > 64 && word + 1 < 4 {
+            out.limbs[word + 1] |= u64::from(limb) >> (64 - shift);
+        }
+    }
+    out
+}
+
+/// Subtract L from a number < 2L, if necessary, to bring it below L.
+fn sub_l_if_needed(x: [u32; 9]) -> [u32; 9] {
+    let mut diff = [0; 9];
+    let mut borrow = 0;
+    for i in 0..9 {
+        let d = x[i].wrapping_sub(L[i] + borrow);
+        diff[i] = d & LOW_29;
+        borrow = d >> 31;
+    }
+    // If subtracting L underflowed, x was already reduced.
+    let keep = Choice::from(borrow as u8);
+    let mut out = [0; 9];
+    for i in 0..9 {
+        out[i] = u32::conditional_select(&diff[i], &x[i], keep);
+    }
+    out
+}
+
+/// Calculate the full product of two numbers, with wide limbs.
+fn mul_internal(a: &[u32; 9], b: &[u32; 9]) -> [u64; 17] {
+    let mut out = [0; 17];
+    for i in 0..9 {
+        for j in 0..9 {
+            out[i + j] += u64::from(a[i]) * u64::from(b[j]);
+        }
+    }
+    out
+}
+
+/// Calculate x / R mod L, for an input x < L * R.
+fn montgomery_reduce(x: [u64; 17]) -> [u32; 9] {
+    let mut c = [0u64; 18];
+    c[..17].copy_from_slice(&x);
+    for i in 0..9 {
+        // Adding p * L makes the bottom 29 bits of this limb vanish.
+        let p = (c[i] as u32).wrapping_mul(LFACTOR) & LOW_29;
+        for j in 0..9 {
+            c[i + j] += u64::from(p) * u64::from(L[j]);
+        }
+        c[i + 1] += c[i] >> 29;
+    }
+    let mut out = [0; 9];
+    let mut carry = 0;
+    for (i, limb) in out.iter_mut().enumerate() {
+        let v = c[9 + i] + carry;
+        *limb = (v as u32) & LOW_29;
+        carry = v >> 29;
+    }
+    sub_l_if_needed(out)
+}
+
+/// Calculate a * b / R mod L.
+fn montgomery_mul(a: &[u32; 9], b: &[u32; 9]) -> [u32; 9] {
+    montgomery_reduce(mul_internal(a, b))
+}
+
+/// Calculate a * b / R mod L, for a, b < L.
+pub fn montgomery_product(a: &U256, b: &U256) -> U256 {
+    to_u256(&montgomery_mul(&from_u256(a), &from_u256(b)))
+}
+
+/// Calculate x * R mod L, moving x < L into the Montgomery domain.
+pub fn to_montgomery(x: &U256) -> U256 {
+    to_u256(&montgomery_mul(&from_u256(x), &RR))
+}
+
+/// Calculate x / R mod L, moving x < L out of the Montgomery domain.
+pub fn from_montgomery(x: &U256) -> U256 {
+    let mut wide = [0; 17];
+    for (w, &limb) in wide.iter_mut().zip(from_u256(x).iter()) {
+        *w = u64::from(limb);
+    }
+    to_u256(&montgomery_reduce(wide))
+}
+
+/// Calculate a * b mod L, for a, b < L.
+pub fn mul(a: &U256, b: &U256) -> U256 {
+    let ab_over_r = montgomery_mul(&from_u256(a), &from_u256(b));
+    to_u256(&montgomery_mul(&ab_over_r, &RR))
+}
+
+/// Reduce a 512 bit little endian number modulo L.
+pub fn from_bytes_wide(bytes: [u8; 64]) -> U256 {
+    let mut words = [0u64; 8];
+    for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+        words[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    // Split the input as lo + hi * 2^261, with 9 limbs each.
+    let mut lo = [0; 9];
+    let mut hi = [0; 9];
+    for i in 0..18 {
+        let (word, shift) = ((29 * i) / 64, (29 * i) % 64);
+        let mut bits = words[word] >> shift;
+        if shift + 29 > 64 && word + 1 < 8 {
+            bits |= words[word + 1] << (64 - shift);
+        }
+        let limb = (bits as u32) & LOW_29;
+        if i < 9 {
+            lo[i] = limb;
+        } else {
+            hi[i - 9] = limb;
+        }
+    }
+    // lo * (R mod L) / R = lo, and hi * R^2 / R = hi * R, both mod L
+    let lo = montgomery_mul(&lo, &R);
+    let hi = montgomery_mul(&hi, &RR);
+    let mut sum = [0; 9];
+    let mut carry = 0;
+    for i in 0..9 {
+        let v = lo[i] + hi[i] + carry;
+        sum[i] = v & LOW_29;
+        carry = v >> 29;
+    }
+    to_u256(&sub_l_if_needed(sum))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_limb_conversion_roundtrip() {
+        let x = U256 {
+            limbs: [
+                0x0123456789abcdef,
+                0xfedcba9876543210,
+                0xdeadbeefcafebabe,
+                0x0f0e0d0c0b0a0908,
+            ],
+        };
+        assert_eq!(to_u256(&from_u256(&x)), x);
+        assert_eq!(from_u256(&super::super::L), L);
+    }
+
+    #[test]
+    fn test_montgomery_constants() {
+        // R * R^-1 = 1, so reducing R mod L should give back 1.
+        let mut wide = [0; 17];
+        for (w, &r) in wide.iter_mut().zip(R.iter()) {
+            *w = u64::from(r);
+        }
+        assert_eq!(montgomery_reduce(wide), [1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(montgomery_mul(&RR, &[1, 0, 0, 0, 0, 0, 0, 0, 0]), R);
+    }
+}
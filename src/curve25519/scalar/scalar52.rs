@@ -0,0 +1,220 @@
+//! Scalar multiplication and reduction using 5 limbs of 52 bits.
+//!
+//! This is the 64 bit counterpart to the 29 bit backend, using Montgomery
+//! multiplication with R := 2^260, as in curve25519-dalek's `Scalar52`:
+//! https://github.com/dalek-cryptography/curve25519-dalek
+//!
+//! Like Barrett reduction, this needs 64 x 64 -> 128 bit products, but the
+//! products are between 52 bit limbs, so many of them can be accumulated
+//! before carrying.
+
+use core::convert::TryInto;
+
+use subtle::{Choice, ConditionallySelectable};
+
+use crate::uint::U256;
+
+/// A mask for the lower 52 bits of a limb.
+const LOW_52: u64 = (1 << 52) - 1;
+
+/// The limbs of L.
+const L: [u64; 5] = [
+    0x0002631a5cf5d3ed,
+    0x000dea2f79cd6581,
+    0x000000000014def9,
+    0x0000000000000000,
+    0x0000100000000000,
+];
+
+/// -L^-1 mod 2^52, used to clear the bottom limb during reduction.
+const LFACTOR: u64 = 0x00051da312547e1b;
+
+/// R mod L.
+const R: [u64; 5] = [
+    0x000f48bd6721e6ed,
+    0x0003bab5ac67e45a,
+    0x000fffffeb35e51b,
+    0x000fffffffffffff,
+    0x00000fffffffffff,
+];
+
+/// R^2 mod L.
+const RR: [u64; 5] = [
+    0x0009d265e952d13b,
+    0x000d63c715bea69f,
+    0x0005be65cb687604,
+    0x0003dceec73d217f,
+    0x000009411b7c309a,
+];
+
+/// Split a 256 bit number into 5 limbs of 52 bits.
+fn from_u256(x: &U256) -> [u64; 5] {
+    let w = &x.limbs;
+    [
+        w[0] & LOW_52,
+        ((w[0] >> 52) | (w[1] << 12)) & LOW_52,
+        ((w[1] >> 40) | (w[2] << 24)) & LOW_52,
+        ((w[2] >> 28) | (w[3] << 36)) & LOW_52,
+        w[3] >> 16,
+    ]
+}
+
+/// Combine 5 limbs of 52 bits, holding a number < 2^256, into a 256 bit number.
+fn to_u256(x: &[u64; 5]) -> U256 {
+    U256 {
+        limbs: [
+            x[0] | (x[1] << 52),
+            (x[1] >> 12) | (x[2] << 40),
+            (x[2] >> 24) | (x[3] << 28),
+            (x[3] >> 36) | (x[4] << 16),
+        ],
+    }
+}
+
+/// Subtract L from a number < 2L, if necessary, to bring it below L.
+fn sub_l_if_needed(x: [u64; 5]) -> [u64; 5] {
+    let mut diff = [0; 5];
+    let mut borrow = 0;
+    for i in 0..5 {
+        let d = x[i].wrapping_sub(L[i] + borrow);
+        diff[i] = d & LOW_52;
+        borrow = d >> 63;
+    }
+    // If subtracting L underflowed, x was already reduced.
+    let keep = Choice::from(borrow as u8);
+    let mut out = [0; 5];
+    for i in 0..5 {
+        out[i] = u64::conditional_select(&diff[i], &x[i], keep);
+    }
+    out
+}
+
+/// Calculate the full product of two numbers, with wide limbs.
+fn mul_internal(a: &[u64; 5], b: &[u64; 5]) -> [u128; 9] {
+    let mut out = [0; 9];
+    for i in 0..5 {
+        for j in 0..5 {
+            out[i + j] += u128::from(a[i]) * u128::from(b[j]);
+        }
+    }
+    out
+}
+
+/// Calculate x / R mod L, for an input x < L * R.
+fn montgomery_reduce(x: [u128; 9]) -> [u64; 5] {
+    let mut c = [0u128; 10];
+    c[..9].copy_from_slice(&x);
+    for i in 0..5 {
+        // Adding p * L makes the bottom 52 bits of this limb vanish.
+        let p = (c[i] as u64).wrapping_mul(LFACTOR) & LOW_52;
+        for j in 0..5 {
+            c[i + j] += u128::from(p) * u128::from(L[j]);
+        }
+        c[i + 1] += c[i] >> 52;
+    }
+    let mut out = [0; 5];
+    let mut carry = 0;
+    for (i, limb) in out.iter_mut().enumerate() {
+        let v = c[5 + i] + carry;
+        *limb = (v as u64) & LOW_52;
+        carry = v >> 52;
+    }
+    sub_l_if_needed(out)
+}
+
+/// Calculate a * b / R mod L.
+fn montgomery_mul(a: &[u64; 5], b: &[u64; 5]) -> [u64; 5] {
+    montgomery_reduce(mul_internal(a, b))
+}
+
+/// Calculate a * b / R mod L, for a, b < L.
+pub fn montgomery_product(a: &U256, b: &U256) -> U256 {
+    to_u256(&montgomery_mul(&from_u256(a), &from_u256(b)))
+}
+
+/// Calculate x * R mod L, moving x < L into the Montgomery domain.
+pub fn to_montgomery(x: &U256) -> U256 {
+    to_u256(&montgomery_mul(&from_u256(x), &RR))
+}
+
+/// Calculate x / R mod L, moving x < L out of the Montgomery domain.
+pub fn from_montgomery(x: &U256) -> U256 {
+    let mut wide = [0; 9];
+    for (w, &limb) in wide.iter_mut().zip(from_u256(x).iter()) {
+        *w = u128::from(limb);
+    }
+    to_u256(&montgomery_reduce(wide))
+}
+
+/// Calculate a * b mod L, for a, b < L.
+#[cfg_attr(not(feature = "montgomery_scalar"), allow(dead_code))]
+pub fn mul(a: &U256, b: &U256) -> U256 {
+    let ab_over_r = montgomery_mul(&from_u256(a), &from_u256(b));
+    to_u256(&montgomery_mul(&ab_over_r, &RR))
+}
+
+/// Reduce a 512 bit little endian number modulo L.
+#[cfg_attr(not(feature = "montgomery_scalar"), allow(dead_code))]
+pub fn from_bytes_wide(bytes: [u8; 64]) -> U256 {
+    let mut words = [0u64; 8];
+    for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+        words[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    // Split the input as lo + hi * 2^260, with 5 limbs each.
+    let mut lo = [0; 5];
+    let mut hi = [0; 5];
+    for i in 0..10 {
+        let (word, shift) = ((52 * i) / 64, (52 * i) % 64);
+        let mut bits = words[word] >> shift;
+        if shift + 52 > 64 && word + 1 < 8 {
+            bits |= words[word + 1] << (64 - shift);
+        }
+        let limb = bits & LOW_52;
+        if i < 5 {
+            lo[i] = limb;
+        } else {
+            hi[i - 5] = limb;
+        }
+    }
+    // lo * (R mod L) / R = lo, and hi * R^2 / R = hi * R, both mod L
+    let lo = montgomery_mul(&lo, &R);
+    let hi = montgomery_mul(&hi, &RR);
+    let mut sum = [0; 5];
+    let mut carry = 0;
+    for i in 0..5 {
+        let v = lo[i] + hi[i] + carry;
+        sum[i] = v & LOW_52;
+        carry = v >> 52;
+    }
+    to_u256(&sub_l_if_needed(sum))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_limb_conversion_roundtrip() {
+        let x = U256 {
+            limbs: [
+                0x0123456789abcdef,
+                0xfedcba9876543210,
+                0xdeadbeefcafebabe,
+                0x0f0e0d0c0b0a0908,
+            ],
+        };
+        assert_eq!(to_u256(&from_u256(&x)), x);
+        assert_eq!(from_u256(&super::super::L), L);
+    }
+
+    #[test]
+    fn test_montgomery_constants() {
+        // R * R^-1 = 1, so reducing R mod L should give back 1.
+        let mut wide = [0; 9];
+        for (w, &r) in wide.iter_mut().zip(R.iter()) {
+            *w = u128::from(r);
+        }
+        assert_eq!(montgomery_reduce(wide), [1, 0, 0, 0, 0]);
+        assert_eq!(montgomery_mul(&RR, &[1, 0, 0, 0, 0]), R);
+    }
+}
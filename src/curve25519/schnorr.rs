@@ -0,0 +1,158 @@
+//! This module implements non-interactive Schnorr proofs of knowledge, as described in RFC 8235:
+//! https://datatracker.ietf.org/doc/html/rfc8235
+//!
+//! A proof shows that whoever made it knows the secret scalar behind a public key,
+//! which is useful as a proof of possession when registering keys. The challenge
+//! is SHA-512, with both the identity of the prover, and some other context, mixed in,
+//! so that proofs can't be replayed elsewhere.
+
+use core::convert::{TryFrom, TryInto};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::sha512::{self, Sha512};
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error, PrivateKey, PublicKey,
+};
+
+/// The size of a proof, containing a point, and a scalar.
+pub const SCHNORR_PROOF_SIZE: usize = 64;
+
+/// A proof of knowledge of the secret scalar behind some public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrProof {
+    pub bytes: [u8; SCHNORR_PROOF_SIZE],
+}
+
+impl From<[u8; SCHNORR_PROOF_SIZE]> for SchnorrProof {
+    fn from(bytes: [u8; SCHNORR_PROOF_SIZE]) -> Self {
+        SchnorrProof { bytes }
+    }
+}
+
+/// Calculate c = H(G || V || A || UserID || OtherInfo), following Section 3.3.
+fn challenge(v: &[u8], a: &[u8; 32], user_id: &[u8], other_info: &[u8]) -> Scalar {
+    let g: [u8; 32] = point::B.into();
+    let mut hasher = Sha512::new();
+    hasher.update(&g);
+    hasher.update(v);
+    hasher.update(a);
+    hasher.update(user_id);
+    hasher.update(other_info);
+    Scalar::from_hash(hasher)
+}
+
+/// Prove knowledge of a, with A = a * G.
+pub(super) fn prove<R: RngCore + CryptoRng>(
+    a: Scalar,
+    public: &[u8; 32],
+    user_id: &[u8],
+    other_info: &[u8],
+    rng: &mut R,
+) -> SchnorrProof {
+    let v = Zeroizing::new(Scalar::random(rng));
+    let big_v: [u8; 32] = (point::B * *v).into();
+    let c = challenge(&big_v, public, user_id, other_info);
+    let r: [u8; 32] = (*v - a * c).into();
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(&big_v);
+    bytes[32..].copy_from_slice(&r);
+    SchnorrProof { bytes }
+}
+
+/// Verify a proof of knowledge, following Section 3.2.
+///
+/// Public keys of small order get rejected, since anyone can prove knowledge for them.
+pub(super) fn verify(
+    public: &[u8; 32],
+    proof: &SchnorrProof,
+    user_id: &[u8],
+    other_info: &[u8],
+) -> Result<(), Error> {
+    let a = Point::try_from(&public[..]).map_err(|_| Error::PointNotOnCurve)?;
+    let identity: [u8; 32] = Point::identity().into();
+    if <[u8; 32]>::from(a.doubled().doubled().doubled()) == identity {
+        return Err(Error::WeakPublicKey);
+    }
+    let r = Scalar::try_from(&proof.bytes[32..]).map_err(|_| Error::NonCanonicalScalar)?;
+    let c = challenge(&proof.bytes[..32], public, user_id, other_info);
+    let expected: [u8; 32] = Point::vartime_multiscalar_mul(&[r, c], &[point::B, a]).into();
+    if expected[..] != proof.bytes[..32] {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(())
+}
+
+impl PrivateKey {
+    /// Prove knowledge of this key, for some user identity, and other context.
+    pub fn prove_knowledge<R: RngCore + CryptoRng>(
+        &self,
+        user_id: &[u8],
+        other_info: &[u8],
+        rng: &mut R,
+    ) -> SchnorrProof {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        let a = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
+        let public = PublicKey::from_hash(&hash);
+        prove(*a, &public.bytes, user_id, other_info, rng)
+    }
+}
+
+impl PublicKey {
+    /// Verify a proof of knowledge of the private key, with the same identity, and context.
+    pub fn verify_knowledge(
+        &self,
+        proof: &SchnorrProof,
+        user_id: &[u8],
+        other_info: &[u8],
+    ) -> Result<(), Error> {
+        verify(&self.bytes, proof, user_id, other_info)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let private = PrivateKey::from([4; 32]);
+        let public = private.derive_public_key();
+        let proof = private.prove_knowledge(b"alice", b"registration", &mut OsRng);
+        assert!(public
+            .verify_knowledge(&proof, b"alice", b"registration")
+            .is_ok());
+        assert_eq!(
+            public.verify_knowledge(&proof, b"bob", b"registration"),
+            Err(Error::SignatureInvalid)
+        );
+        assert_eq!(
+            public.verify_knowledge(&proof, b"alice", b"other"),
+            Err(Error::SignatureInvalid)
+        );
+        let other = PrivateKey::from([5; 32]).derive_public_key();
+        assert!(other
+            .verify_knowledge(&proof, b"alice", b"registration")
+            .is_err());
+    }
+
+    #[test]
+    fn test_rejects_small_order_keys() {
+        // Anyone can make a proof for the identity, with V = r * G.
+        let identity: [u8; 32] = Point::identity().into();
+        let r = Scalar::from(7);
+        let mut proof = SchnorrProof { bytes: [0; 64] };
+        proof.bytes[..32].copy_from_slice(&<[u8; 32]>::from(point::B * r));
+        proof.bytes[32..].copy_from_slice(&<[u8; 32]>::from(r));
+        assert_eq!(
+            verify(&identity, &proof, b"", b""),
+            Err(Error::WeakPublicKey)
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! This module implements serde support for keys, signatures, and scalars.
+//!
+//! Human readable formats, like JSON, get a hex string, which is convenient in
+//! configuration files. Binary formats get the raw bytes, without any overhead.
+
+use core::{convert::TryFrom, fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{scalar::Scalar, PrivateKey, PublicKey, Signature};
+use crate::zeroize::Zeroizing;
+
+/// The most bytes we serialize at once, in a signature.
+const MAX_BYTES: usize = 64;
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        // This goes on the stack, so that serializing doesn't need an allocator,
+        // and might be the encoding of a private key, so it gets erased.
+        let mut buf = Zeroizing::new([0; 2 * MAX_BYTES]);
+        let encoded = &mut buf[..2 * bytes.len()];
+        hex::encode_to_slice(bytes, encoded).unwrap();
+        // Hex digits are always valid UTF-8
+        serializer.serialize_str(core::str::from_utf8(encoded).unwrap())
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+/// A visitor accepting exactly N bytes, either as hex, or raw.
+struct BytesVisitor<const N: usize>(PhantomData<[u8; N]>);
+
+impl<'de, const N: usize> Visitor<'de> for BytesVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bytes, or {} hex characters", N, 2 * N)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let mut out = [0; N];
+        hex::decode_to_slice(v, &mut out)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))?;
+        Ok(out)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        <[u8; N]>::try_from(v).map_err(|_| E::invalid_length(v.len(), &self))
+    }
+
+    // Some binary formats encode bytes as a sequence of integers.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut out = [0; N];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        Ok(out)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(BytesVisitor(PhantomData))
+    } else {
+        deserializer.deserialize_bytes(BytesVisitor(PhantomData))
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&self.bytes, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(PublicKey {
+            bytes: deserialize_bytes(deserializer)?,
+        })
+    }
+}
+
+impl Serialize for PrivateKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&self.bytes, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PrivateKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(PrivateKey {
+            bytes: deserialize_bytes(deserializer)?,
+        })
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&self.bytes, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Signature {
+            bytes: deserialize_bytes(deserializer)?,
+        })
+    }
+}
+
+impl Serialize for Scalar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: [u8; 32] = (*self).into();
+        serialize_bytes(&bytes, serializer)
+    }
+}
+
+/// Only canonical encodings of scalars are accepted.
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; 32] = deserialize_bytes(deserializer)?;
+        Scalar::try_from(&bytes[..]).map_err(|_| de::Error::custom("non canonical scalar"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_uses_hex() {
+        let private = PrivateKey { bytes: [1; 32] };
+        let public = private.derive_public_key();
+        let sig = private.sign(b"message");
+        let json = serde_json::to_string(&public).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex::encode(public.bytes)));
+        let decoded: PublicKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, public);
+        let decoded: Signature =
+            serde_json::from_str(&serde_json::to_string(&sig).unwrap()).unwrap();
+        assert_eq!(decoded, sig);
+        let decoded: PrivateKey =
+            serde_json::from_str(&serde_json::to_string(&private).unwrap()).unwrap();
+        assert_eq!(decoded.bytes, private.bytes);
+    }
+
+    #[test]
+    fn test_binary_uses_bytes() {
+        let sig = PrivateKey { bytes: [2; 32] }.sign(b"message");
+        let cbor = serde_cbor::to_vec(&sig).unwrap();
+        // A 2 byte header, for a byte string of length 64
+        assert_eq!(cbor.len(), 2 + 64);
+        let decoded: Signature = serde_cbor::from_slice(&cbor).unwrap();
+        assert_eq!(decoded, sig);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(serde_json::from_str::<PublicKey>("\"abcd\"").is_err());
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"{}\"", "zz".repeat(32))).is_err());
+        // A CBOR byte string of length 4
+        assert!(serde_cbor::from_slice::<PublicKey>(&[0x44, 0, 0, 0, 0]).is_err());
+        let non_canonical = format!("\"{}\"", "ff".repeat(32));
+        assert!(serde_json::from_str::<Scalar>(&non_canonical).is_err());
+        let scalar = Scalar::from(1234);
+        let json = serde_json::to_string(&scalar).unwrap();
+        assert_eq!(serde_json::from_str::<Scalar>(&json).unwrap(), scalar);
+    }
+}
@@ -0,0 +1,73 @@
+//! This module implements the `Signer`, and `Verifier`, traits of the `signature` crate:
+//! https://docs.rs/signature
+//!
+//! This lets our keys drop into generic code written against these traits. Like
+//! `VerificationError`, the errors returned here say nothing about why a check failed.
+
+use signature::{Error as SignatureError, Signer, Verifier};
+
+use super::{PrivateKey, PublicKey, Signature, SigningOnlyKey, VerifyingKey};
+
+impl Signer<Signature> for PrivateKey {
+    fn try_sign(&self, message: &[u8]) -> Result<Signature, SignatureError> {
+        Ok(self.sign(message))
+    }
+}
+
+impl Signer<Signature> for SigningOnlyKey {
+    fn try_sign(&self, message: &[u8]) -> Result<Signature, SignatureError> {
+        Ok(self.sign(message))
+    }
+}
+
+impl Verifier<Signature> for PublicKey {
+    fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.try_verify(message, *signature)
+            .map_err(|_| SignatureError::new())
+    }
+}
+
+impl Verifier<Signature> for VerifyingKey {
+    fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        self.try_verify(message, *signature)
+            .map_err(|_| SignatureError::new())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sign_generic<S: Signer<Signature>>(signer: &S, message: &[u8]) -> Signature {
+        signer.sign(message)
+    }
+
+    fn verify_generic<V: Verifier<Signature>>(
+        verifier: &V,
+        message: &[u8],
+        signature: &Signature,
+    ) -> bool {
+        verifier.verify(message, signature).is_ok()
+    }
+
+    #[test]
+    fn test_generic_signing_matches_inherent() {
+        let private = PrivateKey::from_seed([3; 32]);
+        let signature = sign_generic(&private, b"message");
+        assert_eq!(signature, private.sign(b"message"));
+        let signing_only = private.clone().into_signing_only();
+        assert_eq!(sign_generic(&signing_only, b"message"), signature);
+    }
+
+    #[test]
+    fn test_generic_verification() {
+        let private = PrivateKey::from_seed([3; 32]);
+        let public = private.derive_public_key();
+        let verifying = VerifyingKey::from(public);
+        let signature = private.sign(b"message");
+        assert!(verify_generic(&public, b"message", &signature));
+        assert!(verify_generic(&verifying, b"message", &signature));
+        assert!(!verify_generic(&public, b"other message", &signature));
+        assert!(!verify_generic(&verifying, b"other message", &signature));
+    }
+}
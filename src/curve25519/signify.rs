@@ -0,0 +1,404 @@
+//! This module implements the key and signature files of OpenBSD's signify, and minisign.
+//!
+//! Both tools use files made of an "untrusted comment: " line, followed by a line of Base64,
+//! holding an algorithm tag, a random key ID, and the key or signature itself:
+//! https://man.openbsd.org/signify
+//!
+//! Minisign extends this with prehashed signatures, using BLAKE2b-512, and trusted comments,
+//! which are covered by a second signature:
+//! https://jedisct1.github.io/minisign/#signature-format
+//!
+//! Only unencrypted private keys are supported.
+
+use core::convert::{TryFrom, TryInto};
+
+use subtle::ConstantTimeEq;
+
+use crate::{base64, blake2b, sha512, zeroize::Zeroizing};
+
+use super::{
+    Error, PrivateKey, PublicKey, Signature, PRIVATE_KEY_SIZE, PUBLIC_KEY_SIZE, SIGNATURE_SIZE,
+};
+
+/// The size of the key IDs identifying which key a signature was made with.
+pub const SIGNIFY_KEY_ID_SIZE: usize = 8;
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+/// The algorithm tag for keys, and signatures over the message itself.
+const ALGORITHM: &[u8] = b"Ed";
+/// The algorithm tag for minisign signatures, over the BLAKE2b-512 hash of the message.
+const PREHASHED_ALGORITHM: &[u8] = b"ED";
+const SIGNIFY_KDF: &[u8] = b"BK";
+const MINISIGN_KDF_NONE: &[u8] = &[0, 0];
+const MINISIGN_CHECKSUM: &[u8] = b"B2";
+const MINISIGN_CHECKSUM_SIZE: usize = 32;
+
+/// Encode a file, made of an untrusted comment, and some Base64 data.
+fn encode_file(comment: &str, data: &[u8]) -> String {
+    assert!(!comment.contains('\n'), "comments must fit on one line");
+    format!(
+        "{}{}\n{}\n",
+        UNTRUSTED_PREFIX,
+        comment,
+        base64::encode(data)
+    )
+}
+
+/// Read a line of a file, which needs to start with a given prefix, returning the rest.
+fn read_line<'a>(lines: &mut core::str::Lines<'a>, prefix: &str) -> Result<&'a str, Error> {
+    let line = lines.next().ok_or(Error::InvalidEncoding)?;
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    line.strip_prefix(prefix).ok_or(Error::InvalidEncoding)
+}
+
+fn read_data(lines: &mut core::str::Lines) -> Result<Vec<u8>, Error> {
+    let line = read_line(lines, "")?;
+    base64::decode(line.as_bytes()).map_err(|_| Error::InvalidEncoding)
+}
+
+/// An algorithm tag, a key ID, and the data following them.
+type Tagged<'a> = (&'a [u8], [u8; SIGNIFY_KEY_ID_SIZE], &'a [u8]);
+
+/// Split data into an algorithm tag, a key ID, and the rest, which must be `len` bytes long.
+fn split_data(data: &[u8], len: usize) -> Result<Tagged<'_>, Error> {
+    if data.len() != 2 + SIGNIFY_KEY_ID_SIZE + len {
+        return Err(Error::InvalidLength);
+    }
+    let (algorithm, rest) = data.split_at(2);
+    let (key_id, rest) = rest.split_at(SIGNIFY_KEY_ID_SIZE);
+    Ok((algorithm, key_id.try_into().unwrap(), rest))
+}
+
+/// Minisign displays key IDs as a little endian integer, in hex.
+fn minisign_key_id(key_id: &[u8; SIGNIFY_KEY_ID_SIZE]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn minisign_checksum(
+    key_id: &[u8; SIGNIFY_KEY_ID_SIZE],
+    pair: &[u8],
+) -> [u8; MINISIGN_CHECKSUM_SIZE] {
+    let mut hasher = blake2b::Blake2b::new(MINISIGN_CHECKSUM_SIZE);
+    hasher.update(ALGORITHM);
+    hasher.update(key_id);
+    hasher.update(pair);
+    let mut out = [0; MINISIGN_CHECKSUM_SIZE];
+    hasher.finalize(&mut out);
+    out
+}
+
+impl PublicKey {
+    /// Encode this key as a signify public key file.
+    pub fn to_signify(&self, key_id: &[u8; SIGNIFY_KEY_ID_SIZE], comment: &str) -> String {
+        let mut data = ALGORITHM.to_vec();
+        data.extend_from_slice(key_id);
+        data.extend_from_slice(&self.bytes);
+        encode_file(comment, &data)
+    }
+
+    /// Parse a signify public key file, returning the key, and its ID.
+    pub fn from_signify(input: &str) -> Result<(PublicKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        let mut lines = input.lines();
+        read_line(&mut lines, UNTRUSTED_PREFIX)?;
+        let data = read_data(&mut lines)?;
+        let (algorithm, key_id, key) = split_data(&data, PUBLIC_KEY_SIZE)?;
+        if algorithm != ALGORITHM {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        Ok((PublicKey::try_from(key)?, key_id))
+    }
+
+    /// Encode this key as a minisign public key file.
+    ///
+    /// These are the same as signify's, but with a comment displaying the key ID.
+    pub fn to_minisign(&self, key_id: &[u8; SIGNIFY_KEY_ID_SIZE]) -> String {
+        let comment = format!("minisign public key {}", minisign_key_id(key_id));
+        self.to_signify(key_id, &comment)
+    }
+
+    /// Parse a minisign public key file, returning the key, and its ID.
+    pub fn from_minisign(input: &str) -> Result<(PublicKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        Self::from_signify(input)
+    }
+
+    /// Verify a signify signature file over a message, made by this key.
+    pub fn verify_signify(
+        &self,
+        key_id: &[u8; SIGNIFY_KEY_ID_SIZE],
+        message: &[u8],
+        input: &str,
+    ) -> Result<(), Error> {
+        let mut lines = input.lines();
+        read_line(&mut lines, UNTRUSTED_PREFIX)?;
+        let data = read_data(&mut lines)?;
+        let (algorithm, signed_key_id, signature) = split_data(&data, SIGNATURE_SIZE)?;
+        if algorithm != ALGORITHM {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let signature = Signature::try_from(signature)?;
+        if signed_key_id != *key_id || !self.verify(message, signature) {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(())
+    }
+
+    /// Verify a minisign signature file over a message, made by this key.
+    ///
+    /// This returns the trusted comment, which is only meaningful once verified.
+    /// Both prehashed and legacy signatures are accepted.
+    pub fn verify_minisign(
+        &self,
+        key_id: &[u8; SIGNIFY_KEY_ID_SIZE],
+        message: &[u8],
+        input: &str,
+    ) -> Result<String, Error> {
+        let mut lines = input.lines();
+        read_line(&mut lines, UNTRUSTED_PREFIX)?;
+        let data = read_data(&mut lines)?;
+        let trusted_comment = read_line(&mut lines, TRUSTED_PREFIX)?;
+        let global_signature = read_data(&mut lines)?;
+
+        let (algorithm, signed_key_id, signature_bytes) = split_data(&data, SIGNATURE_SIZE)?;
+        let signature = Signature::try_from(signature_bytes)?;
+        let global_signature = Signature::try_from(&global_signature[..])?;
+        let valid = if algorithm == PREHASHED_ALGORITHM {
+            let mut hash = [0; blake2b::MAX_HASH_SIZE];
+            blake2b::hash(message, &mut hash);
+            self.verify(&hash, signature)
+        } else if algorithm == ALGORITHM {
+            self.verify(message, signature)
+        } else {
+            return Err(Error::UnsupportedAlgorithm);
+        };
+        // The global signature covers the trusted comment, binding it to the first signature.
+        let mut global_message = signature_bytes.to_vec();
+        global_message.extend_from_slice(trusted_comment.as_bytes());
+        if signed_key_id != *key_id || !valid || !self.verify(&global_message, global_signature) {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(trusted_comment.to_string())
+    }
+}
+
+impl PrivateKey {
+    /// Encode this key as an unencrypted signify private key file.
+    pub fn to_signify(&self, key_id: &[u8; SIGNIFY_KEY_ID_SIZE], comment: &str) -> String {
+        // Like OpenSSH, signify stores the seed, followed by the public key.
+        let mut pair = Zeroizing::new(self.bytes.to_vec());
+        pair.extend_from_slice(&self.derive_public_key().bytes);
+        let mut data = Zeroizing::new(ALGORITHM.to_vec());
+        data.extend_from_slice(SIGNIFY_KDF);
+        // Zero rounds, and an empty salt, mean that the key isn't encrypted.
+        data.extend_from_slice(&[0; 4 + 16]);
+        data.extend_from_slice(&sha512::hash(&pair)[..8]);
+        data.extend_from_slice(key_id);
+        data.extend_from_slice(&pair);
+        encode_file(comment, &data)
+    }
+
+    /// Parse an unencrypted signify private key file, returning the key, and its ID.
+    pub fn from_signify(input: &str) -> Result<(PrivateKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        let mut lines = input.lines();
+        read_line(&mut lines, UNTRUSTED_PREFIX)?;
+        let data = Zeroizing::new(read_data(&mut lines)?);
+        Self::parse_signify(&data)
+    }
+
+    fn parse_signify(data: &[u8]) -> Result<(PrivateKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        const HEADER_SIZE: usize = 2 + 4 + 16 + 8;
+        let pair_size = PRIVATE_KEY_SIZE + PUBLIC_KEY_SIZE;
+        let (algorithm, _, _) = split_data(data, HEADER_SIZE + pair_size)?;
+        if algorithm != ALGORITHM || &data[2..4] != SIGNIFY_KDF {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        if data[4..8] != [0; 4] {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let checksum = &data[24..32];
+        let key_id = data[32..40].try_into().unwrap();
+        let pair = &data[40..];
+        if !bool::from(sha512::hash(pair)[..8].ct_eq(checksum)) {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok((check_pair(pair)?, key_id))
+    }
+
+    /// Create a signify signature file over a message.
+    pub fn sign_signify(
+        &self,
+        key_id: &[u8; SIGNIFY_KEY_ID_SIZE],
+        message: &[u8],
+        comment: &str,
+    ) -> String {
+        let mut data = ALGORITHM.to_vec();
+        data.extend_from_slice(key_id);
+        data.extend_from_slice(&self.sign(message).bytes);
+        encode_file(comment, &data)
+    }
+
+    /// Encode this key as an unencrypted minisign private key file.
+    pub fn to_minisign(&self, key_id: &[u8; SIGNIFY_KEY_ID_SIZE]) -> String {
+        let mut pair = Zeroizing::new(self.bytes.to_vec());
+        pair.extend_from_slice(&self.derive_public_key().bytes);
+        let mut data = Zeroizing::new(ALGORITHM.to_vec());
+        data.extend_from_slice(MINISIGN_KDF_NONE);
+        data.extend_from_slice(MINISIGN_CHECKSUM);
+        // The salt, and scrypt limits, are unused without encryption.
+        data.extend_from_slice(&[0; 32 + 8 + 8]);
+        data.extend_from_slice(key_id);
+        data.extend_from_slice(&pair);
+        data.extend_from_slice(&minisign_checksum(key_id, &pair));
+        encode_file("minisign secret key", &data)
+    }
+
+    /// Parse an unencrypted minisign private key file, returning the key, and its ID.
+    ///
+    /// Keys encrypted with a password aren't supported.
+    pub fn from_minisign(input: &str) -> Result<(PrivateKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        let mut lines = input.lines();
+        read_line(&mut lines, UNTRUSTED_PREFIX)?;
+        let data = Zeroizing::new(read_data(&mut lines)?);
+        Self::parse_minisign(&data)
+    }
+
+    fn parse_minisign(data: &[u8]) -> Result<(PrivateKey, [u8; SIGNIFY_KEY_ID_SIZE]), Error> {
+        const HEADER_SIZE: usize = 2 + 2 + 2 + 32 + 8 + 8;
+        let pair_size = PRIVATE_KEY_SIZE + PUBLIC_KEY_SIZE;
+        // The key ID doesn't come right after the algorithm here, so we split by hand.
+        if data.len() != HEADER_SIZE + SIGNIFY_KEY_ID_SIZE + pair_size + MINISIGN_CHECKSUM_SIZE {
+            return Err(Error::InvalidLength);
+        }
+        if &data[..2] != ALGORITHM
+            || &data[2..4] != MINISIGN_KDF_NONE
+            || &data[4..6] != MINISIGN_CHECKSUM
+        {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let rest = &data[HEADER_SIZE..];
+        let (key_id, rest) = rest.split_at(SIGNIFY_KEY_ID_SIZE);
+        let key_id = key_id.try_into().unwrap();
+        let (pair, checksum) = rest.split_at(pair_size);
+        if !bool::from(minisign_checksum(&key_id, pair)[..].ct_eq(checksum)) {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok((check_pair(pair)?, key_id))
+    }
+
+    /// Create a prehashed minisign signature file over a message.
+    ///
+    /// The trusted comment is signed as well, and has to fit on one line.
+    pub fn sign_minisign(
+        &self,
+        key_id: &[u8; SIGNIFY_KEY_ID_SIZE],
+        message: &[u8],
+        trusted_comment: &str,
+    ) -> String {
+        assert!(
+            !trusted_comment.contains('\n'),
+            "comments must fit on one line"
+        );
+        let mut hash = [0; blake2b::MAX_HASH_SIZE];
+        blake2b::hash(message, &mut hash);
+        let signature = self.sign(&hash).bytes;
+        let mut global_message = signature.to_vec();
+        global_message.extend_from_slice(trusted_comment.as_bytes());
+        let global_signature = self.sign(&global_message);
+
+        let mut data = PREHASHED_ALGORITHM.to_vec();
+        data.extend_from_slice(key_id);
+        data.extend_from_slice(&signature);
+        let mut out = encode_file("signature from minisign secret key", &data);
+        out.push_str(TRUSTED_PREFIX);
+        out.push_str(trusted_comment);
+        out.push('\n');
+        out.push_str(&base64::encode(&global_signature.bytes));
+        out.push('\n');
+        out
+    }
+}
+
+/// Parse a seed followed by a public key, checking that the two agree.
+fn check_pair(pair: &[u8]) -> Result<PrivateKey, Error> {
+    let key = PrivateKey {
+        bytes: pair[..PRIVATE_KEY_SIZE].try_into().unwrap(),
+    };
+    if !bool::from(key.derive_public_key().bytes[..].ct_eq(&pair[PRIVATE_KEY_SIZE..])) {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY_ID: [u8; SIGNIFY_KEY_ID_SIZE] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn example_key() -> PrivateKey {
+        let mut bytes = [0; PRIVATE_KEY_SIZE];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        PrivateKey::from(bytes)
+    }
+
+    #[test]
+    fn test_signify_files() {
+        let key = example_key();
+        let public = key.derive_public_key();
+        let encoded = public.to_signify(&KEY_ID, "signify public key");
+        assert_eq!(
+            encoded,
+            "untrusted comment: signify public key\nRWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4\n"
+        );
+        assert_eq!(PublicKey::from_signify(&encoded), Ok((public, KEY_ID)));
+
+        let (decoded, key_id) = PrivateKey::from_signify(&key.to_signify(&KEY_ID, "")).unwrap();
+        assert_eq!(decoded.bytes, key.bytes);
+        assert_eq!(key_id, KEY_ID);
+
+        let signature = key.sign_signify(&KEY_ID, b"message", "verify with key.pub");
+        assert_eq!(
+            public.verify_signify(&KEY_ID, b"message", &signature),
+            Ok(())
+        );
+        assert_eq!(
+            public.verify_signify(&KEY_ID, b"massage", &signature),
+            Err(Error::SignatureInvalid)
+        );
+        assert_eq!(
+            public.verify_signify(&[0; SIGNIFY_KEY_ID_SIZE], b"message", &signature),
+            Err(Error::SignatureInvalid)
+        );
+    }
+
+    #[test]
+    fn test_minisign_files() {
+        let key = example_key();
+        let public = key.derive_public_key();
+        let encoded = public.to_minisign(&KEY_ID);
+        assert!(encoded.starts_with("untrusted comment: minisign public key 0807060504030201\n"));
+        assert_eq!(PublicKey::from_minisign(&encoded), Ok((public, KEY_ID)));
+
+        let (decoded, key_id) = PrivateKey::from_minisign(&key.to_minisign(&KEY_ID)).unwrap();
+        assert_eq!(decoded.bytes, key.bytes);
+        assert_eq!(key_id, KEY_ID);
+
+        let signature = key.sign_minisign(&KEY_ID, b"message", "timestamp:0");
+        assert_eq!(
+            public.verify_minisign(&KEY_ID, b"message", &signature),
+            Ok("timestamp:0".to_string())
+        );
+        assert_eq!(
+            public.verify_minisign(&KEY_ID, b"massage", &signature),
+            Err(Error::SignatureInvalid)
+        );
+        // Changing the trusted comment breaks the global signature
+        let forged = signature.replace("timestamp:0", "timestamp:1");
+        assert_eq!(
+            public.verify_minisign(&KEY_ID, b"message", &forged),
+            Err(Error::SignatureInvalid)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! This module implements hierarchical key derivation for Ed25519, as described in SLIP-0010:
+//! https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//!
+//! Each key in the hierarchy is a seed, along with a chain code, and children come from
+//! HMAC-SHA512 over their parent. Ed25519 only supports hardened derivation, so there's
+//! no way to derive child public keys from a parent public key.
+
+use core::fmt;
+
+use crate::hmac::{self, HmacSha512};
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::zeroize::Zeroizing;
+
+use super::{Error, PrivateKey, PublicKey, PRIVATE_KEY_SIZE};
+
+/// The first hardened index, which all indices in SLIP-0010 Ed25519 paths are above.
+pub const HARDENED_INDEX: u32 = 0x8000_0000;
+
+/// The size of the chain code accompanying each key.
+pub const CHAIN_CODE_SIZE: usize = 32;
+
+const MASTER_KEY: &[u8] = b"ed25519 seed";
+
+/// A key in a SLIP-0010 hierarchy, which can derive child keys.
+#[derive(Clone)]
+pub struct Slip10Key {
+    key: [u8; PRIVATE_KEY_SIZE],
+    chain_code: [u8; CHAIN_CODE_SIZE],
+}
+
+impl Slip10Key {
+    fn from_tag(tag: [u8; hmac::TAG_SIZE]) -> Self {
+        let tag = Zeroizing::new(tag);
+        let mut out = Slip10Key {
+            key: [0; PRIVATE_KEY_SIZE],
+            chain_code: [0; CHAIN_CODE_SIZE],
+        };
+        out.key.copy_from_slice(&tag[..PRIVATE_KEY_SIZE]);
+        out.chain_code.copy_from_slice(&tag[PRIVATE_KEY_SIZE..]);
+        out
+    }
+
+    /// Derive the master key of a hierarchy, from a seed, often made with BIP-39.
+    ///
+    /// SLIP-0010 recommends seeds between 16 and 64 bytes.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Self::from_tag(hmac::hmac(MASTER_KEY, seed))
+    }
+
+    /// Derive the child key at some index, which has to be hardened.
+    pub fn derive(&self, index: u32) -> Result<Self, Error> {
+        if index < HARDENED_INDEX {
+            return Err(Error::InvalidDerivationPath);
+        }
+        let mut mac = HmacSha512::new(&self.chain_code);
+        mac.update(&[0]);
+        mac.update(&self.key);
+        mac.update(&index.to_be_bytes());
+        Ok(Self::from_tag(mac.finalize()))
+    }
+
+    /// Derive the key at a path below this one, like "m/44'/501'/0'".
+    ///
+    /// Every component has to be hardened, marked with "'", "h", or "H".
+    pub fn derive_path(&self, path: &str) -> Result<Self, Error> {
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(Error::InvalidDerivationPath);
+        }
+        let mut out = self.clone();
+        for component in components {
+            let index = component
+                .strip_suffix(&['\'', 'h', 'H'][..])
+                .filter(|x| x.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|x| x.parse::<u32>().ok())
+                .filter(|&x| x < HARDENED_INDEX)
+                .ok_or(Error::InvalidDerivationPath)?;
+            out = out.derive(HARDENED_INDEX | index)?;
+        }
+        Ok(out)
+    }
+
+    /// The signing key at this point in the hierarchy.
+    pub fn private_key(&self) -> PrivateKey {
+        PrivateKey::from(self.key)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.private_key().derive_public_key()
+    }
+
+    pub fn chain_code(&self) -> [u8; CHAIN_CODE_SIZE] {
+        self.chain_code
+    }
+}
+
+/// This never prints the key, or the chain code.
+impl fmt::Debug for Slip10Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slip10Key").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Slip10Key {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vector_1() {
+        // From the first Ed25519 test vector in SLIP-0010
+        let master =
+            Slip10Key::from_seed(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap());
+        assert_eq!(
+            hex::encode(master.private_key().to_bytes()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(master.chain_code()),
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+        assert_eq!(
+            hex::encode(master.public_key().to_bytes()),
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed"
+        );
+
+        let child = master.derive_path("m/0'/1'/2h/2H/1000000000'").unwrap();
+        assert_eq!(
+            hex::encode(child.private_key().to_bytes()),
+            "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793"
+        );
+        assert_eq!(
+            hex::encode(child.chain_code()),
+            "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230"
+        );
+        assert_eq!(
+            master.derive_path("m").unwrap().chain_code(),
+            master.chain_code()
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_paths() {
+        let master = Slip10Key::from_seed(&[7; 32]);
+        assert!(master.derive(0).is_err());
+        for path in ["", "0'", "m/0", "m/0'/", "m/+1'", "m/2147483648'", "m//0'"].iter() {
+            assert_eq!(
+                master.derive_path(path).unwrap_err(),
+                Error::InvalidDerivationPath,
+                "{}",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! This module provides libsodium's `crypto_sign` functions, byte for byte:
+//! https://doc.libsodium.org/public-key_cryptography/public-key_signatures
+//!
+//! libsodium stores secret keys as 64 bytes, the seed followed by the public key,
+//! which these functions read, and write. Verification follows libsodium in also
+//! rejecting public keys, and commitments R, of small order. The conversions to
+//! X25519 keys match `crypto_sign_ed25519_pk_to_curve25519`, and its `sk` counterpart:
+//! https://doc.libsodium.org/advanced/ed25519-curve25519
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+
+use subtle::ConstantTimeEq;
+
+use crate::sha512;
+use crate::zeroize::Zeroizing;
+
+use super::{error::Error, point::Point, PrivateKey, PublicKey, Signature};
+
+pub const CRYPTO_SIGN_BYTES: usize = 64;
+
+pub const CRYPTO_SIGN_SEEDBYTES: usize = 32;
+
+pub const CRYPTO_SIGN_PUBLICKEYBYTES: usize = 32;
+
+pub const CRYPTO_SIGN_SECRETKEYBYTES: usize = 64;
+
+/// Load the private key from a secret key, checking that its public half matches.
+///
+/// libsodium trusts the stored public key when signing, but signing with a mismatched
+/// public key leaks the private key, so we refuse to.
+fn private_key(sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES]) -> Result<PrivateKey, Error> {
+    let private = PrivateKey::from_seed(crypto_sign_ed25519_sk_to_seed(sk));
+    if !bool::from(private.derive_public_key().bytes.ct_eq(&sk[32..])) {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(private)
+}
+
+/// Derive a key pair from a seed, returning the public key, and the 64 byte secret key.
+pub fn crypto_sign_seed_keypair(
+    seed: &[u8; CRYPTO_SIGN_SEEDBYTES],
+) -> (
+    [u8; CRYPTO_SIGN_PUBLICKEYBYTES],
+    [u8; CRYPTO_SIGN_SECRETKEYBYTES],
+) {
+    let public = PrivateKey::from_seed(*seed).derive_public_key();
+    let mut sk = [0; CRYPTO_SIGN_SECRETKEYBYTES];
+    sk[..32].copy_from_slice(seed);
+    sk[32..].copy_from_slice(&public.bytes);
+    (public.bytes, sk)
+}
+
+/// Extract the seed from a secret key.
+pub fn crypto_sign_ed25519_sk_to_seed(
+    sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES],
+) -> [u8; CRYPTO_SIGN_SEEDBYTES] {
+    sk[..32].try_into().unwrap()
+}
+
+/// Extract the public key from a secret key.
+pub fn crypto_sign_ed25519_sk_to_pk(
+    sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES],
+) -> [u8; CRYPTO_SIGN_PUBLICKEYBYTES] {
+    sk[32..].try_into().unwrap()
+}
+
+/// Sign a message, returning a detached signature.
+///
+/// This fails if the public key in the secret key doesn't match its seed.
+pub fn crypto_sign_detached(
+    message: &[u8],
+    sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES],
+) -> Result<[u8; CRYPTO_SIGN_BYTES], Error> {
+    Ok(private_key(sk)?.sign(message).bytes)
+}
+
+/// Sign a message, returning the signature followed by the message.
+#[cfg(feature = "alloc")]
+pub fn crypto_sign(
+    message: &[u8],
+    sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES],
+) -> Result<Vec<u8>, Error> {
+    Ok(private_key(sk)?.sign_combined(message))
+}
+
+/// Verify a signed message, made by `crypto_sign`, returning the message.
+pub fn crypto_sign_open<'a>(
+    signed_message: &'a [u8],
+    pk: &[u8; CRYPTO_SIGN_PUBLICKEYBYTES],
+) -> Result<&'a [u8], Error> {
+    if signed_message.len() < CRYPTO_SIGN_BYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (signature, message) = signed_message.split_at(CRYPTO_SIGN_BYTES);
+    crypto_sign_verify_detached(signature.try_into().unwrap(), message, pk)?;
+    Ok(message)
+}
+
+/// Verify a detached signature of a message.
+pub fn crypto_sign_verify_detached(
+    signature: &[u8; CRYPTO_SIGN_BYTES],
+    message: &[u8],
+    pk: &[u8; CRYPTO_SIGN_PUBLICKEYBYTES],
+) -> Result<(), Error> {
+    let a = Point::try_from(&pk[..]).map_err(|_| Error::PointNotOnCurve)?;
+    if a.has_small_order() {
+        return Err(Error::WeakPublicKey);
+    }
+    let public = PublicKey { bytes: *pk };
+    // An R which fails to decode still fails verification, below.
+    if matches!(Point::try_from(&signature[..32]), Ok(r) if r.has_small_order()) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(public.try_verify(message, Signature { bytes: *signature })?)
+}
+
+/// Convert a public key into an X25519 public key.
+///
+/// This fails for keys of small order, or outside of the subgroup of order L.
+pub fn crypto_sign_ed25519_pk_to_curve25519(
+    pk: &[u8; CRYPTO_SIGN_PUBLICKEYBYTES],
+) -> Result<[u8; 32], Error> {
+    let a = Point::try_from(&pk[..]).map_err(|_| Error::PointNotOnCurve)?;
+    if a.has_small_order() || !a.is_torsion_free() {
+        return Err(Error::WeakPublicKey);
+    }
+    let u = a.montgomery_u();
+    Ok((u.u * u.w.inverse()).into())
+}
+
+/// Convert a secret key into an X25519 scalar, clamped as libsodium does.
+pub fn crypto_sign_ed25519_sk_to_curve25519(sk: &[u8; CRYPTO_SIGN_SECRETKEYBYTES]) -> [u8; 32] {
+    let hash = Zeroizing::new(sha512::hash(&sk[..32]));
+    let mut out: [u8; 32] = hash[..32].try_into().unwrap();
+    out[0] &= 248;
+    out[31] &= 127;
+    out[31] |= 64;
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::x25519;
+
+    fn decode<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(s, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_rfc_8032_vector() {
+        // Test 1 from Section 7.1 of RFC 8032, with an empty message.
+        let seed = decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+        let (pk, sk) = crypto_sign_seed_keypair(&seed);
+        assert_eq!(
+            pk,
+            decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+        );
+        assert_eq!(crypto_sign_ed25519_sk_to_seed(&sk), seed);
+        assert_eq!(crypto_sign_ed25519_sk_to_pk(&sk), pk);
+        let signature = crypto_sign_detached(b"", &sk).unwrap();
+        assert_eq!(
+            signature,
+            decode::<64>("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b")
+        );
+        assert!(crypto_sign_verify_detached(&signature, b"", &pk).is_ok());
+        assert!(crypto_sign_verify_detached(&signature, b"x", &pk).is_err());
+        #[cfg(feature = "alloc")]
+        {
+            let signed = crypto_sign(b"", &sk).unwrap();
+            assert_eq!(&signed[..], &signature[..]);
+            assert_eq!(crypto_sign_open(&signed, &pk), Ok(&b""[..]));
+            assert!(crypto_sign_open(&signed[1..], &pk).is_err());
+        }
+        let mut mismatched = sk;
+        mismatched[63] ^= 1;
+        assert_eq!(
+            crypto_sign_detached(b"", &mismatched),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_curve25519_conversion() {
+        // From libsodium's test/default/ed25519_convert.c
+        let seed = decode("421151a459faeade3d247115f94aedae42318124095afabe4d1451a559faedee");
+        let (pk, sk) = crypto_sign_seed_keypair(&seed);
+        let curve_pk = crypto_sign_ed25519_pk_to_curve25519(&pk).unwrap();
+        let curve_sk = crypto_sign_ed25519_sk_to_curve25519(&sk);
+        assert_eq!(
+            curve_pk,
+            decode("f1814f0e8ff1043d8a44d25babff3cedcae6c22c3edaa48f857ae70de2baae50")
+        );
+        assert_eq!(
+            curve_sk,
+            decode("8052030376d47112be7f73ed7a019293dd12ad910b654455798b4667d73de166")
+        );
+        let mut base = [0; 32];
+        base[0] = 9;
+        assert_eq!(x25519(curve_sk, base), curve_pk);
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        assert_eq!(
+            crypto_sign_ed25519_pk_to_curve25519(&identity),
+            Err(Error::WeakPublicKey)
+        );
+    }
+}
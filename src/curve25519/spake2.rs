@@ -0,0 +1,383 @@
+//! This module implements SPAKE2 over edwards25519, as described in RFC 9382:
+//! https://datatracker.ietf.org/doc/html/rfc9382
+//!
+//! SPAKE2 lets two parties sharing a password agree on a strong key, without
+//! letting an eavesdropper, or an active attacker, run an offline dictionary attack.
+//! We use the SPAKE2-edwards25519-SHA256-HKDF-HMAC-SHA256 ciphersuite, with the M and N
+//! constants from Section 6, and no additional authenticated data.
+//!
+//! The password should already have gone through a memory hard function, like
+//! scrypt, or argon2; we only hash it with SHA-512 to get a scalar.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "zeroize")]
+use crate::zeroize::Zeroize;
+use crate::{hkdf, hmac::hmac_sha256, sha256, sha512, zeroize::Zeroizing};
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error,
+};
+
+/// The size of the message each party sends.
+pub const SPAKE2_MESSAGE_SIZE: usize = 32;
+
+/// The size of the shared key, Ke, agreed on at the end of the exchange.
+pub const SPAKE2_KEY_SIZE: usize = 16;
+
+/// The size of the confirmation MAC each party sends after the exchange.
+pub const SPAKE2_CONFIRMATION_SIZE: usize = sha256::HASH_SIZE;
+
+const M: [u8; 32] = [
+    0xd0, 0x48, 0x03, 0x2c, 0x6e, 0xa0, 0xb6, 0xd6, 0x97, 0xdd, 0xc2, 0xe8, 0x6b, 0xda, 0x85, 0xa3,
+    0x3a, 0xda, 0xc9, 0x20, 0xf1, 0xbf, 0x18, 0xe1, 0xb0, 0xc6, 0xd1, 0x66, 0xa5, 0xce, 0xcd, 0xaf,
+];
+
+const N: [u8; 32] = [
+    0xd3, 0xbf, 0xb5, 0x18, 0xf4, 0x4f, 0x34, 0x30, 0xf2, 0x9d, 0x0c, 0x92, 0xaf, 0x50, 0x38, 0x65,
+    0xa1, 0xed, 0x32, 0x81, 0xdc, 0x69, 0xb3, 0x5d, 0xd8, 0x68, 0xba, 0x85, 0xf8, 0x86, 0xc4, 0xab,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    A,
+    B,
+    Symmetric,
+}
+
+impl Role {
+    /// The constants used to blind our message, and to unblind theirs.
+    fn constants(self) -> (Point, Point) {
+        let m = Point::try_from(&M[..]).unwrap();
+        let n = Point::try_from(&N[..]).unwrap();
+        match self {
+            Role::A => (m, n),
+            Role::B => (n, m),
+            Role::Symmetric => (m, m),
+        }
+    }
+}
+
+/// Append some data to the transcript, prefixed with its 8 byte length.
+fn push_prefixed(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// One side of a SPAKE2 exchange, waiting for the message of the other side.
+pub struct Spake2 {
+    role: Role,
+    x: Scalar,
+    w: Scalar,
+    id_a: Vec<u8>,
+    id_b: Vec<u8>,
+    message: [u8; SPAKE2_MESSAGE_SIZE],
+}
+
+impl Spake2 {
+    fn start_with_scalar(
+        role: Role,
+        password: &[u8],
+        id_a: &[u8],
+        id_b: &[u8],
+        x: Scalar,
+    ) -> (Self, [u8; SPAKE2_MESSAGE_SIZE]) {
+        let hash = Zeroizing::new(sha512::hash(password));
+        let w = Scalar::from(*hash);
+        let (ours, _) = role.constants();
+        let message: [u8; 32] = (point::B * x + ours * w).into();
+        let out = Spake2 {
+            role,
+            x,
+            w,
+            id_a: id_a.to_vec(),
+            id_b: id_b.to_vec(),
+            message,
+        };
+        (out, message)
+    }
+
+    /// Start the exchange as party A, returning the message to send to B.
+    ///
+    /// The identities can be empty, if both parties know them implicitly.
+    pub fn start_a<R: RngCore + CryptoRng>(
+        password: &[u8],
+        id_a: &[u8],
+        id_b: &[u8],
+        rng: &mut R,
+    ) -> (Self, [u8; SPAKE2_MESSAGE_SIZE]) {
+        Self::start_with_scalar(Role::A, password, id_a, id_b, Scalar::random(rng))
+    }
+
+    /// Start the exchange as party B, returning the message to send to A.
+    pub fn start_b<R: RngCore + CryptoRng>(
+        password: &[u8],
+        id_a: &[u8],
+        id_b: &[u8],
+        rng: &mut R,
+    ) -> (Self, [u8; SPAKE2_MESSAGE_SIZE]) {
+        Self::start_with_scalar(Role::B, password, id_a, id_b, Scalar::random(rng))
+    }
+
+    /// Start an exchange where neither party knows which role it has.
+    ///
+    /// Both sides blind their message with M, and the transcript puts the smaller
+    /// message first, so that both agree on it. The identity is shared by both sides,
+    /// e.g. the name of a channel.
+    pub fn start_symmetric<R: RngCore + CryptoRng>(
+        password: &[u8],
+        id: &[u8],
+        rng: &mut R,
+    ) -> (Self, [u8; SPAKE2_MESSAGE_SIZE]) {
+        Self::start_with_scalar(Role::Symmetric, password, id, id, Scalar::random(rng))
+    }
+
+    /// Finish the exchange with the message of the other party, deriving the keys.
+    ///
+    /// This fails if the message isn't a point, or if the shared element is the identity.
+    pub fn finish(self, theirs: &[u8; SPAKE2_MESSAGE_SIZE]) -> Result<Spake2Keys, Error> {
+        let (_, their_constant) = self.role.constants();
+        let their_point = Point::try_from(&theirs[..]).map_err(|_| Error::PointNotOnCurve)?;
+        let k = (their_point + their_constant * -self.w) * self.x;
+        let k = Zeroizing::new(<[u8; 32]>::from(k.doubled().doubled().doubled()));
+        let identity: [u8; 32] = Point::identity().into();
+        if *k == identity {
+            return Err(Error::WeakPublicKey);
+        }
+
+        // In the symmetric case, whoever sent the smaller message acts as A.
+        let we_are_a = match self.role {
+            Role::A => true,
+            Role::B => false,
+            Role::Symmetric => self.message <= *theirs,
+        };
+        let (p_a, p_b) = if we_are_a {
+            (&self.message, theirs)
+        } else {
+            (theirs, &self.message)
+        };
+        let mut w = Zeroizing::new(<[u8; 32]>::from(self.w));
+        w.reverse();
+        let mut transcript = Zeroizing::new(Vec::new());
+        push_prefixed(&mut transcript, &self.id_a);
+        push_prefixed(&mut transcript, &self.id_b);
+        push_prefixed(&mut transcript, p_a);
+        push_prefixed(&mut transcript, p_b);
+        push_prefixed(&mut transcript, &*k);
+        push_prefixed(&mut transcript, &*w);
+
+        let hash = Zeroizing::new(sha256::hash(&transcript));
+        let mut out = Spake2Keys {
+            shared_key: [0; SPAKE2_KEY_SIZE],
+            ours: [0; SPAKE2_CONFIRMATION_SIZE],
+            theirs: [0; SPAKE2_CONFIRMATION_SIZE],
+        };
+        out.shared_key.copy_from_slice(&hash[..SPAKE2_KEY_SIZE]);
+        let mut confirmation_keys = Zeroizing::new([0; 2 * SPAKE2_KEY_SIZE]);
+        hkdf::derive_sha256(
+            &[],
+            &hash[SPAKE2_KEY_SIZE..],
+            b"ConfirmationKeys",
+            &mut *confirmation_keys,
+        );
+        let c_a = hmac_sha256(&confirmation_keys[..SPAKE2_KEY_SIZE], &transcript);
+        let c_b = hmac_sha256(&confirmation_keys[SPAKE2_KEY_SIZE..], &transcript);
+        if we_are_a {
+            out.ours = c_a;
+            out.theirs = c_b;
+        } else {
+            out.ours = c_b;
+            out.theirs = c_a;
+        }
+        Ok(out)
+    }
+}
+
+/// This never prints the secret scalars.
+impl fmt::Debug for Spake2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spake2")
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Spake2 {
+    fn drop(&mut self) {
+        self.x.zeroize();
+        self.w.zeroize();
+    }
+}
+
+/// The keys resulting from a SPAKE2 exchange.
+///
+/// The shared key shouldn't be used before the other party's confirmation
+/// has been checked, since that's the only way of knowing they had the password.
+pub struct Spake2Keys {
+    shared_key: [u8; SPAKE2_KEY_SIZE],
+    ours: [u8; SPAKE2_CONFIRMATION_SIZE],
+    theirs: [u8; SPAKE2_CONFIRMATION_SIZE],
+}
+
+impl Spake2Keys {
+    pub fn shared_key(&self) -> [u8; SPAKE2_KEY_SIZE] {
+        self.shared_key
+    }
+
+    /// The confirmation MAC to send to the other party.
+    pub fn confirmation(&self) -> [u8; SPAKE2_CONFIRMATION_SIZE] {
+        self.ours
+    }
+
+    /// Check, in constant time, the confirmation MAC sent by the other party.
+    pub fn verify_confirmation(
+        &self,
+        theirs: &[u8; SPAKE2_CONFIRMATION_SIZE],
+    ) -> Result<(), Error> {
+        if !bool::from(self.theirs.ct_eq(theirs)) {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(())
+    }
+}
+
+/// This never prints the keys.
+impl fmt::Debug for Spake2Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spake2Keys").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Spake2Keys {
+    fn drop(&mut self) {
+        self.shared_key.zeroize();
+        self.ours.zeroize();
+        self.theirs.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scalar(byte: u8) -> Scalar {
+        let mut wide = [0; 64];
+        wide[..32].copy_from_slice(&[byte; 32]);
+        Scalar::from(wide)
+    }
+
+    fn check_exchange(role_b: Role, id_a: &[u8], id_b: &[u8], expected: [&str; 5]) {
+        let role_a = if role_b == Role::B { Role::A } else { role_b };
+        let (a, p_a) = Spake2::start_with_scalar(role_a, b"password", id_a, id_b, scalar(1));
+        let (b, p_b) = Spake2::start_with_scalar(role_b, b"password", id_a, id_b, scalar(2));
+        assert_eq!(hex::encode(p_a), expected[0]);
+        assert_eq!(hex::encode(p_b), expected[1]);
+        let keys_a = a.finish(&p_b).unwrap();
+        let keys_b = b.finish(&p_a).unwrap();
+        assert_eq!(hex::encode(keys_a.shared_key()), expected[2]);
+        assert_eq!(keys_a.shared_key(), keys_b.shared_key());
+        assert_eq!(hex::encode(keys_a.confirmation()), expected[3]);
+        assert_eq!(hex::encode(keys_b.confirmation()), expected[4]);
+        assert!(keys_a.verify_confirmation(&keys_b.confirmation()).is_ok());
+        assert!(keys_b.verify_confirmation(&keys_a.confirmation()).is_ok());
+    }
+
+    #[test]
+    fn test_exchange_vectors() {
+        // RFC 9382 only has test vectors for P-256, in Appendix B, so there are none for
+        // edwards25519. These pin down the outputs of this implementation, with x, and y,
+        // being [1; 32], and [2; 32], reduced; test_transcript_follows_section_4 rebuilds
+        // the first exchange from the equations of the RFC, step by step.
+        check_exchange(
+            Role::B,
+            b"client",
+            b"server",
+            [
+                "056e75a35848a8511e859328771c63051dd7106fbc1b542ec6b39d1e97612d2f",
+                "112cf0e75eb10a4a5594da2a0463ce55a3406d9f4ac072f5df9978285a48195c",
+                "898ba437e35ce1239e0556704f9a99e8",
+                "f29ab9faf173f2f362288a5275f0fe0122dcb446005e45271f3287a32a5ab222",
+                "1c369cf4fa9310349c4215aa05378890f82ef77b845ab2d2614a4ab9c85b6e78",
+            ],
+        );
+        check_exchange(
+            Role::Symmetric,
+            b"channel",
+            b"channel",
+            [
+                "056e75a35848a8511e859328771c63051dd7106fbc1b542ec6b39d1e97612d2f",
+                "d2869b3e1692ce9bb01f165478ec11fb003601ce3731563d96d99cd2566c4a8b",
+                "4e123efda5eb052b6b50be417aefe601",
+                "eab7dc3831f7ed1f37e893bdb06bdb7ab33f118858be5684e03c2f5ee2764397",
+                "16314fa17aee9ef5010be83e28f8d5f2bb1999eb29213c42b83260a060d4f079",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_transcript_follows_section_4() {
+        let w = Scalar::from(sha512::hash(b"password"));
+        let m = Point::try_from(&M[..]).unwrap();
+        let n = Point::try_from(&N[..]).unwrap();
+        // Section 3.3: pA = x * G + w * M, pB = y * G + w * N, K = h * x * (pB - w * N)
+        let p_a: [u8; 32] = (point::B * scalar(1) + m * w).into();
+        let p_b: [u8; 32] = (point::B * scalar(2) + n * w).into();
+        let big_k: [u8; 32] = (point::B * (scalar(1) * scalar(2) * Scalar::from(8))).into();
+        let mut w_bytes: [u8; 32] = w.into();
+        w_bytes.reverse();
+
+        // Section 4: TT = len(A) || A || len(B) || B || len(pA) || pA || len(pB) || pB
+        //                 || len(K) || K || len(w) || w, with 8 byte little endian lengths
+        let mut tt = Vec::new();
+        tt.extend_from_slice(&[6, 0, 0, 0, 0, 0, 0, 0]);
+        tt.extend_from_slice(b"client");
+        tt.extend_from_slice(&[6, 0, 0, 0, 0, 0, 0, 0]);
+        tt.extend_from_slice(b"server");
+        for element in [&p_a, &p_b, &big_k, &w_bytes].iter() {
+            tt.extend_from_slice(&[32, 0, 0, 0, 0, 0, 0, 0]);
+            tt.extend_from_slice(&element[..]);
+        }
+
+        // Ke || Ka = Hash(TT), KcA || KcB = KDF(nil, Ka, "ConfirmationKeys")
+        let hash = sha256::hash(&tt);
+        let mut kc = [0; 32];
+        hkdf::derive_sha256(&[], &hash[16..], b"ConfirmationKeys", &mut kc);
+
+        let (a, _) =
+            Spake2::start_with_scalar(Role::A, b"password", b"client", b"server", scalar(1));
+        let keys = a.finish(&p_b).unwrap();
+        assert_eq!(keys.shared_key(), hash[..16]);
+        assert_eq!(keys.confirmation(), hmac_sha256(&kc[..16], &tt));
+        assert!(keys
+            .verify_confirmation(&hmac_sha256(&kc[16..], &tt))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_wrong_password_fails_confirmation() {
+        let mut rng = rand::thread_rng();
+        let (a, p_a) = Spake2::start_a(b"password", b"", b"", &mut rng);
+        let (b, p_b) = Spake2::start_b(b"passw0rd", b"", b"", &mut rng);
+        let keys_a = a.finish(&p_b).unwrap();
+        let keys_b = b.finish(&p_a).unwrap();
+        assert_ne!(keys_a.shared_key(), keys_b.shared_key());
+        assert_eq!(
+            keys_a.verify_confirmation(&keys_b.confirmation()),
+            Err(Error::SignatureInvalid)
+        );
+
+        // Sending just w * N makes the shared element the identity.
+        let (a, _) = Spake2::start_a(b"password", b"", b"", &mut rng);
+        let (_, w_n) = Spake2::start_with_scalar(Role::B, b"password", b"", b"", scalar(0));
+        assert_eq!(a.finish(&w_n).unwrap_err(), Error::WeakPublicKey);
+    }
+}
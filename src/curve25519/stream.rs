@@ -0,0 +1,190 @@
+//! This module implements signing and verifying streams, through `io::Write`.
+//!
+//! A stream can't be hashed twice, as plain Ed25519 needs, so these sign with Ed25519ph,
+//! which signs the SHA-512 hash of the message instead, with an empty context, following
+//! Section 5.1 of RFC 8032:
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.1
+//!
+//! Ed25519ph signatures are not valid Ed25519 signatures of the same message, and vice versa.
+
+use core::convert::{TryFrom, TryInto};
+use std::io;
+
+use super::{
+    error::{SignatureError, VerificationError},
+    point::{self, Point},
+    scalar::Scalar,
+    PrivateKey, PublicKey, Signature,
+};
+use crate::sha512::{self, Sha512};
+use crate::zeroize::Zeroizing;
+
+/// The prefix dom2(1, ""), separating Ed25519ph from plain Ed25519.
+const DOM2_PH: &[u8] = b"SigEd25519 no Ed25519 collisions\x01\x00";
+
+/// Calculate the challenge k := H(dom2 || R || A || PH(M)) of a prehashed signature.
+fn challenge(r_bytes: &[u8], a_bytes: &[u8; 32], prehash: &[u8; 64]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(DOM2_PH);
+    hasher.update(r_bytes);
+    hasher.update(a_bytes);
+    hasher.update(prehash);
+    Scalar::from_hash(hasher)
+}
+
+/// A writer signing everything written to it, created by `PrivateKey::sign_stream`.
+pub struct SigningStream<'a> {
+    key: &'a PrivateKey,
+    hasher: Sha512,
+}
+
+impl<'a> SigningStream<'a> {
+    /// Produce an Ed25519ph signature over everything written so far.
+    pub fn finalize(self) -> Signature {
+        let prehash = self.hasher.finalize();
+        let hash = Zeroizing::new(sha512::hash(&self.key.bytes));
+        let s = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
+        let a: [u8; 32] = (point::B * *s).into();
+
+        let mut hasher = Sha512::new();
+        hasher.update(DOM2_PH);
+        hasher.update(&hash[32..]);
+        hasher.update(&prehash);
+        let r = Zeroizing::new(Scalar::from_hash(hasher));
+
+        let big_r: [u8; 32] = (point::B * *r).into();
+        let k = challenge(&big_r, &a, &prehash);
+        let big_s: [u8; 32] = (*r + k * *s).into();
+
+        let mut out = Signature { bytes: [0; 64] };
+        out.bytes[..32].copy_from_slice(&big_r);
+        out.bytes[32..].copy_from_slice(&big_s);
+        out
+    }
+}
+
+impl<'a> io::Write for SigningStream<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hasher.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer checking a signature of everything written to it, created by `PublicKey::verify_stream`.
+pub struct VerifyingStream {
+    key: PublicKey,
+    hasher: Sha512,
+}
+
+impl VerifyingStream {
+    fn finalize_result(self, signature: Signature) -> Result<(), SignatureError> {
+        let prehash = self.hasher.finalize();
+        let s = Scalar::try_from(&signature.bytes[32..])?;
+        let a = Point::try_from(&self.key.bytes[..])?;
+        let r_bytes = &signature.bytes[..32];
+        let k = challenge(r_bytes, &self.key.bytes, &prehash);
+        super::verifying::check_equation(r_bytes, s, k, a, None)
+    }
+
+    /// Check an Ed25519ph signature over everything written so far.
+    pub fn finalize(self, signature: Signature) -> bool {
+        self.finalize_result(signature).is_ok()
+    }
+
+    /// Check an Ed25519ph signature over everything written so far, returning an error if it's invalid.
+    pub fn try_finalize(self, signature: Signature) -> Result<(), VerificationError> {
+        Ok(self.finalize_result(signature)?)
+    }
+}
+
+impl io::Write for VerifyingStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hasher.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl PrivateKey {
+    /// Start signing a message incrementally, e.g. with `io::copy`.
+    ///
+    /// This produces an Ed25519ph signature, which only `PublicKey::verify_stream` accepts.
+    pub fn sign_stream(&self) -> SigningStream<'_> {
+        SigningStream {
+            key: self,
+            hasher: Sha512::new(),
+        }
+    }
+}
+
+impl PublicKey {
+    /// Start verifying an Ed25519ph signature of a message incrementally.
+    pub fn verify_stream(&self) -> VerifyingStream {
+        VerifyingStream {
+            key: *self,
+            hasher: Sha512::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    fn decode<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(s, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_rfc_8032_vector() {
+        // The Ed25519ph test from Section 7.3 of RFC 8032.
+        let private = PrivateKey::from_seed(decode(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+        ));
+        let public = private.derive_public_key();
+        assert_eq!(
+            public.bytes,
+            decode("ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf")
+        );
+        let mut signer = private.sign_stream();
+        signer.write_all(b"a").unwrap();
+        signer.write_all(b"bc").unwrap();
+        let signature = signer.finalize();
+        assert_eq!(
+            signature.bytes,
+            decode::<64>("98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406")
+        );
+        let mut verifier = public.verify_stream();
+        verifier.write_all(b"abc").unwrap();
+        assert!(verifier.finalize(signature));
+    }
+
+    #[test]
+    fn test_rejects_other_messages() {
+        let private = PrivateKey::from_seed([14; 32]);
+        let public = private.derive_public_key();
+        let data = vec![7; 100_000];
+        let mut signer = private.sign_stream();
+        io::copy(&mut &data[..], &mut signer).unwrap();
+        let signature = signer.finalize();
+        let mut verifier = public.verify_stream();
+        io::copy(&mut &data[..], &mut verifier).unwrap();
+        assert!(verifier.try_finalize(signature).is_ok());
+        let mut verifier = public.verify_stream();
+        verifier.write_all(&data[1..]).unwrap();
+        assert!(verifier.try_finalize(signature).is_err());
+        // Prehashed signatures don't verify as plain ones.
+        assert!(!public.verify(&data, signature));
+    }
+}
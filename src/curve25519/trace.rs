@@ -0,0 +1,178 @@
+//! This module emits structured spans, and events, with the `tracing` feature.
+//!
+//! Operators can count, and time, key generation, signing, and verification,
+//! by installing a subscriber. Only lengths, counts, and outcomes get recorded:
+//! never keys, nonces, scalars, or messages.
+//!
+//! Without the feature, all of these functions compile down to nothing.
+
+/// The target every span, and event, gets recorded under.
+#[cfg(feature = "tracing")]
+const TARGET: &str = "eddo";
+
+/// Keeps a span entered until it gets dropped.
+#[cfg(feature = "tracing")]
+pub type Guard = tracing::span::EnteredSpan;
+
+/// Keeps a span entered until it gets dropped.
+#[cfg(not(feature = "tracing"))]
+pub struct Guard;
+
+/// Enter the span around generating a key pair.
+pub fn keygen() -> Guard {
+    #[cfg(feature = "tracing")]
+    return tracing::debug_span!(target: TARGET, "keygen").entered();
+    #[cfg(not(feature = "tracing"))]
+    Guard
+}
+
+/// Enter the span around signing a message.
+pub fn sign(message_len: usize) -> Guard {
+    #[cfg(feature = "tracing")]
+    return tracing::debug_span!(target: TARGET, "sign", message_len).entered();
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = message_len;
+        Guard
+    }
+}
+
+/// Enter the span around verifying a single signature.
+pub fn verify(message_len: usize) -> Guard {
+    #[cfg(feature = "tracing")]
+    return tracing::debug_span!(target: TARGET, "verify", message_len).entered();
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = message_len;
+        Guard
+    }
+}
+
+/// Enter the span around verifying a batch of signatures.
+#[cfg(feature = "alloc")]
+pub fn verify_batch(size: usize) -> Guard {
+    #[cfg(feature = "tracing")]
+    return tracing::debug_span!(target: TARGET, "verify_batch", size).entered();
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = size;
+        Guard
+    }
+}
+
+/// Record the outcome of verification, inside of the current span.
+pub fn verified(valid: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(target: TARGET, valid);
+    #[cfg(not(feature = "tracing"))]
+    let _ = valid;
+}
+
+#[cfg(all(test, feature = "tracing", feature = "std"))]
+mod test {
+    use std::{
+        fmt,
+        string::{String, ToString},
+        sync::{Arc, Mutex},
+        vec::Vec,
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::super::{gen_keypair_from_seed, verify_batch};
+
+    /// Records the name, and fields, of everything reported to it.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Line<'a>(&'a mut String);
+
+    impl<'a> Visit for Line<'a> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut line = span.metadata().name().to_string();
+            span.record(&mut Line(&mut line));
+            let mut lines = self.lines.lock().unwrap();
+            lines.push(line);
+            span::Id::from_u64(lines.len() as u64)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = String::from("event");
+            event.record(&mut Line(&mut line));
+            self.lines.lock().unwrap().push(line);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    fn record<F: FnOnce()>(f: F) -> Vec<String> {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), f);
+        let lines = recorder.lines.lock().unwrap();
+        lines.clone()
+    }
+
+    #[test]
+    fn test_operations_are_recorded() {
+        let lines = record(|| {
+            let (public, private) = gen_keypair_from_seed([7; 32]);
+            let signature = private.sign(b"hello");
+            assert!(public.verify(b"hello", signature));
+            assert!(!public.verify(b"goodbye", signature));
+            assert!(verify_batch(
+                &[&b"hello"[..]],
+                &[signature],
+                &[public],
+                &mut rand::rngs::OsRng
+            ));
+        });
+        assert_eq!(
+            lines,
+            [
+                "keygen",
+                "sign message_len=5",
+                "verify message_len=5",
+                "event valid=true",
+                "verify message_len=7",
+                "event valid=false",
+                "verify_batch size=1",
+                "event valid=true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_secrets_are_recorded() {
+        let seed = [0xAB; 32];
+        let lines = record(|| {
+            let (_, private) = gen_keypair_from_seed(seed);
+            private.sign(b"secret message");
+        });
+        let secret = hex::encode(seed);
+        for line in lines {
+            assert!(!line.contains(&secret));
+            assert!(!line.contains("secret message"));
+        }
+    }
+}
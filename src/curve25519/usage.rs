@@ -0,0 +1,208 @@
+//! This module provides wrappers restricting a private key to a single use.
+//!
+//! An Ed25519 seed can be used for signing, but the scalar it expands to can also
+//! be used for X25519 key exchange. Using the same key for both is a common source
+//! of cross-protocol attacks, so we make doing so an explicit decision.
+//!
+//! A `PrivateKey` can be turned into a `SigningOnlyKey`, or an `ExchangeOnlyKey`,
+//! but neither of these can be turned back into a `PrivateKey`, or into each other.
+//! Getting both from the same seed requires calling `PrivateKey::into_dual_use`.
+
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
+
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::{
+    sha512,
+    zeroize::{Zeroize, Zeroizing},
+};
+
+use super::{
+    error::Error,
+    montgomery::{self, BASE_U},
+    point::Point,
+    PrivateKey, PublicKey, Signature,
+};
+
+/// A private key which can only be used for signing.
+#[derive(Debug)]
+pub struct SigningOnlyKey {
+    key: PrivateKey,
+}
+
+impl SigningOnlyKey {
+    /// The public key used to verify signatures made with this key.
+    pub fn public_key(&self) -> PublicKey {
+        self.key.derive_public_key()
+    }
+
+    /// Sign a message, as with `PrivateKey::sign`.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.key.sign(message)
+    }
+}
+
+pub const EXCHANGE_PUBLIC_KEY_SIZE: usize = 32;
+
+/// A public key for X25519 key exchange, i.e. an encoded Montgomery u coordinate.
+///
+/// Any 32 bytes are accepted: the top bit gets ignored, and values ≥ p get reduced,
+/// following RFC 7748. Keys of small order get rejected when exchanging.
+#[derive(Debug, Clone, Copy)]
+pub struct ExchangePublicKey {
+    pub bytes: [u8; EXCHANGE_PUBLIC_KEY_SIZE],
+}
+
+impl ConstantTimeEq for ExchangePublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+/// This compares in constant time, like `ct_eq`.
+impl PartialEq for ExchangePublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ExchangePublicKey {}
+
+impl ExchangePublicKey {
+    /// Convert a signing public key into the corresponding exchange public key.
+    ///
+    /// This returns `None` if the public key isn't a valid point.
+    pub fn from_signing_key(public: &PublicKey) -> Option<Self> {
+        let point = Point::try_from(&public.bytes[..]).ok()?;
+        let u = point.montgomery_u();
+        Some(ExchangePublicKey {
+            bytes: (u.u * u.w.inverse()).into(),
+        })
+    }
+}
+
+/// A private key which can only be used for X25519 key exchange.
+///
+/// This uses the same secret scalar that signing would, so the exchange public key
+/// is the Montgomery form of the signing public key.
+pub struct ExchangeOnlyKey {
+    scalar: [u8; 32],
+}
+
+/// Like with `PrivateKey`, this never prints the secret scalar.
+impl fmt::Debug for ExchangeOnlyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExchangeOnlyKey").finish_non_exhaustive()
+    }
+}
+
+impl ExchangeOnlyKey {
+    /// The public key other parties use to exchange with this key.
+    pub fn public_key(&self) -> ExchangePublicKey {
+        ExchangePublicKey {
+            bytes: montgomery::x25519(self.scalar, BASE_U),
+        }
+    }
+
+    /// Calculate a shared secret with another party's public key.
+    ///
+    /// This fails if the result is all zeros, which happens when the other
+    /// public key has small order, and means that the secret doesn't depend on our key.
+    pub fn exchange(&self, public: &ExchangePublicKey) -> Result<[u8; 32], Error> {
+        let shared = montgomery::x25519(self.scalar, public.bytes);
+        if bool::from(shared.ct_eq(&[0; 32])) {
+            return Err(Error::WeakPublicKey);
+        }
+        Ok(shared)
+    }
+}
+
+impl Zeroize for ExchangeOnlyKey {
+    fn zeroize(&mut self) {
+        self.scalar.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ExchangeOnlyKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl PrivateKey {
+    /// Restrict this key to only be used for signing.
+    pub fn into_signing_only(self) -> SigningOnlyKey {
+        SigningOnlyKey { key: self }
+    }
+
+    /// Restrict this key to only be used for key exchange.
+    pub fn into_exchange_only(self) -> ExchangeOnlyKey {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        ExchangeOnlyKey {
+            scalar: hash[..32].try_into().unwrap(),
+        }
+    }
+
+    /// Use this key for both signing and key exchange.
+    ///
+    /// This should only be done when the protocols using this key have been designed
+    /// to safely share it, since otherwise messages from one protocol might be
+    /// usable in the other.
+    pub fn into_dual_use(self) -> (SigningOnlyKey, ExchangeOnlyKey) {
+        let exchange = self.clone().into_exchange_only();
+        (self.into_signing_only(), exchange)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signing_only_key_signs() {
+        let signing = PrivateKey { bytes: [3; 32] }.into_signing_only();
+        let public = signing.public_key();
+        let sig = signing.sign(b"message");
+        assert!(public.verify(b"message", sig));
+    }
+
+    #[test]
+    fn test_exchange_agrees() {
+        let alice = PrivateKey { bytes: [1; 32] }.into_exchange_only();
+        let bob = PrivateKey { bytes: [2; 32] }.into_exchange_only();
+        let alice_shared = alice.exchange(&bob.public_key()).unwrap();
+        let bob_shared = bob.exchange(&alice.public_key()).unwrap();
+        assert_eq!(alice_shared, bob_shared);
+    }
+
+    #[test]
+    fn test_exchange_rejects_zero_output() {
+        let key = PrivateKey { bytes: [1; 32] }.into_exchange_only();
+        let zero = ExchangePublicKey { bytes: [0; 32] };
+        assert!(key.exchange(&zero).is_err());
+        // p + 1 is the same as 1, which has order 4, even with the top bit set
+        let mut p_plus_1 = ExchangePublicKey { bytes: [0xFF; 32] };
+        p_plus_1.bytes[0] = 0xEE;
+        assert!(key.exchange(&p_plus_1).is_err());
+    }
+
+    #[test]
+    fn test_dual_use_public_keys_correspond() {
+        let (signing, exchange) = PrivateKey { bytes: [5; 32] }.into_dual_use();
+        let converted = ExchangePublicKey::from_signing_key(&signing.public_key()).unwrap();
+        assert_eq!(converted.bytes, exchange.public_key().bytes);
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let (signing, exchange) = PrivateKey { bytes: [0xAB; 32] }.into_dual_use();
+        let printed = format!("{:?} {:?}", signing, exchange);
+        // Derived formatting would print the bytes in decimal
+        assert!(!printed.contains("171"));
+        assert_eq!(format!("{:?}", exchange), "ExchangeOnlyKey { .. }");
+    }
+}
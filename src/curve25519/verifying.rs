@@ -0,0 +1,149 @@
+//! This module implements a public key prepared for verifying many signatures.
+//!
+//! Verifying a signature needs the public key A as a point, along with a table of
+//! its small multiples, for Straus's method. A `VerifyingKey` does this work once,
+//! up front, rather than once per signature. The equation checked is the same as
+//! in `PublicKey::verify`, following Section 5.1.7 of RFC 8032:
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.7
+
+use core::{convert::TryFrom, fmt, ops::Deref};
+
+use subtle::ConstantTimeEq;
+
+use super::{
+    error::{SignatureError, VerificationError},
+    msm::{self, StrausTable},
+    point::{self, Point},
+    scalar::Scalar,
+    trace, PublicKey, Signature,
+};
+
+/// Check that R = [s]B - [k]A, given the encoding of R.
+///
+/// With a table of multiples of A, this skips building it.
+pub(super) fn check_equation(
+    r_bytes: &[u8],
+    s: Scalar,
+    k: Scalar,
+    a: Point,
+    table: Option<&StrausTable>,
+) -> Result<(), SignatureError> {
+    let check = match table {
+        Some(table) => msm::straus_with_tables(&[s, -k], &[msm::straus_table(point::B), *table]),
+        None => Point::vartime_multiscalar_mul(&[s, -k], &[point::B, a]),
+    };
+    let check_encoded: [u8; 32] = check.into();
+    if !bool::from(r_bytes.ct_eq(&check_encoded[..])) {
+        return Err(SignatureError::InvalidEquation);
+    }
+    Ok(())
+}
+
+/// A public key, along with the precomputation used to verify signatures.
+///
+/// This is worth creating when checking several signatures under the same key.
+#[derive(Clone)]
+pub struct VerifyingKey {
+    public: PublicKey,
+    point: Point,
+    encoded: [u8; 32],
+    table: StrausTable,
+}
+
+/// Every public key encodes a valid point, so this can't fail.
+impl From<PublicKey> for VerifyingKey {
+    fn from(public: PublicKey) -> Self {
+        let point = Point::try_from(&public.bytes[..]).unwrap();
+        VerifyingKey {
+            public,
+            point,
+            encoded: point.into(),
+            table: msm::straus_table(point),
+        }
+    }
+}
+
+/// This only shows the public key, leaving out the precomputed points.
+impl fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifyingKey")
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+impl VerifyingKey {
+    /// The public key this was created from.
+    pub fn public_key(&self) -> PublicKey {
+        self.public
+    }
+
+    fn verify_result<M: Deref<Target = [u8]>>(
+        &self,
+        message: &[M],
+        signature: Signature,
+    ) -> Result<(), SignatureError> {
+        let _span = trace::verify(message.iter().map(|part| part.len()).sum());
+        let result = self.verify_equation(message, signature);
+        trace::verified(result.is_ok());
+        result
+    }
+
+    fn verify_equation<M: Deref<Target = [u8]>>(
+        &self,
+        message: &[M],
+        signature: Signature,
+    ) -> Result<(), SignatureError> {
+        let s = Scalar::try_from(&signature.bytes[32..])?;
+        let r_bytes = &signature.bytes[..32];
+        let k = PublicKey::challenge_encoded(r_bytes, &self.encoded, message);
+        check_equation(r_bytes, s, k, self.point, Some(&self.table))
+    }
+
+    /// Verify a signature, accepting exactly the same signatures as `PublicKey::verify`.
+    pub fn verify(&self, message: &[u8], signature: Signature) -> bool {
+        self.verify_result(&[message], signature).is_ok()
+    }
+
+    /// Verify a signature, returning an error if it's invalid.
+    pub fn try_verify(
+        &self,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<(), VerificationError> {
+        Ok(self.verify_result(&[message], signature)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_agrees_with_public_key() {
+        let private = PrivateKey::from_seed([3; 32]);
+        let public = private.derive_public_key();
+        let key = VerifyingKey::from(public);
+        assert_eq!(key.public_key(), public);
+        let signature = private.sign(b"many messages");
+        let mut forged = signature;
+        forged.bytes[0] ^= 1;
+        let mut non_canonical = signature;
+        non_canonical.bytes[63] |= 0xF0;
+        for &(message, signature) in &[
+            (&b"many messages"[..], signature),
+            (&b"other messages"[..], signature),
+            (&b"many messages"[..], forged),
+            (&b"many messages"[..], non_canonical),
+        ] {
+            assert_eq!(
+                key.verify(message, signature),
+                public.verify(message, signature)
+            );
+        }
+        assert!(key.try_verify(b"many messages", signature).is_ok());
+        assert!(key.try_verify(b"many messages", forged).is_err());
+    }
+}
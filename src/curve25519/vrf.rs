@@ -0,0 +1,214 @@
+//! This module implements ECVRF-EDWARDS25519-SHA512-TAI, as described in RFC 9381:
+//! https://datatracker.ietf.org/doc/html/rfc9381
+//!
+//! A verifiable random function maps inputs to outputs which look random to anyone
+//! without the private key, along with a proof that the output is the right one.
+//!
+//! We only implement the suite hashing to the curve with try and increment, from
+//! Section 5.4.1.1. This isn't constant time, but the input is usually public.
+
+use core::convert::{TryFrom, TryInto};
+
+use subtle::ConstantTimeEq;
+
+use crate::sha512::{self, Sha512};
+use crate::zeroize::Zeroizing;
+
+use super::{
+    point::{self, Point},
+    scalar::Scalar,
+    Error, PrivateKey, PublicKey,
+};
+
+/// The size of a proof, containing a point, a 16 byte challenge, and a scalar.
+pub const VRF_PROOF_SIZE: usize = 80;
+
+/// The size of the output of the VRF.
+pub const VRF_OUTPUT_SIZE: usize = 64;
+
+const SUITE: u8 = 0x03;
+const CHALLENGE_SIZE: usize = 16;
+
+fn clear_cofactor(point: Point) -> Point {
+    point.doubled().doubled().doubled()
+}
+
+/// Hash an input to a point, following Section 5.4.1.1.
+fn encode_to_curve(public: &PublicKey, alpha: &[u8]) -> Result<Point, Error> {
+    for ctr in 0..=255u8 {
+        let mut hasher = Sha512::new();
+        hasher.update(&[SUITE, 0x01]);
+        hasher.update(&public.bytes);
+        hasher.update(alpha);
+        hasher.update(&[ctr, 0x00]);
+        if let Ok(point) = Point::try_from(&hasher.finalize()[..32]) {
+            return Ok(clear_cofactor(point));
+        }
+    }
+    // This happens with probability about 2^-256.
+    Err(Error::PointNotOnCurve)
+}
+
+/// Calculate the challenge for a proof, following Section 5.4.3.
+fn challenge(points: [Point; 5]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(&[SUITE, 0x02]);
+    for point in points.iter() {
+        let bytes: [u8; 32] = (*point).into();
+        hasher.update(&bytes);
+    }
+    hasher.update(&[0x00]);
+    let mut wide = [0; 64];
+    wide[..CHALLENGE_SIZE].copy_from_slice(&hasher.finalize()[..CHALLENGE_SIZE]);
+    Scalar::from(wide)
+}
+
+fn truncated(c: Scalar) -> [u8; CHALLENGE_SIZE] {
+    let bytes: [u8; 32] = c.into();
+    bytes[..CHALLENGE_SIZE].try_into().unwrap()
+}
+
+/// A proof that some output of the VRF is correct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VrfProof {
+    pub bytes: [u8; VRF_PROOF_SIZE],
+}
+
+impl From<[u8; VRF_PROOF_SIZE]> for VrfProof {
+    fn from(bytes: [u8; VRF_PROOF_SIZE]) -> Self {
+        VrfProof { bytes }
+    }
+}
+
+impl VrfProof {
+    fn gamma(&self) -> Result<Point, Error> {
+        Point::try_from(&self.bytes[..32]).map_err(|_| Error::PointNotOnCurve)
+    }
+
+    /// Extract the output of the VRF from this proof, without checking it.
+    ///
+    /// This should only be used on proofs that have been verified already.
+    pub fn output(&self) -> Result<[u8; VRF_OUTPUT_SIZE], Error> {
+        let gamma: [u8; 32] = clear_cofactor(self.gamma()?).into();
+        let mut hasher = Sha512::new();
+        hasher.update(&[SUITE, 0x03]);
+        hasher.update(&gamma);
+        hasher.update(&[0x00]);
+        Ok(hasher.finalize())
+    }
+}
+
+impl PrivateKey {
+    /// Evaluate the VRF on some input, producing a proof, which contains the output.
+    pub fn vrf_prove(&self, alpha: &[u8]) -> Result<VrfProof, Error> {
+        let hash = Zeroizing::new(sha512::hash(&self.bytes));
+        let x = Zeroizing::new(Scalar::clamped(hash[..32].try_into().unwrap()));
+        let public = PublicKey {
+            bytes: (point::B * *x).into(),
+        };
+        let h = encode_to_curve(&public, alpha)?;
+        let gamma = h * *x;
+
+        let h_bytes: [u8; 32] = h.into();
+        let mut hasher = Sha512::new();
+        hasher.update(&hash[32..]);
+        hasher.update(&h_bytes);
+        let k = Zeroizing::new(Scalar::from_hash(hasher));
+
+        let y = Point::try_from(&public.bytes[..]).unwrap();
+        let c = challenge([y, h, gamma, point::B * *k, h * *k]);
+        let s: [u8; 32] = (*k + c * *x).into();
+
+        let mut out = VrfProof {
+            bytes: [0; VRF_PROOF_SIZE],
+        };
+        out.bytes[..32].copy_from_slice(&<[u8; 32]>::from(gamma));
+        out.bytes[32..48].copy_from_slice(&truncated(c));
+        out.bytes[48..].copy_from_slice(&s);
+        Ok(out)
+    }
+}
+
+impl PublicKey {
+    /// Verify a proof for some input, returning the output of the VRF if it's valid.
+    ///
+    /// Keys of small order get rejected, since they don't bind the output to the input.
+    pub fn vrf_verify(
+        &self,
+        alpha: &[u8],
+        proof: &VrfProof,
+    ) -> Result<[u8; VRF_OUTPUT_SIZE], Error> {
+        let y = Point::try_from(&self.bytes[..]).unwrap();
+        let identity: [u8; 32] = Point::identity().into();
+        if <[u8; 32]>::from(clear_cofactor(y)) == identity {
+            return Err(Error::WeakPublicKey);
+        }
+        let gamma = proof.gamma()?;
+        let mut wide = [0; 64];
+        wide[..CHALLENGE_SIZE].copy_from_slice(&proof.bytes[32..48]);
+        let c = Scalar::from(wide);
+        let s = Scalar::try_from(&proof.bytes[48..]).map_err(|_| Error::NonCanonicalScalar)?;
+
+        let h = encode_to_curve(self, alpha)?;
+        let u = Point::vartime_multiscalar_mul(&[s, -c], &[point::B, y]);
+        let v = Point::vartime_multiscalar_mul(&[s, -c], &[h, gamma]);
+        let expected = challenge([y, h, gamma, u, v]);
+        if !bool::from(truncated(expected).ct_eq(&proof.bytes[32..48])) {
+            return Err(Error::SignatureInvalid);
+        }
+        proof.output()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_vector(private: &str, alpha: &str, pi: &str, beta: &str) {
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(private, &mut bytes).unwrap();
+        let private = PrivateKey::from(bytes);
+        let alpha = hex::decode(alpha).unwrap();
+        let proof = private.vrf_prove(&alpha).unwrap();
+        assert_eq!(hex::encode(&proof.bytes[..]), pi);
+        let output = private.derive_public_key().vrf_verify(&alpha, &proof);
+        assert_eq!(hex::encode(&output.unwrap()[..]), beta);
+    }
+
+    #[test]
+    fn test_rfc_9381_vectors() {
+        // From Appendix B.3, examples 16 and 17
+        check_vector(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "",
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805",
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+        );
+        check_vector(
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "72",
+            "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed5933bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926da3ef39226bbc355bdc9850112c8f4b02",
+            "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_proofs() {
+        let private = PrivateKey::from([5; 32]);
+        let public = private.derive_public_key();
+        let proof = private.vrf_prove(b"input").unwrap();
+        assert_eq!(public.vrf_verify(b"input", &proof), proof.output());
+        assert_eq!(
+            public.vrf_verify(b"other", &proof),
+            Err(Error::SignatureInvalid)
+        );
+        let mut tweaked = proof;
+        tweaked.bytes[40] ^= 1;
+        assert_eq!(
+            public.vrf_verify(b"input", &tweaked),
+            Err(Error::SignatureInvalid)
+        );
+        let other = PrivateKey::from([6; 32]).derive_public_key();
+        assert!(other.vrf_verify(b"input", &proof).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! This module implements Shamir secret sharing of scalars, along with Feldman's
+//! verifiable secret sharing, from "A Practical Scheme for Non-interactive Verifiable
+//! Secret Sharing":
+//! https://www.cs.umd.edu/~gasarch/TOPICS/secretsharing/feldmanVSS.pdf
+//!
+//! A secret is the constant term of a random polynomial of degree threshold - 1,
+//! and each share is the value of that polynomial at a non zero index. Any threshold
+//! shares can reconstruct the secret, and fewer reveal nothing about it.
+//!
+//! Publishing the coefficients multiplied by the basepoint lets each shareholder check
+//! their share, without learning anything more about the secret than its public key.
+// Outside of hazmat, only part of this is used, by threshold signatures.
+#![cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use rand::{CryptoRng, RngCore};
+
+use super::{
+    point::{self, Point},
+    poly::{evaluate_many, interpolate, Polynomial},
+    scalar::Scalar,
+    Error,
+};
+
+fn index_scalar(index: u16) -> Scalar {
+    Scalar::from(u64::from(index))
+}
+
+/// The share of a secret held by one party, the value of the polynomial at its index.
+#[derive(Clone, Copy, Debug)]
+pub struct SecretShare {
+    pub index: u16,
+    pub value: Scalar,
+}
+
+/// Commitments to the coefficients of a sharing polynomial, starting with the secret.
+#[derive(Clone, Debug)]
+pub struct FeldmanCommitment {
+    coefficients: Vec<Point>,
+}
+
+impl FeldmanCommitment {
+    /// Decode commitments, rejecting any which aren't points.
+    pub fn from_bytes(bytes: &[[u8; 32]]) -> Result<Self, Error> {
+        if bytes.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        let coefficients = bytes
+            .iter()
+            .map(|b| Point::try_from(&b[..]).map_err(|_| Error::PointNotOnCurve))
+            .collect::<Result<_, _>>()?;
+        Ok(FeldmanCommitment { coefficients })
+    }
+
+    pub fn to_bytes(&self) -> Vec<[u8; 32]> {
+        Point::batch_encode(&self.coefficients)
+    }
+
+    /// The number of shares needed to reconstruct the secret.
+    pub fn threshold(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// The secret multiplied by the basepoint.
+    pub fn public_key(&self) -> Point {
+        self.coefficients[0]
+    }
+
+    /// The share at some index multiplied by the basepoint, which anyone can calculate.
+    pub fn share_public_key(&self, index: u16) -> Point {
+        let x = index_scalar(index);
+        let mut power = Scalar::from(1);
+        let powers: Vec<Scalar> = (0..self.coefficients.len())
+            .map(|_| {
+                let out = power;
+                power *= x;
+                out
+            })
+            .collect();
+        Point::vartime_multiscalar_mul(&powers, &self.coefficients)
+    }
+
+    /// Check that a share is the value of the committed polynomial at its index.
+    pub fn verify(&self, share: &SecretShare) -> Result<(), Error> {
+        let expected: [u8; 32] = self.share_public_key(share.index).into();
+        let actual: [u8; 32] = (point::B * share.value).into();
+        if share.index == 0 || expected != actual {
+            return Err(Error::SignatureInvalid);
+        }
+        Ok(())
+    }
+
+    /// Add the commitments of two sharings, committing to the sum of their polynomials.
+    pub fn add(&self, other: &FeldmanCommitment) -> FeldmanCommitment {
+        let (long, short) = if self.coefficients.len() >= other.coefficients.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut coefficients = long.coefficients.clone();
+        for (c, &d) in coefficients.iter_mut().zip(short.coefficients.iter()) {
+            *c = *c + d;
+        }
+        FeldmanCommitment { coefficients }
+    }
+}
+
+/// Split a secret into `count` shares, at indices 1 to `count`, any `threshold` of
+/// which can reconstruct it, along with commitments for checking the shares.
+pub fn share_secret<R: RngCore + CryptoRng>(
+    secret: Scalar,
+    threshold: u16,
+    count: u16,
+    rng: &mut R,
+) -> Result<(Vec<SecretShare>, FeldmanCommitment), Error> {
+    if threshold == 0 || threshold > count {
+        return Err(Error::InvalidLength);
+    }
+    let coefficients: Vec<Scalar> = core::iter::once(secret)
+        .chain((1..threshold).map(|_| Scalar::random(rng)))
+        .collect();
+    let commitment = FeldmanCommitment {
+        coefficients: coefficients.iter().map(|&c| point::B * c).collect(),
+    };
+    let indices: Vec<Scalar> = (1..=count).map(index_scalar).collect();
+    let values = evaluate_many(&Polynomial::new(coefficients), &indices);
+    let shares = (1..=count)
+        .zip(values)
+        .map(|(index, value)| SecretShare { index, value })
+        .collect();
+    Ok((shares, commitment))
+}
+
+/// Reconstruct a secret from shares at distinct, non zero, indices.
+///
+/// This always produces some scalar, which is only the secret if there are enough shares,
+/// and they're all correct.
+pub fn reconstruct_secret(shares: &[SecretShare]) -> Result<Scalar, Error> {
+    if shares.is_empty() || shares.iter().any(|s| s.index == 0) {
+        return Err(Error::InvalidLength);
+    }
+    let indices: Vec<Scalar> = shares.iter().map(|s| index_scalar(s.index)).collect();
+    let values: Vec<Scalar> = shares.iter().map(|s| s.value).collect();
+    let polynomial = interpolate(&indices, &values).ok_or(Error::InvalidEncoding)?;
+    Ok(polynomial.evaluate(Scalar::from(0)))
+}
+
+/// Calculate the Lagrange coefficient of one index, for interpolating at 0 among others.
+///
+/// The indices have to be distinct, and include the index itself.
+pub fn lagrange_coefficient(index: u16, indices: &[u16]) -> Scalar {
+    let x_i = index_scalar(index);
+    let mut numerator = Scalar::from(1);
+    let mut denominator = Scalar::from(1);
+    for &j in indices.iter().filter(|&&j| j != index) {
+        let x_j = index_scalar(j);
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.inverse()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_split_and_reconstruct() {
+        let secret = Scalar::from(1234567);
+        let (shares, commitment) = share_secret(secret, 3, 5, &mut OsRng).unwrap();
+        assert_eq!(commitment.threshold(), 3);
+        for share in shares.iter() {
+            assert!(commitment.verify(share).is_ok());
+        }
+        for chosen in [[0, 1, 2], [4, 2, 0], [1, 3, 4]].iter() {
+            let subset: Vec<_> = chosen.iter().map(|&i| shares[i]).collect();
+            assert_eq!(reconstruct_secret(&subset).unwrap(), secret);
+            let indices: Vec<u16> = subset.iter().map(|s| s.index).collect();
+            let combined = subset.iter().fold(Scalar::from(0), |acc, s| {
+                acc + lagrange_coefficient(s.index, &indices) * s.value
+            });
+            assert_eq!(combined, secret);
+        }
+        assert_ne!(reconstruct_secret(&shares[..2]).unwrap(), secret);
+        let public: [u8; 32] = commitment.public_key().into();
+        assert_eq!(public, <[u8; 32]>::from(point::B * secret));
+    }
+
+    #[test]
+    fn test_rejects_bad_shares() {
+        let (shares, commitment) = share_secret(Scalar::from(9), 2, 3, &mut OsRng).unwrap();
+        let mut bad = shares[1];
+        bad.value += Scalar::from(1);
+        assert_eq!(commitment.verify(&bad), Err(Error::SignatureInvalid));
+        bad = shares[1];
+        bad.index = 3;
+        assert!(commitment.verify(&bad).is_err());
+        assert_eq!(
+            reconstruct_secret(&[shares[0], shares[0]]),
+            Err(Error::InvalidEncoding)
+        );
+
+        let decoded = FeldmanCommitment::from_bytes(&commitment.to_bytes()).unwrap();
+        assert!(decoded.verify(&shares[2]).is_ok());
+
+        // Adding shares, and commitments, shares the sum of the secrets.
+        let (others, other_commitment) = share_secret(Scalar::from(4), 3, 3, &mut OsRng).unwrap();
+        let sum = commitment.add(&other_commitment);
+        assert_eq!(sum.threshold(), 3);
+        let mut summed = others[2];
+        summed.value += shares[2].value;
+        assert!(sum.verify(&summed).is_ok());
+        assert!(share_secret(Scalar::from(9), 4, 3, &mut OsRng).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! This module implements arithmetic in the field Z/(2^448 - 2^224 - 1).
+//!
+//! Elements are stored as 8 limbs of 56 bits, which makes the special form of
+//! the prime easy to exploit. With φ = 2^224, we have p = φ^2 - φ - 1, so reducing
+//! multiples of 2^448 only involves adding them back in at limbs 0 and 4.
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+const MASK: u64 = (1 << 56) - 1;
+
+/// The limbs of p, in the same representation as our field elements.
+const P: [u64; 8] = [MASK, MASK, MASK, MASK, MASK - 1, MASK, MASK, MASK];
+
+/// The exponent p - 2, used for inversion, with limbs of 64 bits.
+const P_MINUS_2: [u64; 7] = [
+    0xfffffffffffffffd,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xfffffffeffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+];
+
+/// The exponent (p - 3) / 4, used for square roots, with limbs of 64 bits.
+const P_MINUS_3_OVER_4: [u64; 7] = [
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffbfffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x3fffffffffffffff,
+];
+
+/// An element of the field Z/(2^448 - 2^224 - 1).
+///
+/// The limbs are only loosely reduced, so each one may be slightly larger than
+/// 56 bits, and the value may be larger than p.
+#[derive(Clone, Copy, Debug)]
+pub struct Z448 {
+    limbs: [u64; 8],
+}
+
+impl Z448 {
+    pub const fn from_limbs(limbs: [u64; 8]) -> Self {
+        Z448 { limbs }
+    }
+
+    /// Propagate the carries of each limb, bringing them back to around 56 bits.
+    fn carry(&mut self) {
+        for i in 0..7 {
+            self.limbs[i + 1] += self.limbs[i] >> 56;
+            self.limbs[i] &= MASK;
+        }
+        // 2^448 = φ + 1
+        let top = self.limbs[7] >> 56;
+        self.limbs[7] &= MASK;
+        self.limbs[0] += top;
+        self.limbs[4] += top;
+    }
+
+    /// Calculate the unique representation of this element, with a value below p.
+    fn canonical(&self) -> [u64; 8] {
+        let mut x = *self;
+        x.carry();
+        // After carrying, the value is always smaller than 2p, so subtracting p
+        // once is enough. We check whether or not this underflows.
+        let mut reduced = [0; 8];
+        let mut normalized = [0; 8];
+        let mut borrow: i128 = 0;
+        let mut carry: u64 = 0;
+        for i in 0..8 {
+            borrow += i128::from(x.limbs[i]) - i128::from(P[i]);
+            reduced[i] = (borrow as u64) & MASK;
+            borrow >>= 56;
+            carry += x.limbs[i];
+            normalized[i] = carry & MASK;
+            carry >>= 56;
+        }
+        let underflowed = Choice::from((borrow < 0) as u8);
+        let mut out = [0; 8];
+        for i in 0..8 {
+            out[i] = u64::conditional_select(&reduced[i], &normalized[i], underflowed);
+        }
+        out
+    }
+
+    /// Return 1 if the canonical form of this element is odd, and 0 otherwise.
+    pub fn parity(&self) -> u8 {
+        (self.canonical()[0] & 1) as u8
+    }
+
+    /// Check whether or not this element is zero, in constant time.
+    pub fn is_zero(&self) -> Choice {
+        self.ct_eq(&Z448::from(0))
+    }
+
+    pub fn squared(self) -> Z448 {
+        self * self
+    }
+
+    /// Raise this element to a public power, given as limbs of 64 bits.
+    fn pow(self, exponent: &[u64; 7]) -> Z448 {
+        let mut out = Z448::from(1);
+        for limb in exponent.iter().rev() {
+            for i in (0..64).rev() {
+                out = out.squared();
+                // The exponent is public, so branching on it is fine.
+                if (limb >> i) & 1 == 1 {
+                    out = out * self;
+                }
+            }
+        }
+        out
+    }
+
+    /// Calculate self^-1, which only works for non zero elements.
+    pub fn inverse(self) -> Z448 {
+        // By Fermat, self ^ (p - 2) is an inverse.
+        self.pow(&P_MINUS_2)
+    }
+
+    /// Calculate the square root of u / v, if it exists.
+    ///
+    /// This follows Section 5.2.3 of RFC 8032:
+    /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.2.3
+    pub fn fraction_root(u: Self, v: Self) -> Option<Self> {
+        // x = u^3 v (u^5 v^3)^((p - 3) / 4)
+        let u_2 = u.squared();
+        let u_3_v = u_2 * u * v;
+        let u_5_v_3 = u_3_v * u_2 * v.squared();
+        let x = u_3_v * u_5_v_3.pow(&P_MINUS_3_OVER_4);
+        if bool::from((v * x.squared()).ct_eq(&u)) {
+            return Some(x);
+        }
+        None
+    }
+}
+
+impl From<u64> for Z448 {
+    fn from(x: u64) -> Self {
+        let mut out = Z448 { limbs: [0; 8] };
+        out.limbs[0] = x & MASK;
+        out.limbs[1] = x >> 56;
+        out
+    }
+}
+
+impl From<Z448> for [u8; 56] {
+    fn from(x: Z448) -> [u8; 56] {
+        let mut out = [0; 56];
+        for (chunk, limb) in out.chunks_exact_mut(7).zip(x.canonical().iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes()[..7]);
+        }
+        out
+    }
+}
+
+impl Z448 {
+    /// Parse a little endian field element, returning `None` if it's not below p.
+    pub fn from_canonical_bytes(bytes: &[u8; 56]) -> Option<Self> {
+        let mut limbs = [0; 8];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(7)) {
+            let mut padded = [0; 8];
+            padded[..7].copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(padded);
+        }
+        let out = Z448 { limbs };
+        if out.canonical() != limbs {
+            return None;
+        }
+        Some(out)
+    }
+}
+
+impl ConditionallySelectable for Z448 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let mut limbs = [0; 8];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = u64::conditional_select(&a.limbs[i], &b.limbs[i], choice);
+        }
+        Z448 { limbs }
+    }
+}
+
+impl ConstantTimeEq for Z448 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.canonical()[..].ct_eq(&other.canonical()[..])
+    }
+}
+
+// Only implement equality for tests. This is to avoid the temptation to introduce
+// a timing leak through equality comparison in other situations.
+#[cfg(test)]
+impl PartialEq for Z448 {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Add for Z448 {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self::Output {
+        for i in 0..8 {
+            self.limbs[i] += other.limbs[i];
+        }
+        self.carry();
+        self
+    }
+}
+
+impl Sub for Z448 {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self::Output {
+        // Adding 2p first avoids underflow, since other's limbs are below 2^57 - 4.
+        for ((x, p), y) in self.limbs.iter_mut().zip(P.iter()).zip(other.limbs.iter()) {
+            *x = *x + 2 * p - y;
+        }
+        self.carry();
+        self
+    }
+}
+
+impl Neg for Z448 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Z448::from(0) - self
+    }
+}
+
+impl Mul<u64> for Z448 {
+    type Output = Self;
+
+    fn mul(self, small: u64) -> Self::Output {
+        self * Z448::from(small)
+    }
+}
+
+impl Mul for Z448 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut wide = [0u128; 15];
+        for i in 0..8 {
+            for j in 0..8 {
+                wide[i + j] += u128::from(self.limbs[i]) * u128::from(other.limbs[j]);
+            }
+        }
+        // Limb k >= 8 stands for 2^(56 (k - 8)) * (φ + 1). Going downwards,
+        // limbs which land above 8 again get folded in a later iteration.
+        for k in (8..15).rev() {
+            let x = wide[k];
+            wide[k - 8] += x;
+            wide[k - 4] += x;
+        }
+        for _ in 0..2 {
+            for i in 0..7 {
+                wide[i + 1] += wide[i] >> 56;
+                wide[i] &= u128::from(MASK);
+            }
+            let top = wide[7] >> 56;
+            wide[7] &= u128::from(MASK);
+            wide[0] += top;
+            wide[4] += top;
+        }
+        let mut limbs = [0; 8];
+        for (limb, w) in limbs.iter_mut().zip(wide.iter()) {
+            *limb = *w as u64;
+        }
+        Z448 { limbs }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    prop_compose! {
+        fn arb_z448()(limbs in prop::array::uniform8(0..(1u64 << 56))) -> Z448 {
+            Z448::from_limbs(limbs)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_field_axioms(a in arb_z448(), b in arb_z448(), c in arb_z448()) {
+            assert_eq!(a * (b + c), a * b + a * c);
+            assert_eq!((a - b) + b, a);
+            assert_eq!(a + -a, 0.into());
+            assert_eq!(a * b, b * a);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_inverse(a in arb_z448()) {
+            prop_assume!(!bool::from(a.is_zero()));
+            assert_eq!(a * a.inverse(), 1.into());
+        }
+    }
+
+    #[test]
+    fn test_reduction_examples() {
+        let p = Z448::from_limbs(P);
+        assert_eq!(p, 0.into());
+        assert_eq!(<[u8; 56]>::from(p), [0; 56]);
+        assert_eq!(p + Z448::from(5), 5.into());
+        let minus_one = -Z448::from(1);
+        assert_eq!(minus_one * minus_one, 1.into());
+        let bytes: [u8; 56] = minus_one.into();
+        assert!(Z448::from_canonical_bytes(&bytes).is_some());
+        assert!(Z448::from_canonical_bytes(&[0xFF; 56]).is_none());
+    }
+
+    #[test]
+    fn test_fraction_root() {
+        let x = Z448::from(1234567);
+        let v = Z448::from(89);
+        let root = Z448::fraction_root(x.squared() * v, v).unwrap();
+        assert!(bool::from(root.ct_eq(&x) | root.ct_eq(&-x)));
+        // -1 isn't a square, since p = 3 mod 4
+        assert!(Z448::fraction_root(-Z448::from(1), 1.into()).is_none());
+    }
+}
@@ -0,0 +1,388 @@
+//! This module implements Ed448 signatures, as described in RFC 8032:
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.2
+//!
+//! Ed448 targets the 224 bit security level, compared to 128 bits for Ed25519,
+//! at the cost of larger keys and signatures, and slower operations.
+//! Unlike Ed25519, every signature is bound to a context string, which is
+//! empty unless one of the `_with_context` methods is used.
+
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
+
+use rand::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::{
+    shake::Shake256,
+    zeroize::{Zeroize, Zeroizing},
+    Error,
+};
+
+use self::{
+    point::{Point, B, POINT_SIZE},
+    scalar::{Scalar, SCALAR_SIZE},
+};
+
+mod field;
+mod point;
+mod scalar;
+
+pub const SIGNATURE_SIZE: usize = POINT_SIZE + SCALAR_SIZE;
+pub const PUBLIC_KEY_SIZE: usize = POINT_SIZE;
+pub const PRIVATE_KEY_SIZE: usize = 57;
+
+/// The longest context string allowed, since its length gets encoded as a byte.
+pub const MAX_CONTEXT_SIZE: usize = 255;
+
+/// Start hashing with dom4(0, context), which separates Ed448 from other uses of SHAKE256.
+///
+/// This is defined in Section 5.2 of RFC 8032.
+fn dom4(context: &[u8]) -> Shake256 {
+    let mut hasher = Shake256::new();
+    hasher.update(b"SigEd448");
+    hasher.update(&[0, context.len() as u8]);
+    hasher.update(context);
+    hasher
+}
+
+/// Hash some parts with dom4, and reduce the 114 bytes of output into a scalar.
+fn hash_to_scalar(context: &[u8], parts: &[&[u8]]) -> Scalar {
+    let mut hasher = dom4(context);
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut out = [0; 114];
+    hasher.finalize().read(&mut out);
+    Scalar::from_hash(&out)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+    pub bytes: [u8; SIGNATURE_SIZE],
+}
+
+impl ConstantTimeEq for Signature {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+/// This compares in constant time, so comparing against an expected signature is safe.
+impl PartialEq for Signature {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Signature {}
+
+/// This doesn't check the encoding, which happens when verifying.
+impl From<[u8; SIGNATURE_SIZE]> for Signature {
+    fn from(bytes: [u8; SIGNATURE_SIZE]) -> Self {
+        Signature { bytes }
+    }
+}
+
+/// This rejects signatures with the wrong length, or with a non canonical scalar.
+impl<'a> TryFrom<&'a [u8]> for Signature {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; SIGNATURE_SIZE] = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Scalar::from_canonical_bytes(bytes[POINT_SIZE..].try_into().unwrap())
+            .ok_or(Error::NonCanonicalScalar)?;
+        Ok(Signature { bytes })
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Signature {
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_SIZE] {
+        self.bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PublicKey {
+    pub bytes: [u8; PUBLIC_KEY_SIZE],
+}
+
+impl ConstantTimeEq for PublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+/// This compares in constant time, like `ct_eq`.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PublicKey {}
+
+/// This rejects keys with the wrong length, or which aren't valid points.
+impl<'a> TryFrom<&'a [u8]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; PUBLIC_KEY_SIZE] = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Point::decode(&bytes).ok_or(Error::PointNotOnCurve)?;
+        Ok(PublicKey { bytes })
+    }
+}
+
+/// Like with Ed25519 keys, not every 57 bytes encode a point on the curve.
+impl TryFrom<[u8; PUBLIC_KEY_SIZE]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: [u8; PUBLIC_KEY_SIZE]) -> Result<Self, Self::Error> {
+        Self::try_from(&value[..])
+    }
+}
+
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl PublicKey {
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.bytes
+    }
+
+    pub fn verify(&self, message: &[u8], signature: Signature) -> bool {
+        self.verify_with_context(b"", message, signature)
+    }
+
+    /// Verify a signature made with a given context string.
+    ///
+    /// This follows Section 5.2.7 of RFC 8032:
+    /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.2.7
+    pub fn verify_with_context(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: Signature,
+    ) -> bool {
+        if context.len() > MAX_CONTEXT_SIZE {
+            return false;
+        }
+        let (r_bytes, s_bytes) = signature.bytes.split_at(POINT_SIZE);
+        let s = match Scalar::from_canonical_bytes(s_bytes.try_into().unwrap()) {
+            Some(s) => s,
+            None => return false,
+        };
+        let (r, a) = match (
+            Point::decode(r_bytes.try_into().unwrap()),
+            Point::decode(&self.bytes),
+        ) {
+            (Some(r), Some(a)) => (r, a),
+            _ => return false,
+        };
+        let k = hash_to_scalar(context, &[r_bytes, &self.bytes, message]);
+        // Checking [S]B = R + [k]A is sufficient, as the RFC notes.
+        (B * s).ct_eq(&(r + a * k)).into()
+    }
+}
+
+#[derive(Clone)]
+pub struct PrivateKey {
+    bytes: [u8; PRIVATE_KEY_SIZE],
+}
+
+/// Any 57 bytes make a valid private key.
+impl From<[u8; PRIVATE_KEY_SIZE]> for PrivateKey {
+    fn from(bytes: [u8; PRIVATE_KEY_SIZE]) -> Self {
+        PrivateKey { bytes }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PrivateKey {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value.try_into().map_err(|_| Error::InvalidLength)?;
+        Ok(PrivateKey { bytes })
+    }
+}
+
+impl AsRef<[u8]> for PrivateKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// This never prints the key itself, so that secrets don't end up in logs.
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
+}
+
+impl PrivateKey {
+    /// Load a private key from its bytes, as defined in Section 5.2.5 of RFC 8032.
+    ///
+    /// Any 57 bytes make a valid private key, so this can't fail.
+    pub fn from_bytes(bytes: [u8; PRIVATE_KEY_SIZE]) -> Self {
+        PrivateKey { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PRIVATE_KEY_SIZE] {
+        &self.bytes
+    }
+
+    pub fn to_bytes(&self) -> [u8; PRIVATE_KEY_SIZE] {
+        self.bytes
+    }
+
+    /// Hash the key, as per Section 5.2.5, into a secret scalar, and a nonce prefix.
+    fn expand(&self) -> [u8; 114] {
+        let mut hasher = Shake256::new();
+        hasher.update(&self.bytes);
+        let mut out = [0; 114];
+        hasher.finalize().read(&mut out);
+        out
+    }
+
+    /// Calculate the public key corresponding to this private key.
+    pub fn derive_public_key(&self) -> PublicKey {
+        let hash = Zeroizing::new(self.expand());
+        let s = Zeroizing::new(Scalar::clamped(hash[..SCALAR_SIZE].try_into().unwrap()));
+        PublicKey {
+            bytes: (B * *s).encode(),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.sign_with_context(b"", message)
+    }
+
+    /// Sign a message, binding the signature to a context string.
+    ///
+    /// This follows Section 5.2.6 of RFC 8032:
+    /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.2.6
+    ///
+    /// This will panic if the context is longer than `MAX_CONTEXT_SIZE`.
+    pub fn sign_with_context(&self, context: &[u8], message: &[u8]) -> Signature {
+        assert!(context.len() <= MAX_CONTEXT_SIZE, "Ed448 context too long");
+        let hash = Zeroizing::new(self.expand());
+        let s = Zeroizing::new(Scalar::clamped(hash[..SCALAR_SIZE].try_into().unwrap()));
+        let a = (B * *s).encode();
+
+        let r = Zeroizing::new(hash_to_scalar(context, &[&hash[SCALAR_SIZE..], message]));
+        let big_r = (B * *r).encode();
+        let k = hash_to_scalar(context, &[&big_r, &a, message]);
+        let big_s: [u8; SCALAR_SIZE] = (*r + k * *s).into();
+
+        let mut out = Signature {
+            bytes: [0; SIGNATURE_SIZE],
+        };
+        out.bytes[..POINT_SIZE].copy_from_slice(&big_r);
+        out.bytes[POINT_SIZE..].copy_from_slice(&big_s);
+        out
+    }
+}
+
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+pub fn gen_keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, PrivateKey) {
+    let mut private = PrivateKey {
+        bytes: [0u8; PRIVATE_KEY_SIZE],
+    };
+    rng.fill_bytes(&mut private.bytes);
+    (private.derive_public_key(), private)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0; N];
+        hex::decode_to_slice(s, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_signature_blank() {
+        // The "blank" test vector of Section 7.4 of RFC 8032:
+        // https://datatracker.ietf.org/doc/html/rfc8032#section-7.4
+        let private = PrivateKey::from(decode(
+        "6c82a562cb808d10d632be89c8513ebf6c929f34ddfa8c9f63c9960ef6e348a3528c8a3fcc2f044e39a3fc5b94492f8f032e7549a20098f95b",
+        ));
+        let public = private.derive_public_key();
+        assert_eq!(
+            public.bytes,
+            decode(
+            "5fd7449b59b461fd2ce787ec616ad46a1da1342485a70e1f8a0ea75d80e96778edf124769b46c7061bd6783df1e50f6cd1fa1abeafe8256180",
+            )
+        );
+        let expected = Signature::from(decode(
+        "533a37f6bbe457251f023c0d88f976ae2dfb504a843e34d2074fd823d41a591f2b233f034f628281f2fd7a22ddd47d7828c59bd0a21bfd3980ff0d2028d4b18a9df63e006c5d1c2d345b925d8dc00b4104852db99ac5c7cdda8530a113a0f4dbb61149f05a7363268c71d95808ff2e652600",
+        ));
+        assert_eq!(private.sign(b""), expected);
+        assert!(public.verify(b"", expected));
+    }
+
+    #[test]
+    fn test_signature_with_context() {
+        // The "1 octet (with context)" test vector of Section 7.4 of RFC 8032
+        let private = PrivateKey::from(decode(
+        "c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463afbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+        ));
+        let public = private.derive_public_key();
+        let expected = Signature::from(decode(
+        "d4f8f6131770dd46f40867d6fd5d5055de43541f8c5e35abbcd001b32a89f7d2151f7647f11d8ca2ae279fb842d607217fce6e042f6815ea000c85741de5c8da1144a6a1aba7f96de42505d7a7298524fda538fccbbb754f578c1cad10d54d0d5428407e85dcbc98a49155c13764e66c3c00",
+        ));
+        assert_eq!(private.sign_with_context(b"foo", &[0x03]), expected);
+        assert!(public.verify_with_context(b"foo", &[0x03], expected));
+        assert!(!public.verify(&[0x03], expected));
+    }
+
+    #[test]
+    fn test_stored_keys_load_again() {
+        let (public, private) = gen_keypair(&mut rand::rngs::OsRng);
+        let loaded = PrivateKey::from_bytes(private.to_bytes());
+        assert_eq!(loaded.as_bytes(), private.as_bytes());
+        assert_eq!(loaded.derive_public_key(), public);
+        assert!(public.verify(b"message", loaded.sign(b"message")));
+    }
+
+    #[test]
+    fn test_rejects_modified_signatures() {
+        let private = PrivateKey::from([7; PRIVATE_KEY_SIZE]);
+        let public = private.derive_public_key();
+        let signature = private.sign(b"message");
+        assert!(public.verify(b"message", signature));
+        assert!(!public.verify(b"other message", signature));
+        let mut bad = signature;
+        bad.bytes[3] ^= 1;
+        assert!(!public.verify(b"message", bad));
+        // Scalars above L aren't canonical
+        let mut bad = signature;
+        bad.bytes[POINT_SIZE..].copy_from_slice(&[0xFF; SCALAR_SIZE]);
+        assert!(Signature::try_from(&bad.bytes[..]).is_err());
+        assert!(!public.verify(b"message", bad));
+    }
+}
@@ -0,0 +1,190 @@
+//! This module defines the Edwards448 group, used by Ed448 signatures.
+//! This follows Section 5.2 of RFC 8032:
+//! https://datatracker.ietf.org/doc/html/rfc8032#section-5.2
+
+use core::{
+    convert::TryInto,
+    ops::{Add, Mul},
+};
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use super::{field::Z448, scalar::Scalar};
+
+/// The size of an encoded point.
+pub const POINT_SIZE: usize = 57;
+
+/// The curve is x^2 + y^2 = 1 + d x^2 y^2, with d = -39081.
+const MINUS_D: u64 = 39081;
+
+pub const B: Point = Point {
+    x: Z448::from_limbs([
+        0x26a82bc70cc05e,
+        0x80e18b00938e26,
+        0xf72ab66511433b,
+        0xa3d3a46412ae1a,
+        0x0f1767ea6de324,
+        0x36da9e14657047,
+        0xed221d15a622bf,
+        0x4f1970c66bed0d,
+    ]),
+    y: Z448::from_limbs([
+        0x08795bf230fa14,
+        0x132c4ed7c8ad98,
+        0x1ce67c39c4fdbd,
+        0x05a0c2d73ad3ff,
+        0xa3984087789c1e,
+        0xc7624bea73736c,
+        0x248876203756c9,
+        0x693f46716eb6bc,
+    ]),
+    z: Z448::from_limbs([1, 0, 0, 0, 0, 0, 0, 0]),
+};
+
+/// Represents a point on Edwards448.
+#[derive(Clone, Copy, Debug)]
+pub struct Point {
+    // We use projective coordinates, as per Section 5.2.4:
+    // https://datatracker.ietf.org/doc/html/rfc8032#section-5.2.4
+    x: Z448,
+    y: Z448,
+    z: Z448,
+}
+
+impl Point {
+    pub fn identity() -> Point {
+        Point {
+            x: Z448::from(0),
+            y: Z448::from(1),
+            z: Z448::from(1),
+        }
+    }
+
+    /// Calculate the affine coordinates (x, y) of this point.
+    pub fn affine(&self) -> (Z448, Z448) {
+        let zinv = self.z.inverse();
+        (self.x * zinv, self.y * zinv)
+    }
+
+    #[must_use]
+    pub fn doubled(&self) -> Point {
+        // This is the doubling routine of Section 5.2.4
+        let b = (self.x + self.y).squared();
+        let c = self.x.squared();
+        let d = self.y.squared();
+        let e = c + d;
+        let h = self.z.squared();
+        let j = e - h - h;
+        Point {
+            x: (b - e) * j,
+            y: e * (c - d),
+            z: e * j,
+        }
+    }
+
+    /// Parse an encoded point, as per Section 5.2.3.
+    pub fn decode(bytes: &[u8; POINT_SIZE]) -> Option<Point> {
+        // Only the top bit of the last byte may be set, holding the sign of x.
+        if bytes[56] & 0x7F != 0 {
+            return None;
+        }
+        let x_0 = bytes[56] >> 7;
+        let y = Z448::from_canonical_bytes(bytes[..56].try_into().unwrap())?;
+        let y_2 = y.squared();
+        // x^2 = (y^2 - 1) / (d y^2 - 1)
+        let u = y_2 - Z448::from(1);
+        let v = -(y_2 * MINUS_D) - Z448::from(1);
+        let mut x = Z448::fraction_root(u, v)?;
+        if x_0 == 1 && bool::from(x.is_zero()) {
+            return None;
+        }
+        if x_0 != x.parity() {
+            x = -x;
+        }
+        Some(Point {
+            x,
+            y,
+            z: Z448::from(1),
+        })
+    }
+
+    pub fn encode(&self) -> [u8; POINT_SIZE] {
+        let (x, y) = self.affine();
+        let mut out = [0; POINT_SIZE];
+        out[..56].copy_from_slice(&<[u8; 56]>::from(y));
+        out[56] = x.parity() << 7;
+        out
+    }
+}
+
+impl ConditionallySelectable for Point {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Point {
+            x: Z448::conditional_select(&a.x, &b.x, choice),
+            y: Z448::conditional_select(&a.y, &b.y, choice),
+            z: Z448::conditional_select(&a.z, &b.z, choice),
+        }
+    }
+}
+
+/// Points are equal when their affine coordinates are, which we check without inverting.
+impl ConstantTimeEq for Point {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (self.x * other.z).ct_eq(&(other.x * self.z))
+            & (self.y * other.z).ct_eq(&(other.y * self.z))
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Self::Output {
+        // This is the addition routine of Section 5.2.4, which is complete.
+        let a = self.z * other.z;
+        let b = a.squared();
+        let c = self.x * other.x;
+        let d = self.y * other.y;
+        let e = -(c * d * MINUS_D);
+        let f = b - e;
+        let g = b + e;
+        let h = (self.x + self.y) * (other.x + other.y);
+        Point {
+            x: a * f * (h - c - d),
+            y: a * g * (d - c),
+            z: f * g,
+        }
+    }
+}
+
+/// Calculate point * scalar, in constant time, one bit at a time.
+fn scalar_mul(point: Point, scalar: Scalar) -> Point {
+    let mut out = Point::identity();
+    for bit in scalar.bits() {
+        out = out.doubled();
+        out.conditional_assign(&(out + point), bit);
+    }
+    out
+}
+
+impl Mul<Scalar> for Point {
+    type Output = Point;
+
+    fn mul(self, other: Scalar) -> Self::Output {
+        scalar_mul(self, other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base_point_round_trip() {
+        let encoded = B.encode();
+        let decoded = Point::decode(&encoded).unwrap();
+        assert!(bool::from(decoded.ct_eq(&B)));
+        assert!(bool::from((B + B).ct_eq(&B.doubled())));
+        assert!(bool::from((B * Scalar::from(3)).ct_eq(&(B + B + B))));
+        assert!(Point::decode(&[0xFF; POINT_SIZE]).is_none());
+    }
+}
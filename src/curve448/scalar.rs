@@ -0,0 +1,238 @@
+//! This module implements arithmetic modulo the order of the Ed448 group,
+//! L = 2^446 - 13818066809895115352007386748515426880336692474882178609894547503885.
+//!
+//! Since 2^446 = C mod L, for a C of only 224 bits, large numbers can be reduced
+//! by repeatedly replacing their top bits, x_hi * 2^446, with x_hi * C.
+
+use core::{
+    convert::TryInto,
+    ops::{Add, Mul},
+};
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::arch::{adc, mulc, sbb};
+
+/// The size of an encoded scalar, which RFC 8032 pads to the size of a point.
+pub const SCALAR_SIZE: usize = 57;
+
+/// The number of limbs of wide numbers, large enough to hold 114 bytes of hash.
+const WIDE: usize = 15;
+
+const L: [u64; 7] = [
+    0x2378c292ab5844f3,
+    0x216cc2728dc58f55,
+    0xc44edb49aed63690,
+    0xffffffff7cca23e9,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x3fffffffffffffff,
+];
+
+/// C = 2^446 - L
+const C: [u64; 4] = [
+    0xdc873d6d54a7bb0d,
+    0xde933d8d723a70aa,
+    0x3bb124b65129c96f,
+    0x000000008335dc16,
+];
+
+/// Represents a scalar modulo L, the order of the Ed448 group.
+#[derive(Clone, Copy, Debug)]
+// Only implement equality for tests. This is to avoid the temptation to introduce
+// a timing leak through equality comparison in other situations.
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Scalar {
+    limbs: [u64; 7],
+}
+
+/// Reduce a number, made of WIDE limbs, modulo L.
+fn reduce(mut x: [u64; WIDE]) -> Scalar {
+    // Each time, the excess over 446 bits shrinks by at least 222 bits, so starting
+    // from 960 bits, 4 rounds leave us with something below 2^446 + C < 2L.
+    for _ in 0..4 {
+        let mut hi = [0u64; WIDE];
+        for i in 0..(WIDE - 7) {
+            hi[i] = (x[i + 6] >> 62) | (x[i + 7] << 2);
+        }
+        hi[WIDE - 7] = x[WIDE - 1] >> 62;
+        for limb in x[7..].iter_mut() {
+            *limb = 0;
+        }
+        x[6] &= (1 << 62) - 1;
+        // x = lo + hi * C, where the product never exceeds WIDE limbs
+        for (j, &c) in C.iter().enumerate() {
+            let mut carry = 0;
+            let mut add_carry = 0;
+            for i in 0..(WIDE - j) {
+                let mut product = 0;
+                carry = mulc(carry, hi[i], c, &mut product);
+                add_carry = adc(add_carry, x[i + j], product, &mut x[i + j]);
+            }
+        }
+    }
+    let mut limbs: [u64; 7] = x[..7].try_into().unwrap();
+    let mut reduced = [0; 7];
+    let mut borrow = 0;
+    for i in 0..7 {
+        borrow = sbb(borrow, limbs[i], L[i], &mut reduced[i]);
+    }
+    let choice = borrow.ct_eq(&0);
+    for i in 0..7 {
+        limbs[i].conditional_assign(&reduced[i], choice);
+    }
+    Scalar { limbs }
+}
+
+impl Scalar {
+    /// Reduce a 114 byte hash, interpreted in little endian, into a scalar.
+    pub fn from_hash(hash: &[u8; 114]) -> Scalar {
+        let mut padded = [0; 8 * WIDE];
+        padded[..114].copy_from_slice(hash);
+        let mut wide = [0; WIDE];
+        for (limb, chunk) in wide.iter_mut().zip(padded.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        reduce(wide)
+    }
+
+    /// Creates a secret scalar from the first half of a hashed private key.
+    ///
+    /// This prunes the bytes, as described in Section 5.2.5 of RFC 8032:
+    /// https://datatracker.ietf.org/doc/html/rfc8032#section-5.2.5
+    pub fn clamped(mut bytes: [u8; SCALAR_SIZE]) -> Scalar {
+        bytes[0] &= 0xFC;
+        bytes[56] = 0;
+        bytes[55] |= 0x80;
+        let mut limbs = [0; 7];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        // This isn't reduced, which doesn't matter for multiplying points.
+        Scalar { limbs }
+    }
+
+    /// Parse a scalar, returning `None` if the encoding isn't below L.
+    pub fn from_canonical_bytes(bytes: &[u8; SCALAR_SIZE]) -> Option<Scalar> {
+        if bytes[56] != 0 {
+            return None;
+        }
+        let mut limbs = [0; 7];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        let mut scratch = [0; 7];
+        let mut borrow = 0;
+        for i in 0..7 {
+            borrow = sbb(borrow, limbs[i], L[i], &mut scratch[i]);
+        }
+        if borrow == 0 {
+            return None;
+        }
+        Some(Scalar { limbs })
+    }
+
+    /// Iterate over the bits of this scalar, from most to least significant.
+    pub fn bits(&self) -> impl Iterator<Item = Choice> + '_ {
+        (0..448)
+            .rev()
+            .map(move |i| Choice::from(((self.limbs[i / 64] >> (i % 64)) & 1) as u8))
+    }
+}
+
+impl crate::zeroize::Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        crate::zeroize::wipe(&mut self.limbs);
+    }
+}
+
+impl From<u64> for Scalar {
+    fn from(x: u64) -> Self {
+        let mut limbs = [0; 7];
+        limbs[0] = x;
+        Scalar { limbs }
+    }
+}
+
+impl From<Scalar> for [u8; SCALAR_SIZE] {
+    fn from(x: Scalar) -> Self {
+        let mut out = [0; SCALAR_SIZE];
+        for (chunk, limb) in out.chunks_exact_mut(8).zip(x.limbs.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        out
+    }
+}
+
+impl Add for Scalar {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let mut wide = [0; WIDE];
+        let mut carry = 0;
+        for (i, out) in wide[..7].iter_mut().enumerate() {
+            carry = adc(carry, self.limbs[i], other.limbs[i], out);
+        }
+        wide[7] = u64::from(carry);
+        reduce(wide)
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut wide = [0; WIDE];
+        for i in 0..7 {
+            let mut carry = 0;
+            for j in 0..7 {
+                let mut product = 0;
+                carry = mulc(carry, self.limbs[i], other.limbs[j], &mut product);
+                let c = adc(0, wide[i + j], product, &mut wide[i + j]);
+                carry += u64::from(c);
+            }
+            wide[i + 7] = carry;
+        }
+        reduce(wide)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reduction_examples() {
+        let mut l_bytes = [0; SCALAR_SIZE];
+        for (chunk, limb) in l_bytes.chunks_exact_mut(8).zip(L.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        assert!(Scalar::from_canonical_bytes(&l_bytes).is_none());
+        let mut hash = [0; 114];
+        hash[..SCALAR_SIZE].copy_from_slice(&l_bytes);
+        assert_eq!(Scalar::from_hash(&hash), Scalar::from(0));
+        hash[0] += 7;
+        assert_eq!(Scalar::from_hash(&hash), Scalar::from(7));
+        // L - 1 is the largest canonical scalar, and squares to 1
+        l_bytes[0] -= 1;
+        let minus_one = Scalar::from_canonical_bytes(&l_bytes).unwrap();
+        assert_eq!(minus_one * minus_one, Scalar::from(1));
+        assert_eq!(minus_one + Scalar::from(3), Scalar::from(2));
+    }
+
+    #[test]
+    fn test_wide_reduction() {
+        // 2^(8 * 113) mod L, calculated separately
+        let mut hash = [0; 114];
+        hash[113] = 1;
+        let mut expected = [0; SCALAR_SIZE];
+        hex::decode_to_slice(
+        "905ae363d93f7481b6641181ae4ececa5f040cdcaaf7cbe61d0a9b80c972cf17ae447cc4a34bc19c1aaf70d0e4b7bc522029b723f839a90200",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(
+            Scalar::from_hash(&hash),
+            Scalar::from_canonical_bytes(&expected).unwrap()
+        );
+    }
+}
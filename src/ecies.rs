@@ -0,0 +1,190 @@
+//! This module implements a simple ECIES, for encrypting messages to an X25519 public key.
+//!
+//! This follows the usual hybrid recipe, as in SEC 1, Section 5.1:
+//! https://www.secg.org/sec1-v2.pdf
+//!
+//! The sender generates an ephemeral key pair, and exchanges it with the recipient's key.
+//! HKDF-SHA512 turns the shared secret into a key, and nonce, for ChaCha20-Poly1305,
+//! with both public keys mixed in. A ciphertext is laid out as:
+//!
+//! version (1 byte) || ephemeral public key (32 bytes) || encrypted message || tag (16 bytes)
+//!
+//! Unlike the NaCl layouts in `box_`, this isn't meant to interoperate with anything else,
+//! but the version byte leaves room to change the recipe later.
+
+use alloc::{vec, vec::Vec};
+use core::convert::TryInto;
+
+use rand::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    box_, chacha20, hkdf, x25519, zeroize::Zeroizing, Error, ExchangeOnlyKey, ExchangePublicKey,
+};
+
+/// The version of the format produced by `encrypt`.
+pub const VERSION: u8 = 1;
+
+/// How many bytes encrypting adds to a message.
+pub const OVERHEAD: usize = 1 + 32 + chacha20::TAG_SIZE;
+
+const INFO: &[u8] = b"eddo ECIES v1 X25519 HKDF-SHA512 ChaCha20-Poly1305";
+
+/// Derive the key, and nonce, for ChaCha20-Poly1305 from the exchange.
+fn derive(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> [u8; 44] {
+    let mut info = INFO.to_vec();
+    info.extend_from_slice(ephemeral);
+    info.extend_from_slice(recipient);
+    let mut out = [0; 44];
+    hkdf::derive(&[], shared, &info, &mut out);
+    out
+}
+
+fn encrypt_with_ephemeral(
+    recipient: &ExchangePublicKey,
+    message: &[u8],
+    associated_data: &[u8],
+    ephemeral_secret: &[u8; 32],
+) -> Result<Vec<u8>, Error> {
+    let ephemeral = box_::public_key(ephemeral_secret);
+    let shared = Zeroizing::new(x25519(*ephemeral_secret, recipient.bytes));
+    if bool::from(shared.ct_eq(&[0; 32])) {
+        return Err(Error::WeakPublicKey);
+    }
+    let okm = Zeroizing::new(derive(&shared, &ephemeral, &recipient.bytes));
+    let mut out = vec![VERSION];
+    out.extend_from_slice(&ephemeral);
+    out.extend_from_slice(&chacha20::seal(
+        okm[..32].try_into().unwrap(),
+        okm[32..].try_into().unwrap(),
+        associated_data,
+        message,
+    ));
+    Ok(out)
+}
+
+/// Encrypt a message to a public key, along with some associated data.
+///
+/// The associated data isn't included in the output, but decryption fails
+/// unless exactly the same data is provided.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    recipient: &ExchangePublicKey,
+    message: &[u8],
+    associated_data: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, Error> {
+    let mut ephemeral_secret = Zeroizing::new([0; 32]);
+    rng.fill_bytes(&mut *ephemeral_secret);
+    encrypt_with_ephemeral(recipient, message, associated_data, &ephemeral_secret)
+}
+
+/// Decrypt a ciphertext sent to our key, with the same associated data used to encrypt it.
+pub fn decrypt(
+    key: &ExchangeOnlyKey,
+    ciphertext: &[u8],
+    associated_data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if ciphertext.len() < OVERHEAD {
+        return Err(Error::InvalidLength);
+    }
+    if ciphertext[0] != VERSION {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    let ephemeral = ExchangePublicKey {
+        bytes: ciphertext[1..33].try_into().unwrap(),
+    };
+    let shared = Zeroizing::new(key.exchange(&ephemeral)?);
+    let okm = Zeroizing::new(derive(&shared, &ephemeral.bytes, &key.public_key().bytes));
+    chacha20::open(
+        okm[..32].try_into().unwrap(),
+        okm[32..].try_into().unwrap(),
+        associated_data,
+        &ciphertext[33..],
+    )
+    .ok_or(Error::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    use crate::PrivateKey;
+
+    fn decode(data: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        hex::decode_to_slice(data, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_vector() {
+        // This format is specific to this crate, so there are no published vectors.
+        // Instead, this uses the keys from RFC 7748, Section 6.1, as the ephemeral,
+        // and recipient keys, and rebuilds the ciphertext from the recipe above.
+        let ephemeral_secret =
+            decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let recipient = ExchangePublicKey {
+            bytes: decode("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"),
+        };
+        let ciphertext =
+            encrypt_with_ephemeral(&recipient, b"hello", b"context", &ephemeral_secret).unwrap();
+        assert_eq!(ciphertext[0], VERSION);
+        let ephemeral = decode("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        assert_eq!(ciphertext[1..33], ephemeral);
+
+        let shared = decode("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        let mut info = b"eddo ECIES v1 X25519 HKDF-SHA512 ChaCha20-Poly1305".to_vec();
+        info.extend_from_slice(&ephemeral);
+        info.extend_from_slice(&recipient.bytes);
+        let mut okm = [0; 44];
+        hkdf::derive(&[], &shared, &info, &mut okm);
+        let sealed = chacha20::seal(
+            okm[..32].try_into().unwrap(),
+            okm[32..].try_into().unwrap(),
+            b"context",
+            b"hello",
+        );
+        assert_eq!(ciphertext[33..], sealed[..]);
+        assert_eq!(ciphertext.len(), 5 + OVERHEAD);
+
+        let key = PrivateKey::from([2; 32]).into_exchange_only();
+        let ciphertext =
+            encrypt_with_ephemeral(&key.public_key(), b"hello", b"context", &ephemeral_secret)
+                .unwrap();
+        assert_eq!(decrypt(&key, &ciphertext, b"context").unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_roundtrip_and_failures() {
+        let key = PrivateKey::from([3; 32]).into_exchange_only();
+        let mut ciphertext =
+            encrypt(&key.public_key(), b"attack at dawn", b"", &mut OsRng).unwrap();
+        assert_eq!(ciphertext.len(), 14 + OVERHEAD);
+        assert_eq!(decrypt(&key, &ciphertext, b"").unwrap(), b"attack at dawn");
+        assert_eq!(
+            decrypt(&key, &ciphertext, b"other"),
+            Err(Error::DecryptionFailed)
+        );
+        let other = PrivateKey::from([4; 32]).into_exchange_only();
+        assert_eq!(
+            decrypt(&other, &ciphertext, b""),
+            Err(Error::DecryptionFailed)
+        );
+        assert_eq!(
+            decrypt(&key, &ciphertext[..OVERHEAD - 1], b""),
+            Err(Error::InvalidLength)
+        );
+        ciphertext[0] = 2;
+        assert_eq!(
+            decrypt(&key, &ciphertext, b""),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        let weak = ExchangePublicKey { bytes: [0; 32] };
+        assert_eq!(
+            encrypt(&weak, b"", b"", &mut OsRng),
+            Err(Error::WeakPublicKey)
+        );
+    }
+}
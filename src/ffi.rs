@@ -0,0 +1,258 @@
+//! This module exposes signing, verification, and X25519 to C.
+//!
+//! Every function returns 0 on success, and -1 on failure, including when passed
+//! a null pointer. Keys, and signatures, are passed as fixed size byte buffers,
+//! with the sizes given by the constants here. The header in `include/eddo.h` is
+//! generated from this module with cbindgen, using the configuration in `cbindgen.toml`.
+//!
+//! The shared library is built with:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+
+use core::slice;
+use std::os::raw::c_int;
+
+use rand::rngs::OsRng;
+
+use crate::{gen_keypair, x25519, PrivateKey, PublicKey, Signature};
+
+pub const EDDO_PUBLIC_KEY_SIZE: usize = crate::PUBLIC_KEY_SIZE;
+
+pub const EDDO_PRIVATE_KEY_SIZE: usize = crate::PRIVATE_KEY_SIZE;
+
+pub const EDDO_SIGNATURE_SIZE: usize = crate::SIGNATURE_SIZE;
+
+pub const EDDO_X25519_SIZE: usize = 32;
+
+const OK: c_int = 0;
+
+const ERROR: c_int = -1;
+
+/// Read a fixed size buffer, returning `None` for null pointers.
+unsafe fn read<const N: usize>(ptr: *const u8) -> Option<[u8; N]> {
+    if ptr.is_null() {
+        return None;
+    }
+    let mut out = [0; N];
+    out.copy_from_slice(slice::from_raw_parts(ptr, N));
+    Some(out)
+}
+
+/// Write to a fixed size buffer, returning false for null pointers.
+unsafe fn write(ptr: *mut u8, data: &[u8]) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    slice::from_raw_parts_mut(ptr, data.len()).copy_from_slice(data);
+    true
+}
+
+/// Read a message, which can be null if it's empty.
+unsafe fn message<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        return Some(&[]);
+    }
+    if ptr.is_null() {
+        return None;
+    }
+    Some(slice::from_raw_parts(ptr, len))
+}
+
+/// Generate a new key pair, using the randomness of the operating system.
+///
+/// # Safety
+///
+/// `public_out` must point to `EDDO_PUBLIC_KEY_SIZE` writable bytes, and `private_out`
+/// to `EDDO_PRIVATE_KEY_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_keygen(public_out: *mut u8, private_out: *mut u8) -> c_int {
+    if public_out.is_null() || private_out.is_null() {
+        return ERROR;
+    }
+    let (public, private) = gen_keypair(&mut OsRng);
+    write(public_out, public.as_bytes());
+    write(private_out, private.as_bytes());
+    OK
+}
+
+/// Calculate the public key corresponding to a private key.
+///
+/// # Safety
+///
+/// `private_key` must point to `EDDO_PRIVATE_KEY_SIZE` readable bytes, and `public_out`
+/// to `EDDO_PUBLIC_KEY_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_public_key(private_key: *const u8, public_out: *mut u8) -> c_int {
+    let private = match read(private_key) {
+        Some(bytes) => PrivateKey::from(bytes),
+        None => return ERROR,
+    };
+    let public = private.into_signing_only().public_key();
+    if !write(public_out, public.as_bytes()) {
+        return ERROR;
+    }
+    OK
+}
+
+/// Sign a message with a private key.
+///
+/// # Safety
+///
+/// `private_key` must point to `EDDO_PRIVATE_KEY_SIZE` readable bytes, `message` to
+/// `message_len` readable bytes, and `signature_out` to `EDDO_SIGNATURE_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_sign(
+    private_key: *const u8,
+    message: *const u8,
+    message_len: usize,
+    signature_out: *mut u8,
+) -> c_int {
+    let (private, message) = match (read(private_key), self::message(message, message_len)) {
+        (Some(bytes), Some(message)) => (PrivateKey::from(bytes), message),
+        _ => return ERROR,
+    };
+    if !write(signature_out, &private.sign(message).bytes) {
+        return ERROR;
+    }
+    OK
+}
+
+/// Verify a signature of a message, returning 0 only if it's valid.
+///
+/// # Safety
+///
+/// `public_key` must point to `EDDO_PUBLIC_KEY_SIZE` readable bytes, `message` to
+/// `message_len` readable bytes, and `signature` to `EDDO_SIGNATURE_SIZE` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_verify(
+    public_key: *const u8,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+) -> c_int {
+    match (
+        read(public_key),
+        self::message(message, message_len),
+        read(signature),
+    ) {
+        (Some(public), Some(message), Some(signature)) => match PublicKey::from_bytes(public) {
+            Ok(public) if public.verify(message, Signature { bytes: signature }) => OK,
+            _ => ERROR,
+        },
+        _ => ERROR,
+    }
+}
+
+/// Calculate the X25519 function of RFC 7748, with a scalar, and a u coordinate.
+///
+/// This fails if the result is all zeros, which happens for points of small order.
+///
+/// # Safety
+///
+/// `scalar`, and `u`, must point to `EDDO_X25519_SIZE` readable bytes, and `out`
+/// to `EDDO_X25519_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_x25519(scalar: *const u8, u: *const u8, out: *mut u8) -> c_int {
+    let (scalar, u) = match (read(scalar), read(u)) {
+        (Some(scalar), Some(u)) => (scalar, u),
+        _ => return ERROR,
+    };
+    let shared = x25519(scalar, u);
+    if shared == [0; EDDO_X25519_SIZE] || !write(out, &shared) {
+        return ERROR;
+    }
+    OK
+}
+
+/// Calculate the X25519 public key for a scalar, i.e. X25519 with the base point.
+///
+/// # Safety
+///
+/// `scalar` must point to `EDDO_X25519_SIZE` readable bytes, and `out` to
+/// `EDDO_X25519_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn eddo_x25519_base(scalar: *const u8, out: *mut u8) -> c_int {
+    let mut base = [0; EDDO_X25519_SIZE];
+    base[0] = 9;
+    eddo_x25519(scalar, base.as_ptr(), out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use core::ptr;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut public = [0; EDDO_PUBLIC_KEY_SIZE];
+        let mut private = [0; EDDO_PRIVATE_KEY_SIZE];
+        let mut derived = [0; EDDO_PUBLIC_KEY_SIZE];
+        let mut signature = [0; EDDO_SIGNATURE_SIZE];
+        let message = b"hello from C";
+        unsafe {
+            assert_eq!(eddo_keygen(public.as_mut_ptr(), private.as_mut_ptr()), OK);
+            assert_eq!(eddo_public_key(private.as_ptr(), derived.as_mut_ptr()), OK);
+            assert_eq!(public, derived);
+            assert_eq!(
+                eddo_sign(
+                    private.as_ptr(),
+                    message.as_ptr(),
+                    message.len(),
+                    signature.as_mut_ptr()
+                ),
+                OK
+            );
+            let verify =
+                |len| eddo_verify(public.as_ptr(), message.as_ptr(), len, signature.as_ptr());
+            assert_eq!(verify(message.len()), OK);
+            assert_eq!(verify(message.len() - 1), ERROR);
+            assert_eq!(
+                eddo_verify(public.as_ptr(), ptr::null(), 0, signature.as_ptr()),
+                ERROR
+            );
+            assert_eq!(eddo_keygen(ptr::null_mut(), private.as_mut_ptr()), ERROR);
+            assert_eq!(
+                eddo_sign(private.as_ptr(), ptr::null(), 1, signature.as_mut_ptr()),
+                ERROR
+            );
+        }
+    }
+
+    #[test]
+    fn test_x25519() {
+        let alice = [1; EDDO_X25519_SIZE];
+        let bob = [2; EDDO_X25519_SIZE];
+        let mut alice_public = [0; EDDO_X25519_SIZE];
+        let mut bob_public = [0; EDDO_X25519_SIZE];
+        let mut alice_shared = [0; EDDO_X25519_SIZE];
+        let mut bob_shared = [0; EDDO_X25519_SIZE];
+        unsafe {
+            assert_eq!(
+                eddo_x25519_base(alice.as_ptr(), alice_public.as_mut_ptr()),
+                OK
+            );
+            assert_eq!(eddo_x25519_base(bob.as_ptr(), bob_public.as_mut_ptr()), OK);
+            assert_eq!(
+                eddo_x25519(
+                    alice.as_ptr(),
+                    bob_public.as_ptr(),
+                    alice_shared.as_mut_ptr()
+                ),
+                OK
+            );
+            assert_eq!(
+                eddo_x25519(bob.as_ptr(), alice_public.as_ptr(), bob_shared.as_mut_ptr()),
+                OK
+            );
+            assert_eq!(alice_shared, bob_shared);
+            let zero = [0; EDDO_X25519_SIZE];
+            assert_eq!(
+                eddo_x25519(alice.as_ptr(), zero.as_ptr(), alice_shared.as_mut_ptr()),
+                ERROR
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! This module implements HKDF over SHA-512, and SHA-256, as described in RFC 5869:
+//! https://datatracker.ietf.org/doc/html/rfc5869
+//!
+//! This is the standard way of turning a shared secret, e.g. from `x25519`, into
+//! keys for specific uses, with a different `info` string for each of them.
+
+use crate::hmac::{hmac, hmac_sha256, HmacSha256, HmacSha512, SHA256_TAG_SIZE};
+use crate::sha512::HASH_SIZE;
+use crate::zeroize::Zeroizing;
+
+/// Extract a pseudorandom key from some input keying material, and a salt.
+///
+/// This is the first step of HKDF, described in Section 2.2:
+/// https://datatracker.ietf.org/doc/html/rfc5869#section-2.2
+pub fn extract(salt: &[u8], ikm: &[u8]) -> [u8; HASH_SIZE] {
+    hmac(salt, ikm)
+}
+
+/// Expand a pseudorandom key into output keying material, filling `okm`.
+///
+/// This is the second step of HKDF, described in Section 2.3:
+/// https://datatracker.ietf.org/doc/html/rfc5869#section-2.3
+///
+/// The output can be at most 255 * 64 bytes long, and this function will panic
+/// if asked to produce more.
+pub fn expand(prk: &[u8; HASH_SIZE], info: &[u8], okm: &mut [u8]) {
+    assert!(okm.len() <= 255 * HASH_SIZE, "HKDF output too long");
+
+    // T(0) is empty, so there's no previous block to start with.
+    let mut previous = Zeroizing::new([0; HASH_SIZE]);
+    for (i, chunk) in okm.chunks_mut(HASH_SIZE).enumerate() {
+        // T(i) = HMAC(PRK, T(i - 1) | info | i)
+        let mut mac = HmacSha512::new(prk);
+        if i > 0 {
+            mac.update(&*previous);
+        }
+        mac.update(info);
+        mac.update(&[(i + 1) as u8]);
+        *previous = mac.finalize();
+        chunk.copy_from_slice(&previous[..chunk.len()]);
+    }
+}
+
+/// Run both steps of HKDF, filling `okm` with keying material derived from `ikm`.
+pub fn derive(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) {
+    let prk = Zeroizing::new(extract(salt, ikm));
+    expand(&prk, info, okm);
+}
+
+/// Extract a pseudorandom key, like `extract`, but with HKDF-SHA-256.
+pub fn extract_sha256(salt: &[u8], ikm: &[u8]) -> [u8; SHA256_TAG_SIZE] {
+    hmac_sha256(salt, ikm)
+}
+
+/// Expand a pseudorandom key, like `expand`, but with HKDF-SHA-256.
+///
+/// The output can be at most 255 * 32 bytes long, and this function will panic
+/// if asked to produce more.
+pub fn expand_sha256(prk: &[u8; SHA256_TAG_SIZE], info: &[u8], okm: &mut [u8]) {
+    assert!(okm.len() <= 255 * SHA256_TAG_SIZE, "HKDF output too long");
+
+    let mut previous = Zeroizing::new([0; SHA256_TAG_SIZE]);
+    for (i, chunk) in okm.chunks_mut(SHA256_TAG_SIZE).enumerate() {
+        let mut mac = HmacSha256::new(prk);
+        if i > 0 {
+            mac.update(&*previous);
+        }
+        mac.update(info);
+        mac.update(&[(i + 1) as u8]);
+        *previous = mac.finalize();
+        chunk.copy_from_slice(&previous[..chunk.len()]);
+    }
+}
+
+/// Run both steps of HKDF-SHA-256, filling `okm` with keying material derived from `ikm`.
+pub fn derive_sha256(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) {
+    let prk = Zeroizing::new(extract_sha256(salt, ikm));
+    expand_sha256(&prk, info, okm);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_derive_vector() {
+        // The inputs of test case 1 from RFC 5869, but using SHA-512:
+        // https://datatracker.ietf.org/doc/html/rfc5869#appendix-A.1
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0..13).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+        let mut expected = [0; 42];
+        hex::decode_to_slice(
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb",
+            &mut expected,
+        )
+        .unwrap();
+        let mut okm = [0; 42];
+        derive(&salt, &ikm, &info, &mut okm);
+        assert_eq!(okm, expected);
+    }
+
+    #[test]
+    fn test_expand_prefixes_agree() {
+        let prk = extract(b"salt", b"input keying material");
+        let mut short = [0; 10];
+        let mut long = [0; 200];
+        expand(&prk, b"info", &mut short);
+        expand(&prk, b"info", &mut long);
+        assert_eq!(short, long[..10]);
+        // The first block is just an HMAC with the counter appended
+        assert_eq!(long[..HASH_SIZE], hmac(&prk, b"info\x01"));
+    }
+
+    #[test]
+    fn test_sha256_vector() {
+        // Test case 1 from RFC 5869, which uses SHA-256:
+        // https://datatracker.ietf.org/doc/html/rfc5869#appendix-A.1
+        let salt: Vec<u8> = (0..13).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+        let mut expected_prk = [0; SHA256_TAG_SIZE];
+        hex::decode_to_slice(
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+            &mut expected_prk,
+        )
+        .unwrap();
+        let prk = extract_sha256(&salt, &[0x0b; 22]);
+        assert_eq!(prk, expected_prk);
+        let mut expected = [0; 42];
+        hex::decode_to_slice(
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+            &mut expected,
+        )
+        .unwrap();
+        let mut okm = [0; 42];
+        expand_sha256(&prk, &info, &mut okm);
+        assert_eq!(okm, expected);
+        derive_sha256(&salt, &[0x0b; 22], &info, &mut okm);
+        assert_eq!(okm, expected);
+    }
+}
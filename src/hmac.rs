@@ -0,0 +1,238 @@
+//! This module implements HMAC over SHA-512, and SHA-256, as defined in RFC 2104:
+//! https://datatracker.ietf.org/doc/html/rfc2104
+//!
+//! Test cases come from RFC 4231:
+//! https://datatracker.ietf.org/doc/html/rfc4231
+
+use subtle::ConstantTimeEq;
+
+use crate::sha256::{self, Sha256};
+use crate::sha512::{self, Sha512, HASH_SIZE};
+use crate::zeroize::Zeroizing;
+
+/// The block size of SHA-512, which HMAC uses to pad keys.
+const BLOCK_SIZE: usize = 128;
+
+/// The block size of SHA-256.
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// The number of bytes in an HMAC-SHA-512 tag.
+pub const TAG_SIZE: usize = HASH_SIZE;
+
+/// The number of bytes in an HMAC-SHA-256 tag.
+pub const SHA256_TAG_SIZE: usize = sha256::HASH_SIZE;
+
+/// An incremental HMAC-SHA-512 calculation.
+///
+/// Both the inner and the outer hash absorb their padded key up front, so the key
+/// itself isn't kept around.
+#[derive(Clone)]
+pub struct HmacSha512 {
+    inner: Sha512,
+    outer: Sha512,
+}
+
+impl HmacSha512 {
+    /// Start calculating an HMAC with a given key.
+    ///
+    /// This follows Section 2 of RFC 2104:
+    /// https://datatracker.ietf.org/doc/html/rfc2104#section-2
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first, and then zero padded.
+        let mut padded_key = Zeroizing::new([0; BLOCK_SIZE]);
+        if key.len() > BLOCK_SIZE {
+            padded_key[..HASH_SIZE].copy_from_slice(&sha512::hash(key));
+        } else {
+            padded_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut pad = Zeroizing::new([0; BLOCK_SIZE]);
+        for (p, k) in pad.iter_mut().zip(padded_key.iter()) {
+            *p = k ^ 0x36;
+        }
+        let mut inner = Sha512::new();
+        inner.update(&*pad);
+
+        for (p, k) in pad.iter_mut().zip(padded_key.iter()) {
+            *p = k ^ 0x5C;
+        }
+        let mut outer = Sha512::new();
+        outer.update(&*pad);
+        HmacSha512 { inner, outer }
+    }
+
+    /// Add more data to the message being authenticated.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Produce the tag for all the data passed in so far.
+    pub fn finalize(self) -> [u8; TAG_SIZE] {
+        let HmacSha512 { inner, mut outer } = self;
+        let inner = Zeroizing::new(inner.finalize());
+        outer.update(&*inner);
+        outer.finalize()
+    }
+
+    /// Check, in constant time, that a tag matches the data passed in so far.
+    ///
+    /// Only full length tags are accepted.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        if tag.len() != TAG_SIZE {
+            return false;
+        }
+        self.finalize()[..].ct_eq(tag).into()
+    }
+}
+
+/// Calculate HMAC-SHA-512 over a message, with a given key.
+pub fn hmac(key: &[u8], message: &[u8]) -> [u8; TAG_SIZE] {
+    let mut mac = HmacSha512::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+/// An incremental HMAC-SHA-256 calculation, like `HmacSha512`.
+#[derive(Clone)]
+pub struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    /// Start calculating an HMAC with a given key.
+    pub fn new(key: &[u8]) -> Self {
+        let mut padded_key = Zeroizing::new([0; SHA256_BLOCK_SIZE]);
+        if key.len() > SHA256_BLOCK_SIZE {
+            padded_key[..SHA256_TAG_SIZE].copy_from_slice(&sha256::hash(key));
+        } else {
+            padded_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut pad = Zeroizing::new([0; SHA256_BLOCK_SIZE]);
+        for (p, k) in pad.iter_mut().zip(padded_key.iter()) {
+            *p = k ^ 0x36;
+        }
+        let mut inner = Sha256::new();
+        inner.update(&*pad);
+
+        for (p, k) in pad.iter_mut().zip(padded_key.iter()) {
+            *p = k ^ 0x5C;
+        }
+        let mut outer = Sha256::new();
+        outer.update(&*pad);
+        HmacSha256 { inner, outer }
+    }
+
+    /// Add more data to the message being authenticated.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Produce the tag for all the data passed in so far.
+    pub fn finalize(self) -> [u8; SHA256_TAG_SIZE] {
+        let HmacSha256 { inner, mut outer } = self;
+        let inner = Zeroizing::new(inner.finalize());
+        outer.update(&*inner);
+        outer.finalize()
+    }
+
+    /// Check, in constant time, that a full length tag matches the data passed in so far.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        if tag.len() != SHA256_TAG_SIZE {
+            return false;
+        }
+        self.finalize()[..].ct_eq(tag).into()
+    }
+}
+
+/// Calculate HMAC-SHA-256 over a message, with a given key.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; SHA256_TAG_SIZE] {
+    let mut mac = HmacSha256::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hmac_vectors() {
+        // These are test cases 1 and 2 from RFC 4231:
+        // https://datatracker.ietf.org/doc/html/rfc4231#section-4
+        let mut expected = [0; TAG_SIZE];
+
+        let actual = hmac(&[0x0b; 20], b"Hi There");
+        hex::decode_to_slice(
+        "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(actual, expected);
+
+        let actual = hmac(b"Jefe", b"what do ya want for nothing?");
+        hex::decode_to_slice(
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(actual, expected);
+
+        // Test case 6 uses a key longer than a block.
+        let actual = hmac(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        hex::decode_to_slice(
+        "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_incremental_and_verify() {
+        let tag = hmac(b"key", b"some message in pieces");
+        let mut mac = HmacSha512::new(b"key");
+        mac.update(b"some message");
+        let copy = mac.clone();
+        mac.update(b" in pieces");
+        assert_eq!(mac.clone().finalize(), tag);
+        assert!(mac.clone().verify(&tag));
+        assert!(!mac.clone().verify(&tag[..32]));
+        let mut wrong = tag;
+        wrong[5] ^= 1;
+        assert!(!mac.verify(&wrong));
+        assert!(!copy.verify(&tag));
+    }
+
+    #[test]
+    fn test_hmac_sha256_vectors() {
+        // Test cases 2, and 6, from RFC 4231, again.
+        let mut expected = [0; SHA256_TAG_SIZE];
+        hex::decode_to_slice(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?"),
+            expected
+        );
+        let mut mac = HmacSha256::new(b"Jefe");
+        mac.update(b"what do ya want ");
+        mac.update(b"for nothing?");
+        assert!(mac.clone().verify(&expected));
+        assert!(!mac.verify(&expected[..16]));
+
+        hex::decode_to_slice(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            &mut expected,
+        )
+        .unwrap();
+        let actual = hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        assert_eq!(actual, expected);
+    }
+}
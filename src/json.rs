@@ -0,0 +1,275 @@
+//! This module implements just enough of JSON, as described in RFC 8259, for JWK, and JWS:
+//! https://datatracker.ietf.org/doc/html/rfc8259
+//!
+//! We only ever need the string members of a single object, so other values get
+//! validated, but not kept around.
+
+use crate::Error;
+use alloc::{format, string::String, vec::Vec};
+
+/// How deeply arrays and objects may be nested, which keeps recursion bounded.
+const MAX_DEPTH: usize = 32;
+
+/// A member of an object, only keeping the contents of strings.
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Other,
+}
+
+/// The members of an object, in order.
+pub type Members = Vec<(String, Value)>;
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(Error::InvalidEncoding);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .ok_or(Error::InvalidEncoding)?;
+        let digits = core::str::from_utf8(digits).map_err(|_| Error::InvalidEncoding)?;
+        let out = u32::from_str_radix(digits, 16).map_err(|_| Error::InvalidEncoding)?;
+        self.pos += 4;
+        Ok(out)
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let c = self.peek().ok_or(Error::InvalidEncoding)?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.peek().ok_or(Error::InvalidEncoding)?;
+                    self.pos += 1;
+                    let decoded = match escape {
+                        b'"' | b'\\' | b'/' => char::from(escape),
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex_escape()?;
+                            // Characters outside of the BMP are escaped as surrogate pairs
+                            if (0xD800..0xDC00).contains(&code) {
+                                if self.input.get(self.pos..self.pos + 2) != Some(b"\\u") {
+                                    return Err(Error::InvalidEncoding);
+                                }
+                                self.pos += 2;
+                                let low = self.hex_escape()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(Error::InvalidEncoding);
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code).ok_or(Error::InvalidEncoding)?
+                        }
+                        _ => return Err(Error::InvalidEncoding),
+                    };
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+                }
+                0..=0x1F => return Err(Error::InvalidEncoding),
+                _ => out.push(c),
+            }
+        }
+        String::from_utf8(out).map_err(|_| Error::InvalidEncoding)
+    }
+
+    fn literal(&mut self) -> Result<(), Error> {
+        let start = self.pos;
+        while let Some(b'+' | b'-' | b'.' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z') = self.peek() {
+            self.pos += 1;
+        }
+        let literal = &self.input[start..self.pos];
+        let valid = match literal {
+            b"true" | b"false" | b"null" => true,
+            // This is a bit more lenient than JSON, accepting e.g. "01", which doesn't matter here.
+            [b'-' | b'0'..=b'9', ..] => core::str::from_utf8(literal)
+                .ok()
+                .and_then(|x| x.parse::<f64>().ok())
+                .is_some(),
+            _ => false,
+        };
+        if !valid {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidEncoding);
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"') => return Ok(Value::String(self.string()?)),
+            Some(b'{') => {
+                self.object(depth + 1)?;
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.value(depth + 1)?;
+                        self.skip_whitespace();
+                        match self.peek() {
+                            Some(b',') => self.pos += 1,
+                            Some(b']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(Error::InvalidEncoding),
+                        }
+                    }
+                }
+            }
+            _ => self.literal()?,
+        }
+        Ok(Value::Other)
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Members, Error> {
+        self.expect(b'{')?;
+        let mut out = Members::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(out);
+        }
+        loop {
+            let name = self.string()?;
+            self.expect(b':')?;
+            let value = self.value(depth)?;
+            // Duplicate names are ambiguous, which is dangerous in security formats.
+            if out.iter().any(|(n, _)| *n == name) {
+                return Err(Error::InvalidEncoding);
+            }
+            out.push((name, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                _ => return Err(Error::InvalidEncoding),
+            }
+        }
+    }
+}
+
+/// Parse a JSON object, returning its members, in order.
+pub fn parse_object(input: &str) -> Result<Members, Error> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        pos: 0,
+    };
+    let out = parser.object(0)?;
+    parser.skip_whitespace();
+    if parser.pos != parser.input.len() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(out)
+}
+
+/// Find the string member with a given name, returning `None` if it's missing.
+///
+/// Members with the right name, but which aren't strings, are an error.
+pub fn get_string<'a>(
+    members: &'a [(String, Value)],
+    name: &str,
+) -> Result<Option<&'a str>, Error> {
+    match members.iter().find(|(n, _)| n == name) {
+        None => Ok(None),
+        Some((_, Value::String(s))) => Ok(Some(s)),
+        Some((_, Value::Other)) => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Encode a string as JSON, with quotes.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{0}'..='\u{1F}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_object() {
+        let members = parse_object(
+            r#" {"a": "x\"\u00e9\ud83d\ude00", "b": [1, -2.5e3, {"c": null}], "d": true} "#,
+        )
+        .unwrap();
+        assert_eq!(get_string(&members, "a"), Ok(Some("x\"é😀")));
+        assert_eq!(members[1], ("b".to_string(), Value::Other));
+        assert_eq!(get_string(&members, "e"), Ok(None));
+        assert!(get_string(&members, "d").is_err());
+        assert_eq!(
+            parse_object(&format!("{{\"q\":{}}}", quote("a\"b\\\n"))).unwrap()[0].1,
+            Value::String("a\"b\\\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for input in [
+            "",
+            "[]",
+            "{\"a\":1,}",
+            "{\"a\":1}x",
+            "{\"a\":1,\"a\":2}",
+            "{\"a\":tru}",
+            "{\"a\":nan}",
+            "{\"a\":\"\\ud800\"}",
+            "{\"a\":\"\n\"}",
+        ]
+        .iter()
+        {
+            assert!(parse_object(input).is_err(), "{}", input);
+        }
+        let deep = format!("{{\"a\":{}{}}}", "[".repeat(100), "]".repeat(100));
+        assert!(parse_object(&deep).is_err());
+    }
+}
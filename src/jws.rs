@@ -0,0 +1,136 @@
+//! This module implements JSON Web Signatures, in the compact serialization, using
+//! the "EdDSA" algorithm of RFC 8037:
+//! https://datatracker.ietf.org/doc/html/rfc8037#section-3.1
+//!
+//! The serialization itself is described in Section 7.1 of RFC 7515:
+//! https://datatracker.ietf.org/doc/html/rfc7515#section-7.1
+
+use core::convert::TryFrom;
+
+use crate::{base64, json, Error, PrivateKey, PublicKey, Signature};
+
+const ALGORITHM: &str = "EdDSA";
+
+/// Sign a payload with a key, producing the compact serialization of a JWS.
+///
+/// The protected header only contains "alg", set to "EdDSA".
+pub fn sign(key: &PrivateKey, payload: &[u8]) -> String {
+    sign_with_header(key, &format!(r#"{{"alg":"{}"}}"#, ALGORITHM), payload)
+}
+
+/// Sign a payload, like `sign`, but also include a "kid" in the header.
+///
+/// This lets verifiers pick the right key, using `key_id`.
+pub fn sign_with_key_id(key: &PrivateKey, key_id: &str, payload: &[u8]) -> String {
+    let header = format!(r#"{{"alg":"{}","kid":{}}}"#, ALGORITHM, json::quote(key_id));
+    sign_with_header(key, &header, payload)
+}
+
+fn sign_with_header(key: &PrivateKey, header: &str, payload: &[u8]) -> String {
+    // The signing input is BASE64URL(header) || '.' || BASE64URL(payload)
+    let mut out = base64::encode_url(header.as_bytes());
+    out.push('.');
+    out.push_str(&base64::encode_url(payload));
+    let signature = key.sign(out.as_bytes());
+    out.push('.');
+    out.push_str(&base64::encode_url(&signature.bytes));
+    out
+}
+
+/// Split a token into its header, and the rest, checking that it has three parts.
+fn split(token: &str) -> Result<(json::Members, [&str; 3]), Error> {
+    let mut parts = token.split('.');
+    let (header, payload, signature) =
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(h), Some(p), Some(s), None) => (h, p, s),
+            _ => return Err(Error::InvalidEncoding),
+        };
+    let decoded = base64::decode_url(header.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    let decoded = String::from_utf8(decoded).map_err(|_| Error::InvalidEncoding)?;
+    Ok((json::parse_object(&decoded)?, [header, payload, signature]))
+}
+
+/// Read the "kid" from the header of a token, without verifying it.
+///
+/// This is only useful to pick which key to verify the token with.
+pub fn key_id(token: &str) -> Result<Option<String>, Error> {
+    let (header, _) = split(token)?;
+    Ok(json::get_string(&header, "kid")?.map(String::from))
+}
+
+/// Verify a JWS in the compact serialization, returning its payload.
+///
+/// Tokens using another algorithm, or a header with critical extensions, are rejected.
+pub fn verify(key: &PublicKey, token: &str) -> Result<Vec<u8>, Error> {
+    let (header, [encoded_header, encoded_payload, encoded_signature]) = split(token)?;
+    // Checking this explicitly avoids accepting e.g. "none", as has happened elsewhere.
+    if json::get_string(&header, "alg")? != Some(ALGORITHM) {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    // We don't understand any extensions, e.g. unencoded payloads from RFC 7797.
+    if header.iter().any(|(name, _)| name == "crit") {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    let signature =
+        base64::decode_url(encoded_signature.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    let signature = Signature::try_from(&signature[..])?;
+    let payload =
+        base64::decode_url(encoded_payload.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    let signing_input = &token[..encoded_header.len() + 1 + encoded_payload.len()];
+    if !key.verify(signing_input.as_bytes(), signature) {
+        return Err(Error::SignatureInvalid);
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rfc_8037_vector() {
+        // From Appendix A.4, with the key from Appendix A.1
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            &mut bytes,
+        )
+        .unwrap();
+        let key = PrivateKey::from(bytes);
+        let public: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            .parse()
+            .unwrap();
+        let token = "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc.hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg";
+        assert_eq!(sign(&key, b"Example of Ed25519 signing"), token);
+        assert_eq!(
+            verify(&public, token).unwrap(),
+            b"Example of Ed25519 signing"
+        );
+        assert_eq!(key_id(token), Ok(None));
+    }
+
+    #[test]
+    fn test_rejects_invalid_tokens() {
+        let (public, key) = crate::gen_keypair(&mut rand::rngs::OsRng);
+        let token = sign_with_key_id(&key, "key \"1\"", b"payload");
+        assert_eq!(key_id(&token), Ok(Some("key \"1\"".to_string())));
+        assert_eq!(verify(&public, &token).unwrap(), b"payload");
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let other_payload = format!("{}.{}.{}", parts[0], base64::encode_url(b"other"), parts[2]);
+        assert_eq!(
+            verify(&public, &other_payload),
+            Err(Error::SignatureInvalid)
+        );
+        let none = format!("{}.{}.", base64::encode_url(br#"{"alg":"none"}"#), parts[1]);
+        assert_eq!(verify(&public, &none), Err(Error::UnsupportedAlgorithm));
+        let critical =
+            sign_with_header(&key, r#"{"alg":"EdDSA","b64":false,"crit":["b64"]}"#, b"x");
+        assert_eq!(verify(&public, &critical), Err(Error::UnsupportedAlgorithm));
+        assert_eq!(verify(&public, "a.b"), Err(Error::InvalidEncoding));
+        assert_eq!(
+            verify(&public, &format!("{}.", token)),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
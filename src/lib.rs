@@ -1,11 +1,83 @@
+//! An implementation of Ed25519 signatures, along with X25519 key exchange.
+//!
+//! The API is split in two. The `safe` module, re-exported here, contains keys,
+//! signing, verification, key exchange, and hashing, and follows semver. With the
+//! `hazmat` feature, the `hazmat` module also exposes raw field, scalar and group
+//! operations, and the protocols built on them, which are unstable, and easy to misuse.
+//!
+//! Without the default `std` feature, the crate is `no_std`. Key generation,
+//! signing, verification, and key exchange, never allocate, and work without
+//! an allocator. Everything producing vectors, or strings, needs the `alloc`
+//! feature, and everything built on `std::io` needs `std`. The crate in `no-alloc/`
+//! checks this, by linking these operations into a library without an allocator.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 extern crate hex;
 extern crate subtle;
 
+#[cfg(feature = "std")]
+pub mod age;
 mod arch;
+#[cfg(feature = "alloc")]
+mod base58;
+#[cfg(feature = "std")]
+pub mod base64;
+#[cfg(feature = "std")]
+mod bech32;
+#[cfg(feature = "alloc")]
+mod blake2b;
+#[cfg(feature = "alloc")]
+pub mod box_;
+#[cfg(feature = "alloc")]
+mod cbor;
+#[cfg(feature = "alloc")]
+mod chacha20;
+pub mod cpu;
 mod curve25519;
+pub mod curve448;
+#[cfg(feature = "alloc")]
+pub mod ecies;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod hkdf;
+pub mod hmac;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+pub mod jws;
+#[cfg(feature = "std")]
+pub mod paseto;
+#[cfg(feature = "std")]
+pub mod pem;
+#[cfg(feature = "alloc")]
+mod poly1305;
+#[cfg(feature = "rand_core_09")]
+pub mod rng;
+pub mod safe;
+#[cfg(feature = "alloc")]
+mod salsa20;
+mod sha256;
 mod sha512;
+mod shake;
+pub mod uint;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "zeroize")]
+pub mod zeroize;
+// Without the feature, `Zeroizing` still wraps secrets, but never erases them.
+#[cfg(not(feature = "zeroize"))]
+#[allow(dead_code)]
+mod zeroize;
+
+pub use safe::*;
+
+#[cfg(feature = "compat")]
+pub use curve25519::compat;
+#[cfg(feature = "hazmat")]
+pub use curve25519::hazmat;
+pub use curve25519::sodium;
 
-pub use curve25519::{
-    gen_keypair, PrivateKey, PublicKey, Signature, PRIVATE_KEY_SIZE, PUBLIC_KEY_SIZE,
-    SIGNATURE_SIZE,
-};
+#[cfg(feature = "debug-tools")]
+pub use curve25519::debug;
@@ -0,0 +1,155 @@
+//! This module implements PASETO tokens, using the "v4.public" purpose, which signs
+//! tokens with Ed25519:
+//! https://github.com/paseto-standard/paseto-spec/blob/master/docs/01-Protocol-Versions/Version4.md#sign
+//!
+//! Both the footer, which gets sent along with the token, and the implicit assertion,
+//! which doesn't, are covered by the signature, through the pre-authentication encoding:
+//! https://github.com/paseto-standard/paseto-spec/blob/master/docs/01-Protocol-Versions/Common.md#pae-definition
+
+use core::convert::TryFrom;
+
+use subtle::ConstantTimeEq;
+
+use crate::{base64, Error, PrivateKey, PublicKey, Signature, SIGNATURE_SIZE};
+
+const HEADER: &str = "v4.public.";
+
+/// Encode some pieces unambiguously, as PAE(pieces) in the specification.
+///
+/// Each length is a 64 bit little endian integer, with the top bit cleared.
+fn pre_auth_encode(pieces: &[&[u8]]) -> Vec<u8> {
+    let le64 = |n: usize| ((n as u64) & (u64::MAX >> 1)).to_le_bytes();
+    let mut out = le64(pieces.len()).to_vec();
+    for piece in pieces {
+        out.extend_from_slice(&le64(piece.len()));
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+/// Sign a payload with a key, producing a "v4.public" token.
+///
+/// The footer is appended to the token, unless it's empty, while the implicit assertion
+/// must be supplied again by the verifier. Both can be empty.
+pub fn sign(key: &PrivateKey, payload: &[u8], footer: &[u8], implicit: &[u8]) -> String {
+    let signature = key.sign(&pre_auth_encode(&[
+        HEADER.as_bytes(),
+        payload,
+        footer,
+        implicit,
+    ]));
+    let mut body = payload.to_vec();
+    body.extend_from_slice(&signature.bytes);
+    let mut out = String::from(HEADER);
+    out.push_str(&base64::encode_url(&body));
+    if !footer.is_empty() {
+        out.push('.');
+        out.push_str(&base64::encode_url(footer));
+    }
+    out
+}
+
+/// Split a token into its decoded body, and footer, checking the header.
+fn split(token: &str) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let rest = token
+        .strip_prefix(HEADER)
+        .ok_or(Error::UnsupportedAlgorithm)?;
+    let mut parts = rest.split('.');
+    let (body, footer) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(b), None, None) => (b, ""),
+        (Some(b), Some(f), None) => (b, f),
+        _ => return Err(Error::InvalidEncoding),
+    };
+    let body = base64::decode_url(body.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    let footer = base64::decode_url(footer.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    Ok((body, footer))
+}
+
+/// Read the footer of a token, without verifying it.
+///
+/// This is only useful to pick which key to verify the token with, e.g. by a "kid".
+pub fn footer(token: &str) -> Result<Vec<u8>, Error> {
+    Ok(split(token)?.1)
+}
+
+/// Verify a "v4.public" token, returning its payload.
+///
+/// The footer must match the one expected, and the implicit assertion the one used
+/// when signing, or the token gets rejected.
+pub fn verify(
+    key: &PublicKey,
+    token: &str,
+    footer: &[u8],
+    implicit: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let (mut body, actual_footer) = split(token)?;
+    if body.len() < SIGNATURE_SIZE {
+        return Err(Error::InvalidEncoding);
+    }
+    // The footer isn't secret, but the specification asks for a constant-time comparison.
+    if !bool::from(actual_footer.ct_eq(footer)) {
+        return Err(Error::SignatureInvalid);
+    }
+    let signature = Signature::try_from(&body[body.len() - SIGNATURE_SIZE..])?;
+    body.truncate(body.len() - SIGNATURE_SIZE);
+    let message = pre_auth_encode(&[HEADER.as_bytes(), &body, footer, implicit]);
+    key.try_verify(&message, signature)?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vector_4_s_1() {
+        // From the official test vectors for version 4, with an empty footer.
+        let mut seed = [0; 32];
+        hex::decode_to_slice(
+            "b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774",
+            &mut seed,
+        )
+        .unwrap();
+        let key = PrivateKey::from_seed(seed);
+        let public: PublicKey = "1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2"
+            .parse()
+            .unwrap();
+        assert_eq!(key.derive_public_key(), public);
+        let payload = br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+        let token = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA";
+        assert_eq!(sign(&key, payload, b"", b""), token);
+        assert_eq!(verify(&public, token, b"", b"").unwrap(), &payload[..]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_tokens() {
+        let (public, key) = crate::gen_keypair_from_seed([1; 32]);
+        let token = sign(&key, b"payload", br#"{"kid":"1"}"#, b"context");
+        assert_eq!(footer(&token).unwrap(), br#"{"kid":"1"}"#);
+        assert_eq!(
+            verify(&public, &token, br#"{"kid":"1"}"#, b"context").unwrap(),
+            b"payload"
+        );
+        assert_eq!(
+            verify(&public, &token, br#"{"kid":"2"}"#, b"context"),
+            Err(Error::SignatureInvalid)
+        );
+        assert_eq!(
+            verify(&public, &token, br#"{"kid":"1"}"#, b"other"),
+            Err(Error::SignatureInvalid)
+        );
+        let v3 = token.replacen("v4", "v3", 1);
+        assert_eq!(
+            verify(&public, &v3, br#"{"kid":"1"}"#, b"context"),
+            Err(Error::UnsupportedAlgorithm)
+        );
+        assert_eq!(
+            verify(&public, "v4.public.AAAA", b"", b""),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            verify(&public, &format!("{}.", token), b"", b""),
+            Err(Error::InvalidEncoding)
+        );
+    }
+}
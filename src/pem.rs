@@ -0,0 +1,228 @@
+//! This module implements reading and writing PEM armored data, as described in RFC 7468:
+//! https://datatracker.ietf.org/doc/html/rfc7468
+//!
+//! When reading, the body gets decoded as it's read, so large armored payloads never
+//! need to be buffered in full. Only a single line is kept in memory at a time.
+
+use std::io::{self, BufRead, Read};
+
+use crate::base64;
+
+/// The longest line we accept, to keep memory use bounded.
+///
+/// Conforming encoders produce lines of at most 64 characters.
+const MAX_LINE_SIZE: usize = 1024;
+
+/// The length of the lines we produce, as required by Section 2.
+const LINE_SIZE: usize = 64;
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Read a single line, without its line ending, returning false at the end of input.
+fn read_line<R: BufRead>(inner: &mut R, line: &mut Vec<u8>) -> io::Result<bool> {
+    line.clear();
+    loop {
+        let available = match inner.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            return Ok(!line.is_empty());
+        }
+        let (chunk, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (&available[..i], i + 1),
+            None => (available, available.len()),
+        };
+        if line.len() + chunk.len() > MAX_LINE_SIZE {
+            return Err(invalid("PEM line too long"));
+        }
+        line.extend_from_slice(chunk);
+        let found_end = done > chunk.len();
+        inner.consume(done);
+        if found_end {
+            break;
+        }
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(true)
+}
+
+/// Parse the label out of an encapsulation boundary, like "-----BEGIN label-----".
+fn boundary_label<'a>(line: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    let rest = line.strip_prefix(b"-----")?.strip_prefix(kind)?;
+    let rest = rest.strip_prefix(b" ")?;
+    rest.strip_suffix(b"-----")
+}
+
+/// The base64 text of a PEM body, stopping at the end boundary.
+struct Body<R> {
+    inner: R,
+    label: String,
+    line: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Read for Body<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.line.len() {
+            if self.done {
+                return Ok(0);
+            }
+            if !read_line(&mut self.inner, &mut self.line)? {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "missing PEM end boundary",
+                ));
+            }
+            self.pos = 0;
+            if let Some(label) = boundary_label(&self.line, b"END") {
+                if label != self.label.as_bytes() {
+                    return Err(invalid("mismatched PEM end boundary"));
+                }
+                self.line.clear();
+                self.done = true;
+            }
+        }
+        let n = (self.line.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A reader for a single PEM armored document, producing the decoded body.
+///
+/// Any text before the begin boundary is skipped, as explanatory text is allowed there.
+/// Reading fails if the end boundary is missing, or has a different label.
+pub struct Reader<R> {
+    decoder: base64::Decoder<Body<R>>,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Start reading a PEM document, consuming everything up to the begin boundary.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut line = Vec::with_capacity(MAX_LINE_SIZE);
+        let label = loop {
+            if !read_line(&mut inner, &mut line)? {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "missing PEM begin boundary",
+                ));
+            }
+            if let Some(label) = boundary_label(&line, b"BEGIN") {
+                let label =
+                    core::str::from_utf8(label).map_err(|_| invalid("invalid PEM label"))?;
+                break label.to_string();
+            }
+        };
+        line.clear();
+        let body = Body {
+            inner,
+            label,
+            line,
+            pos: 0,
+            done: false,
+        };
+        Ok(Reader {
+            decoder: base64::Decoder::new(body),
+        })
+    }
+
+    /// The label of this document, e.g. "PRIVATE KEY".
+    pub fn label(&self) -> &str {
+        &self.decoder.get_ref().label
+    }
+}
+
+impl<R: BufRead> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
+
+/// Armor some data, with a given label, e.g. "PUBLIC KEY".
+///
+/// This produces the strict format of Section 3, with lines ending in "\n".
+pub fn encode(label: &str, data: &[u8]) -> String {
+    let encoded = base64::encode(data);
+    let mut out = String::with_capacity(encoded.len() * 65 / 64 + 2 * label.len() + 40);
+    out.push_str("-----BEGIN ");
+    out.push_str(label);
+    out.push_str("-----\n");
+    // Base64 is ASCII, so lines can be split at any byte.
+    for line in encoded.as_bytes().chunks(LINE_SIZE) {
+        out.push_str(core::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push_str("-----END ");
+    out.push_str(label);
+    out.push_str("-----\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_all(input: &[u8]) -> io::Result<(String, Vec<u8>)> {
+        let mut reader = Reader::new(input)?;
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out)?;
+        Ok((reader.label().to_string(), out))
+    }
+
+    #[test]
+    fn test_reads_document() {
+        let input = b"some explanatory text\n-----BEGIN TEST DATA-----\r\nZm9v\r\nYmFy\r\n-----END TEST DATA-----\r\ntrailing";
+        let (label, data) = read_all(input).unwrap();
+        assert_eq!(label, "TEST DATA");
+        assert_eq!(data, b"foobar");
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let data: Vec<u8> = (0..100).collect();
+        let encoded = encode("TEST DATA", &data);
+        assert!(encoded.starts_with("-----BEGIN TEST DATA-----\n"));
+        assert!(encoded.lines().all(|line| line.len() <= LINE_SIZE));
+        let (label, decoded) = read_all(encoded.as_bytes()).unwrap();
+        assert_eq!(label, "TEST DATA");
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_rejects_bad_boundaries() {
+        assert!(read_all(b"Zm9v\n").is_err());
+        assert!(read_all(b"-----BEGIN A-----\nZm9v\n").is_err());
+        assert!(read_all(b"-----BEGIN A-----\nZm9v\n-----END B-----\n").is_err());
+        assert!(read_all(b"-----BEGIN A-----\nZm9v!\n-----END A-----\n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_long_lines() {
+        let mut input = b"-----BEGIN A-----\n".to_vec();
+        input.extend_from_slice(&[b'A'; 2 * MAX_LINE_SIZE]);
+        input.extend_from_slice(b"\n-----END A-----\n");
+        assert!(read_all(&input).is_err());
+    }
+
+    #[test]
+    fn test_large_document() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let encoded = base64::encode(&data);
+        let mut input = b"-----BEGIN LARGE-----\n".to_vec();
+        for line in encoded.as_bytes().chunks(64) {
+            input.extend_from_slice(line);
+            input.push(b'\n');
+        }
+        input.extend_from_slice(b"-----END LARGE-----\n");
+        let (_, out) = read_all(&input).unwrap();
+        assert_eq!(out, data);
+    }
+}
@@ -0,0 +1,138 @@
+//! This module implements the Poly1305 one-time authenticator, as described in RFC 8439:
+//! https://datatracker.ietf.org/doc/html/rfc8439#section-2.5
+//!
+//! The accumulator is kept in five 26 bit limbs, as in poly1305-donna, so that
+//! products fit comfortably in 64 bits.
+
+use core::convert::TryInto;
+
+use subtle::ConstantTimeEq;
+
+/// The size of a Poly1305 key, which must only ever be used for a single message.
+pub const KEY_SIZE: usize = 32;
+
+/// The size of a Poly1305 tag.
+pub const TAG_SIZE: usize = 16;
+
+const MASK: u64 = (1 << 26) - 1;
+
+fn word(bytes: &[u8]) -> u64 {
+    u64::from(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Calculate the tag of a message, with a one-time key.
+pub fn mac(key: &[u8; KEY_SIZE], message: &[u8]) -> [u8; TAG_SIZE] {
+    // r is clamped, as Section 2.5.1 requires.
+    let r = [
+        word(&key[0..4]) & 0x3ffffff,
+        (word(&key[3..7]) >> 2) & 0x3ffff03,
+        (word(&key[6..10]) >> 4) & 0x3ffc0ff,
+        (word(&key[9..13]) >> 6) & 0x3f03fff,
+        (word(&key[12..16]) >> 8) & 0x00fffff,
+    ];
+    let s = [r[1] * 5, r[2] * 5, r[3] * 5, r[4] * 5];
+    let mut h = [0u64; 5];
+
+    for chunk in message.chunks(16) {
+        let mut block = [0; 17];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+        h[0] += word(&block[0..4]) & MASK;
+        h[1] += (word(&block[3..7]) >> 2) & MASK;
+        h[2] += (word(&block[6..10]) >> 4) & MASK;
+        h[3] += (word(&block[9..13]) >> 6) & MASK;
+        h[4] += (word(&block[12..16]) >> 8) | (u64::from(block[16]) << 24);
+
+        // Multiply by r, using 2^130 = 5 mod p to fold the high limbs back in.
+        let d = [
+            h[0] * r[0] + h[1] * s[3] + h[2] * s[2] + h[3] * s[1] + h[4] * s[0],
+            h[0] * r[1] + h[1] * r[0] + h[2] * s[3] + h[3] * s[2] + h[4] * s[1],
+            h[0] * r[2] + h[1] * r[1] + h[2] * r[0] + h[3] * s[3] + h[4] * s[2],
+            h[0] * r[3] + h[1] * r[2] + h[2] * r[1] + h[3] * r[0] + h[4] * s[3],
+            h[0] * r[4] + h[1] * r[3] + h[2] * r[2] + h[3] * r[1] + h[4] * r[0],
+        ];
+        let mut carry = 0;
+        for i in 0..5 {
+            let x = d[i] + carry;
+            h[i] = x & MASK;
+            carry = x >> 26;
+        }
+        h[0] += carry * 5;
+        h[1] += h[0] >> 26;
+        h[0] &= MASK;
+    }
+
+    // Fully carry, and then subtract p if h >= p, in constant time.
+    let mut carry = 0;
+    for limb in h.iter_mut() {
+        *limb += carry;
+        carry = *limb >> 26;
+        *limb &= MASK;
+    }
+    h[0] += carry * 5;
+    h[1] += h[0] >> 26;
+    h[0] &= MASK;
+    let mut g = [0u64; 5];
+    let mut carry = 5;
+    for i in 0..5 {
+        let x = h[i] + carry;
+        g[i] = x & MASK;
+        carry = x >> 26;
+    }
+    // If h + 5 overflowed 2^130, then h >= p, and we use g = h + 5 - 2^130 instead.
+    let select = 0u64.wrapping_sub(carry);
+    for i in 0..5 {
+        h[i] = (h[i] & !select) | (g[i] & select);
+    }
+
+    let h = u128::from(h[0])
+        | (u128::from(h[1]) << 26)
+        | (u128::from(h[2]) << 52)
+        | (u128::from(h[3]) << 78)
+        | (u128::from(h[4]) << 104);
+    let pad = u128::from_le_bytes(key[16..].try_into().unwrap());
+    h.wrapping_add(pad).to_le_bytes()
+}
+
+/// Check, in constant time, that a tag matches a message.
+pub fn verify(key: &[u8; KEY_SIZE], message: &[u8], tag: &[u8; TAG_SIZE]) -> bool {
+    mac(key, message).ct_eq(tag).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rfc_8439_vector() {
+        // From Section 2.5.2
+        let mut key = [0; KEY_SIZE];
+        hex::decode_to_slice(
+            "85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b",
+            &mut key,
+        )
+        .unwrap();
+        let tag = mac(&key, b"Cryptographic Forum Research Group");
+        assert_eq!(hex::encode(tag), "a8061dc1305136c6c22b8baf0c0127a9");
+        assert!(verify(&key, b"Cryptographic Forum Research Group", &tag));
+        assert!(!verify(&key, b"Cryptographic Forum Research Groups", &tag));
+    }
+
+    #[test]
+    fn test_near_modulus() {
+        // From Appendix A.3, test vectors 6 and 9, where h ends up close to p
+        let mut key = [0; KEY_SIZE];
+        key[0] = 2;
+        let tag = mac(&key, &[0xff; 16]);
+        assert_eq!(hex::encode(tag), "03000000000000000000000000000000");
+        let mut key = [0; KEY_SIZE];
+        key[0] = 2;
+        key[16..].copy_from_slice(&[0xff; 16]);
+        let mut message = [0; 16];
+        message[0] = 2;
+        assert_eq!(
+            hex::encode(mac(&key, &message)),
+            "03000000000000000000000000000000"
+        );
+    }
+}
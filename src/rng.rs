@@ -0,0 +1,88 @@
+//! This module lets RNGs from rand_core 0.9 be used with this crate.
+//!
+//! Every function taking randomness here is written against rand_core 0.6, which
+//! is what rand 0.8 uses. RNGs implementing the traits of rand_core 0.9 instead,
+//! like those of rand 0.9, can be wrapped in `Compat09`, and passed in just the same.
+
+use rand::{CryptoRng, Error, RngCore};
+
+/// Wraps an RNG from rand_core 0.9, implementing the traits of rand_core 0.6.
+///
+/// The wrapped RNG has to be cryptographically secure, like it would have to be
+/// if it implemented the traits of rand_core 0.6 directly.
+#[derive(Clone, Debug, Default)]
+pub struct Compat09<R>(pub R);
+
+impl<R: rand_core_09::CryptoRng> RngCore for Compat09<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    /// RNGs in rand_core 0.9 never fail, so this doesn't either.
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<R: rand_core_09::CryptoRng> CryptoRng for Compat09<R> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{gen_keypair, gen_keypair_from_seed};
+
+    /// Counts up from 0, one byte at a time, which is fine for a deterministic test.
+    struct Counter(u8);
+
+    impl rand_core_09::RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            rand_core_09::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core_09::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+    }
+
+    impl rand_core_09::CryptoRng for Counter {}
+
+    #[test]
+    fn test_gen_keypair_with_rand_core_09() {
+        let (public, private) = gen_keypair(&mut Compat09(Counter(0)));
+        let mut seed = [0; 32];
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let (expected_public, expected_private) = gen_keypair_from_seed(seed);
+        assert_eq!(public, expected_public);
+        assert_eq!(private.to_bytes(), expected_private.to_bytes());
+        let signature = private.sign(b"hello");
+        assert!(public.verify(b"hello", signature));
+    }
+
+    #[test]
+    fn test_reads_match_rand_core_09() {
+        let mut rng = Compat09(Counter(0));
+        assert_eq!(rng.next_u32(), u32::from_le_bytes([0, 1, 2, 3]));
+        let mut bytes = [0; 4];
+        rng.try_fill_bytes(&mut bytes).unwrap();
+        assert_eq!(bytes, [4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,21 @@
+//! This module contains the stable API of this crate, following semver.
+//!
+//! This covers keys, signing, verification, key exchange, and hashing, which is everything
+//! re-exported at the root of the crate. The raw group and field operations, and the
+//! protocols built on them, live in `hazmat` instead, which makes no such promises.
+
+#[cfg(feature = "alloc")]
+pub use crate::curve25519::verify_batch;
+pub use crate::curve25519::{
+    gen_keypair, gen_keypair_from_seed, x25519, CommittedCounter, CounterError, Dh25519, Error,
+    ExchangeOnlyKey, ExchangePublicKey, NonceCounter, PrivateKey, PublicKey, Signature,
+    SigningOnlyKey, VerificationCost, VerificationError, VerifyingKey, EXCHANGE_PUBLIC_KEY_SIZE,
+    NOISE_DH_LEN, PRIVATE_KEY_SIZE, PUBLIC_KEY_SIZE, SIGNATURE_SIZE,
+};
+#[cfg(feature = "std")]
+pub use crate::curve25519::{SigningStream, VerifyingStream, SIGNIFY_KEY_ID_SIZE};
+pub use crate::sha512::{
+    hash as sha512, hash384 as sha384, hash512_256 as sha512_256, Sha384, Sha512, Sha512_256,
+    HASH_SIZE as SHA512_HASH_SIZE, SHA384_SIZE, SHA512_256_SIZE,
+};
+pub use crate::shake::{shake256, Shake256, Shake256Reader};
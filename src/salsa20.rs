@@ -0,0 +1,117 @@
+//! This module implements the Salsa20 stream cipher, along with HSalsa20, and XSalsa20:
+//! https://cr.yp.to/snuffle/spec.pdf
+//! https://cr.yp.to/snuffle/xsalsa-20081128.pdf
+//!
+//! Only what NaCl's crypto_box needs is provided: XSalsa20 with a 24 byte nonce,
+//! built by deriving a subkey with HSalsa20 from the first 16 bytes of the nonce.
+
+use core::convert::TryInto;
+
+use crate::zeroize::Zeroizing;
+
+/// The size of the key used by all of these functions.
+pub const KEY_SIZE: usize = 32;
+
+/// The size of an XSalsa20 nonce.
+pub const NONCE_SIZE: usize = 24;
+
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+fn initial_state(key: &[u8; KEY_SIZE], input: &[u8; 16]) -> [u32; 16] {
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+    let mut state = [0; 16];
+    for i in 0..4 {
+        state[5 * i] = CONSTANTS[i];
+        state[1 + i] = word(&key[4 * i..4 * i + 4]);
+        state[11 + i] = word(&key[16 + 4 * i..20 + 4 * i]);
+        state[6 + i] = word(&input[4 * i..4 * i + 4]);
+    }
+    state
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+/// Apply the 20 rounds of Salsa20, without adding the input back in.
+fn rounds(state: &[u32; 16]) -> [u32; 16] {
+    let mut x = *state;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 5, 9, 13, 1);
+        quarter_round(&mut x, 10, 14, 2, 6);
+        quarter_round(&mut x, 15, 3, 7, 11);
+        quarter_round(&mut x, 0, 1, 2, 3);
+        quarter_round(&mut x, 5, 6, 7, 4);
+        quarter_round(&mut x, 10, 11, 8, 9);
+        quarter_round(&mut x, 15, 12, 13, 14);
+    }
+    x
+}
+
+/// Derive a subkey from a key, and 16 bytes of input, without the final addition.
+pub fn hsalsa20(key: &[u8; KEY_SIZE], input: &[u8; 16]) -> [u8; KEY_SIZE] {
+    let x = Zeroizing::new(rounds(&initial_state(key, input)));
+    let mut out = [0; KEY_SIZE];
+    for (i, &j) in [0, 5, 10, 15, 6, 7, 8, 9].iter().enumerate() {
+        out[4 * i..4 * i + 4].copy_from_slice(&x[j].to_le_bytes());
+    }
+    out
+}
+
+/// XOR data, in place, with the XSalsa20 keystream, starting at the beginning of the stream.
+pub fn xsalsa20_xor(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], data: &mut [u8]) {
+    let subkey = Zeroizing::new(hsalsa20(key, nonce[..16].try_into().unwrap()));
+    let mut input = [0; 16];
+    input[..8].copy_from_slice(&nonce[16..]);
+    for (counter, chunk) in data.chunks_mut(64).enumerate() {
+        input[8..].copy_from_slice(&(counter as u64).to_le_bytes());
+        let state = Zeroizing::new(initial_state(&subkey, &input));
+        let mut block = Zeroizing::new(rounds(&state));
+        for (word, &initial) in block.iter_mut().zip(state.iter()) {
+            *word = word.wrapping_add(initial);
+        }
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte ^= block[i / 4].to_le_bytes()[i % 4];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hsalsa20() {
+        // The "firstkey" from the NaCl tests, derived from the X25519 shared secret in RFC 7748
+        let mut shared = [0; 32];
+        hex::decode_to_slice(
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742",
+            &mut shared,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(hsalsa20(&shared, &[0; 16])),
+            "1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389"
+        );
+    }
+
+    #[test]
+    fn test_xsalsa20_is_involution() {
+        let key = [7; KEY_SIZE];
+        let nonce = [9; NONCE_SIZE];
+        let mut data = vec![0x42; 200];
+        xsalsa20_xor(&key, &nonce, &mut data);
+        assert_ne!(data, vec![0x42; 200]);
+        // Encrypting in pieces, at block boundaries, gives the same stream.
+        let mut zeros = vec![0; 200];
+        xsalsa20_xor(&key, &nonce, &mut zeros);
+        let stream: Vec<u8> = data.iter().map(|x| x ^ 0x42).collect();
+        assert_eq!(stream, zeros);
+        xsalsa20_xor(&key, &nonce, &mut data);
+        assert_eq!(data, vec![0x42; 200]);
+    }
+}
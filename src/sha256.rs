@@ -0,0 +1,197 @@
+//! This module implements the SHA-256 hash function, following RFC 6234:
+//! https://datatracker.ietf.org/doc/html/rfc6234
+//!
+//! Ed25519 itself doesn't need this, but some formats built around it do, e.g.
+//! JWK thumbprints, along with protocols like SPAKE2, which use HMAC, and HKDF,
+//! over SHA-256, from the `hmac`, and `hkdf`, modules.
+
+use core::convert::TryInto;
+
+use crate::zeroize::Zeroizing;
+
+/// The number of bytes output by SHA-256.
+pub const HASH_SIZE: usize = 32;
+
+const BLOCK_SIZE: usize = 64;
+
+/// The initial hash value, from Section 6.1.
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The round constants, from Section 5.1.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Update the hash value with one block, as per Section 6.2.
+fn update(h: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+    let mut w = [0u32; 64];
+    for (word, chunk) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = *h;
+    for t in 0..64 {
+        let bsig1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = hh
+            .wrapping_add(bsig1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let bsig0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = bsig0.wrapping_add(maj);
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (x, y) in h.iter_mut().zip([a, b, c, d, e, f, g, hh].iter()) {
+        *x = x.wrapping_add(*y);
+    }
+}
+
+/// An incremental SHA-256 hasher, for messages which aren't contiguous in memory.
+#[derive(Clone)]
+pub struct Sha256 {
+    h: [u32; 8],
+    // The start of a block we haven't processed yet
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    // The total number of bytes we've been fed so far
+    message_len: u64,
+}
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Sha256 {
+            h: IV,
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            message_len: 0,
+        }
+    }
+
+    /// Feed more data into the hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.message_len += data.len() as u64;
+        if self.buffer_len > 0 {
+            let n = (BLOCK_SIZE - self.buffer_len).min(data.len());
+            self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&data[..n]);
+            self.buffer_len += n;
+            data = &data[n..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            update(&mut self.h, &self.buffer);
+            self.buffer_len = 0;
+        }
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            update(&mut self.h, block.try_into().unwrap());
+        }
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffer_len = remainder.len();
+    }
+
+    /// Finish hashing, producing 256 bits of output.
+    pub fn finalize(mut self) -> [u8; HASH_SIZE] {
+        // Padding, as per Section 4.1, with a "1" bit, and the bit length at the end.
+        let mut block = Zeroizing::new(self.buffer);
+        block[self.buffer_len..].fill(0);
+        block[self.buffer_len] = 0x80;
+        if self.buffer_len + 1 + 8 > BLOCK_SIZE {
+            update(&mut self.h, &block);
+            block.fill(0);
+        }
+        block[BLOCK_SIZE - 8..].copy_from_slice(&(8 * self.message_len).to_be_bytes());
+        update(&mut self.h, &block);
+
+        let mut out = [0; HASH_SIZE];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.h.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The input to the hash may contain secrets, which can linger in our buffers.
+#[cfg(feature = "zeroize")]
+impl Drop for Sha256 {
+    fn drop(&mut self) {
+        crate::zeroize::wipe(&mut self.buffer);
+        crate::zeroize::wipe(&mut self.h);
+    }
+}
+
+/// Calculate the SHA-256 hash of a message.
+pub fn hash(message: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        let vectors: [(&[u8], &str); 3] = [
+            (
+                b"",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                b"abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+        ];
+        for &(message, expected) in vectors.iter() {
+            assert_eq!(hex::encode(hash(message)), expected);
+        }
+    }
+
+    #[test]
+    fn test_incremental_matches_hash() {
+        let message: Vec<u8> = (0..200).collect();
+        for &split in &[0, 1, 55, 56, 64, 119, 200] {
+            let mut hasher = Sha256::new();
+            hasher.update(&message[..split]);
+            hasher.update(&message[split..]);
+            assert_eq!(hasher.finalize(), hash(&message), "split at {}", split);
+        }
+    }
+}
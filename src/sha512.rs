@@ -1,13 +1,24 @@
 //! This module exists to implement the SHA-512 hash function, which is necessary
 //! for Ed25519 signatures.
 //!
+//! The truncated variants, SHA-384 and SHA-512/256, are also provided. They use
+//! the same compression function, starting from a different initial hash value.
+//!
 //! This file tries to follow RFC 6234 (https://datatracker.ietf.org/doc/html/rfc6234).
 
-use std::{convert::TryInto, mem::size_of};
+use core::{convert::TryInto, mem::size_of};
+
+use crate::zeroize::Zeroizing;
 
 // This is the number of bytes in our 512 bit hash.
 pub const HASH_SIZE: usize = 64;
 
+/// The number of bytes output by SHA-384.
+pub const SHA384_SIZE: usize = 48;
+
+/// The number of bytes output by SHA-512/256.
+pub const SHA512_256_SIZE: usize = 32;
+
 /// BLOCK_SIZE is the number of bytes needed to make a 1024 bit block
 ///
 /// This block structure is described in Section 4:
@@ -80,6 +91,7 @@ const K: [u64; 80] = [
 /// This is a struct of our invention, and is used to carry out part 1 of the algorithm
 /// in Section 6.3:
 /// https://datatracker.ietf.org/doc/html/rfc6234#section-6.3
+#[derive(Clone)]
 struct MessageSchedule {
     words: [u64; 80],
 }
@@ -111,11 +123,51 @@ impl MessageSchedule {
     }
 }
 
+/// The initial hash value for SHA-512, from Section 6.3:
+/// https://datatracker.ietf.org/doc/html/rfc6234#section-6.3
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The initial hash value for SHA-384, from Section 6.3:
+/// https://datatracker.ietf.org/doc/html/rfc6234#section-6.3
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+/// The initial hash value for SHA-512/256, from Section 5.3.6.2 of FIPS 180-4:
+/// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
+const SHA512_256_IV: [u64; 8] = [
+    0x22312194fc2bf72c,
+    0x9f555fa3c84c64c2,
+    0x2393b86b6f53b151,
+    0x963877195940eabd,
+    0x96283ee2a88effe3,
+    0xbe5e1e2553863992,
+    0x2b0199fc2c85b8aa,
+    0x0eb72ddc81c52ca2,
+];
+
 /// Represents a "hash value", as described in Section 6:
 /// https://datatracker.ietf.org/doc/html/rfc6234#section-6
 ///
 /// This can be thought of as the ongoing state of our hash function,
 /// which gets modified using our message blocks.
+#[derive(Clone)]
 struct HashValue {
     data: [u64; 8],
     schedule: MessageSchedule,
@@ -124,18 +176,9 @@ struct HashValue {
 impl HashValue {
     /// Create an initial hash value, as per Section 6.3:
     /// https://datatracker.ietf.org/doc/html/rfc6234#section-6.3
-    fn initial() -> HashValue {
+    fn initial(iv: [u64; 8]) -> HashValue {
         HashValue {
-            data: [
-                0x6a09e667f3bcc908,
-                0xbb67ae8584caa73b,
-                0x3c6ef372fe94f82b,
-                0xa54ff53a5f1d36f1,
-                0x510e527fade682d1,
-                0x9b05688c2b3e6c1f,
-                0x1f83d9abfb41bd6b,
-                0x5be0cd19137e2179,
-            ],
+            data: iv,
             schedule: MessageSchedule::new(),
         }
     }
@@ -199,51 +242,284 @@ impl HashValue {
     }
 }
 
+/// The number of blocks the compression function processes, when hashing a message.
+///
+/// This includes the blocks needed for padding, as per Section 4.2:
+/// https://datatracker.ietf.org/doc/html/rfc6234#section-4.2
+pub fn block_count(message_len: usize) -> u64 {
+    // We need at least one "1" byte, and 16 bytes for the length
+    (message_len + 1 + size_of::<u128>()).div_ceil(BLOCK_SIZE) as u64
+}
+
+/// An incremental SHA-512 hasher, for messages which aren't contiguous in memory.
+///
+/// Data can be fed in pieces of any size, and the result is the same as hashing
+/// the concatenation of all of them at once.
+#[derive(Clone)]
+pub struct Sha512 {
+    hash_value: HashValue,
+    // The start of a block we haven't processed yet
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    // The total number of bytes we've been fed so far
+    message_len: u128,
+}
+
+impl Sha512 {
+    pub fn new() -> Self {
+        Self::with_iv(SHA512_IV)
+    }
+
+    fn with_iv(iv: [u64; 8]) -> Self {
+        Sha512 {
+            hash_value: HashValue::initial(iv),
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            message_len: 0,
+        }
+    }
+
+    /// Go back to the initial state, forgetting any data fed in so far.
+    pub fn reset(&mut self) {
+        *self = Sha512::new();
+    }
+
+    /// Feed more data into the hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.message_len += data.len() as u128;
+        if self.buffer_len > 0 {
+            let n = (BLOCK_SIZE - self.buffer_len).min(data.len());
+            self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&data[..n]);
+            self.buffer_len += n;
+            data = &data[n..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.hash_value.update(&block);
+            self.buffer_len = 0;
+        }
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.hash_value.update(block.try_into().unwrap());
+        }
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffer_len = remainder.len();
+    }
+
+    /// Finish hashing, producing 512 bits of output.
+    pub fn finalize(mut self) -> [u8; HASH_SIZE] {
+        // Now, we need to handle padding, as per Section 4.2:
+        // https://datatracker.ietf.org/doc/html/rfc6234#section-4.2
+
+        // This buffer is used to contain whatever remaining blocks we feed into the hasher
+        let remainder_len = self.buffer_len;
+        let mut scratch_block = Zeroizing::new(self.buffer);
+        scratch_block[remainder_len..].fill(0);
+
+        // a. "1" is appended
+        scratch_block[remainder_len] = 0b1000_0000;
+
+        // b. K "0"s are appended where K is the smallest, non-negative solution
+        // to the equation
+        //     ( L + 1 + K ) mod 1024 = 896
+
+        // Here, the 1 we add includes the zero bits we've already added.
+        let l_plus_1 = remainder_len + 1;
+        let desired_size = BLOCK_SIZE - size_of::<u128>();
+        // In this case, we have two extra blocks, one of which is already ready
+        if l_plus_1 > desired_size {
+            self.hash_value.update(&scratch_block);
+            scratch_block.fill(0);
+        }
+
+        // c. Then append the 128-bit block that is L in binary representation.
+        let l = 8 * self.message_len;
+        scratch_block[BLOCK_SIZE - size_of::<u128>()..].copy_from_slice(&l.to_be_bytes());
+
+        self.hash_value.update(&scratch_block);
+
+        self.hash_value.result()
+    }
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// With these, Sha512 implements `digest::Digest`, and works with generic code, like HMAC.
+#[cfg(feature = "digest")]
+impl digest::HashMarker for Sha512 {}
+
+#[cfg(feature = "digest")]
+impl digest::OutputSizeUser for Sha512 {
+    type OutputSize = digest::consts::U64;
+}
+
+#[cfg(feature = "digest")]
+impl digest::crypto_common::BlockSizeUser for Sha512 {
+    type BlockSize = digest::consts::U128;
+}
+
+#[cfg(feature = "digest")]
+impl digest::Update for Sha512 {
+    fn update(&mut self, data: &[u8]) {
+        Sha512::update(self, data);
+    }
+}
+
+#[cfg(feature = "digest")]
+impl digest::FixedOutput for Sha512 {
+    fn finalize_into(self, out: &mut digest::Output<Self>) {
+        out.copy_from_slice(&Sha512::finalize(self));
+    }
+}
+
+#[cfg(feature = "digest")]
+impl digest::Reset for Sha512 {
+    fn reset(&mut self) {
+        Sha512::reset(self);
+    }
+}
+
+#[cfg(feature = "digest")]
+impl digest::FixedOutputReset for Sha512 {
+    fn finalize_into_reset(&mut self, out: &mut digest::Output<Self>) {
+        out.copy_from_slice(&core::mem::take(self).finalize());
+    }
+}
+
+/// The input to the hash may contain secrets, which can linger in our buffers.
+#[cfg(feature = "zeroize")]
+impl Drop for Sha512 {
+    fn drop(&mut self) {
+        crate::zeroize::wipe(&mut self.buffer);
+        crate::zeroize::wipe(&mut self.hash_value.data);
+        crate::zeroize::wipe(&mut self.hash_value.schedule.words);
+    }
+}
+
 /// This calculates the SHA-512 hash of some arbitrary input, producing 512 bits of output.
 ///
 /// This implements the function as defined in RFC 6234:
 /// https://datatracker.ietf.org/doc/html/rfc6234
 pub fn hash(message: &[u8]) -> [u8; HASH_SIZE] {
-    let mut hash_value = HashValue::initial();
+    let mut hasher = Sha512::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// An incremental SHA-384 hasher, producing 384 bits of output.
+#[derive(Clone)]
+pub struct Sha384(Sha512);
 
-    let mut blocks = message.chunks_exact(BLOCK_SIZE);
-    for block in &mut blocks {
-        hash_value.update(block.try_into().unwrap());
+impl Sha384 {
+    pub fn new() -> Self {
+        Sha384(Sha512::with_iv(SHA384_IV))
     }
 
-    let remainder = blocks.remainder();
-    let remainder_len = remainder.len();
+    pub fn reset(&mut self) {
+        *self = Sha384::new();
+    }
 
-    // Now, we need to handle padding, as per Section 4.2:
-    // https://datatracker.ietf.org/doc/html/rfc6234#section-4.2
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
 
-    // This buffer is used to contain whatever remaining blocks we feed into the hasher
-    let mut scratch_block = [0; BLOCK_SIZE];
-    scratch_block[..remainder_len].copy_from_slice(remainder);
+    pub fn finalize(self) -> [u8; SHA384_SIZE] {
+        self.0.finalize()[..SHA384_SIZE].try_into().unwrap()
+    }
+}
 
-    // a. "1" is appended
-    scratch_block[remainder_len] = 0b1000_0000;
+impl Default for Sha384 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    // b. K "0"s are appended where K is the smallest, non-negative solution
-    // to the equation
-    //     ( L + 1 + K ) mod 1024 = 896
+/// An incremental SHA-512/256 hasher, producing 256 bits of output.
+///
+/// This is faster than SHA-256 on 64 bit machines, and, unlike SHA-256, isn't
+/// vulnerable to length extension attacks.
+#[derive(Clone)]
+pub struct Sha512_256(Sha512);
+
+impl Sha512_256 {
+    pub fn new() -> Self {
+        Sha512_256(Sha512::with_iv(SHA512_256_IV))
+    }
 
-    // Here, the 1 we add includes the zero bits we've already added.
-    let l_plus_1 = remainder_len + 1;
-    let desired_size = BLOCK_SIZE - size_of::<u128>();
-    // In this case, we have two extra blocks, one of which is already ready
-    if l_plus_1 > desired_size {
-        hash_value.update(&scratch_block);
-        scratch_block.fill(0);
+    pub fn reset(&mut self) {
+        *self = Sha512_256::new();
     }
 
-    // c. Then append the 128-bit block that is L in binary representation.
-    let l = 8 * (message.len() as u128);
-    scratch_block[BLOCK_SIZE - size_of::<u128>()..].copy_from_slice(&l.to_be_bytes());
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
 
-    hash_value.update(&scratch_block);
+    pub fn finalize(self) -> [u8; SHA512_256_SIZE] {
+        self.0.finalize()[..SHA512_256_SIZE].try_into().unwrap()
+    }
+}
 
-    hash_value.result()
+impl Default for Sha512_256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// This calculates the SHA-384 hash of some input.
+pub fn hash384(message: &[u8]) -> [u8; SHA384_SIZE] {
+    let mut hasher = Sha384::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// This calculates the SHA-512/256 hash of some input.
+pub fn hash512_256(message: &[u8]) -> [u8; SHA512_256_SIZE] {
+    let mut hasher = Sha512_256::new();
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// Expand a message into uniform bytes, filling `out`, following Section 5.3.1 of RFC 9380:
+/// https://datatracker.ietf.org/doc/html/rfc9380#section-5.3.1
+///
+/// This is the building block for hashing to groups, and scalars. The domain separation
+/// tag, and the output, have to be shorter than 256 bytes, and 255 blocks, respectively.
+#[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+pub fn expand_message_xmd(message: &[u8], dst: &[u8], out: &mut [u8]) {
+    assert!(dst.len() <= 255, "domain separation tag too long");
+    let blocks = out.len().div_ceil(HASH_SIZE);
+    assert!(blocks <= 255, "output too long");
+    let update_dst = |hasher: &mut Sha512| {
+        hasher.update(dst);
+        hasher.update(&[dst.len() as u8]);
+    };
+
+    let mut hasher = Sha512::new();
+    hasher.update(&[0; BLOCK_SIZE]);
+    hasher.update(message);
+    hasher.update(&(out.len() as u16).to_be_bytes());
+    hasher.update(&[0]);
+    update_dst(&mut hasher);
+    let b_0 = hasher.finalize();
+
+    let mut b_i = [0; HASH_SIZE];
+    for (i, chunk) in out.chunks_mut(HASH_SIZE).enumerate() {
+        for (x, y) in b_i.iter_mut().zip(b_0.iter()) {
+            *x ^= y;
+        }
+        let mut hasher = Sha512::new();
+        hasher.update(&b_i);
+        hasher.update(&[i as u8 + 1]);
+        update_dst(&mut hasher);
+        b_i = hasher.finalize();
+        chunk.copy_from_slice(&b_i[..chunk.len()]);
+    }
 }
 
 #[cfg(test)]
@@ -289,4 +565,108 @@ mod test {
         ).unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_block_count() {
+        assert_eq!(block_count(0), 1);
+        assert_eq!(block_count(111), 1);
+        assert_eq!(block_count(112), 2);
+        assert_eq!(block_count(128), 2);
+        assert_eq!(block_count(239), 2);
+        assert_eq!(block_count(240), 3);
+    }
+
+    #[test]
+    fn test_incremental_matches_one_shot() {
+        let message: Vec<u8> = (0..1000u32).map(|i| (i * 13) as u8).collect();
+        for &split in [0, 1, 111, 127, 128, 129, 500, 1000].iter() {
+            let mut hasher = Sha512::new();
+            hasher.update(&message[..split]);
+            for piece in message[split..].chunks(37) {
+                hasher.update(piece);
+            }
+            assert_eq!(hasher.finalize(), hash(&message));
+        }
+        let mut hasher = Sha512::new();
+        hasher.update(b"discarded");
+        hasher.reset();
+        hasher.update(b"abc");
+        assert_eq!(hasher.clone().finalize(), hash(b"abc"));
+        hasher.update(b"de");
+        assert_eq!(hasher.finalize(), hash(b"abcde"));
+    }
+
+    #[test]
+    fn test_truncated_variants() {
+        let mut expected = [0; SHA384_SIZE];
+        hex::decode_to_slice(
+        "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(hash384(b"abc"), expected);
+        hex::decode_to_slice(
+        "0691b6e978614b67d60557b2a2cddd53406508522efa21c624dbbfa8ab6e726d5c586b489c7c09f24109a64c10211d48",
+        &mut expected,
+        ).unwrap();
+        assert_eq!(hash384(&[b'a'; 200]), expected);
+
+        let mut expected = [0; SHA512_256_SIZE];
+        hex::decode_to_slice(
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(hash512_256(b"abc"), expected);
+        hex::decode_to_slice(
+            "19b1e37317d7fd3d7651f397005e31f154ef4912d1345743d2d5889aaca28996",
+            &mut expected,
+        )
+        .unwrap();
+        assert_eq!(hash512_256(&[b'a'; 200]), expected);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_digest_traits() {
+        fn generic_hash<D: digest::Digest>(parts: &[&[u8]]) -> digest::Output<D> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize()
+        }
+
+        let expected = hash(b"abcde");
+        assert_eq!(generic_hash::<Sha512>(&[b"ab", b"cde"])[..], expected[..]);
+        let mut hasher = Sha512::new();
+        digest::Update::update(&mut hasher, b"abcde");
+        assert_eq!(
+            digest::FixedOutputReset::finalize_fixed_reset(&mut hasher)[..],
+            expected[..]
+        );
+        // After a reset, the hasher starts over
+        digest::Update::update(&mut hasher, b"abcde");
+        assert_eq!(
+            digest::FixedOutput::finalize_fixed(hasher)[..],
+            expected[..]
+        );
+    }
+
+    #[test]
+    fn test_expand_message_xmd() {
+        // From Appendix K.3 of RFC 9380
+        let dst = b"QUUX-V01-CS02-with-expander-SHA512-256";
+        let mut out = [0; 32];
+        expand_message_xmd(b"", dst, &mut out);
+        assert_eq!(
+            hex::encode(out),
+            "6b9a7312411d92f921c6f68ca0b6380730a1a4d982c507211a90964c394179ba"
+        );
+        let mut out = [0; 128];
+        expand_message_xmd(b"abc", dst, &mut out);
+        assert_eq!(
+            hex::encode(&out[..]),
+            "7f1dddd13c08b543f2e2037b14cefb255b44c83cc397c1786d975653e36a6b11bdd7732d8b38adb4a0edc26a0cef4bb45217135456e58fbca1703cd6032cb1347ee720b87972d63fbf232587043ed2901bce7f22610c0419751c065922b488431851041310ad659e4b23520e1772ab29dcdeb2002222a363f0c2b1c972b3efe1"
+        );
+    }
 }
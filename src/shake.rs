@@ -0,0 +1,283 @@
+//! This module implements the SHAKE256 extendable output function, as described in FIPS 202:
+//! https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf
+//!
+//! Unlike SHA-512, this can produce as much output as needed, which Ed448 relies on.
+//!
+//! SHA3-256 uses the same rate, only differing in its padding, so it lives here as well.
+//! Tor relies on it for onion service addresses.
+
+use core::convert::TryInto;
+
+/// The number of bytes absorbed, or squeezed, per permutation.
+///
+/// This is (1600 - 2 * 256) / 8, as described in Section 6.2.
+const RATE: usize = 136;
+
+/// The round constants, from Section 3.2.5.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation offsets of the rho step, from Section 3.2.2, indexed by x + 5 * y.
+const ROTATIONS: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// Apply Keccak-f[1600] to a state, with lane (x, y) stored at index x + 5 * y.
+///
+/// This follows Section 3.4, with each step of a round described in Section 3.2.
+fn keccak_f(a: &mut [u64; 25]) {
+    for &rc in ROUND_CONSTANTS.iter() {
+        // θ
+        let mut c = [0; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+        // ρ and π, with B[y, 2x + 3y] = rot(A[x, y])
+        let mut b = [0; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+            }
+        }
+        // χ
+        for y in 0..5 {
+            for x in 0..5 {
+                a[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        // ι
+        a[0] ^= rc;
+    }
+}
+
+/// The sponge state, along with how many bytes of the current block have been used.
+#[derive(Clone)]
+struct Sponge {
+    state: [u64; 25],
+    position: usize,
+}
+
+impl Sponge {
+    fn new() -> Self {
+        Sponge {
+            state: [0; 25],
+            position: 0,
+        }
+    }
+
+    /// XOR a byte into the state, with lanes being little endian.
+    fn xor_byte(&mut self, i: usize, byte: u8) {
+        self.state[i / 8] ^= u64::from(byte) << (8 * (i % 8));
+    }
+
+    fn byte(&self, i: usize) -> u8 {
+        (self.state[i / 8] >> (8 * (i % 8))) as u8
+    }
+}
+
+/// An incremental SHAKE256 computation, absorbing input.
+#[derive(Clone)]
+pub struct Shake256 {
+    sponge: Sponge,
+}
+
+impl Shake256 {
+    pub fn new() -> Self {
+        Shake256 {
+            sponge: Sponge::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let sponge = &mut self.sponge;
+        // Whole lanes can be absorbed directly, once we're aligned.
+        while !data.is_empty() {
+            if sponge.position.is_multiple_of(8) && data.len() >= 8 {
+                let lane = u64::from_le_bytes(data[..8].try_into().unwrap());
+                sponge.state[sponge.position / 8] ^= lane;
+                sponge.position += 8;
+                data = &data[8..];
+            } else {
+                sponge.xor_byte(sponge.position, data[0]);
+                sponge.position += 1;
+                data = &data[1..];
+            }
+            if sponge.position == RATE {
+                keccak_f(&mut sponge.state);
+                sponge.position = 0;
+            }
+        }
+    }
+
+    /// Finish absorbing input, and start producing output.
+    pub fn finalize(self) -> Shake256Reader {
+        let mut sponge = self.sponge;
+        // The domain separation bits 1111, followed by pad10*1, as per Section 6.2,
+        // and Appendix B.2, with the bits within each byte being little endian.
+        sponge.xor_byte(sponge.position, 0x1F);
+        sponge.xor_byte(RATE - 1, 0x80);
+        keccak_f(&mut sponge.state);
+        sponge.position = 0;
+        Shake256Reader { sponge }
+    }
+}
+
+impl Default for Shake256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The output of SHAKE256, which can be read from indefinitely.
+#[derive(Clone)]
+pub struct Shake256Reader {
+    sponge: Sponge,
+}
+
+impl Shake256Reader {
+    /// Fill a buffer with the next bytes of output.
+    pub fn read(&mut self, out: &mut [u8]) {
+        let sponge = &mut self.sponge;
+        for o in out.iter_mut() {
+            if sponge.position == RATE {
+                keccak_f(&mut sponge.state);
+                sponge.position = 0;
+            }
+            *o = sponge.byte(sponge.position);
+            sponge.position += 1;
+        }
+    }
+}
+
+/// The state may have absorbed secrets, e.g. an Ed448 private key.
+#[cfg(feature = "zeroize")]
+impl Drop for Sponge {
+    fn drop(&mut self) {
+        crate::zeroize::wipe(&mut self.state);
+    }
+}
+
+/// The number of bytes output by SHA3-256.
+#[cfg_attr(not(any(feature = "alloc", feature = "hazmat")), allow(dead_code))]
+pub const SHA3_256_SIZE: usize = 32;
+
+/// Calculate the SHA3-256 hash of a message.
+#[cfg_attr(not(any(feature = "alloc", feature = "hazmat")), allow(dead_code))]
+pub fn sha3_256(message: &[u8]) -> [u8; SHA3_256_SIZE] {
+    let mut hasher = Shake256::new();
+    hasher.update(message);
+    let mut sponge = hasher.sponge;
+    // The domain separation bits 01, followed by pad10*1, as per Section 6.1.
+    sponge.xor_byte(sponge.position, 0x06);
+    sponge.xor_byte(RATE - 1, 0x80);
+    keccak_f(&mut sponge.state);
+    let mut out = [0; SHA3_256_SIZE];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = sponge.byte(i);
+    }
+    out
+}
+
+/// Calculate SHAKE256 over a message, filling the output buffer.
+pub fn shake256(message: &[u8], out: &mut [u8]) {
+    let mut hasher = Shake256::new();
+    hasher.update(message);
+    hasher.finalize().read(out);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        let mut expected = [0; 64];
+        let mut actual = [0; 64];
+        hex::decode_to_slice(
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+        &mut expected,
+        ).unwrap();
+        shake256(b"", &mut actual);
+        assert_eq!(actual, expected);
+
+        let mut expected = [0; 32];
+        let mut actual = [0; 32];
+        hex::decode_to_slice(
+            "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739",
+            &mut expected,
+        )
+        .unwrap();
+        shake256(b"abc", &mut actual);
+        assert_eq!(actual, expected);
+
+        // This input, and output, spans several blocks
+        hex::decode_to_slice(
+            "51be74461539b8c090773b49dbbf4b4849024888173c57ad8c45102d2e38faa9",
+            &mut expected,
+        )
+        .unwrap();
+        let mut long = [0; 300];
+        shake256(&[b'a'; 300], &mut long);
+        assert_eq!(long[268..], expected);
+    }
+
+    #[test]
+    fn test_sha3_256_vectors() {
+        assert_eq!(
+            hex::encode(sha3_256(b"")),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_eq!(
+            hex::encode(sha3_256(&[b'a'; 200])),
+            "cce34485baf2bf2aca99b94833892a4f52896d3d153f7b840cc4f9fe695f1387"
+        );
+    }
+
+    #[test]
+    fn test_incremental_matches_one_shot() {
+        let message: Vec<u8> = (0..500).map(|i| i as u8).collect();
+        let mut expected = [0; 400];
+        shake256(&message, &mut expected);
+
+        let mut hasher = Shake256::new();
+        for chunk in message.chunks(37) {
+            hasher.update(chunk);
+        }
+        let mut reader = hasher.finalize();
+        let mut actual = [0; 400];
+        for chunk in actual.chunks_mut(51) {
+            reader.read(chunk);
+        }
+        assert_eq!(actual[..], expected[..]);
+    }
+}
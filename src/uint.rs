@@ -1,13 +1,34 @@
-use std::{
+//! This module provides fixed width unsigned integers, built from 64 bit limbs.
+//!
+//! These are the integers underneath the field, and scalar, arithmetic of the crate.
+//! Arithmetic wraps around, like the `wrapping_` methods on the primitive integers,
+//! and runs in constant time, unless a method says otherwise. Byte conversions use
+//! little endian order, like the encodings of Ed25519, and hex uses big endian order,
+//! the way numbers are usually written.
+
+use core::{
     cell::Cell,
     convert::TryInto,
-    ops::{Add, AddAssign, Mul, Sub, SubAssign},
+    ops::{
+        Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Mul, Not,
+        Shl, ShlAssign, Shr, ShrAssign, Sub, SubAssign,
+    },
 };
 
-use subtle::{Choice, ConditionallySelectable};
+use subtle::{
+    Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater, ConstantTimeLess,
+    CtOption,
+};
 
 use crate::arch::{adc, mulc, sbb};
+use crate::Error;
+
+#[cfg(all(feature = "adx", target_arch = "x86_64"))]
+pub(crate) mod adx;
 
+/// An unsigned integer with N limbs, i.e. 64 * N bits.
+///
+/// The limbs are in little endian order, so `limbs[0]` holds the lowest 64 bits.
 #[derive(Clone, Copy, Debug)]
 // Only implement equality for tests. This is to avoid the temptation to introduce
 // a timing leak through equality comparison in other situations.
@@ -17,6 +38,43 @@ pub struct U<const N: usize> {
 }
 
 impl<const N: usize> U<N> {
+    pub const ZERO: Self = U { limbs: [0; N] };
+
+    pub const ONE: Self = {
+        let mut limbs = [0; N];
+        limbs[0] = 1;
+        U { limbs }
+    };
+
+    /// Parse a number from exactly 16 * N hex digits, with the most significant first.
+    ///
+    /// This method is not constant-time.
+    pub fn from_hex(input: &str) -> Result<Self, Error> {
+        if input.len() != 16 * N {
+            return Err(Error::InvalidLength);
+        }
+        if !input.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidHex);
+        }
+        let mut out = Self::ZERO;
+        for (i, chunk) in input.as_bytes().chunks_exact(16).rev().enumerate() {
+            // The chunk is ASCII, so this can't fail.
+            let digits = core::str::from_utf8(chunk).unwrap();
+            out.limbs[i] = u64::from_str_radix(digits, 16).map_err(|_| Error::InvalidHex)?;
+        }
+        Ok(out)
+    }
+
+    /// Format this number as 16 * N lowercase hex digits, with the most significant first.
+    #[cfg(feature = "alloc")]
+    pub fn to_hex(&self) -> alloc::string::String {
+        self.limbs
+            .iter()
+            .rev()
+            .map(|limb| alloc::format!("{:016x}", limb))
+            .collect()
+    }
+
     /// sub_with_borrow subtracts other from this elements in place, returning a borrow
     ///
     /// A borrow is generated (returning 1), when this subtraction underflows.
@@ -59,36 +117,106 @@ impl<const N: usize> U<N> {
         }
     }
 
-    /// Check if self >= other.
-    ///
-    /// This method is not constant-time.
-    pub fn geq(&self, other: Self) -> bool {
-        for i in (0..N).rev() {
-            if other.limbs[i] > self.limbs[i] {
-                return false;
+    /// Add another number, returning `None` if the addition overflows.
+    pub fn checked_add(&self, other: Self) -> CtOption<Self> {
+        let mut out = *self;
+        let carry = out.add_with_carry(other);
+        CtOption::new(out, Choice::from(carry ^ 1))
+    }
+
+    /// Subtract another number, returning `None` if the subtraction underflows.
+    pub fn checked_sub(&self, other: Self) -> CtOption<Self> {
+        let mut out = *self;
+        let borrow = out.sub_with_borrow(other);
+        CtOption::new(out, Choice::from(borrow ^ 1))
+    }
+
+    /// Add another number, modulo 2^(64 * N).
+    pub fn wrapping_add(&self, other: Self) -> Self {
+        *self + other
+    }
+
+    /// Subtract another number, modulo 2^(64 * N).
+    pub fn wrapping_sub(&self, other: Self) -> Self {
+        *self - other
+    }
+
+    /// Multiply by another number, returning the high, and low, halves of the product.
+    pub fn widening_mul(&self, other: &Self) -> (Self, Self) {
+        let mut lo = Self::ZERO;
+        let mut hi = Self::ZERO;
+        for i in 0..N {
+            let mut carry = 0;
+            for j in 0..N {
+                // The current limb of the product is at position i + j.
+                let k = i + j;
+                let acc = if k < N {
+                    &mut lo.limbs[k]
+                } else {
+                    &mut hi.limbs[k - N]
+                };
+                let full = u128::from(self.limbs[i]) * u128::from(other.limbs[j])
+                    + u128::from(*acc)
+                    + u128::from(carry);
+                *acc = full as u64;
+                carry = (full >> 64) as u64;
             }
+            hi.limbs[i] = carry;
         }
-        true
+        (hi, lo)
     }
 
-    /// Check if self == other.
+    /// Check whether bit i is set, with bit 0 being the least significant.
     ///
-    /// This method is not constant-time.
-    pub fn eq(&self, other: Self) -> bool {
-        for i in (0..N).rev() {
-            if other.limbs[i] != self.limbs[i] {
-                return false;
+    /// Bits past the end of the number are never set.
+    pub fn bit(&self, i: usize) -> Choice {
+        if i >= 64 * N {
+            return Choice::from(0);
+        }
+        Choice::from(((self.limbs[i / 64] >> (i % 64)) & 1) as u8)
+    }
+
+    /// Check if self >= other.
+    ///
+    /// This method is not constant-time, see `ct_lt` for an alternative.
+    pub const fn geq(&self, other: Self) -> bool {
+        // Iterators aren't usable in const functions, hence the manual loop.
+        let mut i = N;
+        while i > 0 {
+            i -= 1;
+            if self.limbs[i] != other.limbs[i] {
+                return self.limbs[i] > other.limbs[i];
             }
         }
         true
     }
 }
 
+impl<const N: usize> ConstantTimeEq for U<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.limbs.ct_eq(&other.limbs)
+    }
+}
+
+impl<const N: usize> ConstantTimeGreater for U<N> {
+    fn ct_gt(&self, other: &Self) -> Choice {
+        // other - self only underflows when self is larger.
+        let mut scratch = *other;
+        Choice::from(scratch.sub_with_borrow(*self))
+    }
+}
+
+impl<const N: usize> ConstantTimeLess for U<N> {
+    fn ct_lt(&self, other: &Self) -> Choice {
+        other.ct_gt(self)
+    }
+}
+
 impl<const N: usize> ConditionallySelectable for U<N> {
     fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
         let mut limbs = [0; N];
-        for i in 0..N {
-            limbs[i] = u64::conditional_select(&a.limbs[i], &b.limbs[i], choice)
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            *limb = u64::conditional_select(&a.limbs[i], &b.limbs[i], choice)
         }
         Self { limbs }
     }
@@ -102,11 +230,11 @@ impl<const N: usize> From<u64> for U<N> {
     }
 }
 
-impl Into<[u8; 32]> for U256 {
-    fn into(self) -> [u8; 32] {
+impl From<U256> for [u8; 32] {
+    fn from(x: U256) -> [u8; 32] {
         let mut out = [0; 32];
         let mut i = 0;
-        for limb in &self.limbs {
+        for limb in &x.limbs {
             for &b in &limb.to_le_bytes() {
                 out[i] = b;
                 i += 1;
@@ -116,9 +244,38 @@ impl Into<[u8; 32]> for U256 {
     }
 }
 
+impl U256 {
+    /// Read a number from 32 little endian bytes, in a const context if need be.
+    pub const fn from_le_bytes(x: [u8; 32]) -> Self {
+        let mut limbs = [0; 4];
+        let mut i = 0;
+        while i < 32 {
+            limbs[i / 8] |= (x[i] as u64) << (8 * (i % 8));
+            i += 1;
+        }
+        U256 { limbs }
+    }
+}
+
 impl From<[u8; 32]> for U256 {
     fn from(x: [u8; 32]) -> Self {
-        let mut out = Self { limbs: [0; 4] };
+        Self::from_le_bytes(x)
+    }
+}
+
+impl From<U512> for [u8; 64] {
+    fn from(x: U512) -> [u8; 64] {
+        let mut out = [0; 64];
+        for (chunk, limb) in out.chunks_exact_mut(8).zip(x.limbs.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        out
+    }
+}
+
+impl From<[u8; 64]> for U512 {
+    fn from(x: [u8; 64]) -> Self {
+        let mut out = Self { limbs: [0; 8] };
         for (i, chunk) in x.chunks_exact(8).enumerate() {
             out.limbs[i] = u64::from_le_bytes(chunk.try_into().unwrap());
         }
@@ -156,6 +313,127 @@ impl<const N: usize> Sub for U<N> {
     }
 }
 
+impl<const N: usize> BitAndAssign for U<N> {
+    fn bitand_assign(&mut self, other: Self) {
+        for (a, b) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *a &= b;
+        }
+    }
+}
+
+impl<const N: usize> BitAnd for U<N> {
+    type Output = Self;
+
+    fn bitand(mut self, other: Self) -> Self::Output {
+        self &= other;
+        self
+    }
+}
+
+impl<const N: usize> BitOrAssign for U<N> {
+    fn bitor_assign(&mut self, other: Self) {
+        for (a, b) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *a |= b;
+        }
+    }
+}
+
+impl<const N: usize> BitOr for U<N> {
+    type Output = Self;
+
+    fn bitor(mut self, other: Self) -> Self::Output {
+        self |= other;
+        self
+    }
+}
+
+impl<const N: usize> BitXorAssign for U<N> {
+    fn bitxor_assign(&mut self, other: Self) {
+        for (a, b) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *a ^= b;
+        }
+    }
+}
+
+impl<const N: usize> BitXor for U<N> {
+    type Output = Self;
+
+    fn bitxor(mut self, other: Self) -> Self::Output {
+        self ^= other;
+        self
+    }
+}
+
+impl<const N: usize> Not for U<N> {
+    type Output = Self;
+
+    fn not(mut self) -> Self::Output {
+        for limb in &mut self.limbs {
+            *limb = !*limb;
+        }
+        self
+    }
+}
+
+/// Shifting is constant-time in the number, but not in the shift amount.
+///
+/// Shifting by 64 * N bits, or more, produces 0.
+impl<const N: usize> ShlAssign<usize> for U<N> {
+    fn shl_assign(&mut self, shift: usize) {
+        let limbs = shift / 64;
+        let bits = shift % 64;
+        for i in (0..N).rev() {
+            let mut limb = 0;
+            if i >= limbs {
+                limb = self.limbs[i - limbs] << bits;
+                if bits > 0 && i > limbs {
+                    limb |= self.limbs[i - limbs - 1] >> (64 - bits);
+                }
+            }
+            self.limbs[i] = limb;
+        }
+    }
+}
+
+impl<const N: usize> Shl<usize> for U<N> {
+    type Output = Self;
+
+    fn shl(mut self, shift: usize) -> Self::Output {
+        self <<= shift;
+        self
+    }
+}
+
+/// Shifting is constant-time in the number, but not in the shift amount.
+///
+/// Shifting by 64 * N bits, or more, produces 0.
+impl<const N: usize> ShrAssign<usize> for U<N> {
+    fn shr_assign(&mut self, shift: usize) {
+        let limbs = shift / 64;
+        let bits = shift % 64;
+        for i in 0..N {
+            let mut limb = 0;
+            if i + limbs < N {
+                limb = self.limbs[i + limbs] >> bits;
+                if bits > 0 && i + limbs + 1 < N {
+                    limb |= self.limbs[i + limbs + 1] << (64 - bits);
+                }
+            }
+            self.limbs[i] = limb;
+        }
+    }
+}
+
+impl<const N: usize> Shr<usize> for U<N> {
+    type Output = Self;
+
+    fn shr(mut self, shift: usize) -> Self::Output {
+        self >>= shift;
+        self
+    }
+}
+
+/// Multiplying by a single limb produces the limb carried out, and the lower limbs.
 impl<const N: usize> Mul<u64> for U<N> {
     type Output = (u64, Self);
 
@@ -182,20 +460,21 @@ pub type U256 = U<4>;
 pub type U512 = U<8>;
 
 impl U512 {
+    /// The lower 256 bits of this number.
     pub fn lo(&self) -> U256 {
         U256 {
             limbs: [self.limbs[0], self.limbs[1], self.limbs[2], self.limbs[3]],
         }
     }
 
-    #[cfg(test)]
+    /// The upper 256 bits of this number.
     pub fn hi(&self) -> U256 {
         U256 {
             limbs: [self.limbs[4], self.limbs[5], self.limbs[6], self.limbs[7]],
         }
     }
 
-    #[cfg(test)]
+    /// Create a number from its upper, and lower, 256 bits.
     pub fn from_hi_lo(hi: U256, lo: U256) -> Self {
         U512 {
             limbs: [
@@ -212,6 +491,7 @@ impl U512 {
     }
 }
 
+/// Multiplying two 256 bit numbers produces the full 512 bit product.
 impl Mul for U256 {
     type Output = U512;
 
@@ -281,6 +561,8 @@ impl Mul for U256 {
     }
 }
 
+/// Multiplying a 512 bit number by a 256 bit one produces the full product, split
+/// into its upper 256 bits, and lower 512 bits.
 impl Mul<U256> for U512 {
     type Output = (U256, U512);
 
@@ -400,6 +682,89 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_hex_round_trip() {
+        let l = U256::from_hex("1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed")
+            .unwrap();
+        assert_eq!(
+            l.limbs,
+            [0x5812631a5cf5d3ed, 0x14def9dea2f79cd6, 0, 1 << 60]
+        );
+        assert_eq!(U256::from_hex(&l.to_hex()).unwrap(), l);
+        assert_eq!(U256::from_hex("00"), Err(Error::InvalidLength));
+        assert_eq!(U256::from_hex(&"+1".repeat(32)), Err(Error::InvalidHex));
+        assert_eq!(U512::ONE.to_hex(), format!("{:0>128}", 1));
+    }
+
+    proptest! {
+        #[test]
+        fn test_byte_round_trips(a in arb_u256(), b in arb_u256()) {
+            let wide = U512::from_hi_lo(a, b);
+            assert_eq!(U512::from(<[u8; 64]>::from(wide)), wide);
+            assert_eq!(U256::from(<[u8; 32]>::from(a)), a);
+            assert!(bool::from(wide.ct_eq(&wide)));
+            assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+        }
+    }
+
+    fn from_u128(x: u128) -> U<2> {
+        U {
+            limbs: [x as u64, (x >> 64) as u64],
+        }
+    }
+
+    fn to_u128(x: U<2>) -> u128 {
+        u128::from(x.limbs[0]) | (u128::from(x.limbs[1]) << 64)
+    }
+
+    proptest! {
+        #[test]
+        fn test_operations_match_u128(a in any::<u128>(), b in any::<u128>(), shift in 0..160usize) {
+            let (x, y) = (from_u128(a), from_u128(b));
+            assert_eq!(Option::from(x.checked_add(y)).map(to_u128), a.checked_add(b));
+            assert_eq!(Option::from(x.checked_sub(y)).map(to_u128), a.checked_sub(b));
+            assert_eq!(to_u128(x.wrapping_sub(y)), a.wrapping_sub(b));
+            assert_eq!(to_u128(x << shift), a.checked_shl(shift as u32).unwrap_or(0));
+            assert_eq!(to_u128(x >> shift), a.checked_shr(shift as u32).unwrap_or(0));
+            assert_eq!(to_u128(x & y), a & b);
+            assert_eq!(to_u128(x | y), a | b);
+            assert_eq!(to_u128(x ^ y), a ^ b);
+            assert_eq!(to_u128(!x), !a);
+            assert_eq!(bool::from(x.ct_lt(&y)), a < b);
+            assert_eq!(bool::from(x.ct_gt(&y)), a > b);
+            assert_eq!(bool::from(x.bit(shift)), shift < 128 && (a >> shift) & 1 == 1);
+            let (hi, lo) = x.widening_mul(&y);
+            assert_eq!(to_u128(lo), a.wrapping_mul(b));
+            let wide = |z: U<2>| U256 { limbs: [z.limbs[0], z.limbs[1], 0, 0] };
+            assert_eq!((wide(x) * wide(y)).lo().limbs, [lo.limbs[0], lo.limbs[1], hi.limbs[0], hi.limbs[1]]);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_widening_mul_matches_mul(a in arb_u256(), b in arb_u256()) {
+            let (hi, lo) = a.widening_mul(&b);
+            assert_eq!(U512::from_hi_lo(hi, lo), a * b);
+            let (hi, lo) = U512::from_hi_lo(0.into(), a).widening_mul(&U512::from_hi_lo(0.into(), b));
+            assert_eq!(hi, U512::ZERO);
+            assert_eq!(lo, a * b);
+        }
+    }
+
+    #[test]
+    fn test_geq_examples() {
+        let a = U256 {
+            limbs: [0, 0, 0, 2],
+        };
+        let b = U256 {
+            limbs: [1, 0, 0, 1],
+        };
+        assert!(a.geq(b));
+        assert!(!b.geq(a));
+        assert!(a.geq(a));
+    }
+
     #[test]
     fn test_addition_examples() {
         let a = U256 {
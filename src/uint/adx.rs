@@ -0,0 +1,80 @@
+//! This module implements multiplication of saturated limbs using the BMI2, and ADX,
+//! extensions to x86_64.
+//!
+//! `mulx` multiplies without touching the flags, so the additions accumulating each
+//! row of partial products can form unbroken carry chains, instead of saving the carry
+//! around every multiplication. ADX adds `adcx`, and `adox`, which allow two such
+//! chains at once. We keep the low, and high, halves of each row in separate chains,
+//! and leave the choice of instructions to the compiler. See Intel's "New Instructions
+//! Supporting Large Integer Arithmetic":
+//! https://www.intel.com/content/dam/www/public/us/en/documents/white-papers/ia-large-integer-arithmetic-paper.pdf
+//!
+//! Every function here requires these extensions, which `crate::cpu` checks before
+//! dispatching here.
+
+// Only scalar multiplication with Barrett reduction uses this
+#![cfg_attr(
+    any(feature = "u32_backend", feature = "montgomery_scalar"),
+    allow(dead_code)
+)]
+
+use core::arch::x86_64::{_addcarryx_u64, _mulx_u64};
+
+/// Calculate the full product of a, and b, which has K = N + M limbs.
+///
+/// # Safety
+///
+/// The CPU needs to support BMI2, and ADX.
+#[inline]
+#[target_feature(enable = "bmi2,adx")]
+pub unsafe fn mul<const N: usize, const M: usize, const K: usize>(
+    a: &[u64; N],
+    b: &[u64; M],
+) -> [u64; K] {
+    debug_assert_eq!(K, N + M);
+    let mut out = [0; K];
+    for j in 0..M {
+        // The low halves go into one carry chain, and the high halves into another.
+        let mut lo_carry = 0;
+        let mut hi_carry = 0;
+        for i in 0..N {
+            let mut hi = 0;
+            let lo = _mulx_u64(a[i], b[j], &mut hi);
+            lo_carry = _addcarryx_u64(lo_carry, out[i + j], lo, &mut out[i + j]);
+            hi_carry = _addcarryx_u64(hi_carry, out[i + j + 1], hi, &mut out[i + j + 1]);
+        }
+        // The top limb of this row started at 0, so the high chain can't carry out of it,
+        // and, since the partial product fits in N + j + 1 limbs, neither can the low one.
+        debug_assert_eq!(hi_carry, 0);
+        _addcarryx_u64(lo_carry, out[N + j], 0, &mut out[N + j]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::uint::{U256, U512};
+
+    #[test]
+    fn test_mul_matches_portable() {
+        if !crate::cpu::detected().adx {
+            return;
+        }
+        let max = U256 { limbs: [!0; 4] };
+        let odd = U256 {
+            limbs: [
+                0x0123456789abcdef,
+                0xfedcba9876543210,
+                0xdeadbeefcafebabe,
+                0x0f0e0d0c0b0a0908,
+            ],
+        };
+        for &(a, b) in [(max, max), (max, odd), (odd, odd), (odd, U256::ONE)].iter() {
+            let (hi, lo) = a.widening_mul(&b);
+            let out: [u64; 8] = unsafe { mul(&a.limbs, &b.limbs) };
+            assert_eq!(out, U512::from_hi_lo(hi, lo).limbs);
+        }
+    }
+}
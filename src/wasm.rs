@@ -0,0 +1,144 @@
+//! This module exposes signing, verification, and X25519 to JavaScript, with wasm-bindgen.
+//!
+//! Keys, messages, and signatures, are all passed as `Uint8Array`s. Functions which
+//! can fail throw an error with a description of what went wrong. Randomness comes
+//! from the `crypto.getRandomValues` API, through getrandom's `js` feature.
+//!
+//! The module is built with:
+//!
+//! ```text
+//! cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! ```
+
+use core::convert::{TryFrom, TryInto};
+
+use rand::rngs::OsRng;
+use subtle::ConstantTimeEq;
+use wasm_bindgen::prelude::*;
+
+use crate::{gen_keypair, x25519 as x25519_raw, Error, PrivateKey, PublicKey, Signature};
+
+fn to_js(error: Error) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+/// A freshly generated key pair.
+#[wasm_bindgen]
+pub struct Keypair {
+    public: PublicKey,
+    private: PrivateKey,
+}
+
+#[wasm_bindgen]
+impl Keypair {
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.public.to_bytes().to_vec()
+    }
+
+    #[wasm_bindgen(getter, js_name = privateKey)]
+    pub fn private_key(&self) -> Vec<u8> {
+        self.private.to_bytes().to_vec()
+    }
+}
+
+/// Generate a new key pair.
+#[wasm_bindgen]
+pub fn keygen() -> Keypair {
+    let (public, private) = gen_keypair(&mut OsRng);
+    Keypair { public, private }
+}
+
+fn derive_public_key(private_key: &[u8]) -> Result<Vec<u8>, Error> {
+    let private = PrivateKey::try_from(private_key)?;
+    Ok(private.derive_public_key().to_bytes().to_vec())
+}
+
+/// Calculate the public key corresponding to a private key.
+#[wasm_bindgen(js_name = publicKey)]
+pub fn public_key(private_key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    derive_public_key(private_key).map_err(to_js)
+}
+
+fn sign_bytes(private_key: &[u8], message: &[u8]) -> Result<Vec<u8>, Error> {
+    let private = PrivateKey::try_from(private_key)?;
+    Ok(private.sign(message).bytes.to_vec())
+}
+
+/// Sign a message with a private key.
+#[wasm_bindgen]
+pub fn sign(private_key: &[u8], message: &[u8]) -> Result<Vec<u8>, JsValue> {
+    sign_bytes(private_key, message).map_err(to_js)
+}
+
+/// Verify a signature of a message.
+///
+/// This returns false, rather than throwing, for malformed keys, or signatures.
+#[wasm_bindgen]
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match (
+        PublicKey::try_from(public_key),
+        Signature::try_from(signature),
+    ) {
+        (Ok(public), Ok(signature)) => public.verify(message, signature),
+        _ => false,
+    }
+}
+
+fn x25519_bytes(scalar: &[u8], u: &[u8]) -> Result<Vec<u8>, Error> {
+    let scalar = scalar.try_into().map_err(|_| Error::InvalidLength)?;
+    let u = u.try_into().map_err(|_| Error::InvalidLength)?;
+    let shared = x25519_raw(scalar, u);
+    if bool::from(shared.ct_eq(&[0; 32])) {
+        return Err(Error::WeakPublicKey);
+    }
+    Ok(shared.to_vec())
+}
+
+/// Calculate the X25519 function of RFC 7748, with a scalar, and a u coordinate.
+///
+/// This throws if the result is all zeros, which happens for points of small order.
+#[wasm_bindgen]
+pub fn x25519(scalar: &[u8], u: &[u8]) -> Result<Vec<u8>, JsValue> {
+    x25519_bytes(scalar, u).map_err(to_js)
+}
+
+/// Calculate the X25519 public key for a scalar, i.e. X25519 with the base point.
+#[wasm_bindgen(js_name = x25519Base)]
+pub fn x25519_base(scalar: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let mut base = [0; 32];
+    base[0] = 9;
+    x25519_bytes(scalar, &base).map_err(to_js)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = keygen();
+        let private = keypair.private_key();
+        let public = keypair.public_key();
+        assert_eq!(derive_public_key(&private), Ok(public.clone()));
+        let signature = sign_bytes(&private, b"hello from JS").unwrap();
+        assert!(verify(&public, b"hello from JS", &signature));
+        assert!(!verify(&public, b"hello from C", &signature));
+        assert!(!verify(&public[1..], b"hello from JS", &signature));
+        assert_eq!(sign_bytes(&private[1..], b""), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_x25519() {
+        let mut base = [0; 32];
+        base[0] = 9;
+        let alice_public = x25519_bytes(&[1; 32], &base).unwrap();
+        let bob_public = x25519_bytes(&[2; 32], &base).unwrap();
+        assert_eq!(
+            x25519_bytes(&[1; 32], &bob_public),
+            x25519_bytes(&[2; 32], &alice_public)
+        );
+        assert_eq!(x25519_bytes(&[1; 32], &[0; 32]), Err(Error::WeakPublicKey));
+        assert_eq!(x25519_bytes(&[1; 31], &base), Err(Error::InvalidLength));
+    }
+}
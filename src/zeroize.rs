@@ -0,0 +1,119 @@
+//! This module provides a way to securely erase secrets from memory.
+//!
+//! Simply overwriting a value before letting it go out of scope isn't enough,
+//! since the compiler is free to remove writes that are never read. Instead,
+//! we use volatile writes, followed by a fence, which can't be optimized away.
+//!
+//! With this feature enabled, private keys also erase themselves when dropped.
+//! Secrets living in local variables get wrapped in `Zeroizing`, which erases them
+//! when dropped, so that returning early, or on an error, still erases them.
+//! Without the feature, `Zeroizing` is a plain wrapper, which erases nothing.
+
+use core::{
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{self, Ordering},
+};
+
+/// A value which can be securely set to zero.
+pub trait Zeroize {
+    /// Overwrite this value with zeros, in a way that won't get optimized away.
+    fn zeroize(&mut self);
+}
+
+/// Overwrite some words with zeros.
+pub(crate) fn wipe<T: Copy + Default>(data: &mut [T]) {
+    for x in data.iter_mut() {
+        // Safe, because we're writing through a valid mutable reference.
+        unsafe { ptr::write_volatile(x, T::default()) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+impl<T: Copy + Default, const N: usize> Zeroize for [T; N] {
+    fn zeroize(&mut self) {
+        wipe(self);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Zeroize for alloc::vec::Vec<u8> {
+    /// Overwrite the contents of this vector, and then clear it.
+    ///
+    /// Memory left behind by previous reallocations can't be reached, and isn't erased.
+    fn zeroize(&mut self) {
+        wipe(self);
+        self.clear();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Zeroize for alloc::string::String {
+    fn zeroize(&mut self) {
+        // Safe, because zero bytes are still valid UTF-8.
+        unsafe { self.as_mut_vec() }.zeroize();
+    }
+}
+
+/// A value which gets set to zero when dropped.
+pub struct Zeroizing<T: Zeroize>(T);
+
+impl<T: Zeroize> Zeroizing<T> {
+    /// Wrap a value, so that it gets erased once it goes out of scope.
+    pub fn new(value: T) -> Self {
+        Zeroizing(value)
+    }
+}
+
+impl<T: Zeroize> Deref for Zeroizing<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Zeroizing<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Zeroizing<T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_zeroize_bytes() {
+        let mut bytes = [0xAB; 40];
+        bytes.zeroize();
+        assert_eq!(bytes, [0; 40]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_zeroize_vec() {
+        let mut v = vec![1, 2, 3];
+        v.zeroize();
+        assert!(v.is_empty());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroizing_erases_on_drop() {
+        // Dropping in place lets us look at the memory afterwards, while it's still ours.
+        let mut guard = core::mem::ManuallyDrop::new(Zeroizing::new([0xABu32; 4]));
+        guard[1] = 7;
+        assert_eq!(**guard, [0xAB, 7, 0xAB, 0xAB]);
+        let inner: *const [u32; 4] = &**guard;
+        unsafe { core::mem::ManuallyDrop::drop(&mut guard) };
+        assert_eq!(unsafe { *inner }, [0; 4]);
+    }
+}
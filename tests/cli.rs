@@ -0,0 +1,75 @@
+//! These tests run the command line tool, which is only built with the `cli` feature.
+#![cfg(feature = "cli")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn eddo(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_eddo"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let mut path = env::temp_dir();
+    path.push(format!("eddo-cli-{}-{}", std::process::id(), name));
+    path
+}
+
+fn roundtrip(format: &str) {
+    let key = scratch(&format!("key.{}", format));
+    let data = scratch(&format!("data.{}", format));
+    fs::write(&data, b"some data to sign").unwrap();
+    let key_path = key.to_str().unwrap();
+    let data_path = data.to_str().unwrap();
+
+    let public = stdout(&eddo(&["keygen", "-o", key_path, "-f", format]));
+    assert_eq!(
+        stdout(&eddo(&["pubkey", "-k", key_path, "-f", format])),
+        public
+    );
+    let signature = stdout(&eddo(&["sign", "-k", key_path, data_path]));
+    let public_file = scratch(&format!("public.{}", format));
+    fs::write(&public_file, &public).unwrap();
+    let public_path = public_file.to_str().unwrap();
+    let signature = signature.trim();
+    assert_eq!(
+        stdout(&eddo(&[
+            "verify",
+            "-p",
+            public_path,
+            "-s",
+            signature,
+            data_path
+        ])),
+        "Ok!\n"
+    );
+
+    fs::write(&data, b"some other data").unwrap();
+    assert!(
+        !eddo(&["verify", "-p", public_path, "-s", signature, data_path])
+            .status
+            .success()
+    );
+    for path in [key, data, public_file] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_hex_roundtrip() {
+    roundtrip("hex");
+}
+
+#[test]
+fn test_pem_roundtrip() {
+    roundtrip("pem");
+}
@@ -0,0 +1,181 @@
+//! These tests look for timing leaks, in the style of dudect:
+//! https://eprint.iacr.org/2016/1123
+//!
+//! Each operation gets run on inputs from two classes, one where the secret is fixed,
+//! and one where it's random, interleaved at random. If the operation runs in constant
+//! time, then Welch's t-test shouldn't be able to tell the two distributions of timings
+//! apart. As in dudect, we also test after cropping the slowest measurements, since
+//! the noise from the rest of the system is mostly in the upper tail.
+//!
+//! A t-statistic above 5 suggests a leak, and we fail above 10, which is very unlikely
+//! to happen by chance. Since timings are noisy, these only run with the `dudect` feature,
+//! and should be run in release mode, on a quiet machine:
+//!
+//!     cargo test --release --features dudect --test dudect -- --nocapture --test-threads 1
+//!
+//! The number of measurements for each operation can be set with `DUDECT_SAMPLES`.
+#![cfg(feature = "dudect")]
+
+use std::env;
+use std::hint::black_box;
+use std::time::Instant;
+
+use eddo::hazmat::{Point, Scalar, B};
+use eddo::PrivateKey;
+use rand::{rngs::OsRng, Rng, RngCore};
+use subtle::{Choice, ConditionallySelectable};
+
+/// Above this, we consider the operation to definitely not be constant time.
+const T_THRESHOLD: f64 = 10.0;
+
+/// The fractions of measurements kept, after cropping the slowest ones.
+const CROP_FRACTIONS: &[f64] = &[1.0, 0.9, 0.75, 0.5];
+
+/// The two classes of inputs.
+#[derive(Clone, Copy)]
+enum Class {
+    Fixed,
+    Random,
+}
+
+/// Welch's t-test, calculated from the running mean, and variance, of each class.
+#[derive(Default)]
+struct Welch {
+    n: [f64; 2],
+    mean: [f64; 2],
+    m2: [f64; 2],
+}
+
+impl Welch {
+    fn push(&mut self, class: Class, x: f64) {
+        let i = class as usize;
+        self.n[i] += 1.0;
+        let delta = x - self.mean[i];
+        self.mean[i] += delta / self.n[i];
+        self.m2[i] += delta * (x - self.mean[i]);
+    }
+
+    fn t(&self) -> f64 {
+        let var0 = self.m2[0] / (self.n[0] - 1.0);
+        let var1 = self.m2[1] / (self.n[1] - 1.0);
+        (self.mean[0] - self.mean[1]) / (var0 / self.n[0] + var1 / self.n[1]).sqrt()
+    }
+}
+
+fn samples(default: usize) -> usize {
+    env::var("DUDECT_SAMPLES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Measure an operation, on inputs prepared outside of the timing, returning the largest t.
+fn measure<T>(
+    name: &str,
+    samples: usize,
+    mut prepare: impl FnMut(Class) -> T,
+    mut run: impl FnMut(&T),
+) -> f64 {
+    let mut timings = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let class = if OsRng.gen() {
+            Class::Fixed
+        } else {
+            Class::Random
+        };
+        let input = prepare(class);
+        let start = Instant::now();
+        run(black_box(&input));
+        timings.push((class, start.elapsed().as_nanos() as f64));
+    }
+    let mut sorted: Vec<f64> = timings.iter().map(|&(_, x)| x).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut max_t: f64 = 0.0;
+    for &fraction in CROP_FRACTIONS {
+        let cutoff = sorted[((sorted.len() as f64 * fraction) as usize).min(sorted.len() - 1)];
+        let mut welch = Welch::default();
+        for &(class, x) in timings.iter().filter(|&&(_, x)| x <= cutoff) {
+            welch.push(class, x);
+        }
+        let t = welch.t();
+        println!("{}: keeping {:>3}%, t = {:+.2}", name, fraction * 100.0, t);
+        if t.is_finite() {
+            max_t = max_t.max(t.abs());
+        }
+    }
+    max_t
+}
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0; 64];
+    OsRng.fill_bytes(&mut bytes);
+    Scalar::from(bytes)
+}
+
+/// The fixed class uses a small scalar, with mostly zero windows.
+fn scalar(class: Class) -> Scalar {
+    match class {
+        Class::Fixed => {
+            let mut bytes = [0; 64];
+            bytes[0] = 1;
+            Scalar::from(bytes)
+        }
+        Class::Random => random_scalar(),
+    }
+}
+
+#[test]
+fn test_scalar_multiplication() {
+    let base = B * random_scalar();
+    let t = measure("scalar multiplication", samples(10_000), scalar, |&s| {
+        black_box(base * s);
+    });
+    assert!(t < T_THRESHOLD, "t = {}", t);
+}
+
+#[test]
+fn test_conditional_selection() {
+    let a = B * random_scalar();
+    let b = B * random_scalar();
+    let x = random_scalar();
+    let y = random_scalar();
+    // A single selection is too fast to time, so we time many of them at once.
+    let t = measure(
+        "conditional selection",
+        samples(100_000),
+        |class| {
+            let mut choices = [0u8; 64];
+            if let Class::Random = class {
+                OsRng.fill_bytes(&mut choices);
+            }
+            choices
+        },
+        |choices| {
+            for &c in choices.iter() {
+                let choice = Choice::from(c & 1);
+                black_box(Point::conditional_select(&a, &b, choice));
+                black_box(Scalar::conditional_select(&x, &y, choice));
+            }
+        },
+    );
+    assert!(t < T_THRESHOLD, "t = {}", t);
+}
+
+#[test]
+fn test_signing() {
+    let t = measure(
+        "signing",
+        samples(10_000),
+        |class| {
+            let mut seed = [0; 32];
+            if let Class::Random = class {
+                OsRng.fill_bytes(&mut seed);
+            }
+            PrivateKey::from_seed(seed)
+        },
+        |private| {
+            black_box(private.sign(b"dudect"));
+        },
+    );
+    assert!(t < T_THRESHOLD, "t = {}", t);
+}
@@ -0,0 +1,305 @@
+//! This runs Google's Wycheproof vectors for Ed25519, and X25519:
+//! https://github.com/google/wycheproof
+//!
+//! The vector files aren't vendored. Copy `eddsa_test.json`, and `x25519_test.json`,
+//! from the `testvectors` directory into `tests/wycheproof/`, or point `WYCHEPROOF_DIR`
+//! at a checkout of the repository, and run the ignored tests, which fail without them:
+//!
+//! ```text
+//! cargo test --test wycheproof -- --ignored
+//! ```
+//!
+//! A small sample written in the same schema always runs, which still exercises the
+//! parsing, and the mapping of expected results to the behavior of the crate.
+
+use std::convert::{TryFrom, TryInto};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eddo::{x25519, Dh25519, PrivateKey, PublicKey, Signature};
+use serde_json::Value;
+
+/// Cases from RFC 8032, and modifications of them, in the EdDSA schema.
+///
+/// The first group uses the older schema, with a private key, and the second the
+/// newer one, with only a public key.
+const SAMPLE_EDDSA: &str = r#"{
+  "algorithm": "EDDSA",
+  "testGroups": [
+    {
+      "key": {
+        "curve": "edwards25519",
+        "pk": "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "sk": "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+      },
+      "type": "EddsaVerify",
+      "tests": [
+        {
+          "tcId": 1,
+          "comment": "RFC 8032 test 1",
+          "msg": "",
+          "sig": "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+          "result": "valid",
+          "flags": []
+        },
+        {
+          "tcId": 2,
+          "comment": "modified R",
+          "msg": "",
+          "sig": "e4564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+          "result": "invalid",
+          "flags": ["InvalidSignature"]
+        },
+        {
+          "tcId": 3,
+          "comment": "S replaced by S + L",
+          "msg": "",
+          "sig": "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901554c8c7872aa064e049dbb3013fbf29380d25bf5f0595bbe24655141438e7a101b",
+          "result": "invalid",
+          "flags": ["SignatureMalleability"]
+        },
+        {
+          "tcId": 4,
+          "comment": "truncated signature",
+          "msg": "",
+          "sig": "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a10",
+          "result": "invalid",
+          "flags": ["TruncatedSignature"]
+        }
+      ]
+    },
+    {
+      "publicKey": {
+        "curve": "edwards25519",
+        "pk": "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+      },
+      "type": "EddsaVerify",
+      "tests": [
+        {
+          "tcId": 5,
+          "comment": "signature of the message 72",
+          "msg": "72",
+          "sig": "1b79abc415a34efe5915b4c1b53d2435e731b3c92d0ba440de29cab2999fa885bd0eb3c71dfd8df6fbecf8c0ef403e8902dec8e2abd00ab9b04b1df027929609",
+          "result": "valid",
+          "flags": []
+        },
+        {
+          "tcId": 6,
+          "comment": "signature of the empty message",
+          "msg": "72",
+          "sig": "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+          "result": "invalid",
+          "flags": ["InvalidSignature"]
+        }
+      ]
+    }
+  ]
+}"#;
+
+/// Cases from RFC 7748, and modifications of them, in the XDH schema.
+const SAMPLE_X25519: &str = r#"{
+  "algorithm": "XDH",
+  "testGroups": [
+    {
+      "curve": "curve25519",
+      "type": "XdhComp",
+      "tests": [
+        {
+          "tcId": 1,
+          "comment": "RFC 7748 section 5.2",
+          "public": "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+          "private": "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+          "shared": "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+          "result": "valid",
+          "flags": []
+        },
+        {
+          "tcId": 2,
+          "comment": "public key with the top bit set",
+          "public": "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1ccc",
+          "private": "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+          "shared": "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+          "result": "acceptable",
+          "flags": ["NonCanonicalPublic"]
+        },
+        {
+          "tcId": 3,
+          "comment": "public key of order 1",
+          "public": "0000000000000000000000000000000000000000000000000000000000000000",
+          "private": "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+          "shared": "0000000000000000000000000000000000000000000000000000000000000000",
+          "result": "acceptable",
+          "flags": ["LowOrderPublic", "ZeroSharedSecret"]
+        }
+      ]
+    }
+  ]
+}"#;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Expected {
+    Valid,
+    Invalid,
+    Acceptable,
+}
+
+impl Expected {
+    fn of(test: &Value) -> Self {
+        match test["result"].as_str() {
+            Some("valid") => Expected::Valid,
+            Some("invalid") => Expected::Invalid,
+            Some("acceptable") => Expected::Acceptable,
+            other => panic!("unknown result {:?}", other),
+        }
+    }
+
+    /// Whether or not the crate accepting the input, or not, is allowed.
+    fn allows(self, accepted: bool) -> bool {
+        match self {
+            Expected::Valid => accepted,
+            Expected::Invalid => !accepted,
+            Expected::Acceptable => true,
+        }
+    }
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    hex::decode(value.as_str().expect("expected a hex string")).unwrap()
+}
+
+fn describe(test: &Value, problem: &str) -> String {
+    format!(
+        "tcId {} ({}, {:?}): {}",
+        test["tcId"], test["comment"], test["flags"], problem
+    )
+}
+
+/// Run every case in an EdDSA vector file, returning how many ran.
+fn run_eddsa(json: &str) -> usize {
+    let root: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(root["algorithm"], "EDDSA");
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for group in root["testGroups"].as_array().unwrap() {
+        let key = if group["publicKey"].is_object() {
+            &group["publicKey"]
+        } else {
+            &group["key"]
+        };
+        if key["curve"] != "edwards25519" {
+            continue;
+        }
+        let pk = bytes(&key["pk"]);
+        let public = PublicKey::try_from(&pk[..]);
+        // Older versions include the private key, which lets us check signing too.
+        let private = key["sk"]
+            .as_str()
+            .map(|sk| PrivateKey::try_from(&hex::decode(sk).unwrap()[..]).unwrap());
+        if let Some(private) = &private {
+            let derived = private.clone().into_signing_only().public_key();
+            if derived.as_bytes()[..] != pk[..] {
+                failures.push(format!("public key {} not derived", key["pk"]));
+            }
+        }
+        for test in group["tests"].as_array().unwrap() {
+            count += 1;
+            let msg = bytes(&test["msg"]);
+            let sig = bytes(&test["sig"]);
+            let expected = Expected::of(test);
+            let accepted = match (&public, Signature::try_from(&sig[..])) {
+                (Ok(public), Ok(signature)) => public.verify(&msg, signature),
+                _ => false,
+            };
+            if !expected.allows(accepted) {
+                failures.push(describe(test, &format!("accepted: {}", accepted)));
+            }
+            // Signatures are deterministic, so valid ones are exactly what we produce.
+            if let (Some(private), Expected::Valid) = (&private, expected) {
+                if private.sign(&msg).bytes[..] != sig[..] {
+                    failures.push(describe(test, "different signature produced"));
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{:#?}", failures);
+    count
+}
+
+/// Run every case in an XDH vector file, returning how many ran.
+fn run_x25519(json: &str) -> usize {
+    let root: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(root["algorithm"], "XDH");
+    let mut failures = Vec::new();
+    let mut count = 0;
+    for group in root["testGroups"].as_array().unwrap() {
+        if group["curve"] != "curve25519" {
+            continue;
+        }
+        for test in group["tests"].as_array().unwrap() {
+            count += 1;
+            let public = bytes(&test["public"]);
+            let private: [u8; 32] = bytes(&test["private"]).try_into().unwrap();
+            let shared = bytes(&test["shared"]);
+            let expected = Expected::of(test);
+            // The raw function follows the RFC, so it matches all but invalid cases.
+            if let (Ok(u), false) = (
+                <[u8; 32]>::try_from(&public[..]),
+                expected == Expected::Invalid,
+            ) {
+                if x25519(private, u)[..] != shared[..] {
+                    failures.push(describe(test, "different raw output"));
+                }
+            }
+            // The checked function may reject acceptable cases, like low order points.
+            let accepted = match Dh25519::set(private).dh(&public) {
+                Ok(out) => {
+                    if out[..] != shared[..] {
+                        failures.push(describe(test, "different shared secret"));
+                    }
+                    true
+                }
+                Err(_) => false,
+            };
+            if !expected.allows(accepted) {
+                failures.push(describe(test, &format!("accepted: {}", accepted)));
+            }
+            if accepted && shared.iter().all(|&x| x == 0) {
+                failures.push(describe(test, "accepted a zero shared secret"));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{:#?}", failures);
+    count
+}
+
+/// Read a vector file, panicking if it hasn't been downloaded.
+fn vector_file(name: &str) -> String {
+    let dir = env::var_os("WYCHEPROOF_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/wycheproof"));
+    for path in &[dir.join(name), dir.join("testvectors").join(name)] {
+        if let Ok(contents) = fs::read_to_string(path) {
+            return contents;
+        }
+    }
+    panic!("{} is missing from {}", name, dir.display());
+}
+
+#[test]
+fn test_sample_vectors() {
+    assert_eq!(run_eddsa(SAMPLE_EDDSA), 6);
+    assert_eq!(run_x25519(SAMPLE_X25519), 3);
+}
+
+#[test]
+#[ignore = "needs eddsa_test.json from Wycheproof"]
+fn test_wycheproof_eddsa() {
+    assert!(run_eddsa(&vector_file("eddsa_test.json")) > 0);
+}
+
+#[test]
+#[ignore = "needs x25519_test.json from Wycheproof"]
+fn test_wycheproof_x25519() {
+    assert!(run_x25519(&vector_file("x25519_test.json")) > 0);
+}